@@ -0,0 +1,71 @@
+/*
+ * C ABI of the gpkg-to-png library (cargo feature `ffi`).
+ *
+ * Build the shared library with:
+ *   cargo rustc --release --lib --features ffi --crate-type cdylib
+ *
+ * Functions return 0 on success and -1 on error; gpkg_to_png_last_error()
+ * then describes the error of the calling thread. Internal panics are
+ * reported as errors too (a library built with panic = "abort" aborts).
+ */
+
+#ifndef GPKG_TO_PNG_H
+#define GPKG_TO_PNG_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Geometries of a GeoJSON file or a GeoPackage layer, in WGS84. */
+typedef struct GpkgToPngSource GpkgToPngSource;
+
+/* min_lon > max_lon describes a bbox crossing the antimeridian. */
+typedef struct {
+    double min_lon;
+    double min_lat;
+    double max_lon;
+    double max_lat;
+    /* Resolution in degrees per pixel. */
+    double resolution;
+    /* Fill color, RGBA. */
+    uint8_t fill[4];
+    /* Stroke color, RGB. */
+    uint8_t stroke[3];
+    /* Stroke width in pixels (0 disables strokes). */
+    uint32_t stroke_width;
+} GpkgToPngOptions;
+
+/* Row-major RGBA pixels owned by the library. */
+typedef struct {
+    uint8_t *data;
+    size_t len;
+    uint32_t width;
+    uint32_t height;
+} GpkgToPngImage;
+
+int gpkg_to_png_open_geojson(const char *path, GpkgToPngSource **out);
+
+/* Only available when the library is built with the `gpkg` feature. */
+int gpkg_to_png_open_gpkg(const char *path, const char *layer, GpkgToPngSource **out);
+
+void gpkg_to_png_source_free(GpkgToPngSource *source);
+
+int gpkg_to_png_render_rgba(const GpkgToPngSource *source, const GpkgToPngOptions *options,
+                            GpkgToPngImage *out);
+
+void gpkg_to_png_image_free(GpkgToPngImage *image);
+
+int gpkg_to_png_render_file(const GpkgToPngSource *source, const GpkgToPngOptions *options,
+                            const char *path);
+
+/* Valid until the next failing call on the same thread; NULL if none. */
+const char *gpkg_to_png_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GPKG_TO_PNG_H */
@@ -0,0 +1,186 @@
+//! Animations across an attribute (`animate` subcommand).
+//!
+//! Renders one frame per distinct value of a column (a year, a phase...),
+//! each drawing only the features holding that value over the extent of the
+//! whole layers, and assembles the frames in ascending value order into an
+//! animated PNG (APNG) or GIF.
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::Delay;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cli::AnimateArgs;
+use crate::error::{GpkgError, Result};
+use crate::logger;
+use crate::serve::{Frame, Source};
+use crate::style::AttributeValue;
+
+/// Most frames of an animation.
+const MAX_FRAMES: usize = 1000;
+
+/// File format of an animation, from the extension of its path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AnimationFormat {
+    Apng,
+    Gif,
+}
+
+impl AnimationFormat {
+    fn from_path(path: &Path) -> Result<Self> {
+        let extension = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("png" | "apng") => Ok(Self::Apng),
+            Some("gif") => Ok(Self::Gif),
+            _ => Err(GpkgError::InvalidFormatOption(format!(
+                "animation {} must end in .png (APNG) or .gif",
+                path.display()
+            ))),
+        }
+    }
+}
+
+/// Renders the frames of the selected layers and saves the animation.
+pub async fn run(args: AnimateArgs) -> Result<()> {
+    let format = AnimationFormat::from_path(&args.output)?;
+    let source = Source::open(&args.input, &args.layer, &args.style).await?;
+    let bbox = source.bbox().await?;
+    let height = (args.width as f64 * bbox.height() / bbox.width()).round().max(1.0) as u32;
+    logger::info(&format!("Rendering {}x{} frames by {}", args.width, height, args.by));
+
+    let frames = source.render_frames(&args.by, bbox, args.width, height).await?;
+    if frames.is_empty() {
+        return Err(GpkgError::InvalidFormatOption(format!(
+            "no feature has a value of --by {}",
+            args.by
+        )));
+    }
+    if frames.len() > MAX_FRAMES {
+        return Err(GpkgError::InvalidFormatOption(format!(
+            "--by {} has {} distinct values, more than the {} frames of an animation",
+            args.by,
+            frames.len(),
+            MAX_FRAMES
+        )));
+    }
+    for frame in &frames {
+        logger::debug(&format!("Frame {}: {} feature(s)", label(&frame.value), frame.features));
+    }
+
+    let mut part = args.output.as_os_str().to_owned();
+    part.push(".part");
+    let part = PathBuf::from(part);
+    let written = File::create(&part).map_err(GpkgError::from).and_then(|file| {
+        let writer = BufWriter::new(file);
+        match format {
+            AnimationFormat::Apng => write_apng(writer, &frames, args.delay, args.loops),
+            AnimationFormat::Gif => write_gif(writer, &frames, args.delay, args.loops),
+        }
+    });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&part);
+        return Err(e);
+    }
+    std::fs::rename(&part, &args.output)?;
+    logger::info(&format!(
+        "Saved: {} ({} frames, {} to {})",
+        args.output.display(),
+        frames.len(),
+        label(&frames[0].value),
+        label(&frames[frames.len() - 1].value)
+    ));
+    Ok(())
+}
+
+/// Writes `frames` as an APNG showing each for `delay` ms, played `loops`
+/// times (0 = forever).
+fn write_apng<W: Write>(writer: W, frames: &[Frame], delay: u16, loops: u32) -> Result<()> {
+    let (width, height) = frames[0].image.dimensions();
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, loops)?;
+    encoder.set_frame_delay(delay, 1000)?;
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.write_image_data(frame.image.as_raw())?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Writes `frames` as a GIF showing each for `delay` ms, played `loops`
+/// times (0 = forever). GIF colors are quantized and transparency is on/off.
+fn write_gif<W: Write>(writer: W, frames: &[Frame], delay: u16, loops: u32) -> Result<()> {
+    let mut encoder = GifEncoder::new(writer);
+    // Without a repeat count, a GIF plays once
+    match loops {
+        0 => encoder.set_repeat(Repeat::Infinite)?,
+        1 => {}
+        n => encoder.set_repeat(Repeat::Finite((n - 1).min(u16::MAX as u32) as u16))?,
+    }
+    for frame in frames {
+        let delay = Delay::from_numer_denom_ms(delay as u32, 1);
+        encoder.encode_frame(image::Frame::from_parts(frame.image.clone(), 0, 0, delay))?;
+    }
+    Ok(())
+}
+
+/// Displays a frame value (whole numbers without decimals).
+fn label(value: &AttributeValue) -> String {
+    match value {
+        AttributeValue::Number(n) => n.to_string(),
+        AttributeValue::Text(s) => s.clone(),
+        AttributeValue::Null => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{AnimationDecoder, Rgba, RgbaImage};
+
+    fn frames() -> Vec<Frame> {
+        [1990.0, 2000.0, 2010.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &year)| {
+                let mut image = RgbaImage::new(4, 2);
+                image.put_pixel(i as u32, 0, Rgba([255, 0, 0, 255]));
+                Frame {
+                    value: AttributeValue::Number(year),
+                    image,
+                    features: 1,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_write_animations() {
+        assert_eq!(AnimationFormat::from_path(Path::new("a.PNG")).unwrap(), AnimationFormat::Apng);
+        assert_eq!(AnimationFormat::from_path(Path::new("a.gif")).unwrap(), AnimationFormat::Gif);
+        assert!(AnimationFormat::from_path(Path::new("a.tif")).is_err());
+        assert_eq!(label(&AttributeValue::Number(2000.0)), "2000");
+
+        let mut apng = Vec::new();
+        write_apng(&mut apng, &frames(), 250, 0).unwrap();
+        let decoder = png::Decoder::new(std::io::Cursor::new(apng));
+        let reader = decoder.read_info().unwrap();
+        let control = reader.info().animation_control().unwrap();
+        assert_eq!((control.num_frames, control.num_plays), (3, 0));
+
+        let mut gif = Vec::new();
+        write_gif(&mut gif, &frames(), 250, 0).unwrap();
+        let decoded = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(gif))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[0].delay().numer_denom_ms(), (250, 1));
+        assert_eq!(decoded[2].buffer().get_pixel(2, 0), &Rgba([255, 0, 0, 255]));
+        assert_eq!(decoded[2].buffer().get_pixel(0, 0)[3], 0);
+    }
+}
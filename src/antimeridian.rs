@@ -0,0 +1,433 @@
+//! Antimeridian (±180°) handling.
+//!
+//! Longitudes are treated as an unbounded axis: a bbox crossing the dateline
+//! is stored with `max_lon > 180`, rings that jump across ±180° are unwrapped
+//! into continuous coordinates, and geometries are shifted by ±360° copies
+//! wherever those copies overlap the render bbox. Rendering every overlapping
+//! copy is equivalent to splitting the geometry at the dateline.
+//!
+//! A ring circling a pole (its longitudes wind a full turn, as for Antarctica
+//! or an Arctic cap) has no dateline-free form: once unwrapped it spans 360°
+//! and is closed along the pole so it covers the cap instead of a line.
+
+use geo::{Coord, LineString, MapCoords, MultiPolygon, Polygon};
+use std::borrow::Cow;
+
+use crate::math::Bbox;
+
+/// Longitude jump between consecutive vertices that indicates a dateline crossing.
+const WRAP_THRESHOLD: f64 = 180.0;
+
+/// Returns true if a ring has a longitude jump larger than 180° between two
+/// vertices, or circles a pole.
+fn ring_wraps(ring: &LineString<f64>) -> bool {
+    ring.0
+        .windows(2)
+        .any(|w| (w[1].x - w[0].x).abs() > WRAP_THRESHOLD)
+        || ring_circles_pole(ring)
+}
+
+/// Returns true if the longitudes of a ring wind a full turn around a pole.
+///
+/// Steps are taken the short way around, so a ring merely crossing the
+/// dateline winds 0°.
+fn ring_circles_pole(ring: &LineString<f64>) -> bool {
+    let winding: f64 = ring
+        .0
+        .windows(2)
+        .map(|w| {
+            let delta = w[1].x - w[0].x;
+            delta - (delta / 360.0).round() * 360.0
+        })
+        .sum();
+    winding.abs() > WRAP_THRESHOLD
+}
+
+/// Closes an unwrapped ring that ends a full turn away from its start along
+/// the parallel of the pole it circles (the pole of its hemisphere).
+fn close_around_pole(coords: &mut Vec<Coord<f64>>) {
+    let (Some(&first), Some(&last)) = (coords.first(), coords.last()) else {
+        return;
+    };
+    if (last.x - first.x).abs() <= WRAP_THRESHOLD {
+        return;
+    }
+    let mean_lat = coords.iter().map(|c| c.y).sum::<f64>() / coords.len() as f64;
+    let pole = if mean_lat < 0.0 { -90.0 } else { 90.0 };
+    coords.extend([
+        Coord { x: last.x, y: pole },
+        Coord { x: first.x, y: pole },
+        first,
+    ]);
+}
+
+/// Returns true if any ring of the MultiPolygon crosses the antimeridian.
+pub fn multipolygon_wraps(mp: &MultiPolygon<f64>) -> bool {
+    mp.iter().any(|poly| {
+        ring_wraps(poly.exterior()) || poly.interiors().iter().any(ring_wraps)
+    })
+}
+
+/// Makes ring longitudes continuous, starting near `reference`.
+fn unwrap_ring(ring: &LineString<f64>, reference: f64) -> LineString<f64> {
+    let mut offset = 0.0;
+    let mut prev: Option<f64> = None;
+
+    if let Some(first) = ring.0.first() {
+        // Align the ring start with the reference longitude (used for holes)
+        offset = ((reference - first.x) / 360.0).round() * 360.0;
+    }
+
+    let mut coords: Vec<Coord<f64>> = ring
+        .0
+        .iter()
+        .map(|c| {
+            if let Some(p) = prev {
+                let delta = c.x - p;
+                if delta > WRAP_THRESHOLD {
+                    offset -= 360.0;
+                } else if delta < -WRAP_THRESHOLD {
+                    offset += 360.0;
+                }
+            }
+            prev = Some(c.x);
+            Coord {
+                x: c.x + offset,
+                y: c.y,
+            }
+        })
+        .collect();
+    close_around_pole(&mut coords);
+
+    LineString::new(coords)
+}
+
+/// Unwraps every ring so longitudes are continuous across the dateline.
+///
+/// Holes are aligned with their exterior ring.
+pub fn unwrap_multipolygon(mp: &MultiPolygon<f64>) -> MultiPolygon<f64> {
+    MultiPolygon::new(
+        mp.iter()
+            .map(|poly| {
+                let start = poly.exterior().0.first().map(|c| c.x).unwrap_or(0.0);
+                let exterior = unwrap_ring(poly.exterior(), start);
+                let interiors = poly
+                    .interiors()
+                    .iter()
+                    .map(|ring| unwrap_ring(ring, start))
+                    .collect();
+                Polygon::new(exterior, interiors)
+            })
+            .collect(),
+    )
+}
+
+/// Returns the longitude range (min, max) of a MultiPolygon's exterior rings.
+fn lon_range(mp: &MultiPolygon<f64>) -> Option<(f64, f64)> {
+    let mut range: Option<(f64, f64)> = None;
+    for coord in mp.iter().flat_map(|poly| poly.exterior().coords()) {
+        range = Some(match range {
+            None => (coord.x, coord.x),
+            Some((lo, hi)) => (lo.min(coord.x), hi.max(coord.x)),
+        });
+    }
+    range
+}
+
+/// Returns the copies of a geometry that must be rendered for the given bbox.
+///
+/// Geometries crossing the dateline are unwrapped first; then every ±360°
+/// shifted copy overlapping the bbox longitude range is returned. In the
+/// common case (no wrapping involved) the input is returned borrowed.
+pub fn wrap_for_bbox<'a>(mp: &'a MultiPolygon<f64>, bbox: &Bbox) -> Vec<Cow<'a, MultiPolygon<f64>>> {
+    let base: Cow<MultiPolygon<f64>> = if multipolygon_wraps(mp) {
+        Cow::Owned(unwrap_multipolygon(mp))
+    } else {
+        Cow::Borrowed(mp)
+    };
+
+    let Some((lo, hi)) = lon_range(&base) else {
+        return vec![base];
+    };
+
+    let overlaps = |shift: f64| lo + shift <= bbox.max_lon && hi + shift >= bbox.min_lon;
+
+    let mut copies = Vec::new();
+    for shift in [-360.0, 360.0] {
+        if overlaps(shift) {
+            copies.push(Cow::Owned(base.map_coords(|c| Coord {
+                x: c.x + shift,
+                y: c.y,
+            })));
+        }
+    }
+    if overlaps(0.0) || copies.is_empty() {
+        copies.insert(0, base);
+    }
+    copies
+}
+
+/// Computes the smallest longitude extent covering all intervals on the circle.
+///
+/// Each interval is `(min_lon, max_lon)` with `max_lon` possibly above 180 for
+/// unwrapped geometries. Returns `(min_lon, max_lon)` where `max_lon > 180`
+/// means the extent crosses the antimeridian. Intervals with a non-finite
+/// bound are ignored.
+pub fn minimal_lon_extent(intervals: &[(f64, f64)]) -> Option<(f64, f64)> {
+    // Normalize so every interval starts in [-180, 180)
+    let mut normalized: Vec<(f64, f64)> = intervals
+        .iter()
+        .filter(|(lo, hi)| lo.is_finite() && hi.is_finite())
+        .map(|&(lo, hi)| {
+            let shift = ((lo + 180.0) / 360.0).floor() * 360.0;
+            (lo - shift, hi - shift)
+        })
+        .collect();
+    if normalized.is_empty() {
+        return None;
+    }
+    normalized.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Merge overlapping intervals
+    let mut merged: Vec<(f64, f64)> = Vec::new();
+    for (lo, hi) in normalized {
+        match merged.last_mut() {
+            Some(last) if lo <= last.1 => last.1 = last.1.max(hi),
+            _ => merged.push((lo, hi)),
+        }
+    }
+
+    // Anything spanning the whole circle can't be shrunk
+    if merged.iter().any(|&(lo, hi)| hi - lo >= 360.0) {
+        return Some((-180.0, 180.0));
+    }
+
+    // Find the largest gap between consecutive intervals (wrapping around)
+    let mut best_gap = f64::MIN;
+    let mut best_start = 0;
+    for i in 0..merged.len() {
+        let end = merged[i].1;
+        let next_start = if i + 1 < merged.len() {
+            merged[i + 1].0
+        } else {
+            merged[0].0 + 360.0
+        };
+        let gap = next_start - end;
+        if gap > best_gap {
+            best_gap = gap;
+            best_start = (i + 1) % merged.len();
+        }
+    }
+
+    if best_gap <= 0.0 {
+        return Some((-180.0, 180.0));
+    }
+
+    // The extent starts right after the largest gap and covers every interval
+    let min_lon = merged[best_start].0;
+    let max_lon = merged
+        .iter()
+        .map(|&(lo, hi)| if lo < min_lon { hi + 360.0 } else { hi })
+        .fold(f64::MIN, f64::max);
+    Some((min_lon, max_lon))
+}
+
+/// Computes the bounding box of a set of geometries.
+///
+/// Longitudes are resolved on the circle, so data on both sides of the
+/// antimeridian yields a crossing bbox instead of a world-spanning one.
+pub fn geometries_bbox(geometries: &[MultiPolygon<f64>]) -> Option<Bbox> {
+    let mut lon_intervals = Vec::with_capacity(geometries.len());
+    let mut min_lat = f64::MAX;
+    let mut max_lat = f64::MIN;
+
+    for mp in geometries {
+        let mp = if multipolygon_wraps(mp) {
+            Cow::Owned(unwrap_multipolygon(mp))
+        } else {
+            Cow::Borrowed(mp)
+        };
+
+        let mut min_lon = f64::MAX;
+        let mut max_lon = f64::MIN;
+        for poly in mp.iter() {
+            let rings = std::iter::once(poly.exterior()).chain(poly.interiors());
+            for coord in rings.flat_map(|ring| ring.coords()) {
+                min_lon = min_lon.min(coord.x);
+                min_lat = min_lat.min(coord.y);
+                max_lon = max_lon.max(coord.x);
+                max_lat = max_lat.max(coord.y);
+            }
+        }
+        if min_lon != f64::MAX {
+            lon_intervals.push((min_lon, max_lon));
+        }
+    }
+
+    let (min_lon, max_lon) = minimal_lon_extent(&lon_intervals)?;
+    Some(Bbox::new(min_lon, min_lat, max_lon, max_lat))
+}
+
+/// Computes the smallest bounding box covering several boxes, resolving
+/// longitudes on the circle like [`geometries_bbox`].
+pub fn bboxes_union(bboxes: &[Bbox]) -> Option<Bbox> {
+    let lon_intervals: Vec<(f64, f64)> = bboxes.iter().map(|b| (b.min_lon, b.max_lon)).collect();
+    let (min_lon, max_lon) = minimal_lon_extent(&lon_intervals)?;
+    let min_lat = bboxes.iter().map(|b| b.min_lat).fold(f64::MAX, f64::min);
+    let max_lat = bboxes.iter().map(|b| b.max_lat).fold(f64::MIN, f64::max);
+    Some(Bbox::new(min_lon, min_lat, max_lon, max_lat))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::coord;
+
+    fn crossing_square() -> MultiPolygon<f64> {
+        // 179°E -> 179°W square, stored with raw longitudes
+        MultiPolygon::new(vec![Polygon::new(
+            LineString::from(vec![
+                coord! { x: 179.0, y: -1.0 },
+                coord! { x: -179.0, y: -1.0 },
+                coord! { x: -179.0, y: 1.0 },
+                coord! { x: 179.0, y: 1.0 },
+                coord! { x: 179.0, y: -1.0 },
+            ]),
+            vec![],
+        )])
+    }
+
+    #[test]
+    fn test_detects_wrapping() {
+        assert!(multipolygon_wraps(&crossing_square()));
+    }
+
+    #[test]
+    fn test_polar_ring_closed_through_pole() {
+        // Parallel 75°S walked eastward around the South Pole
+        let ring: Vec<_> = (0..=4).map(|i| coord! { x: -180.0 + 90.0 * i as f64, y: -75.0 }).collect();
+        let cap = MultiPolygon::new(vec![Polygon::new(LineString::from(ring), vec![])]);
+        assert!(multipolygon_wraps(&cap));
+        assert!(!multipolygon_wraps(&unwrap_multipolygon(&crossing_square())));
+
+        let bbox = geometries_bbox(&[cap]).unwrap();
+        assert_eq!((bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat), (-180.0, -90.0, 180.0, -75.0));
+    }
+
+    #[test]
+    fn test_unwrap_makes_longitudes_continuous() {
+        let unwrapped = unwrap_multipolygon(&crossing_square());
+        let (lo, hi) = lon_range(&unwrapped).unwrap();
+        assert!((lo - 179.0).abs() < 1e-10);
+        assert!((hi - 181.0).abs() < 1e-10);
+        assert!(!multipolygon_wraps(&unwrapped));
+    }
+
+    #[test]
+    fn test_wrap_for_crossing_bbox() {
+        let bbox = Bbox::new(170.0, -5.0, 190.0, 5.0);
+        let square = crossing_square();
+        let copies = wrap_for_bbox(&square, &bbox);
+        assert_eq!(copies.len(), 1);
+        let (lo, hi) = lon_range(&copies[0]).unwrap();
+        assert!(lo >= 170.0 && hi <= 190.0);
+    }
+
+    #[test]
+    fn test_wrap_for_world_bbox_splits() {
+        // A world bbox needs both halves: one near +180 and one near -180
+        let bbox = Bbox::new(-180.0, -90.0, 180.0, 90.0);
+        let square = crossing_square();
+        let copies = wrap_for_bbox(&square, &bbox);
+        assert_eq!(copies.len(), 2);
+    }
+
+    #[test]
+    fn test_wrap_shifts_west_geometry_into_crossing_bbox() {
+        let west = MultiPolygon::new(vec![Polygon::new(
+            LineString::from(vec![
+                coord! { x: -178.0, y: 0.0 },
+                coord! { x: -177.0, y: 0.0 },
+                coord! { x: -177.0, y: 1.0 },
+                coord! { x: -178.0, y: 0.0 },
+            ]),
+            vec![],
+        )]);
+        let bbox = Bbox::new(170.0, -5.0, 190.0, 5.0);
+        let copies = wrap_for_bbox(&west, &bbox);
+        assert_eq!(copies.len(), 1);
+        let (lo, _) = lon_range(&copies[0]).unwrap();
+        assert!((lo - 182.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_no_wrap_borrows() {
+        let mp = MultiPolygon::new(vec![Polygon::new(
+            LineString::from(vec![
+                coord! { x: 0.0, y: 0.0 },
+                coord! { x: 1.0, y: 0.0 },
+                coord! { x: 1.0, y: 1.0 },
+                coord! { x: 0.0, y: 0.0 },
+            ]),
+            vec![],
+        )]);
+        let copies = wrap_for_bbox(&mp, &Bbox::new(-1.0, -1.0, 2.0, 2.0));
+        assert_eq!(copies.len(), 1);
+        assert!(matches!(copies[0], Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_minimal_lon_extent_across_dateline() {
+        // Fiji-like data on both sides of the antimeridian
+        let extent = minimal_lon_extent(&[(177.0, 179.5), (-179.9, -178.0)]).unwrap();
+        assert!((extent.0 - 177.0).abs() < 1e-10);
+        assert!((extent.1 - 182.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_minimal_lon_extent_overlapping_wrap() {
+        let extent = minimal_lon_extent(&[(170.0, 185.0), (-178.0, -176.0)]).unwrap();
+        assert!((extent.0 - 170.0).abs() < 1e-10);
+        assert!((extent.1 - 185.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_geometries_bbox() {
+        let bbox = geometries_bbox(&[crossing_square()]).unwrap();
+        assert!((bbox.min_lon - 179.0).abs() < 1e-10);
+        assert!((bbox.max_lon - 181.0).abs() < 1e-10);
+        assert!((bbox.min_lat + 1.0).abs() < 1e-10);
+        assert!(geometries_bbox(&[]).is_none());
+    }
+
+    #[test]
+    fn test_bboxes_union() {
+        let corners = |b: Bbox| (b.min_lon, b.min_lat, b.max_lon, b.max_lat);
+        let union = bboxes_union(&[Bbox::new(-4.5, 48.0, -4.0, 48.5), Bbox::new(-4.0, 47.5, -3.5, 48.0)]).unwrap();
+        assert_eq!(corners(union), (-4.5, 47.5, -3.5, 48.5));
+        // Adjacent tiles on both sides of the antimeridian
+        let union = bboxes_union(&[Bbox::new(178.0, -18.0, 180.0, -16.0), Bbox::new(-180.0, -17.0, -179.0, -15.0)]).unwrap();
+        assert_eq!(corners(union), (178.0, -18.0, 181.0, -15.0));
+        assert!(bboxes_union(&[]).is_none());
+    }
+
+    #[test]
+    fn test_nan_vertices_are_ignored() {
+        let mut square = crossing_square();
+        square.0[0].exterior_mut(|ring| ring.0.insert(2, coord! { x: f64::NAN, y: 0.0 }));
+        let bbox = geometries_bbox(&[square, crossing_square()]).unwrap();
+        assert!((bbox.min_lon - 179.0).abs() < 1e-10 && (bbox.max_lon - 181.0).abs() < 1e-10);
+
+        let extent = minimal_lon_extent(&[(f64::NAN, 2.0), (1.0, 2.0), (0.5, f64::INFINITY)]).unwrap();
+        assert_eq!(extent, (1.0, 2.0));
+        assert!(minimal_lon_extent(&[(f64::NAN, f64::NAN)]).is_none());
+        let nan = Bbox::new(f64::NAN, 0.0, 1.0, 1.0);
+        assert!(bboxes_union(&[nan, Bbox::new(3.0, 0.0, 4.0, 1.0)]).is_some());
+    }
+
+    #[test]
+    fn test_minimal_lon_extent_regular() {
+        let extent = minimal_lon_extent(&[(1.0, 2.0), (4.0, 5.0)]).unwrap();
+        assert_eq!(extent, (1.0, 5.0));
+    }
+}
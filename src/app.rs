@@ -0,0 +1,2739 @@
+//! Commands of the `gpkg-to-png` executable (`cli` feature).
+//!
+//! Runs a parsed [`Command`]: drives progress reporting, output naming,
+//! batches of inputs, concurrent layers and interruptions, and hands reading
+//! and rendering to the rest of the crate. The binary only parses the
+//! arguments and turns errors into exit codes.
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+#[cfg(feature = "gpkg")]
+use std::time::Duration;
+use std::time::Instant;
+#[cfg(feature = "gpkg")]
+use tokio::task::JoinSet;
+
+use crate::attributes::{self, AttributeWriter};
+use crate::burn::{self, BurnGrid};
+use crate::cli::{self, Args, Command};
+use crate::error::{GpkgError, Result};
+use crate::geojson::{self, GeojsonReader, SequenceReader};
+#[cfg(feature = "gpkg")]
+use crate::gpkg::{Feature, FeatureFilter, GpkgReader, LayerInfo, TileLayerInfo};
+use crate::logger::{self, ColorMode, VerbosityLevel};
+use crate::manifest;
+use crate::math::{self, Bbox};
+use crate::progress::{ProgressEvent, ProgressMode, ProgressThrottle};
+#[cfg(feature = "reproject")]
+use crate::proj_data;
+use crate::render::{self, FeatureStyle, PngMetadata, RenderConfig, Renderer};
+use crate::style::{AttributeValue, FillScale, OpacityScale, StyleColumns};
+use crate::warning::{self, WarningCode};
+use crate::{
+    antimeridian, canvas, capabilities, compare, doctor, footprint, geotag, interrupt, output, preview, render_features,
+    reproject, resample, resume, stac, stats,
+};
+#[cfg(feature = "gpkg")]
+use crate::{animate, raster, serve, tiles};
+
+/// Runs a command parsed by [`Cli::load`](crate::cli::Cli::load).
+pub async fn run(command: Command) -> Result<()> {
+    // Point libproj at its data files before any projection is created
+    #[cfg(feature = "reproject")]
+    {
+        if let Command::Render(args) | Command::Info(args) = &command {
+            proj_data::configure(args.proj_data.as_deref(), args.proj_network, args.proj_only_best);
+        }
+        proj_data::init();
+    }
+
+    if !matches!(command, Command::Render(_) | Command::Info(_)) {
+        logger::Logger::init(VerbosityLevel::Normal, ColorMode::Auto);
+    }
+    match command {
+        Command::Render(args) => run_render(*args).await,
+        Command::Info(args) => run_info(*args).await,
+        Command::CompareImages(cmp) => compare_images(cmp),
+        Command::Doctor => run_doctor().await,
+        Command::Stats(stats) => run_stats(stats).await,
+        Command::Capabilities(args) => {
+            run_capabilities(args);
+            Ok(())
+        }
+        #[cfg(feature = "gpkg")]
+        Command::ListLayers(list) => run_list_layers(list).await,
+        #[cfg(feature = "gpkg")]
+        Command::Tiles(tiles) => tiles::run(tiles).await,
+        #[cfg(feature = "gpkg")]
+        Command::Serve(serve) => serve::run(serve).await,
+        #[cfg(feature = "gpkg")]
+        Command::Animate(animate) => animate::run(animate).await,
+        #[cfg(not(feature = "gpkg"))]
+        Command::ListLayers(_) | Command::Tiles(_) | Command::Serve(_) | Command::Animate(_) => Err(GpkgError::FeatureDisabled(
+            "GeoPackage".to_string(),
+            "gpkg".to_string(),
+        )),
+    }
+}
+
+/// Validates the arguments of a render (or of its `info` dry run), sets up
+/// logging and checks the inputs.
+///
+/// A canvas given with `--into` provides the bbox and resolution unless they
+/// are given explicitly.
+fn prepare_render(args: Args) -> Result<cli::Config> {
+    let mut config = args.validate()?;
+
+    // Initialize logger with verbosity level; JSON progress events own stdout
+    if config.progress == ProgressMode::Json {
+        logger::Logger::init_on_stderr(config.verbosity, config.color);
+    } else {
+        logger::Logger::init(config.verbosity, config.color);
+    }
+    warning::init(&config.deny);
+    if let Some(metres) = config.proj_accuracy {
+        reproject::require_accuracy(metres);
+    }
+
+    // Check input files exist before processing any of them
+    if let Some(missing) = config.inputs.iter().find(|input| !input.exists()) {
+        return Err(GpkgError::FileNotFound(missing.display().to_string()));
+    }
+
+    // A canvas provides the grid unless it is given explicitly (then checked)
+    if let Some(path) = &config.into {
+        let grid = canvas::read_grid(path)?;
+        config.bbox.get_or_insert(grid.bbox);
+        if config.resolution.is_none() && config.scale.is_none() && config.width.is_none() && config.height.is_none() {
+            config.resolution = Some(grid.resolution);
+        }
+        // Keep the canvas locatable once it is rewritten
+        if canvas::world_file_path(path).is_none() {
+            config.geotag = true;
+        }
+    }
+    Ok(config)
+}
+
+/// Orchestrates the rendering pipeline.
+///
+/// 1. Validates the arguments into a configuration.
+/// 2. Dispatches to format-specific processor (GPKG or GeoJSON).
+async fn run_render(args: Args) -> Result<()> {
+    let config = prepare_render(args)?;
+    interrupt::install();
+    resume::start(&config.output_dir, config.resume)?;
+
+    let start = Instant::now();
+    let result = render_inputs(&config).await;
+    if let Some(path) = &config.manifest {
+        manifest::write(path, &command_line(), result.is_ok(), start.elapsed())?;
+        logger::info(&format!("Manifest written to {}", path.display()));
+    }
+    result?;
+    resume::finish()?;
+    match &config.verify {
+        Some(verify) => verify_outputs(&config, verify),
+        None => Ok(()),
+    }
+}
+
+/// Compares every file of the run with its approved version, writing a diff
+/// next to the diverging ones, and fails when any diverges or has none.
+fn verify_outputs(config: &cli::Config, verify: &cli::Verify) -> Result<()> {
+    // --burn GeoTIFFs hold values rather than pixels to compare
+    let mut paths: Vec<PathBuf> = manifest::entries()
+        .into_iter()
+        .map(|entry| entry.path)
+        .filter(|path| path.extension().is_some_and(|e| e == "png"))
+        .collect();
+    paths.sort();
+    let (mut diverged, mut missing) = (0, 0);
+    for path in &paths {
+        let golden = compare::golden_path(path, &config.output_dir, &verify.dir);
+        if !golden.exists() {
+            logger::warn(&format!("{}: no approved image at {}", path.display(), golden.display()));
+            missing += 1;
+            continue;
+        }
+        let approved = image::open(&golden)?.to_rgba8();
+        let rendered = image::open(path)?.to_rgba8();
+        match compare::compare_rgba(&approved, &rendered, verify.tolerance) {
+            Ok(diff) if diff.changed_percent() <= verify.threshold => {
+                logger::debug(&format!("{}: matches {}", path.display(), golden.display()));
+            }
+            Ok(diff) => {
+                let diff_path = compare::diff_path(path);
+                diff.diff_image.save(&diff_path)?;
+                logger::warn(&format!(
+                    "{}: {} of {} pixels changed ({:.4}%, max delta {}), diff in {}",
+                    path.display(),
+                    diff.changed_pixels,
+                    diff.total_pixels,
+                    diff.changed_percent(),
+                    diff.max_delta.iter().max().unwrap_or(&0),
+                    diff_path.display()
+                ));
+                diverged += 1;
+            }
+            Err(GpkgError::ImageSizeMismatch { left, right }) => {
+                logger::warn(&format!(
+                    "{}: {}x{} pixels, the approved image is {}x{}",
+                    path.display(),
+                    right.0,
+                    right.1,
+                    left.0,
+                    left.1
+                ));
+                diverged += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    logger::info(&format!(
+        "Verified {} output(s) against {}: {} match, {} diverge, {} without an approved image",
+        paths.len(),
+        verify.dir.display(),
+        paths.len() - diverged - missing,
+        diverged,
+        missing
+    ));
+    if diverged + missing > 0 {
+        return Err(GpkgError::VerificationFailed { diverged, missing });
+    }
+    Ok(())
+}
+
+/// Renders every input of the run.
+async fn render_inputs(config: &cli::Config) -> Result<()> {
+    // Create output directory if needed
+    if !config.output_dir.exists() {
+        std::fs::create_dir_all(&config.output_dir)?;
+    }
+
+    // Layer bars of every input share one display, under a bar counting inputs
+    let multi = MultiProgress::new();
+    let total = config.inputs.len();
+    let batch_pb = (total > 1 && config.verbosity == VerbosityLevel::Normal && config.progress == ProgressMode::Bar)
+        .then(|| {
+            let pb = multi.add(ProgressBar::new(total as u64));
+            pb.set_style(
+                ProgressStyle::default_bar()
+                    .template("{spinner:.green} Inputs [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+                    .unwrap()
+                    .progress_chars("#>-"),
+            );
+            pb
+        });
+
+    // Output files of every input, so no render overwrites another
+    let mut claims = output::OutputClaims::default();
+    for (i, input) in config.inputs.iter().enumerate() {
+        let input_config = config.for_input(input);
+        if total > 1 {
+            logger::info(&format!("Input {}/{}: {}", i + 1, total, input.display()));
+            ProgressEvent::InputStarted {
+                input,
+                index: i + 1,
+                total,
+            }
+            .emit(config.progress);
+        }
+        if let Some(ref pb) = batch_pb {
+            pb.set_message(input.display().to_string());
+        }
+
+        match input_config.format {
+            #[cfg(feature = "gpkg")]
+            cli::Format::Gpkg => process_gpkg(input_config, &multi, &mut claims).await?,
+            #[cfg(not(feature = "gpkg"))]
+            cli::Format::Gpkg => {
+                return Err(GpkgError::FeatureDisabled(
+                    "GeoPackage".to_string(),
+                    "gpkg".to_string(),
+                ))
+            }
+            cli::Format::Geojson => process_geojson(input_config, &multi, &mut claims).await?,
+        }
+
+        if let Some(ref pb) = batch_pb {
+            pb.inc(1);
+        }
+    }
+    if let Some(pb) = batch_pb {
+        pb.finish_with_message("All inputs processed");
+    }
+
+    Ok(())
+}
+
+/// Records a produced file in the manifest and writes its `--stac` Item and
+/// `--footprint`.
+fn record_output(config: &cli::Config, entry: manifest::Entry) -> Result<()> {
+    if config.stac {
+        let role = if config.burn.is_some() { "data" } else { "visual" };
+        let path = stac::write(&entry, config.started, role)?;
+        logger::debug(&format!("STAC Item written to {}", path.display()));
+    }
+    if config.footprint {
+        let path = footprint::write(&entry, config.rotation, config.started)?;
+        logger::debug(&format!("Footprint written to {}", path.display()));
+    }
+    manifest::record(entry);
+    Ok(())
+}
+
+/// Records a file found up to date with `--content-addressed` in the manifest.
+fn record_up_to_date(config: &cli::Config, path: &Path, layer: &str, bbox: &Bbox, resolution: f64) -> Result<()> {
+    let (width, height) = image::image_dimensions(path)?;
+    record_output(
+        config,
+        manifest::Entry {
+            path: path.to_path_buf(),
+            input: config.input.clone(),
+            layer: layer.to_string(),
+            bbox: *bbox,
+            width,
+            height,
+            resolution,
+            features: None,
+            timings: None,
+        },
+    )
+}
+
+/// Builds the metadata embedded in an output PNG: the XMP geotag and, unless
+/// `--no-metadata`, text chunks describing how `layer` was rendered.
+fn png_metadata(
+    config: &cli::Config,
+    bbox: &Bbox,
+    resolution: f64,
+    layer: &str,
+    source_crs: Option<&str>,
+) -> PngMetadata {
+    let mut text = Vec::new();
+    if config.metadata {
+        let sources: Vec<String> = std::iter::once(&config.input)
+            .chain(&config.merge)
+            .map(|path| path.display().to_string())
+            .collect();
+        text.push(("Software", format!("gpkg-to-png {}", env!("CARGO_PKG_VERSION"))));
+        text.push(("Source", sources.join(", ")));
+        text.push(("Layer", layer.to_string()));
+        text.push((
+            "Bbox",
+            format!("{},{},{},{}", bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat),
+        ));
+        text.push(("Resolution", resolution.to_string()));
+        if config.rotation != 0.0 {
+            text.push(("Rotation", config.rotation.to_string()));
+        }
+        text.push(("CRS", geojson::WGS84.to_string()));
+        if let Some(crs) = source_crs {
+            text.push(("Source CRS", crs.to_string()));
+        }
+        text.push(("Command", command_line()));
+    }
+    PngMetadata {
+        xmp: config.geotag.then(|| geotag::xmp_packet(bbox)),
+        text: text.into_iter().map(|(keyword, value)| (keyword.to_string(), value)).collect(),
+    }
+}
+
+/// The command line of this run, with arguments quoted for a POSIX shell
+/// where needed so it can be pasted back.
+fn command_line() -> String {
+    let quote = |arg: String| {
+        let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c);
+        if !arg.is_empty() && arg.chars().all(plain) {
+            arg
+        } else {
+            format!("'{}'", arg.replace('\'', "'\\''"))
+        }
+    };
+    std::env::args_os()
+        .map(|arg| quote(arg.to_string_lossy().into_owned()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Source CRS of the GeoJSON inputs: the `--source-crs` override, else the
+/// one declared in each file, else WGS 84.
+fn geojson_source_crs(config: &cli::Config, source: &GeojsonSource) -> String {
+    match source {
+        GeojsonSource::Parsed(reader) => reader.source_crs().join(", "),
+        GeojsonSource::Sequence(..) => config.geojson_inputs()[0]
+            .source_crs
+            .clone()
+            .unwrap_or_else(|| geojson::WGS84.to_string()),
+    }
+}
+
+/// File stem replacing the name of a render in its output path: its
+/// `content_id`, else the expanded `--name-template`.
+fn output_stem(
+    config: &cli::Config,
+    name: &str,
+    bbox: &Bbox,
+    resolution: f64,
+    content_id: Option<String>,
+) -> Result<Option<String>> {
+    let Some(template) = config.name_template.as_ref().filter(|_| content_id.is_none()) else {
+        return Ok(content_id);
+    };
+    let fields = output::NameFields {
+        layer: name,
+        input: &config.input,
+        bbox,
+        resolution,
+        scale: config.scale.unwrap_or_else(|| math::resolution_to_scale(resolution, bbox)),
+        timestamp: config.started,
+    };
+    output::expand_name_template(template, &fields).map(Some)
+}
+
+/// Returns `path` with the extension of the `--burn-type` with `--burn`.
+fn burn_path(config: &cli::Config, path: PathBuf) -> PathBuf {
+    match config.burn {
+        Some(_) => path.with_extension(config.burn_type.extension()),
+        None => path,
+    }
+}
+
+/// Saves a `--burn` grid, warning about the values clamped to the `--burn-type` range.
+fn save_burn(
+    grid: &BurnGrid,
+    path: &Path,
+    config: &cli::Config,
+    bbox: &Bbox,
+    resolution: f64,
+    metadata: &PngMetadata,
+    name: &str,
+) -> Result<()> {
+    let clamped = grid.save(path, bbox, resolution, config.burn_type, metadata)?;
+    if clamped > 0 {
+        warning::emit(
+            WarningCode::BurnValueClamped,
+            &format!(
+                "{}: {} pixel(s) clamped to the {} range",
+                name,
+                clamped,
+                format!("{:?}", config.burn_type).to_lowercase()
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+/// Checks that the render grid matches the `--into` canvas.
+fn check_canvas(config: &cli::Config, bbox: &Bbox, resolution: f64) -> Result<()> {
+    match &config.into {
+        Some(path) => canvas::read_grid(path)?.check(path, bbox, resolution),
+        None => Ok(()),
+    }
+}
+
+/// Checks that `--rename` sources and styled layers are polygon layers, and
+/// that no alias takes the output name of another layer.
+#[cfg(feature = "gpkg")]
+fn check_layer_names(config: &cli::Config, layers: &[LayerInfo]) -> Result<()> {
+    let named = config.rename.iter().map(|(source, _)| source);
+    for name in named.chain(config.layer_styles.iter().map(|(name, _)| name)) {
+        if !layers.iter().any(|l| l.name == *name) {
+            let available = layers.iter().map(|l| l.name.as_str()).collect::<Vec<_>>();
+            return Err(GpkgError::LayerNotFound(name.clone(), available.join(", ")));
+        }
+    }
+    for (_, alias) in &config.rename {
+        if layers.iter().any(|l| l.name == *alias && config.output_name_for(&l.name) == alias) {
+            return Err(GpkgError::InvalidFormatOption(format!(
+                "--rename alias {} collides with the layer of the same name",
+                alias
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Describes every setting that affects the rendered pixels or metadata.
+///
+/// Used to derive content-addressed file names.
+fn content_settings(
+    config: &cli::Config,
+    layer: &str,
+    bbox: &Bbox,
+    resolution: f64,
+) -> Vec<(&'static str, String)> {
+    vec![
+        ("version", env!("CARGO_PKG_VERSION").to_string()),
+        (
+            "bbox",
+            format!("{},{},{},{}", bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat),
+        ),
+        ("resolution", resolution.to_string()),
+        ("rotation", config.rotation.to_string()),
+        ("fill", config.fill.map_or("none".to_string(), hex::encode)),
+        ("stroke", config.stroke.map_or("none".to_string(), hex::encode)),
+        ("stroke_width", config.stroke_width.to_string()),
+        ("vertex_color", format!("{:?}", config.vertex_color)),
+        ("min_feature_px", format!("{:?}", config.min_feature_px)),
+        ("glow", format!("{:?}", config.glow)),
+        ("resample", format!("{:?}", config.resample)),
+        ("supersample", format!("{:?}", config.supersample)),
+        ("overviews", config.overviews.to_string()),
+        ("autocrop", format!("{:?}", config.autocrop)),
+        ("opacity_column", format!("{:?}", config.opacity_column)),
+        ("stroke_color_column", format!("{:?}", config.stroke_color_column)),
+        ("fill_column", format!("{:?}", config.fill_column)),
+        ("ramp", format!("{:?}", config.ramp)),
+        ("classify", format!("{:?}", config.classify)),
+        ("classes", config.classes.to_string()),
+        ("color_per_feature", format!("{:?}", config.color_per_feature)),
+        ("where", config.where_clauses_for(layer).join(" AND ")),
+        ("limit", format!("{:?}", config.limit)),
+        ("offset", format!("{:?}", config.offset)),
+        ("fids", format!("{:?}", config.fids)),
+        ("raster", format!("{:?}", config.raster)),
+        ("curve_tolerance", config.curve_tolerance.to_string()),
+        ("source_crs", format!("{:?}", config.source_crs)),
+        ("geotag", config.geotag.to_string()),
+        ("metadata", config.metadata.to_string()),
+        ("png_options", format!("{:?}", config.png_options)),
+        ("burn", format!("{:?}", config.burn)),
+        ("burn_rule", format!("{:?}", config.burn_rule)),
+        ("burn_type", format!("{:?}", config.burn_type)),
+    ]
+}
+
+/// Identifies a render in the `--resume` journal: the state of its inputs,
+/// its settings and its output file.
+fn resume_key(config: &cli::Config, layer: &str, bbox: &Bbox, resolution: f64, output_path: &Path) -> Result<String> {
+    let mut settings = content_settings(config, layer, bbox, resolution);
+    for path in &config.merge {
+        settings.push(("merge", resume::input_stamp(path)?));
+    }
+    settings.push(("output", output_path.display().to_string()));
+    Ok(output::content_id(&resume::input_stamp(&config.input)?, layer, &settings))
+}
+
+/// Returns the polygon layers to render, or None (after a warning) when the
+/// GeoPackage has none.
+#[cfg(feature = "gpkg")]
+async fn layers_to_render(config: &cli::Config, reader: &GpkgReader) -> Result<Option<Vec<LayerInfo>>> {
+    let all_layers = reader.list_polygon_layers().await?;
+
+    if all_layers.is_empty() {
+        warning::emit(
+            WarningCode::NoPolygonLayers,
+            "No polygon layers found in the GeoPackage",
+        )?;
+        return Ok(None);
+    }
+
+    check_layer_names(config, &all_layers)?;
+
+    // Every --layer or --exclude-layer name or pattern must match at least one layer
+    for pattern in config.layer.iter().chain(&config.exclude_layer) {
+        if !all_layers.iter().any(|l| cli::wildcard_match(pattern, &l.name)) {
+            let available = all_layers
+                .iter()
+                .map(|l| l.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(GpkgError::LayerNotFound(pattern.clone(), available));
+        }
+    }
+    let layers_to_process: Vec<LayerInfo> = all_layers.into_iter().filter(|l| config.selects_layer(&l.name)).collect();
+    if layers_to_process.is_empty() {
+        return Err(GpkgError::InvalidFormatOption(
+            "--exclude-layer leaves no layer to render".to_string(),
+        ));
+    }
+
+    // Every layer would overwrite the same canvas
+    if let (Some(path), true) = (&config.into, layers_to_process.len() > 1) {
+        return Err(GpkgError::Canvas(
+            path.display().to_string(),
+            "--layer must select a single layer when the GeoPackage has several polygon layers".to_string(),
+        ));
+    }
+
+    Ok(Some(layers_to_process))
+}
+
+/// A GeoPackage of the render, with the polygon layers read from it.
+#[cfg(feature = "gpkg")]
+struct GpkgInput {
+    reader: GpkgReader,
+    layers: Vec<LayerInfo>,
+}
+
+/// Returns the input with its selected `layers`, followed by the `--merge`
+/// GeoPackages with their polygon layers of the same names.
+#[cfg(feature = "gpkg")]
+async fn gpkg_inputs(config: &cli::Config, reader: &GpkgReader, layers: &[LayerInfo]) -> Result<Vec<GpkgInput>> {
+    let mut inputs = vec![GpkgInput {
+        reader: reader.clone(),
+        layers: layers.to_vec(),
+    }];
+    for path in &config.merge {
+        let mut merged = GpkgReader::open(path, &config.sqlite).await?;
+        merged.set_curve_tolerance(config.curve_tolerance);
+        merged.set_reprojection_cache(config.reprojection_cache.clone());
+        let matching: Vec<LayerInfo> = merged
+            .list_polygon_layers()
+            .await?
+            .into_iter()
+            .filter(|l| layers.iter().any(|selected| selected.name == l.name))
+            .collect();
+        logger::info(&format!("Merged {}: {} matching layer(s)", path.display(), matching.len()));
+        let names: Vec<&str> = matching.iter().map(|l| l.name.as_str()).collect();
+        check_extensions(&merged, &names).await?;
+        inputs.push(GpkgInput {
+            reader: merged,
+            layers: matching,
+        });
+    }
+    Ok(inputs)
+}
+
+/// A layer left to render, with its settings and output file.
+#[cfg(feature = "gpkg")]
+struct LayerJob {
+    layer: LayerInfo,
+    config: Arc<cli::Config>,
+    output_path: PathBuf,
+    resume_key: String,
+}
+
+/// Bytes of an XY vertex in a geometry blob (two doubles).
+#[cfg(feature = "gpkg")]
+const VERTEX_BYTES: f64 = 16.0;
+
+/// Estimated cost of rendering a layer: its features times their mean
+/// number of vertices, divided by the area of its extent.
+///
+/// Vertices are estimated from the size of the geometry blobs.
+#[cfg(feature = "gpkg")]
+fn layer_cost(features: u64, geometry_bytes: u64, area: f64) -> f64 {
+    if features == 0 {
+        return 0.0;
+    }
+    let vertices = geometry_bytes as f64 / VERTEX_BYTES / features as f64;
+    features as f64 * vertices / area
+}
+
+/// Sorts `jobs` by decreasing [`layer_cost`], so that the longest renders
+/// start first instead of ending the run alone. Layers of equal cost keep
+/// their order.
+///
+/// The area of a layer is its WGS84 extent (the render bbox without one),
+/// at least one pixel.
+#[cfg(feature = "gpkg")]
+async fn order_by_cost(jobs: &mut Vec<LayerJob>, inputs: &[GpkgInput], bbox: &Bbox, resolution: f64) -> Result<()> {
+    if jobs.len() < 2 {
+        return Ok(());
+    }
+    let mut costed = Vec::with_capacity(jobs.len());
+    for job in jobs.drain(..) {
+        let filter = FeatureFilter {
+            bbox: job.config.bbox.map(|_| bbox.rotated_envelope(job.config.rotation)),
+            where_clauses: job.config.where_clauses_for(&job.layer.name),
+            limit: job.config.limit,
+            offset: job.config.offset,
+            fids: job.config.fids.clone(),
+        };
+        let (mut features, mut bytes) = (0, 0);
+        let sources = layer_sources(inputs, &job.layer.name);
+        for (reader, layer) in &sources {
+            features += reader.count_features(layer, &filter).await?;
+            bytes += reader.geometry_bytes(layer, &filter).await?;
+        }
+        let extent = match sources.first() {
+            Some((reader, layer)) => reader.get_layers_bbox_wgs84(std::slice::from_ref(layer)).await?,
+            None => None,
+        };
+        let extent = extent.unwrap_or(*bbox);
+        let area = (extent.width() * extent.height()).max(resolution * resolution);
+        let cost = layer_cost(features, bytes, area);
+        logger::debug(&format!(
+            "Layer {}: {} feature(s), {} geometry bytes, cost {:.3e}",
+            job.layer.name, features, bytes, cost
+        ));
+        costed.push((cost, job));
+    }
+    costed.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    jobs.extend(costed.into_iter().map(|(_, job)| job));
+    Ok(())
+}
+
+/// Returns layer `name` in every input that has it, the input's first.
+#[cfg(feature = "gpkg")]
+fn layer_sources(inputs: &[GpkgInput], name: &str) -> Vec<(GpkgReader, LayerInfo)> {
+    inputs
+        .iter()
+        .flat_map(|input| {
+            input
+                .layers
+                .iter()
+                .filter(|l| l.name == name)
+                .map(|l| (input.reader.clone(), l.clone()))
+        })
+        .collect()
+}
+
+/// Returns the bbox of a GeoPackage render: given, fitted to the `--fid`
+/// features, or the extent of the layers, grown by `--padding` and `--aspect`.
+#[cfg(feature = "gpkg")]
+async fn gpkg_bbox(config: &cli::Config, inputs: &[GpkgInput]) -> Result<Bbox> {
+    // Determine bounding box (use provided or auto-detect from layers)
+    let bbox = if let Some(bbox) = config.bbox {
+        bbox
+    } else if let Some(fids) = &config.fids {
+        // Fit the selected features rather than the whole layer extent
+        logger::info("Auto-detecting bounding box from selected features...");
+        let filter = FeatureFilter {
+            fids: Some(fids.clone()),
+            ..Default::default()
+        };
+        let mut selected = Vec::new();
+        for input in inputs {
+            for layer in &input.layers {
+                selected.extend(input.reader.read_geometries_wgs84(layer, &filter).await?);
+            }
+        }
+        let bbox = antimeridian::geometries_bbox(&selected).ok_or_else(|| {
+            GpkgError::InvalidBbox("No features match the given --fid values".to_string())
+        })?;
+
+        warning::emit(
+            WarningCode::BboxAutoDetected,
+            &format!(
+                "Auto-detected bbox: {},{},{},{}",
+                bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat
+            ),
+        )?;
+        bbox
+    } else {
+        logger::info("Auto-detecting bounding box...");
+        let mut extents = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            extents.extend(input.reader.get_layers_bbox_wgs84(&input.layers).await?);
+        }
+        let bbox = antimeridian::bboxes_union(&extents).ok_or_else(|| {
+            GpkgError::InvalidBbox("Could not determine bounding box from layers".to_string())
+        })?;
+
+        warning::emit(
+            WarningCode::BboxAutoDetected,
+            &format!(
+                "Auto-detected bbox: {},{},{},{}",
+                bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat
+            ),
+        )?;
+        bbox
+    };
+    Ok(frame_bbox(config, bbox))
+}
+
+/// Returns the `--raster` tile layer of the GeoPackage, if one was requested.
+#[cfg(feature = "gpkg")]
+async fn raster_layer(config: &cli::Config, reader: &GpkgReader) -> Result<Option<TileLayerInfo>> {
+    let Some(name) = &config.raster else {
+        return Ok(None);
+    };
+    let layers = reader.list_tile_layers().await?;
+    match layers.iter().find(|l| l.name == *name) {
+        Some(layer) => Ok(Some(layer.clone())),
+        None => {
+            // Gridded coverages (elevation data) are tile pyramids of another data type
+            let extensions = reader.list_extensions().await?;
+            if let Some(extension) = extensions.iter().find(|e| e.applies_to(name) && e.is_unsupported()) {
+                return Err(GpkgError::UnsupportedExtension(name.clone(), extension.extension_name.clone()));
+            }
+            let available = layers.iter().map(|l| l.name.as_str()).collect::<Vec<_>>().join(", ");
+            Err(GpkgError::LayerNotFound(name.clone(), available))
+        }
+    }
+}
+
+/// Warns about the read-write extensions of `tables` (or of the whole
+/// GeoPackage) that the reader does not support, as the output may be
+/// incomplete.
+#[cfg(feature = "gpkg")]
+async fn check_extensions(reader: &GpkgReader, tables: &[&str]) -> Result<()> {
+    for extension in reader.list_extensions().await? {
+        if !extension.is_unsupported() {
+            continue;
+        }
+        let target = match &extension.table_name {
+            None => "GeoPackage".to_string(),
+            Some(table) if tables.iter().any(|t| extension.applies_to(t)) => format!("Layer {}", table),
+            Some(_) => continue,
+        };
+        warning::emit(
+            WarningCode::UnsupportedExtension,
+            &format!(
+                "{}: extension {} is not supported, output may be incomplete",
+                target, extension.extension_name
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+/// Resamples the tiles of `layer` onto the `width` x `height` pixel grid of
+/// `render_config`, reading the coarsest zoom level at least as detailed.
+#[cfg(feature = "gpkg")]
+async fn raster_base(
+    reader: &GpkgReader,
+    layer: &TileLayerInfo,
+    render_config: &RenderConfig,
+    width: u32,
+    height: u32,
+) -> Result<image::RgbaImage> {
+    let srs_def = match layer.srs_id {
+        4326 => None,
+        srs_id => Some(reader.get_srs_definition(srs_id).await?),
+    };
+    // The frame and the area around it shown by a rotation, in the layer SRS
+    let to_layer = |bbox: &Bbox| match &srs_def {
+        None => Some((bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat)),
+        Some(srs_def) => reproject::reproject_bbox_from_wgs84(bbox, srs_def),
+    };
+    let unprojectable = || GpkgError::Reprojection(format!("Raster {}: bbox outside the layer SRS", layer.name));
+    let (min_x, min_y, max_x, max_y) = to_layer(&render_config.bbox).ok_or_else(unprojectable)?;
+    let pixel_size = ((max_x - min_x) / width as f64).min((max_y - min_y) / height as f64);
+    let envelope = render_config.bbox.rotated_envelope(render_config.rotation);
+    let mut area = to_layer(&envelope).ok_or_else(unprojectable)?;
+    if envelope.crosses_antimeridian() {
+        // Both sides of the antimeridian are needed
+        area = (layer.extent.0, area.1, layer.extent.2, area.3);
+    }
+
+    let Some(matrix) = layer.matrix_for(pixel_size) else {
+        logger::debug(&format!("Raster {}: no zoom levels", layer.name));
+        return Ok(image::RgbaImage::new(width, height));
+    };
+    let Some((columns, rows)) = layer.tile_range(matrix, area) else {
+        logger::debug(&format!("Raster {}: no tiles in the bbox", layer.name));
+        return Ok(image::RgbaImage::new(width, height));
+    };
+    let mosaic = reader.read_tile_mosaic(layer, matrix, columns, rows).await?;
+    logger::debug(&format!(
+        "Raster {}: zoom level {}, {} tile(s)",
+        layer.name,
+        matrix.zoom_level,
+        mosaic.len()
+    ));
+
+    let Some(srs_def) = srs_def else {
+        return Ok(raster::composite(&mosaic, render_config, width, height, || {
+            |lon, lat| Some((lon, lat))
+        }));
+    };
+    let transformer = reproject::Transformer::new("EPSG:4326", &srs_def)?;
+    if transformer.backend() == reproject::Backend::Pure {
+        warning::emit(
+            WarningCode::ReprojectionApproximate,
+            &format!(
+                "Raster {}: reprojected with proj4rs (no datum grids, metre-level error possible)",
+                layer.name
+            ),
+        )?;
+    }
+    reproject::check_operation(&transformer, render_config.bbox.center(), &format!("Raster {}", layer.name))?;
+    // Transformers are not Sync: each worker creates its own
+    Ok(raster::composite(&mosaic, render_config, width, height, || {
+        let transformer = reproject::Transformer::new("EPSG:4326", &srs_def).ok();
+        move |lon, lat| transformer.as_ref()?.convert((lon, lat))
+    }))
+}
+
+/// Saves the `--raster` tile layer alone, with `--raster-only`.
+#[cfg(feature = "gpkg")]
+async fn process_raster(
+    config: &cli::Config,
+    reader: &GpkgReader,
+    layer: &TileLayerInfo,
+    claims: &mut output::OutputClaims,
+) -> Result<()> {
+    let start = Instant::now();
+    let bbox = match config.bbox {
+        Some(bbox) => bbox,
+        None => {
+            logger::info("Auto-detecting bounding box...");
+            let (min_x, min_y, max_x, max_y) = layer.extent;
+            let wgs84 = match layer.srs_id {
+                4326 => Some(layer.extent),
+                srs_id => {
+                    let srs_def = reader.get_srs_definition(srs_id).await?;
+                    reproject::reproject_bbox_to_wgs84(min_x, min_y, max_x, max_y, &srs_def)
+                }
+            };
+            let (min_lon, min_lat, max_lon, max_lat) = wgs84.ok_or_else(|| {
+                GpkgError::InvalidBbox(format!("Could not determine bounding box from raster {}", layer.name))
+            })?;
+            let bbox = Bbox::new(min_lon, min_lat, max_lon, max_lat);
+            warning::emit(
+                WarningCode::BboxAutoDetected,
+                &format!(
+                    "Auto-detected bbox: {},{},{},{}",
+                    bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat
+                ),
+            )?;
+            bbox
+        }
+    };
+    let bbox = frame_bbox(config, bbox);
+    let resolution = render_resolution(config, &bbox)?;
+
+    let content_id = if config.content_addressed {
+        let digest = output::file_digest(&config.input)?;
+        let settings = content_settings(config, &layer.name, &bbox, resolution);
+        Some(output::content_id(&digest, &layer.name, &settings))
+    } else {
+        None
+    };
+    let name = output::sanitize_name(config.output_name_for(&layer.name));
+    let stem = output_stem(config, &name, &bbox, resolution, content_id.clone())?;
+    let output_path = output::output_path(
+        &config.output_dir,
+        config.output_layout,
+        &config.input,
+        &name,
+        stem.as_deref(),
+    );
+    if content_id.is_some() && output_path.exists() {
+        logger::info(&format!("Raster {}: up to date", layer.name));
+        logger::output(&output_path.display().to_string());
+        return record_up_to_date(config, &output_path, &layer.name, &bbox, resolution);
+    }
+    let output_path = match content_id {
+        Some(_) => output_path,
+        None => claims.claim(output_path, &layer.name, config.on_collision)?,
+    };
+
+    let render_config = RenderConfig {
+        bbox,
+        resolution,
+        fill: config.fill,
+        stroke: config.stroke,
+        stroke_width: config.stroke_width,
+        vertex_color: config.vertex_color,
+        min_feature_px: config.min_feature_px,
+        max_dimension: config.max_dimension,
+        rotation: config.rotation,
+        glow: config.glow,
+    };
+    let supersample = supersample_factor(config, &render_config, &layer.name)?;
+    let fine_config = render_config.supersampled(supersample);
+    let mut renderer = Renderer::new(fine_config.clone())?;
+    renderer.set_png_options(config.png_options);
+    let (width, height) = renderer.dimensions();
+    renderer.set_base(&raster_base(reader, layer, &fine_config, width, height).await?)?;
+
+    let mut renderer = reduce_supersampled(renderer, &render_config, config, supersample)?;
+    let bbox = &autocrop(&mut renderer, &layer.name, config, &bbox);
+    output::ensure_parent_dir(&output_path)?;
+    let source_crs = reader.get_srs_name(layer.srs_id).await?;
+    let metadata = png_metadata(config, bbox, resolution, &layer.name, source_crs.as_deref());
+    renderer.save(&output_path, &metadata)?;
+    let overviews = save_overviews(&renderer, &render_config, bbox, &output_path, config, &layer.name, source_crs.as_deref())?;
+
+    logger::output(&output_path.display().to_string());
+    for path in &overviews {
+        logger::output(&path.display().to_string());
+    }
+    ProgressEvent::FileSaved {
+        layer: &layer.name,
+        path: &output_path,
+    }
+    .emit(config.progress);
+    let (width, height) = renderer.dimensions();
+    record_output(
+        config,
+        manifest::Entry {
+            path: output_path,
+            input: config.input.clone(),
+            layer: layer.name.clone(),
+            bbox: *bbox,
+            width,
+            height,
+            resolution,
+            features: None,
+            timings: None,
+        },
+    )?;
+    logger::info(&format!("Total time: {:.2?}", start.elapsed()));
+    Ok(())
+}
+
+/// Process a GeoPackage file (multi-layer workflow).
+#[cfg(feature = "gpkg")]
+async fn process_gpkg(config: cli::Config, multi: &MultiProgress, claims: &mut output::OutputClaims) -> Result<()> {
+    let start_total = Instant::now();
+
+    // Open GeoPackage
+    let mut reader = GpkgReader::open(&config.input, &config.sqlite).await?;
+    reader.set_curve_tolerance(config.curve_tolerance);
+    reader.set_reprojection_cache(config.reprojection_cache.clone());
+
+    // Also checks that the --raster layer exists before rendering anything
+    let raster = raster_layer(&config, &reader).await?;
+    if let (Some(raster), true) = (&raster, config.raster_only) {
+        check_extensions(&reader, &[raster.name.as_str()]).await?;
+        return process_raster(&config, &reader, raster, claims).await;
+    }
+
+    let Some(layers_to_process) = layers_to_render(&config, &reader).await? else {
+        return Ok(());
+    };
+    let mut tables: Vec<&str> = layers_to_process.iter().map(|l| l.name.as_str()).collect();
+    tables.extend(raster.as_ref().map(|r| r.name.as_str()));
+    check_extensions(&reader, &tables).await?;
+    let inputs = gpkg_inputs(&config, &reader, &layers_to_process).await?;
+    let bbox = gpkg_bbox(&config, &inputs).await?;
+
+    if !config.preview_scales.is_empty() {
+        // The coarsest scale has the widest panel, which contains all the others
+        let coarsest = config.preview_scales.iter().copied().fold(0.0, f64::max);
+        let window = preview::panel_bbox(&bbox, math::scale_to_resolution(coarsest, &bbox));
+        for layer in &layers_to_process {
+            let filter = FeatureFilter {
+                bbox: Some(window),
+                where_clauses: config.where_clauses_for(&layer.name),
+                limit: config.limit,
+                offset: config.offset,
+                fids: config.fids.clone(),
+            };
+            let sources = layer_sources(&inputs, &layer.name);
+            let style_columns = layer_style_columns(&sources, &filter, &config).await?;
+            let mut geometries = Vec::new();
+            let mut styles = Vec::new();
+            for (reader, layer) in &sources {
+                reader
+                    .for_each_chunk_wgs84(layer, &filter, &style_columns.names(), |chunk| {
+                        for feature in chunk {
+                            styles.push(style_columns.style(feature.fid, &feature.attributes));
+                            geometries.push(feature.geometry);
+                        }
+                        Ok(())
+                    })
+                    .await?;
+            }
+            let name = output::sanitize_name(config.output_name_for(&layer.name));
+            render_preview(&geometries, &styles, &bbox, &config, &name, claims)?;
+        }
+        logger::info(&format!("Total time: {:.2?}", start_total.elapsed()));
+        return Ok(());
+    }
+
+    let resolution = render_resolution(&config, &bbox)?;
+
+    logger::info(&format!("Processing {} layer(s)...", layers_to_process.len()));
+    logger::debug(&format!("Resolution: {:.10} degrees/pixel", resolution));
+    logger::debug(&format!("Bounding box: {:?}", bbox));
+    if bbox.crosses_antimeridian() {
+        logger::debug("Bounding box crosses the antimeridian");
+    }
+
+    let input_digest = if config.content_addressed {
+        Some(output::file_digest(&config.input)?)
+    } else {
+        None
+    };
+    let merge_digests = match input_digest {
+        Some(_) => config.merge.iter().map(|path| output::file_digest(path)).collect::<Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+
+    // Only show progress bars in Normal mode
+    let show_progress = config.verbosity == VerbosityLevel::Normal && config.progress == ProgressMode::Bar;
+    let layer_multi = show_progress.then_some(multi);
+    let main_pb = if show_progress {
+        let pb = multi.add(ProgressBar::new(layers_to_process.len() as u64));
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta}) {msg}")
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+        Some(pb)
+    } else {
+        None
+    };
+
+    // Palette colors follow the order of all polygon layers, so a layer keeps
+    // its color when rendered alone
+    let palette_order = match config.auto_colors {
+        Some(_) => reader.list_polygon_layers().await?,
+        None => Vec::new(),
+    };
+
+    // Output names are claimed in layer order, whatever order the renders start in
+    let config = Arc::new(config);
+    let mut jobs = Vec::new();
+    let mut completed = Vec::new();
+    for layer in layers_to_process {
+        let palette_index = palette_order.iter().position(|l| l.name == layer.name).unwrap_or(0);
+        let layer_config = Arc::new(config.for_layer(&layer.name, palette_index));
+        if config.auto_colors.is_some() {
+            logger::debug(&format!(
+                "Layer {}: fill {}",
+                layer.name,
+                layer_config.fill.map_or("none".to_string(), |fill| format!("#{}", hex::encode_upper(fill)))
+            ));
+        }
+
+        let content_id = input_digest.as_deref().map(|digest| {
+            let mut settings = content_settings(&layer_config, &layer.name, &bbox, resolution);
+            settings.extend(merge_digests.iter().map(|digest| ("merge", digest.clone())));
+            output::content_id(digest, &layer.name, &settings)
+        });
+        let output_path = match &config.into {
+            Some(path) => path.clone(),
+            None => {
+                let name = output::sanitize_name(config.output_name_for(&layer.name));
+                let stem = output_stem(&layer_config, &name, &bbox, resolution, content_id.clone())?;
+                let path = burn_path(
+                    &config,
+                    output::output_path(
+                        &config.output_dir,
+                        config.output_layout,
+                        &config.input,
+                        &name,
+                        stem.as_deref(),
+                    ),
+                );
+                // Content-addressed names only repeat for identical renders
+                if content_id.is_some() {
+                    path
+                } else {
+                    claims.claim(path, &layer.name, config.on_collision)?
+                }
+            }
+        };
+
+        let resume_key = resume_key(&layer_config, &layer.name, &bbox, resolution, &output_path)?;
+        let up_to_date = content_id.is_some() && output_path.exists();
+        if up_to_date || (resume::is_done(&resume_key) && output_path.exists()) {
+            // Same input, layer and settings: the existing file is this render
+            let state = if up_to_date { "up to date" } else { "completed by the previous run" };
+            logger::info(&format!("Layer {}: {}", layer.name, state));
+            logger::output(&output_path.display().to_string());
+            record_up_to_date(&config, &output_path, &layer.name, &bbox, resolution)?;
+            ProgressEvent::FileSaved {
+                layer: &layer.name,
+                path: &output_path,
+            }
+            .emit(config.progress);
+            if let Some(ref pb) = main_pb {
+                pb.inc(1);
+            }
+            completed.push(layer.name);
+            continue;
+        }
+        jobs.push(LayerJob {
+            layer,
+            config: layer_config,
+            output_path,
+            resume_key,
+        });
+    }
+    if config.jobs > 1 {
+        order_by_cost(&mut jobs, &inputs, &bbox, resolution).await?;
+    }
+
+    // Process each layer, up to `--jobs` at a time
+    let mut tasks = JoinSet::new();
+    for job in jobs {
+        if interrupt::requested() {
+            break;
+        }
+        if let Some(ref pb) = main_pb {
+            pb.set_message(format!("Layer: {}", job.layer.name));
+        }
+        if tasks.len() >= config.jobs {
+            join_layer(&mut tasks, main_pb.as_ref(), &mut completed).await?;
+        }
+
+        let sources = layer_sources(&inputs, &job.layer.name);
+        let multi = layer_multi.cloned();
+        tasks.spawn(async move {
+            process_layer(
+                &sources,
+                &bbox,
+                resolution,
+                &job.config,
+                multi.as_ref(),
+                &job.output_path,
+            )
+            .await?;
+            resume::record(&job.resume_key)?;
+            Ok(job.layer.name)
+        });
+    }
+    while !tasks.is_empty() {
+        join_layer(&mut tasks, main_pb.as_ref(), &mut completed).await?;
+    }
+
+    if interrupt::requested() {
+        if let Some(pb) = main_pb {
+            pb.abandon_with_message("Interrupted");
+        }
+        let list = if completed.is_empty() {
+            "none".to_string()
+        } else {
+            completed.join(", ")
+        };
+        logger::info(&format!("Completed layers: {}", list));
+        return Err(GpkgError::Interrupted);
+    }
+
+    if let Some(pb) = main_pb {
+        pb.finish_with_message("All layers processed");
+    }
+
+    let duration = start_total.elapsed();
+    logger::info(&format!("Total time: {:.2?}", duration));
+    Ok(())
+}
+
+/// Resolves the style columns of a layer, detecting the range of its opacity
+/// and fill values over all its `sources`.
+#[cfg(feature = "gpkg")]
+async fn layer_style_columns(
+    sources: &[(GpkgReader, LayerInfo)],
+    filter: &FeatureFilter,
+    config: &cli::Config,
+) -> Result<StyleColumns> {
+    let mut columns = StyleColumns {
+        opacity: None,
+        stroke: config.stroke_color_column.clone(),
+        fill: None,
+        feature_colors: config.color_per_feature,
+    };
+    if let Some(column) = &config.opacity_column {
+        let range = layer_value_range(sources, filter, column).await?;
+        let scale = OpacityScale::detect(range.map(|(_, max)| max));
+        logger::debug(&format!("Layer {}: {} read as {:?} opacity", sources[0].1.name, column, scale));
+        columns.opacity = Some((column.clone(), scale));
+    }
+    if let Some(column) = &config.fill_column {
+        let ramp = config.ramp.clone();
+        let scale = match config.classify {
+            // Classes need every value: read them in a pre-pass
+            Some(classification) => {
+                let mut values = Vec::new();
+                for (reader, layer) in sources {
+                    values.extend(reader.numeric_values(layer, filter, column).await?);
+                }
+                FillScale::from_values(ramp, &values, Some((classification, config.classes)))
+            }
+            None => FillScale::continuous(ramp, layer_value_range(sources, filter, column).await?),
+        };
+        log_fill_scale(&format!("Layer {}", sources[0].1.name), column, &scale);
+        columns.fill = Some((column.clone(), scale));
+    }
+    Ok(columns)
+}
+
+/// Reports how the values of the `--fill-column` map onto the ramp.
+fn log_fill_scale(source: &str, column: &str, scale: &FillScale) {
+    if scale.breaks.is_empty() {
+        logger::debug(&format!("{}: {} ranges from {} to {}", source, column, scale.min, scale.max));
+    } else {
+        let bounds: Vec<String> = scale.breaks.iter().chain([&scale.max]).map(|b| format!("<= {}", b)).collect();
+        logger::info(&format!("{}: {} classes of {}: {}", source, bounds.len(), column, bounds.join(", ")));
+    }
+}
+
+/// Returns the range of the numeric values of `column` over all `sources`.
+#[cfg(feature = "gpkg")]
+async fn layer_value_range(
+    sources: &[(GpkgReader, LayerInfo)],
+    filter: &FeatureFilter,
+    column: &str,
+) -> Result<Option<(f64, f64)>> {
+    let mut range: Option<(f64, f64)> = None;
+    for (reader, layer) in sources {
+        if let Some((min, max)) = reader.value_range(layer, filter, column).await? {
+            range = Some(range.map_or((min, max), |(a, b)| (a.min(min), b.max(max))));
+        }
+    }
+    Ok(range)
+}
+
+/// Returns the resolution of a render over `bbox`, converted from `--scale`
+/// or `--width`/`--height` if needed, fitted to the maximum dimensions and checked against the canvas.
+fn render_resolution(config: &cli::Config, bbox: &Bbox) -> Result<f64> {
+    let resolution = if let Some(scale) = config.scale {
+        let resolution = math::scale_to_resolution(scale, bbox);
+        logger::info(&format!(
+            "Scale: {} m/pixel -> Resolution: {:.10} deg/pixel",
+            scale, resolution
+        ));
+        resolution
+    } else if config.width.is_some() || config.height.is_some() {
+        let resolution = math::size_to_resolution(bbox, config.width, config.height);
+        let (width, height) = math::calculate_dimensions(bbox, resolution);
+        logger::info(&format!(
+            "Size: {}x{} pixels -> Resolution: {:.10} deg/pixel",
+            width, height, resolution
+        ));
+        resolution
+    } else {
+        config.resolution.unwrap()
+    };
+    let resolution = downscale_to_fit(config, bbox, resolution)?;
+    check_canvas(config, bbox, resolution)?;
+    Ok(resolution)
+}
+
+/// Coarsens the resolution when `--auto-downscale` is set and the image
+/// would exceed the maximum dimensions; `Renderer::new` rejects it otherwise.
+fn downscale_to_fit(config: &cli::Config, bbox: &Bbox, resolution: f64) -> Result<f64> {
+    if !config.auto_downscale {
+        return Ok(resolution);
+    }
+    let fitted = render::fit_resolution(bbox, resolution, config.max_dimension);
+    if fitted != resolution {
+        let (width, height) = math::calculate_dimensions(bbox, resolution);
+        let (fitted_width, fitted_height) = math::calculate_dimensions(bbox, fitted);
+        warning::emit(
+            WarningCode::ResolutionDownscaled,
+            &format!(
+                "{}x{} exceeds the maximum of {} pixels, rendering {}x{} at {:.10} deg/pixel",
+                width, height, config.max_dimension, fitted_width, fitted_height, fitted
+            ),
+        )?;
+    }
+    Ok(fitted)
+}
+
+/// Waits for the next layer task to finish, records its name in `completed`
+/// and propagates its error.
+///
+/// Returning early drops the `JoinSet`, which aborts the layers still running.
+/// Interrupted layers are not errors here: the other layers stop on their own
+/// at their next chunk, and files being saved are completed.
+#[cfg(feature = "gpkg")]
+async fn join_layer(
+    tasks: &mut JoinSet<Result<String>>,
+    main_pb: Option<&ProgressBar>,
+    completed: &mut Vec<String>,
+) -> Result<()> {
+    match tasks.join_next().await {
+        Some(Ok(Ok(name))) => completed.push(name),
+        Some(Ok(Err(GpkgError::Interrupted))) => return Ok(()),
+        Some(Ok(Err(e))) => return Err(e),
+        Some(Err(e)) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        // Tasks are only aborted when the set is dropped
+        Some(Err(_)) | None => return Ok(()),
+    }
+    if let Some(pb) = main_pb {
+        pb.inc(1);
+    }
+    Ok(())
+}
+
+/// Processes a single GeoPackage layer, read from each of its `sources` (the
+/// input, then the `--merge` files having it).
+///
+/// This involves:
+/// 1. Counting the selected features and initializing the renderer, over the
+///    `--raster` tiles if any.
+/// 2. Streaming geometries in bounded chunks, reprojecting them to WGS84 and
+///    rasterizing each MultiPolygon as it arrives.
+/// 3. Saving the final image as a PNG.
+#[cfg(feature = "gpkg")]
+async fn process_layer(
+    sources: &[(GpkgReader, LayerInfo)],
+    bbox: &Bbox,
+    resolution: f64,
+    config: &cli::Config,
+    multi: Option<&MultiProgress>,
+    output_path: &Path,
+) -> Result<()> {
+    let start_layer = Instant::now();
+    let (reader, layer) = &sources[0];
+
+    // 1. Count and set up
+    let pb = if let Some(multi) = multi {
+        let pb = multi.add(ProgressBar::new_spinner());
+        pb.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}").unwrap());
+        pb.set_message(format!("Reading {}...", layer.name));
+        Some(pb)
+    } else {
+        None
+    };
+
+    // Only a user-provided bbox (framed) narrows the read; an auto-detected one covers everything.
+    // A rotated frame shows the world area around it in its corners.
+    let filter = FeatureFilter {
+        bbox: config.bbox.map(|_| bbox.rotated_envelope(config.rotation)),
+        where_clauses: config.where_clauses_for(&layer.name),
+        limit: config.limit,
+        offset: config.offset,
+        fids: config.fids.clone(),
+    };
+
+    let skip_empty = |pb: Option<ProgressBar>| {
+        if let Some(pb) = pb {
+            pb.finish_with_message(format!("  Layer {}: skipped (no geometries)", layer.name));
+        }
+        ProgressEvent::LayerSkipped {
+            layer: &layer.name,
+            reason: "no geometries",
+        }
+        .emit(config.progress);
+        warning::emit(
+            WarningCode::LayerEmpty,
+            &format!("Layer {}: skipped (no geometries)", layer.name),
+        )
+    };
+
+    let mut total = 0;
+    for (reader, layer) in sources {
+        total += reader.count_features(layer, &filter).await?;
+    }
+    if total == 0 {
+        return skip_empty(pb);
+    }
+
+    logger::debug(&format!("Layer {}: {} features selected", layer.name, total));
+
+    let style_columns = layer_style_columns(sources, &filter, config).await?;
+    // The --burn column is read after the style columns, then the exported ones
+    let mut columns = style_columns.names();
+    let burn_index = columns.len();
+    columns.extend(config.burn.clone());
+    let export_offset = columns.len();
+    // Only the columns found in every source are exported
+    let mut export_columns = Vec::new();
+    if config.export_attributes.is_some() {
+        export_columns = reader.attribute_columns(layer).await?;
+        for (reader, layer) in &sources[1..] {
+            let present = reader.attribute_columns(layer).await?;
+            export_columns.retain(|c| present.contains(c));
+        }
+    }
+    columns.extend(export_columns.iter().cloned());
+    let mut source_crs: Option<String> = None;
+    for (reader, layer) in sources {
+        if let Some(name) = reader.get_srs_name(layer.srs_id).await? {
+            match &mut source_crs {
+                Some(crs) if !crs.split(", ").any(|c| c == name) => *crs = format!("{}, {}", crs, name),
+                Some(_) => {}
+                None => source_crs = Some(name),
+            }
+        }
+    }
+
+    let render_config = RenderConfig {
+        bbox: *bbox,
+        resolution,
+        fill: config.fill,
+        stroke: config.stroke,
+        stroke_width: config.stroke_width,
+        vertex_color: config.vertex_color,
+        min_feature_px: config.min_feature_px,
+        max_dimension: config.max_dimension,
+        rotation: config.rotation,
+        glow: config.glow,
+    };
+
+    let supersample = supersample_factor(config, &render_config, &layer.name)?;
+    let mut renderer = Renderer::new(render_config.supersampled(supersample))?;
+    renderer.set_png_options(config.png_options);
+    if let Some(path) = &config.into {
+        renderer.set_base(&canvas::read_image(path)?)?;
+    }
+    let (width, height) = renderer.dimensions();
+    if let Some(raster) = raster_layer(config, reader).await? {
+        let base = raster_base(reader, &raster, &render_config.supersampled(supersample), width, height).await?;
+        renderer.set_base(&base)?;
+    }
+    let burn_grid = config.burn.as_ref().map(|_| Arc::new(BurnGrid::new(width, height, config.burn_rule)));
+
+    if let Some(ref pb) = pb {
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("    {msg} [{bar:20.yellow/orange}] {pos}/{len} ({percent}%)")
+                .unwrap()
+                .progress_chars("=>-"),
+        );
+        pb.set_length(total);
+        pb.set_message(format!("Rendering {} ({}x{})", layer.name, width, height));
+    }
+
+    logger::debug(&format!(
+        "Layer {}: image dimensions {}x{}",
+        layer.name, width, height
+    ));
+    ProgressEvent::LayerStarted {
+        layer: &layer.name,
+        features: total,
+        width,
+        height,
+    }
+    .emit(config.progress);
+
+    let mut attribute_table = match config.export_attributes {
+        Some(format) => {
+            output::ensure_parent_dir(output_path)?;
+            let path = attributes::table_path(output_path, format);
+            Some(AttributeWriter::create(path, format, export_columns)?)
+        }
+        None => None,
+    };
+
+    // 2. Read, reproject and render chunk by chunk
+    let start_read = Instant::now();
+    let mut duration_render = Duration::ZERO;
+    let mut rendered = 0u64;
+    let mut throttle = ProgressThrottle::new(total, config.progress_interval);
+    let mut on_chunk = |chunk: Vec<Feature>| {
+        if interrupt::requested() {
+            return Err(GpkgError::Interrupted);
+        }
+        ProgressEvent::GeometriesRead {
+            layer: &layer.name,
+            read: rendered + chunk.len() as u64,
+            total,
+        }
+        .emit(config.progress);
+        let start_render = Instant::now();
+        if config.verbosity == VerbosityLevel::Trace {
+            logger::trace(&format!(
+                "Rendering geometries {}-{}/{}",
+                rendered + 1,
+                rendered + chunk.len() as u64,
+                total
+            ));
+        }
+        match &burn_grid {
+            Some(grid) => {
+                // Features without a numeric value are left out
+                let features = chunk
+                    .iter()
+                    .filter_map(|f| Some((&f.geometry, f.attributes.get(burn_index)?.as_f64()?)));
+                burn::burn_features(&mut renderer, grid, features, bbox);
+            }
+            None => {
+                let features = chunk
+                    .iter()
+                    .map(|f| (&f.geometry, style_columns.style(f.fid, &f.attributes)));
+                render_features(&mut renderer, features, bbox);
+            }
+        }
+        if let Some(table) = attribute_table.as_mut() {
+            for f in &chunk {
+                let values: Vec<_> = f.attributes[export_offset..].iter().map(attributes::json_value).collect();
+                table.write(f.fid, &values)?;
+            }
+        }
+        rendered += chunk.len() as u64;
+        ProgressEvent::Rendered {
+            layer: &layer.name,
+            percent: rendered as f64 * 100.0 / total as f64,
+        }
+        .emit(config.progress);
+        if let Some(ref pb) = pb {
+            if throttle.tick(rendered) {
+                pb.set_position(rendered);
+            }
+        }
+        duration_render += start_render.elapsed();
+        Ok(())
+    };
+    let mut read = Ok(());
+    for (reader, layer) in sources {
+        read = reader.for_each_chunk_wgs84(layer, &filter, &columns, &mut on_chunk).await;
+        if read.is_err() {
+            break;
+        }
+    }
+    if let Err(GpkgError::Interrupted) = read {
+        if let Some(pb) = pb {
+            pb.abandon_with_message(format!("  Layer {}: interrupted", layer.name));
+        }
+        let renderer = reduce_supersampled(renderer, &render_config, config, supersample)?;
+        let metadata = png_metadata(config, bbox, resolution, &layer.name, source_crs.as_deref());
+        return save_interrupted(&renderer, output_path, config, &metadata, &layer.name);
+    }
+    read?;
+    // Time spent fetching, parsing and reprojecting
+    let duration_read = start_read.elapsed().saturating_sub(duration_render);
+
+    // Every selected feature may have been skipped (invalid or non-polygon)
+    if rendered == 0 {
+        return skip_empty(pb);
+    }
+    report_degenerate(&renderer, &layer.name, config)?;
+    if let Some(ref pb) = pb {
+        pb.set_position(total);
+    }
+
+    logger::debug(&format!(
+        "Layer {}: read {} geometries in {:.2?}",
+        layer.name, rendered, duration_read
+    ));
+
+    // 3. Save
+    if let Some(ref pb) = pb {
+        pb.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}").unwrap());
+        pb.set_message(format!("Saving {}...", output_path.display()));
+    }
+
+    let start_save = Instant::now();
+    let mut renderer = reduce_supersampled(renderer, &render_config, config, supersample)?;
+    let bbox = &autocrop(&mut renderer, &layer.name, config, bbox);
+    output::ensure_parent_dir(output_path)?;
+    let metadata = png_metadata(config, bbox, resolution, &layer.name, source_crs.as_deref());
+    let overviews = match &burn_grid {
+        Some(grid) => {
+            save_burn(grid, output_path, config, bbox, resolution, &metadata, &layer.name)?;
+            Vec::new()
+        }
+        None => {
+            renderer.save(output_path, &metadata)?;
+            save_overviews(&renderer, &render_config, bbox, output_path, config, &layer.name, source_crs.as_deref())?
+        }
+    };
+    let attribute_table = attribute_table.map(AttributeWriter::finish).transpose()?;
+    let duration_save = start_save.elapsed();
+
+    let total_layer = start_layer.elapsed();
+
+    if let Some(pb) = pb {
+        pb.finish_with_message(format!(
+            "  Layer {}: done in {:.2?} (Read: {:.2?}, Render: {:.2?}, Save: {:.2?})",
+            layer.name, total_layer, duration_read, duration_render, duration_save
+        ));
+    }
+
+    logger::output(&output_path.display().to_string());
+    for path in overviews.iter().chain(&attribute_table) {
+        logger::output(&path.display().to_string());
+    }
+    ProgressEvent::FileSaved {
+        layer: &layer.name,
+        path: output_path,
+    }
+    .emit(config.progress);
+    logger::debug(&format!(
+        "Layer {} timings: Read: {:.2?}, Render: {:.2?}, Save: {:.2?}",
+        layer.name, duration_read, duration_render, duration_save
+    ));
+    let (width, height) = renderer.dimensions();
+    record_output(
+        config,
+        manifest::Entry {
+            path: output_path.to_path_buf(),
+            input: config.input.clone(),
+            layer: layer.name.clone(),
+            bbox: *bbox,
+            width,
+            height,
+            resolution,
+            features: Some(rendered),
+            timings: Some(manifest::Timings {
+                read: duration_read,
+                render: duration_render,
+                save: duration_save,
+                total: total_layer,
+            }),
+        },
+    )?;
+
+    Ok(())
+}
+
+/// Reads the GeoJSON input, merged with `--merge` files and reprojected to
+/// WGS84 from `--source-crs` or the declared CRS.
+async fn read_geojson(config: &cli::Config) -> Result<GeojsonReader> {
+    if config.merge.is_empty() {
+        logger::info("Reading GeoJSON file...");
+    } else {
+        logger::info(&format!("Reading {} GeoJSON file(s)...", config.merge.len() + 1));
+    }
+    geojson::read_merged(&config.geojson_inputs())
+}
+
+/// Features of a GeoJSON render.
+enum GeojsonSource {
+    /// Parsed into memory.
+    Parsed(GeojsonReader),
+    /// Streamed from a GeoJSON text sequence, with what a first pass gathered.
+    Sequence(SequenceReader, SequenceScan),
+}
+
+/// What a first pass over a GeoJSON text sequence gathers for its render.
+struct SequenceScan {
+    features: usize,
+    /// Extent of the geometries (only without --bbox).
+    bbox: Option<Bbox>,
+    /// Property names in first-seen order (only with --export-attributes).
+    columns: Vec<String>,
+    /// Largest value of the --opacity-column.
+    opacity_max: Option<f64>,
+    /// Numeric values of the --fill-column.
+    fill_values: Vec<f64>,
+}
+
+impl GeojsonSource {
+    /// Opens the GeoJSON input of `config`.
+    ///
+    /// A text sequence is streamed unless merged or previewed, which parse it
+    /// into memory.
+    async fn open(config: &cli::Config) -> Result<Self> {
+        if !geojson::is_sequence(&config.input) || !config.merge.is_empty() || !config.preview_scales.is_empty() {
+            return read_geojson(config).await.map(Self::Parsed);
+        }
+        logger::info("Scanning GeoJSON text sequence...");
+        let reader = SequenceReader::open(&config.geojson_inputs()[0])?;
+        let mut bboxes = Vec::new();
+        let mut columns: Vec<String> = Vec::new();
+        let mut opacity_max: Option<f64> = None;
+        let mut fill_values = Vec::new();
+        let summary = reader.for_each_chunk(|geometries, properties| {
+            if config.bbox.is_none() {
+                bboxes.extend(antimeridian::geometries_bbox(geometries));
+            }
+            for properties in properties.iter().flatten() {
+                if config.export_attributes.is_some() {
+                    for name in properties.keys() {
+                        if !columns.contains(name) {
+                            columns.push(name.clone());
+                        }
+                    }
+                }
+                let opacity = config.opacity_column.as_ref().and_then(|column| properties.get(column));
+                if let Some(value) = opacity.and_then(|v| AttributeValue::from_json(v).as_f64()) {
+                    opacity_max = Some(opacity_max.map_or(value, |m| m.max(value)));
+                }
+                let fill = config.fill_column.as_ref().and_then(|column| properties.get(column));
+                fill_values.extend(fill.and_then(|v| AttributeValue::from_json(v).as_f64()));
+            }
+            Ok(())
+        })?;
+        summary.warn(&config.input.display().to_string())?;
+        if summary.features == 0 {
+            return Err(GpkgError::EmptyGeojson);
+        }
+        let scan = SequenceScan {
+            features: summary.features,
+            bbox: antimeridian::bboxes_union(&bboxes),
+            columns,
+            opacity_max,
+            fill_values,
+        };
+        Ok(Self::Sequence(reader, scan))
+    }
+
+    /// Number of polygon features.
+    fn len(&self) -> usize {
+        match self {
+            Self::Parsed(reader) => reader.get_geometries().len(),
+            Self::Sequence(_, scan) => scan.features,
+        }
+    }
+
+    /// CRS declared by the input (sequences are always WGS84).
+    fn crs(&self) -> Option<&str> {
+        match self {
+            Self::Parsed(reader) => reader.crs(),
+            Self::Sequence(..) => None,
+        }
+    }
+
+    /// Extent of the geometries: the bbox member of the document, if any,
+    /// else computed from the geometries.
+    fn bbox(&self) -> Option<Bbox> {
+        match self {
+            Self::Parsed(reader) => match reader.declared_bbox() {
+                Some(bbox) => {
+                    logger::info("Using the bbox member of the GeoJSON document");
+                    Some(bbox)
+                }
+                None => reader.compute_bbox(),
+            },
+            Self::Sequence(_, scan) => scan.bbox,
+        }
+    }
+
+    /// Property names of the features, in first-seen order.
+    fn property_names(&self) -> Vec<String> {
+        match self {
+            Self::Parsed(reader) => reader.property_names(),
+            Self::Sequence(_, scan) => scan.columns.clone(),
+        }
+    }
+
+    /// Returns the columns styling each feature.
+    fn style_columns(&self, config: &cli::Config) -> StyleColumns {
+        let mut columns = StyleColumns {
+            opacity: None,
+            stroke: config.stroke_color_column.clone(),
+            fill: None,
+            feature_colors: config.color_per_feature,
+        };
+        if let Some(column) = &config.opacity_column {
+            let max = match self {
+                Self::Parsed(reader) => Self::values(reader, column).into_iter().reduce(f64::max),
+                Self::Sequence(_, scan) => scan.opacity_max,
+            };
+            columns.opacity = Some((column.clone(), OpacityScale::detect(max)));
+        }
+        if let Some(column) = &config.fill_column {
+            let values = match self {
+                Self::Parsed(reader) => Self::values(reader, column),
+                Self::Sequence(_, scan) => scan.fill_values.clone(),
+            };
+            let classification = config.classify.map(|c| (c, config.classes));
+            let scale = FillScale::from_values(config.ramp.clone(), &values, classification);
+            log_fill_scale(&config.input.display().to_string(), column, &scale);
+            columns.fill = Some((column.clone(), scale));
+        }
+        columns
+    }
+
+    /// Returns the numeric values of `column`.
+    fn values(reader: &GeojsonReader, column: &str) -> Vec<f64> {
+        let column = [column.to_string()];
+        (0..reader.get_geometries().len())
+            .filter_map(|i| reader.attributes(i, &column)[0].as_f64())
+            .collect()
+    }
+
+    /// Calls `on_chunk` with successive batches of geometries and the
+    /// properties of their feature (possibly fewer entries than geometries).
+    fn for_each_chunk<F>(&self, mut on_chunk: F) -> Result<()>
+    where
+        F: FnMut(&[geo::MultiPolygon<f64>], &[Option<::geojson::JsonObject>]) -> Result<()>,
+    {
+        match self {
+            Self::Parsed(reader) => {
+                let properties = reader.get_properties();
+                for (i, batch) in reader.get_geometries().chunks(render::BATCH_SIZE).enumerate() {
+                    let start = (i * render::BATCH_SIZE).min(properties.len());
+                    let end = (start + batch.len()).min(properties.len());
+                    on_chunk(batch, &properties[start..end])?;
+                }
+                Ok(())
+            }
+            // Warnings were emitted by the first pass
+            Self::Sequence(reader, _) => reader.for_each_chunk(on_chunk).map(drop),
+        }
+    }
+}
+
+/// Returns the bbox of a GeoJSON render: given, or the extent of the
+/// geometries, grown by `--padding` and `--aspect`.
+fn geojson_bbox(config: &cli::Config, source: &GeojsonSource) -> Result<Bbox> {
+    if let Some(bbox) = config.bbox {
+        return Ok(frame_bbox(config, bbox));
+    }
+    logger::info("Auto-detecting bounding box...");
+    let bbox = source.bbox().ok_or_else(|| {
+        GpkgError::InvalidBbox("Could not determine bounding box from geometries".to_string())
+    })?;
+    warning::emit(
+        WarningCode::BboxAutoDetected,
+        &format!(
+            "Auto-detected bbox: {},{},{},{}",
+            bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat
+        ),
+    )?;
+    Ok(frame_bbox(config, bbox))
+}
+
+/// Grows the bbox of a render by `--padding`, then to the `--aspect` ratio,
+/// and returns the frame holding it once rotated by `--rotation`.
+fn frame_bbox(config: &cli::Config, bbox: Bbox) -> Bbox {
+    if config.padding.is_none() && config.aspect.is_none() && config.rotation == 0.0 {
+        return bbox;
+    }
+    let mut framed = bbox;
+    if let Some(padding) = config.padding {
+        framed = framed.padded(padding);
+    }
+    if let Some(ratio) = config.aspect {
+        framed = framed.with_aspect(ratio);
+    }
+    framed = framed.rotated_envelope(config.rotation);
+    logger::info(&format!(
+        "Framed bbox: {},{},{},{}",
+        framed.min_lon, framed.min_lat, framed.max_lon, framed.max_lat
+    ));
+    framed
+}
+
+/// Process a GeoJSON file (single PNG output).
+async fn process_geojson(config: cli::Config, multi: &MultiProgress, claims: &mut output::OutputClaims) -> Result<()> {
+    let start_total = Instant::now();
+
+    let source = GeojsonSource::open(&config).await?;
+    let total = source.len();
+    let duration_read = start_total.elapsed();
+
+    logger::info(&format!("Found {} polygon geometries", total));
+
+    let bbox = geojson_bbox(&config, &source)?;
+
+    let style_columns = source.style_columns(&config);
+    let source_crs = geojson_source_crs(&config, &source);
+
+    if !config.preview_scales.is_empty() {
+        let GeojsonSource::Parsed(reader) = &source else {
+            unreachable!("previewed inputs are parsed into memory");
+        };
+        let output_name = config.output_name.as_ref().unwrap();
+        let styles = geojson_styles(reader, &style_columns);
+        render_preview(reader.get_geometries(), &styles, &bbox, &config, output_name, claims)?;
+        logger::info(&format!("Total time: {:.2?}", start_total.elapsed()));
+        return Ok(());
+    }
+
+    let resolution = render_resolution(&config, &bbox)?;
+
+    if bbox.crosses_antimeridian() {
+        logger::debug("Bounding box crosses the antimeridian");
+    }
+
+    // Resolve the output path first so content-addressed renders can be skipped
+    let output_name = config.output_name.as_ref().unwrap();
+    let content_id = if config.content_addressed {
+        let digest = output::file_digest(&config.input)?;
+        let mut settings = content_settings(&config, output_name, &bbox, resolution);
+        for path in &config.merge {
+            settings.push(("merge", output::file_digest(path)?));
+        }
+        Some(output::content_id(&digest, output_name, &settings))
+    } else {
+        None
+    };
+    let output_path = match &config.into {
+        Some(path) => path.clone(),
+        None => {
+            let stem = output_stem(&config, output_name, &bbox, resolution, content_id.clone())?;
+            let path = burn_path(
+                &config,
+                output::output_path(
+                    &config.output_dir,
+                    config.output_layout,
+                    &config.input,
+                    output_name,
+                    stem.as_deref(),
+                ),
+            );
+            if content_id.is_some() {
+                path
+            } else {
+                claims.claim(path, output_name, config.on_collision)?
+            }
+        }
+    };
+
+    let resume_key = resume_key(&config, output_name, &bbox, resolution, &output_path)?;
+    let up_to_date = content_id.is_some() && output_path.exists();
+    if up_to_date || (resume::is_done(&resume_key) && output_path.exists()) {
+        logger::info(if up_to_date { "Output up to date" } else { "Output completed by the previous run" });
+        logger::output(&output_path.display().to_string());
+        record_up_to_date(&config, &output_path, output_name, &bbox, resolution)?;
+        ProgressEvent::FileSaved {
+            layer: output_name,
+            path: &output_path,
+        }
+        .emit(config.progress);
+        return Ok(());
+    }
+
+    // Create renderer
+    let render_config = RenderConfig {
+        bbox,
+        resolution,
+        fill: config.fill,
+        stroke: config.stroke,
+        stroke_width: config.stroke_width,
+        vertex_color: config.vertex_color,
+        min_feature_px: config.min_feature_px,
+        max_dimension: config.max_dimension,
+        rotation: config.rotation,
+        glow: config.glow,
+    };
+
+    let supersample = supersample_factor(&config, &render_config, output_name)?;
+    let mut renderer = Renderer::new(render_config.supersampled(supersample))?;
+    renderer.set_png_options(config.png_options);
+    if let Some(path) = &config.into {
+        renderer.set_base(&canvas::read_image(path)?)?;
+    }
+    let (width, height) = renderer.dimensions();
+    let burn_column: Option<[String; 1]> = config.burn.as_ref().map(|column| [column.clone()]);
+    let burn_grid = config.burn.as_ref().map(|_| Arc::new(BurnGrid::new(width, height, config.burn_rule)));
+
+    logger::info(&format!("Rendering {}x{} image...", width, height));
+    ProgressEvent::LayerStarted {
+        layer: output_name,
+        features: total as u64,
+        width,
+        height,
+    }
+    .emit(config.progress);
+
+    // Only show progress bar in Normal mode
+    let show_progress = config.verbosity == VerbosityLevel::Normal && config.progress == ProgressMode::Bar;
+    let pb = if show_progress {
+        let pb = multi.add(ProgressBar::new(total as u64));
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) {msg}")
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+        Some(pb)
+    } else {
+        None
+    };
+
+    let export_columns = source.property_names();
+    let mut attribute_table = match config.export_attributes {
+        Some(format) => {
+            output::ensure_parent_dir(&output_path)?;
+            let path = attributes::table_path(&output_path, format);
+            Some(AttributeWriter::create(path, format, export_columns.clone())?)
+        }
+        None => None,
+    };
+
+    // Render all geometries, one sweep per batch
+    let start_render = Instant::now();
+    let style_names = style_columns.names();
+    let mut throttle = ProgressThrottle::new(total as u64, config.progress_interval);
+    let mut rendered = 0;
+    let read = source.for_each_chunk(|batch, properties| {
+        if interrupt::requested() {
+            return Err(GpkgError::Interrupted);
+        }
+        if config.verbosity == VerbosityLevel::Trace {
+            logger::trace(&format!(
+                "Rendering geometries {}-{}/{}",
+                rendered + 1,
+                rendered + batch.len(),
+                total
+            ));
+        }
+        let properties = |i: usize| properties.get(i).and_then(Option::as_ref);
+        match (&burn_grid, &burn_column) {
+            (Some(grid), Some(column)) => {
+                // Features without a numeric value are left out
+                let features = batch
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, geom)| Some((geom, geojson::property_attributes(properties(i), column)[0].as_f64()?)));
+                burn::burn_features(&mut renderer, grid, features, &bbox);
+            }
+            _ => {
+                let features = batch.iter().enumerate().map(|(i, geom)| {
+                    let values = geojson::property_attributes(properties(i), &style_names);
+                    (geom, style_columns.style((rendered + i) as i64, &values))
+                });
+                render_features(&mut renderer, features, &bbox);
+            }
+        }
+        if let Some(table) = attribute_table.as_mut() {
+            for i in 0..batch.len() {
+                table.write((rendered + i) as i64, &geojson::property_values(properties(i), &export_columns))?;
+            }
+        }
+        rendered += batch.len();
+        ProgressEvent::Rendered {
+            layer: output_name,
+            percent: rendered as f64 * 100.0 / total as f64,
+        }
+        .emit(config.progress);
+        if let Some(ref pb) = pb {
+            if throttle.tick(rendered as u64) {
+                pb.set_position(rendered as u64);
+            }
+        }
+        Ok(())
+    });
+    if let Err(GpkgError::Interrupted) = read {
+        if let Some(pb) = pb {
+            pb.abandon_with_message("Interrupted");
+        }
+        let renderer = reduce_supersampled(renderer, &render_config, &config, supersample)?;
+        let metadata = png_metadata(&config, &bbox, resolution, output_name, Some(&source_crs));
+        return save_interrupted(&renderer, &output_path, &config, &metadata, output_name);
+    }
+    read?;
+
+    if let Some(pb) = pb {
+        pb.set_position(total as u64);
+        pb.finish_with_message("Rendering complete");
+    }
+    report_degenerate(&renderer, output_name, &config)?;
+
+    let duration_render = start_render.elapsed();
+
+    let start_save = Instant::now();
+    let mut renderer = reduce_supersampled(renderer, &render_config, &config, supersample)?;
+    let bbox = autocrop(&mut renderer, output_name, &config, &bbox);
+
+    // Save PNG (or the --burn raster)
+    output::ensure_parent_dir(&output_path)?;
+
+    logger::info(&format!("Saving {}...", output_path.display()));
+    let metadata = png_metadata(&config, &bbox, resolution, output_name, Some(&source_crs));
+    let overviews = match &burn_grid {
+        Some(grid) => {
+            save_burn(grid, &output_path, &config, &bbox, resolution, &metadata, output_name)?;
+            Vec::new()
+        }
+        None => {
+            renderer.save(&output_path, &metadata)?;
+            save_overviews(&renderer, &render_config, &bbox, &output_path, &config, output_name, Some(&source_crs))?
+        }
+    };
+    let attribute_table = attribute_table.map(AttributeWriter::finish).transpose()?;
+    let duration_save = start_save.elapsed();
+
+    let duration = start_total.elapsed();
+    let (width, height) = renderer.dimensions();
+    record_output(
+        &config,
+        manifest::Entry {
+            path: output_path.clone(),
+            input: config.input.clone(),
+            layer: output_name.clone(),
+            bbox,
+            width,
+            height,
+            resolution,
+            features: Some(total as u64),
+            timings: Some(manifest::Timings {
+                read: duration_read,
+                render: duration_render,
+                save: duration_save,
+                total: duration,
+            }),
+        },
+    )?;
+    logger::info(&format!("Total time: {:.2?}", duration));
+    logger::output(&output_path.display().to_string());
+    for path in overviews.iter().chain(&attribute_table) {
+        logger::output(&path.display().to_string());
+    }
+    ProgressEvent::FileSaved {
+        layer: output_name,
+        path: &output_path,
+    }
+    .emit(config.progress);
+    resume::record(&resume_key)?;
+
+    Ok(())
+}
+
+/// Warns about the features that collapsed below one pixel in `renderer`.
+fn report_degenerate(renderer: &Renderer, name: &str, config: &cli::Config) -> Result<()> {
+    let count = renderer.degenerate_count();
+    if count == 0 {
+        return Ok(());
+    }
+    let handling = match (config.fill, config.min_feature_px) {
+        (None, _) => "no fill, strokes only".to_string(),
+        (Some(_), Some(px)) => format!("drawn as {}px marks", px),
+        (Some(_), None) => "fill skipped, see --min-feature-px".to_string(),
+    };
+    warning::emit(
+        WarningCode::FeatureDegenerate,
+        &format!(
+            "{}: {} feature(s) collapsed below one pixel ({})",
+            name, count, handling
+        ),
+    )
+}
+
+/// Returns the `--supersample` factor of a render (1 renders directly),
+/// lowered with a warning when its canvas would exceed the maximum dimensions.
+fn supersample_factor(config: &cli::Config, render_config: &RenderConfig, name: &str) -> Result<u32> {
+    let Some(requested) = config.supersample else {
+        return Ok(1);
+    };
+    let (width, height) = math::calculate_dimensions(&render_config.bbox, render_config.resolution);
+    let factor = render::fit_supersample(width, height, requested, config.max_dimension);
+    if factor < requested {
+        let fallback = if factor > 1 {
+            format!("supersampling {}x", factor)
+        } else {
+            "rendering without supersampling".to_string()
+        };
+        warning::emit(
+            WarningCode::SupersampleReduced,
+            &format!(
+                "{}: --supersample {} of {}x{} exceeds the maximum of {} pixels, {}",
+                name, requested, width, height, config.max_dimension, fallback
+            ),
+        )?;
+    }
+    if factor > 1 {
+        logger::debug(&format!("{}: rendering {}x finer", name, factor));
+    }
+    Ok(factor)
+}
+
+/// Brings a `--supersample` render back to the output grid of `render_config`.
+fn reduce_supersampled(
+    renderer: Renderer,
+    render_config: &RenderConfig,
+    config: &cli::Config,
+    factor: u32,
+) -> Result<Renderer> {
+    if factor == 1 {
+        return Ok(renderer);
+    }
+    let mut reduced = Renderer::new(render_config.clone())?;
+    reduced.set_png_options(config.png_options);
+    let (width, height) = reduced.dimensions();
+    let image = renderer.into_image();
+    reduced.set_base(&resample::reduce_supersampled(&image, width, height, factor, config.resample))?;
+    Ok(reduced)
+}
+
+/// Saves the `--overviews` of a render next to `output_path`, each level
+/// half the size of the previous one, down to a single pixel, and returns
+/// their paths.
+fn save_overviews(
+    renderer: &Renderer,
+    render_config: &RenderConfig,
+    bbox: &Bbox,
+    output_path: &Path,
+    config: &cli::Config,
+    name: &str,
+    source_crs: Option<&str>,
+) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    let mut image = renderer.to_image();
+    let mut factor = 1;
+    for _ in 0..config.overviews {
+        if image.width() == 1 && image.height() == 1 {
+            break;
+        }
+        factor *= 2;
+        let resolution = render_config.resolution * factor as f64;
+        let mut overview = Renderer::new(RenderConfig {
+            bbox: *bbox,
+            resolution,
+            ..render_config.clone()
+        })?;
+        overview.set_png_options(config.png_options);
+        let (width, height) = overview.dimensions();
+        image = resample::reduce_supersampled(&image, width, height, 2, config.resample);
+        overview.set_base(&image)?;
+        let path = output::overview_path(output_path, factor);
+        overview.save(&path, &png_metadata(config, bbox, resolution, name, source_crs))?;
+        logger::debug(&format!("{}: overview 1/{} saved ({}x{})", name, factor, width, height));
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Trims transparent borders with `--autocrop` and returns the bbox of the
+/// saved image.
+fn autocrop(renderer: &mut Renderer, name: &str, config: &cli::Config, bbox: &Bbox) -> Bbox {
+    let Some(threshold) = config.autocrop else {
+        return *bbox;
+    };
+    match renderer.autocrop(threshold) {
+        Some(cropped) => {
+            let (width, height) = renderer.dimensions();
+            logger::info(&format!(
+                "{}: cropped to {}x{}, bbox {},{},{},{}",
+                name, width, height, cropped.min_lon, cropped.min_lat, cropped.max_lon, cropped.max_lat
+            ));
+            cropped
+        }
+        None => {
+            logger::debug(&format!("{}: nothing above alpha {}, not cropped", name, threshold));
+            *bbox
+        }
+    }
+}
+
+/// Ends a render stopped by Ctrl-C: the image is discarded, or saved as
+/// `<name>.partial.png` with `--save-partial`. Always returns `Interrupted`.
+fn save_interrupted(
+    renderer: &Renderer,
+    output_path: &Path,
+    config: &cli::Config,
+    metadata: &PngMetadata,
+    name: &str,
+) -> Result<()> {
+    if config.save_partial {
+        let partial_path = output::partial_path(output_path);
+        output::ensure_parent_dir(&partial_path)?;
+        renderer.save(&partial_path, metadata)?;
+        logger::info(&format!("{}: interrupted, partial render saved", name));
+        logger::output(&partial_path.display().to_string());
+    } else {
+        logger::info(&format!("{}: interrupted, render discarded", name));
+    }
+    Err(GpkgError::Interrupted)
+}
+
+/// Returns the style of each GeoJSON geometry (empty without per-feature styling).
+fn geojson_styles(reader: &GeojsonReader, columns: &StyleColumns) -> Vec<FeatureStyle> {
+    if columns.is_empty() {
+        return Vec::new();
+    }
+    let names = columns.names();
+    (0..reader.get_geometries().len())
+        .map(|i| columns.style(i as i64, &reader.attributes(i, &names)))
+        .collect()
+}
+
+/// Renders the bbox center at each preview scale and saves the montage as
+/// `<name>-preview.png`.
+///
+/// `styles[i]` applies to `geometries[i]`; `styles` may be empty.
+fn render_preview(
+    geometries: &[geo::MultiPolygon<f64>],
+    styles: &[FeatureStyle],
+    bbox: &Bbox,
+    config: &cli::Config,
+    name: &str,
+    claims: &mut output::OutputClaims,
+) -> Result<()> {
+    let mut panels = Vec::with_capacity(config.preview_scales.len());
+    for &scale in &config.preview_scales {
+        let resolution = math::scale_to_resolution(scale, bbox);
+        let (full_width, full_height) = math::calculate_dimensions(bbox, resolution);
+        logger::info(&format!(
+            "{}: {} m/pixel -> full render {}x{} pixels",
+            name, scale, full_width, full_height
+        ));
+
+        let window = preview::panel_bbox(bbox, resolution);
+        let mut renderer = Renderer::new(RenderConfig {
+            bbox: window,
+            resolution,
+            fill: config.fill,
+            stroke: config.stroke,
+            stroke_width: config.stroke_width,
+            vertex_color: config.vertex_color,
+            min_feature_px: config.min_feature_px,
+            max_dimension: config.max_dimension,
+            rotation: config.rotation,
+            glow: config.glow,
+        })?;
+        let features = geometries
+            .iter()
+            .enumerate()
+            .map(|(i, geom)| (geom, styles.get(i).copied().unwrap_or_default()));
+        render_features(&mut renderer, features, &window);
+        panels.push(renderer.into_image());
+    }
+
+    let output_path = output::output_path(
+        &config.output_dir,
+        config.output_layout,
+        &config.input,
+        &format!("{}-preview", name),
+        None,
+    );
+    let output_path = claims.claim(output_path, name, config.on_collision)?;
+    output::ensure_parent_dir(&output_path)?;
+    preview::montage(&panels).save(&output_path)?;
+    logger::output(&output_path.display().to_string());
+    ProgressEvent::FileSaved {
+        layer: name,
+        path: &output_path,
+    }
+    .emit(config.progress);
+    Ok(())
+}
+
+/// Reports what a render would produce for each input (layers, SRS, bbox,
+/// resolution, dimensions and memory) without rendering.
+async fn run_info(args: Args) -> Result<()> {
+    let config = prepare_render(args)?;
+    for input in &config.inputs {
+        let input_config = config.for_input(input);
+        match input_config.format {
+            #[cfg(feature = "gpkg")]
+            cli::Format::Gpkg => info_gpkg(input_config).await?,
+            #[cfg(not(feature = "gpkg"))]
+            cli::Format::Gpkg => {
+                return Err(GpkgError::FeatureDisabled(
+                    "GeoPackage".to_string(),
+                    "gpkg".to_string(),
+                ))
+            }
+            cli::Format::Geojson => info_geojson(input_config).await?,
+        }
+    }
+    Ok(())
+}
+
+/// Reports the render plan of a GeoPackage file.
+#[cfg(feature = "gpkg")]
+async fn info_gpkg(config: cli::Config) -> Result<()> {
+    let reader = GpkgReader::open(&config.input, &config.sqlite).await?;
+    logger::info(&format!("Input {}: GeoPackage", config.input.display()));
+    let Some(layers) = layers_to_render(&config, &reader).await? else {
+        return Ok(());
+    };
+
+    let inputs = gpkg_inputs(&config, &reader, &layers).await?;
+    let bbox = gpkg_bbox(&config, &inputs).await?;
+    let mut srs_ids = Vec::new();
+    for layer in &layers {
+        let filter = FeatureFilter {
+            bbox: config.bbox.map(|_| bbox.rotated_envelope(config.rotation)),
+            where_clauses: config.where_clauses_for(&layer.name),
+            limit: config.limit,
+            offset: config.offset,
+            fids: config.fids.clone(),
+        };
+        let mut count = 0;
+        for (reader, layer) in layer_sources(&inputs, &layer.name) {
+            count += reader.count_features(&layer, &filter).await?;
+        }
+        let name = output::sanitize_name(config.output_name_for(&layer.name));
+        logger::info(&format!(
+            "Layer {}: {} features, srs_id {} -> {}.png",
+            layer.name, count, layer.srs_id, name
+        ));
+        if !srs_ids.contains(&layer.srs_id) {
+            srs_ids.push(layer.srs_id);
+        }
+    }
+    for srs_id in srs_ids {
+        let definition = reader.get_srs_definition(srs_id).await?;
+        logger::info(&format!("SRS {}: {}", srs_id, definition));
+    }
+
+    report_plan(&config, &bbox, layers.len())
+}
+
+/// Reports the render plan of a GeoJSON file.
+async fn info_geojson(config: cli::Config) -> Result<()> {
+    let source = GeojsonSource::open(&config).await?;
+    logger::info(&format!(
+        "Input {}: GeoJSON, {} polygon feature(s) -> {}.png",
+        config.input.display(),
+        source.len(),
+        config.output_name.as_deref().unwrap_or_default()
+    ));
+    if config.source_crs.is_empty() {
+        match source.crs() {
+            Some(crs) if crs != geojson::WGS84 => {
+                logger::info(&format!("SRS: {} (declared) -> {}", crs, geojson::WGS84))
+            }
+            Some(crs) => logger::info(&format!("SRS: {} (declared)", crs)),
+            None => logger::info(&format!("SRS: {} (default)", geojson::WGS84)),
+        }
+    } else {
+        for input in config.geojson_inputs() {
+            let crs = input.source_crs.as_deref().unwrap_or("declared in the file");
+            logger::info(&format!("SRS of {}: {} -> {}", input.path.display(), crs, geojson::WGS84));
+        }
+    }
+
+    let bbox = geojson_bbox(&config, &source)?;
+    report_plan(&config, &bbox, 1)
+}
+
+/// Logs the bbox, grid and estimated memory of rendering `layers` over `bbox`.
+///
+/// Each layer being rendered holds an RGBA image; `--jobs` layers render at
+/// a time and an `--into` canvas is held on top of them.
+fn report_plan(config: &cli::Config, bbox: &Bbox, layers: usize) -> Result<()> {
+    logger::info(&format!(
+        "Bbox: {},{},{},{}{}",
+        bbox.min_lon,
+        bbox.min_lat,
+        bbox.max_lon,
+        bbox.max_lat,
+        if config.bbox.is_none() { " (auto-detected)" } else { "" }
+    ));
+    let image_bytes = |width: u32, height: u32| width as u64 * height as u64 * 4;
+
+    if !config.preview_scales.is_empty() {
+        for &scale in &config.preview_scales {
+            logger::info(&format!(
+                "Preview panel: {} m/pixel -> {:.10} deg/pixel, {}x{} pixels",
+                scale,
+                math::scale_to_resolution(scale, bbox),
+                preview::PANEL_SIZE,
+                preview::PANEL_SIZE
+            ));
+        }
+        // The panels of a layer and their montage
+        let bytes = image_bytes(preview::PANEL_SIZE, preview::PANEL_SIZE) * config.preview_scales.len() as u64 * 2;
+        logger::info(&format!("Estimated memory: {:.1} MiB", bytes as f64 / (1024.0 * 1024.0)));
+        return Ok(());
+    }
+
+    let resolution = render_resolution(config, bbox)?;
+    let (width, height) = math::calculate_dimensions(bbox, resolution);
+    if config.resolution.is_some() {
+        logger::info(&format!("Resolution: {:.10} deg/pixel", resolution));
+    }
+    if width > config.max_dimension || height > config.max_dimension {
+        logger::info(&format!(
+            "Dimensions: {}x{} pixels (exceeds --max-dimension {}, see --auto-fit)",
+            width,
+            height,
+            config.max_dimension
+        ));
+    } else {
+        logger::info(&format!("Dimensions: {}x{} pixels", width, height));
+    }
+
+    let concurrent = layers.clamp(1, config.jobs.max(1));
+    let canvas = config.into.is_some() as u64;
+    let bytes = image_bytes(width, height) * (concurrent as u64 + canvas);
+    logger::info(&format!(
+        "Estimated memory: {:.1} MiB ({} image(s) of {}x{} at a time)",
+        bytes as f64 / (1024.0 * 1024.0),
+        concurrent as u64 + canvas,
+        width,
+        height
+    ));
+    Ok(())
+}
+
+/// Describes each feature layer of the GeoPackage, one per line or as JSON.
+#[cfg(feature = "gpkg")]
+async fn run_list_layers(args: cli::ListLayersArgs) -> Result<()> {
+    if !args.input.exists() {
+        return Err(GpkgError::FileNotFound(args.input.display().to_string()));
+    }
+    let reader = GpkgReader::open(&args.input, &Default::default()).await?;
+    let layers = reader.list_feature_layers().await?;
+
+    if args.json {
+        let json = serde_json::Value::Array(layers.iter().map(|layer| layer.to_json()).collect());
+        println!("{}", serde_json::to_string_pretty(&json).expect("JSON values serialize"));
+        return Ok(());
+    }
+    for layer in &layers {
+        let srs = layer.srs.clone().unwrap_or_else(|| format!("srs_id {}", layer.srs_id));
+        let extent = match layer.wgs84_extent {
+            Some(b) => format!("extent {},{},{},{}", b.min_lon, b.min_lat, b.max_lon, b.max_lat),
+            None => "extent unknown".to_string(),
+        };
+        logger::info(&format!(
+            "{}: {}, {}, {} features, {}{}",
+            layer.name,
+            layer.geometry_type,
+            srs,
+            layer.feature_count,
+            extent,
+            if layer.is_polygon() { "" } else { " (not rendered)" }
+        ));
+    }
+    Ok(())
+}
+
+/// Computes and reports hole statistics for each layer of the input.
+async fn run_stats(args: cli::StatsArgs) -> Result<()> {
+    if !args.input.exists() {
+        return Err(GpkgError::FileNotFound(args.input.display().to_string()));
+    }
+
+    match args.format {
+        #[cfg(feature = "gpkg")]
+        cli::Format::Gpkg => {
+            let reader = GpkgReader::open(&args.input, &Default::default()).await?;
+            let layers = reader.list_polygon_layers().await?;
+            if let Some(name) = &args.layer {
+                if !layers.iter().any(|l| l.name == *name) {
+                    let available = layers.iter().map(|l| l.name.as_str()).collect::<Vec<_>>();
+                    return Err(GpkgError::LayerNotFound(name.clone(), available.join(", ")));
+                }
+            }
+            let selected = layers.iter().filter(|l| match &args.layer {
+                Some(name) => l.name == *name,
+                None => true,
+            });
+            for layer in selected {
+                let mut layer_stats = stats::HoleStats::default();
+                reader
+                    .for_each_feature(layer, |fid, mp| layer_stats.add(fid, &mp))
+                    .await?;
+                report_stats(&layer.name, "fid", &layer_stats);
+            }
+        }
+        #[cfg(not(feature = "gpkg"))]
+        cli::Format::Gpkg => {
+            return Err(GpkgError::FeatureDisabled(
+                "GeoPackage".to_string(),
+                "gpkg".to_string(),
+            ))
+        }
+        cli::Format::Geojson => {
+            if args.layer.is_some() {
+                return Err(GpkgError::InvalidFormatOption(
+                    "--layer cannot be used with geojson format".to_string(),
+                ));
+            }
+            let reader = GeojsonReader::open(&args.input).await?;
+            let mut file_stats = stats::HoleStats::default();
+            for (i, mp) in reader.get_geometries().iter().enumerate() {
+                file_stats.add(i + 1, mp);
+            }
+            let name = args.input.file_stem().and_then(|s| s.to_str()).unwrap_or("input");
+            report_stats(name, "feature", &file_stats);
+        }
+    }
+    Ok(())
+}
+
+/// Logs the statistics of one layer; `id_label` names the feature identifiers.
+fn report_stats(name: &str, id_label: &str, stats: &stats::HoleStats) {
+    logger::info(&format!(
+        "Layer {}: {} features, {} polygons, {} holes in {} features, max ring depth {}",
+        name, stats.features, stats.polygons, stats.holes, stats.features_with_holes, stats.max_depth
+    ));
+    if stats.holes_outside_count > 0 {
+        let more = stats.holes_outside_count - stats.holes_outside.len() as u64;
+        logger::warn(&format!(
+            "Layer {}: {} feature(s) with holes outside their exterior ring ({} {}{})",
+            name,
+            stats.holes_outside_count,
+            id_label,
+            stats.holes_outside.join(", "),
+            if more > 0 { format!(" and {} more", more) } else { String::new() }
+        ));
+    }
+}
+
+/// Prints what this binary was built with, as text or JSON.
+fn run_capabilities(args: cli::CapabilitiesArgs) {
+    let capabilities = capabilities::Capabilities::detect();
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&capabilities.to_json()).expect("JSON values serialize"));
+    } else {
+        for line in capabilities.lines() {
+            logger::info(&line);
+        }
+    }
+}
+
+/// Runs the environment checks and fails if any of them failed.
+async fn run_doctor() -> Result<()> {
+    let checks = doctor::run().await;
+    for check in &checks {
+        logger::info(&format!(
+            "[{}] {}: {}",
+            check.status.label(),
+            check.name,
+            check.detail
+        ));
+    }
+
+    let failed = checks
+        .iter()
+        .filter(|c| c.status == doctor::Status::Fail)
+        .count();
+    if failed > 0 {
+        return Err(GpkgError::DoctorFailed(failed));
+    }
+    Ok(())
+}
+
+/// Compare the alpha channel of two images and report the differences.
+fn compare_images(args: cli::CompareArgs) -> Result<()> {
+    let expected = image::open(&args.expected)?.to_rgba8();
+    let actual = image::open(&args.actual)?.to_rgba8();
+
+    let (changed, total, diff_image) = match args.mode {
+        cli::CompareMode::Coverage => {
+            let diff = compare::compare_alpha(&expected, &actual, args.tolerance)?;
+            report_changed(diff.changed_pixels, diff.total_pixels, diff.changed_percent());
+            logger::info(&format!(
+                "Alpha delta: max {}, mean {:.4}",
+                diff.max_delta, diff.mean_delta
+            ));
+            (diff.changed_pixels, diff.total_pixels, diff.diff_image)
+        }
+        cli::CompareMode::Color => {
+            let diff = compare::compare_rgba(&expected, &actual, args.tolerance)?;
+            report_changed(diff.changed_pixels, diff.total_pixels, diff.changed_percent());
+            for (c, name) in ["Red", "Green", "Blue", "Alpha"].iter().enumerate() {
+                logger::info(&format!(
+                    "{} delta: max {}, mean {:.4}",
+                    name, diff.max_delta[c], diff.mean_delta[c]
+                ));
+            }
+            (diff.changed_pixels, diff.total_pixels, diff.diff_image)
+        }
+    };
+
+    if let Some(path) = &args.diff {
+        diff_image.save(path)?;
+        logger::output(&path.display().to_string());
+    }
+
+    if args.fail_on_diff && changed > 0 {
+        return Err(GpkgError::ImagesDiffer { changed, total });
+    }
+
+    Ok(())
+}
+
+/// Logs the number and share of changed pixels of a comparison.
+fn report_changed(changed: u64, total: u64, percent: f64) {
+    logger::info(&format!("Changed pixels: {} / {} ({:.4}%)", changed, total, percent));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::burn::{BurnRule, BurnType};
+    use clap::Parser;
+
+    fn content_id_of(burn: Option<&str>, burn_rule: BurnRule, burn_type: BurnType) -> String {
+        let argv = ["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001"];
+        let mut config = Args::parse_from(argv).validate().unwrap();
+        config.burn = burn.map(str::to_string);
+        config.burn_rule = burn_rule;
+        config.burn_type = burn_type;
+        let bbox = Bbox::new(0.0, 0.0, 1.0, 1.0);
+        output::content_id("digest", "parcels", &content_settings(&config, "parcels", &bbox, 0.001))
+    }
+
+    #[test]
+    fn test_content_settings_cover_burn() {
+        let ids = [
+            content_id_of(None, BurnRule::Last, BurnType::Float64),
+            content_id_of(Some("pop"), BurnRule::Last, BurnType::Float64),
+            content_id_of(Some("density"), BurnRule::Last, BurnType::Float64),
+            content_id_of(Some("pop"), BurnRule::Sum, BurnType::Float64),
+            content_id_of(Some("pop"), BurnRule::Max, BurnType::Float64),
+            content_id_of(Some("pop"), BurnRule::Last, BurnType::Uint16),
+        ];
+        for (i, id) in ids.iter().enumerate() {
+            assert!(!ids[..i].contains(id), "{:?}", ids);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "gpkg")]
+    fn test_layer_cost() {
+        // 100 features of 10 vertices each
+        let cost = layer_cost(100, 100 * 10 * VERTEX_BYTES as u64, 2.0);
+        assert!((cost - 500.0).abs() < 1e-9);
+        // Denser vertices or a smaller extent cost more
+        assert!(layer_cost(100, 100 * 20 * VERTEX_BYTES as u64, 2.0) > cost);
+        assert!(layer_cost(100, 100 * 10 * VERTEX_BYTES as u64, 1.0) > cost);
+        assert_eq!(layer_cost(0, 0, 1.0), 0.0);
+    }
+}
@@ -0,0 +1,196 @@
+//! Attribute tables written next to renders (`--export-attributes`).
+//!
+//! Each rendered feature becomes a row holding its FID and its non-geometry
+//! columns, so the picture can be joined back to the data. Rows are streamed
+//! to a `.part` file as features are rendered, and the table is renamed into
+//! place once complete.
+
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::style::AttributeValue;
+
+/// File format of an attribute table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum AttributeFormat {
+    /// Comma-separated values with a header row (RFC 4180).
+    Csv,
+    /// A JSON array with one object per feature.
+    Json,
+}
+
+impl AttributeFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "attributes.csv",
+            Self::Json => "attributes.json",
+        }
+    }
+}
+
+/// Returns where the attribute table of an output is written:
+/// `<name>.attributes.csv` or `<name>.attributes.json`.
+pub fn table_path(path: &Path, format: AttributeFormat) -> PathBuf {
+    path.with_extension(format.extension())
+}
+
+/// Converts an attribute read from a GeoPackage to JSON; whole numbers are
+/// written as integers.
+pub fn json_value(value: &AttributeValue) -> Value {
+    match value {
+        AttributeValue::Null => Value::Null,
+        AttributeValue::Number(n) if n.fract() == 0.0 && n.abs() < 9.0e15 => Value::from(*n as i64),
+        AttributeValue::Number(n) => Value::from(*n),
+        AttributeValue::Text(s) => Value::from(s.as_str()),
+    }
+}
+
+/// Streams the rows of an attribute table.
+///
+/// A table dropped before [`AttributeWriter::finish`] (interrupted or
+/// skipped render) is removed.
+pub struct AttributeWriter {
+    format: AttributeFormat,
+    columns: Vec<String>,
+    writer: Option<BufWriter<File>>,
+    part: PathBuf,
+    path: PathBuf,
+    rows: u64,
+}
+
+impl AttributeWriter {
+    /// Starts the table of `columns` (after the FID) at `path`.
+    pub fn create(path: PathBuf, format: AttributeFormat, columns: Vec<String>) -> Result<Self> {
+        let mut part = path.as_os_str().to_owned();
+        part.push(".part");
+        let part = PathBuf::from(part);
+        let mut writer = BufWriter::new(File::create(&part)?);
+        match format {
+            AttributeFormat::Csv => {
+                let header: Vec<String> = std::iter::once("fid")
+                    .chain(columns.iter().map(String::as_str))
+                    .map(csv_field)
+                    .collect();
+                writeln!(writer, "{}", header.join(","))?;
+            }
+            AttributeFormat::Json => writer.write_all(b"[")?,
+        }
+        Ok(Self {
+            format,
+            columns,
+            writer: Some(writer),
+            part,
+            path,
+            rows: 0,
+        })
+    }
+
+    /// Appends the row of feature `fid`, with one value per column.
+    pub fn write(&mut self, fid: i64, values: &[Value]) -> Result<()> {
+        let Some(writer) = self.writer.as_mut() else {
+            return Ok(());
+        };
+        match self.format {
+            AttributeFormat::Csv => {
+                let mut fields = vec![fid.to_string()];
+                fields.extend(values.iter().map(|value| match value {
+                    Value::Null => String::new(),
+                    Value::String(s) => csv_field(s),
+                    other => csv_field(&other.to_string()),
+                }));
+                writeln!(writer, "{}", fields.join(","))?;
+            }
+            AttributeFormat::Json => {
+                // Written by hand to keep the column order
+                let separator = if self.rows == 0 { "\n" } else { ",\n" };
+                write!(writer, "{}{{\"fid\":{}", separator, fid)?;
+                for (column, value) in self.columns.iter().zip(values) {
+                    write!(writer, ",{}:{}", Value::from(column.as_str()), value)?;
+                }
+                writer.write_all(b"}")?;
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Completes the table, moves it into place and returns its path.
+    pub fn finish(mut self) -> Result<PathBuf> {
+        let mut writer = self.writer.take().expect("writer is only taken here");
+        if self.format == AttributeFormat::Json {
+            writer.write_all(b"\n]\n")?;
+        }
+        writer.flush()?;
+        drop(writer);
+        std::fs::rename(&self.part, &self.path)?;
+        Ok(std::mem::take(&mut self.path))
+    }
+}
+
+impl Drop for AttributeWriter {
+    fn drop(&mut self) {
+        if self.writer.take().is_some() {
+            let _ = std::fs::remove_file(&self.part);
+        }
+    }
+}
+
+/// Quotes a CSV field when it holds a separator, a quote or a line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let columns = vec!["name".to_string(), "population".to_string()];
+        let rows = [
+            (3, vec![Value::from("Brest, \"Finistère\""), json_value(&AttributeValue::Number(139_000.0))]),
+            (7, vec![json_value(&AttributeValue::Null), json_value(&AttributeValue::Number(0.5))]),
+        ];
+
+        let png = dir.path().join("towns.png");
+        let path = table_path(&png, AttributeFormat::Csv);
+        assert_eq!(path, dir.path().join("towns.attributes.csv"));
+        let mut writer = AttributeWriter::create(path.clone(), AttributeFormat::Csv, columns.clone()).unwrap();
+        for (fid, values) in &rows {
+            writer.write(*fid, values).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), path);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "fid,name,population\n3,\"Brest, \"\"Finistère\"\"\",139000\n7,,0.5\n"
+        );
+
+        let path = table_path(&png, AttributeFormat::Json);
+        let mut writer = AttributeWriter::create(path.clone(), AttributeFormat::Json, columns.clone()).unwrap();
+        for (fid, values) in &rows {
+            writer.write(*fid, values).unwrap();
+        }
+        writer.finish().unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("{\"fid\":3,\"name\":"));
+        let json: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(json[0]["fid"], 3);
+        assert_eq!(json[0]["population"], 139000);
+        assert!(json[1]["name"].is_null());
+
+        // An unfinished table leaves nothing behind
+        let path = dir.path().join("empty.attributes.json");
+        drop(AttributeWriter::create(path.clone(), AttributeFormat::Json, columns).unwrap());
+        assert!(!path.exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
@@ -0,0 +1,340 @@
+//! Attribute burn-in (`--burn`).
+//!
+//! Instead of a picture, the value of a numeric column is written into every
+//! pixel its feature covers, like `gdal_rasterize -a`. Covered spans come
+//! from the scanline renderer through its shader hook, so pixels match the
+//! PNG render of the same grid. The result is saved as a single-band float
+//! GeoTIFF in WGS84, with NaN (`GDAL_NODATA`) where no feature was burned, or
+//! as a 16-bit grayscale PNG whose alpha channel masks the unburned pixels.
+
+use geo::MultiPolygon;
+use image::Rgba;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use tiff::encoder::colortype::{ColorType, Gray32Float, Gray64Float};
+use tiff::encoder::TiffEncoder;
+use tiff::tags::Tag;
+
+use crate::antimeridian;
+use crate::error::Result;
+use crate::math::Bbox;
+use crate::render::{PngMetadata, Renderer};
+
+/// GeoKey directory of a geographic WGS84 raster with pixels as areas:
+/// GTModelType = Geographic, GTRasterType = PixelIsArea, GeographicType = EPSG:4326.
+const WGS84_GEO_KEYS: [u16; 16] = [1, 1, 0, 3, 1024, 0, 1, 2, 1025, 0, 1, 1, 2048, 0, 1, 4326];
+
+/// How the values of overlapping features combine in a pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum BurnRule {
+    /// Value of the last feature drawn over the pixel.
+    #[default]
+    Last,
+    /// Sum of the values of the features covering the pixel.
+    Sum,
+    /// Largest value of the features covering the pixel.
+    Max,
+}
+
+/// Sample type of a burned raster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum BurnType {
+    /// 64-bit float GeoTIFF: values are kept exactly.
+    #[default]
+    Float64,
+    /// 32-bit float GeoTIFF: half the size, about 7 significant digits.
+    Float32,
+    /// 16-bit grayscale PNG: values rounded to integers in 0-65535.
+    Uint16,
+}
+
+impl BurnType {
+    /// Extension of the files written for this type.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Float64 | Self::Float32 => "tif",
+            Self::Uint16 => "png",
+        }
+    }
+
+    /// Converts a burned value to this type's range; returns whether it was clamped.
+    fn clamp(self, value: f64) -> (f64, bool) {
+        let (min, max) = match self {
+            Self::Float64 => return (value, false),
+            Self::Float32 => (f32::MIN as f64, f32::MAX as f64),
+            Self::Uint16 => (0.0, u16::MAX as f64),
+        };
+        let value = if self == Self::Uint16 { value.round() } else { value };
+        (value.clamp(min, max), value < min || value > max)
+    }
+}
+
+/// Values burned into the pixels of a render, NaN where nothing was burned.
+pub struct BurnGrid {
+    width: u32,
+    rule: BurnRule,
+    /// One lock per row: bands are filled in parallel, on disjoint rows.
+    rows: Vec<Mutex<Vec<f64>>>,
+}
+
+impl BurnGrid {
+    /// Creates an empty `width` x `height` grid.
+    pub fn new(width: u32, height: u32, rule: BurnRule) -> Self {
+        let rows = (0..height)
+            .map(|_| Mutex::new(vec![f64::NAN; width as usize]))
+            .collect();
+        Self { width, rule, rows }
+    }
+
+    /// Returns the grid dimensions as (width, height).
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.rows.len() as u32)
+    }
+
+    /// Burns `value` into the pixels `x_range` of row `y`.
+    pub fn burn(&self, x_range: Range<u32>, y: u32, value: f64) {
+        let mut row = self.rows[y as usize].lock().unwrap_or_else(PoisonError::into_inner);
+        for pixel in &mut row[x_range.start as usize..x_range.end as usize] {
+            *pixel = match self.rule {
+                _ if pixel.is_nan() => value,
+                BurnRule::Last => value,
+                BurnRule::Sum => *pixel + value,
+                BurnRule::Max => pixel.max(value),
+            };
+        }
+    }
+
+    /// Returns the value of a pixel, None if no feature was burned into it.
+    pub fn value(&self, x: u32, y: u32) -> Option<f64> {
+        let value = self.rows[y as usize].lock().unwrap_or_else(PoisonError::into_inner)[x as usize];
+        (!value.is_nan()).then_some(value)
+    }
+
+    /// Saves the grid as `data_type`, on a grid of `resolution` degrees per
+    /// pixel whose bottom-left corner is that of `bbox`, as laid out by the renderer.
+    ///
+    /// GeoTIFFs carry the grid in their GeoKeys; PNGs carry `metadata`.
+    /// Written to a `.part` file first, renamed once complete. Returns the
+    /// number of pixels whose value was clamped to the range of `data_type`.
+    pub fn save(
+        &self,
+        path: &Path,
+        bbox: &Bbox,
+        resolution: f64,
+        data_type: BurnType,
+        metadata: &PngMetadata,
+    ) -> Result<u64> {
+        let mut clamped = 0;
+        let values: Vec<f64> = self
+            .rows
+            .iter()
+            .flat_map(|row| row.lock().unwrap_or_else(PoisonError::into_inner).clone())
+            .map(|value| {
+                if value.is_nan() {
+                    return value;
+                }
+                let (value, was_clamped) = data_type.clamp(value);
+                clamped += was_clamped as u64;
+                value
+            })
+            .collect();
+
+        let mut part = path.as_os_str().to_owned();
+        part.push(".part");
+        let part = PathBuf::from(part);
+        let writer = BufWriter::new(File::create(&part)?);
+        let written = match data_type {
+            BurnType::Float64 => self.write_geotiff::<Gray64Float, _>(writer, bbox, resolution, &values),
+            BurnType::Float32 => {
+                let values: Vec<f32> = values.iter().map(|&v| v as f32).collect();
+                self.write_geotiff::<Gray32Float, _>(writer, bbox, resolution, &values)
+            }
+            BurnType::Uint16 => self.write_png(writer, &values, metadata),
+        };
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&part);
+            return Err(e);
+        }
+        std::fs::rename(&part, path)?;
+        Ok(clamped)
+    }
+
+    fn write_geotiff<C, W>(&self, writer: W, bbox: &Bbox, resolution: f64, values: &[C::Inner]) -> Result<()>
+    where
+        C: ColorType,
+        [C::Inner]: tiff::encoder::TiffValue,
+        W: Write + std::io::Seek,
+    {
+        let (width, height) = self.dimensions();
+        let top = bbox.min_lat + height as f64 * resolution;
+        let mut tiff = TiffEncoder::new(writer)?;
+        let mut image = tiff.new_image::<C>(width, height)?;
+        let directory = image.encoder();
+        directory.write_tag(Tag::ModelPixelScaleTag, &[resolution, resolution, 0.0][..])?;
+        directory.write_tag(Tag::ModelTiepointTag, &[0.0, 0.0, 0.0, bbox.min_lon, top, 0.0][..])?;
+        directory.write_tag(Tag::GeoKeyDirectoryTag, &WGS84_GEO_KEYS[..])?;
+        directory.write_tag(Tag::GdalNodata, "nan")?;
+        image.write_data(values)?;
+        Ok(())
+    }
+
+    /// Writes 16-bit gray + alpha samples, transparent where nothing was burned.
+    fn write_png<W: Write>(&self, writer: W, values: &[f64], metadata: &PngMetadata) -> Result<()> {
+        let (width, height) = self.dimensions();
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::GrayscaleAlpha);
+        encoder.set_depth(png::BitDepth::Sixteen);
+        metadata.add_chunks(&mut encoder)?;
+        // PNG samples are big-endian
+        let mut data = Vec::with_capacity(values.len() * 4);
+        for &value in values {
+            let (gray, alpha) = if value.is_nan() { (0, 0) } else { (value as u16, u16::MAX) };
+            data.extend_from_slice(&gray.to_be_bytes());
+            data.extend_from_slice(&alpha.to_be_bytes());
+        }
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Burns the value of each WGS84 feature into `grid` in one renderer sweep,
+/// wrapping each geometry for the bbox like [`crate::render_features`].
+///
+/// Replaces the renderer's shader; the renderer's image is left untouched.
+pub fn burn_features<'a, I>(renderer: &mut Renderer, grid: &Arc<BurnGrid>, features: I, bbox: &Bbox)
+where
+    I: IntoIterator<Item = (&'a MultiPolygon<f64>, f64)>,
+{
+    let (copies, values): (Vec<_>, Vec<_>) = features
+        .into_iter()
+        .flat_map(|(geom, value)| {
+            antimeridian::wrap_for_bbox(geom, bbox)
+                .into_iter()
+                .map(move |copy| (copy, value))
+        })
+        .unzip();
+    let grid = Arc::clone(grid);
+    renderer.set_shader(move |x_range, y, feature| {
+        grid.burn(x_range, y, values[feature]);
+        Rgba([0, 0, 0, 0])
+    });
+    renderer.render_layer(&copies);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{RenderConfig, MAX_DIMENSION};
+    use geo::polygon;
+
+    fn square(x: f64, y: f64, size: f64) -> MultiPolygon<f64> {
+        MultiPolygon::new(vec![polygon![
+            (x: x, y: y),
+            (x: x + size, y: y),
+            (x: x + size, y: y + size),
+            (x: x, y: y + size),
+        ]])
+    }
+
+    fn burn(rule: BurnRule) -> Arc<BurnGrid> {
+        let bbox = Bbox::new(0.0, 0.0, 10.0, 10.0);
+        let mut renderer = Renderer::new(RenderConfig {
+            bbox,
+            resolution: 1.0,
+            fill: Some([255, 0, 0, 255]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        })
+        .unwrap();
+        let grid = Arc::new(BurnGrid::new(10, 10, rule));
+        let (a, b) = (square(0.0, 0.0, 6.0), square(4.0, 4.0, 6.0));
+        burn_features(&mut renderer, &grid, [(&a, 2.0), (&b, 5.0)], &bbox);
+        // The shader leaves the image transparent
+        assert!(renderer.to_image().pixels().all(|p| p.0[3] == 0));
+        grid
+    }
+
+    #[test]
+    fn test_burn_rules() {
+        // Pixel (5, 4) lies in both squares, (1, 8) only in the first
+        let last = burn(BurnRule::Last);
+        assert_eq!(last.value(5, 4), Some(5.0));
+        assert_eq!(last.value(1, 8), Some(2.0));
+        assert_eq!(last.value(8, 8), None);
+        assert_eq!(burn(BurnRule::Sum).value(5, 4), Some(7.0));
+        assert_eq!(burn(BurnRule::Max).value(5, 4), Some(5.0));
+
+        let grid = BurnGrid::new(4, 1, BurnRule::Max);
+        grid.burn(0..4, 0, 3.0);
+        grid.burn(1..2, 0, -1.0);
+        assert_eq!(grid.value(1, 0), Some(3.0));
+    }
+
+    #[test]
+    fn test_save_geotiff() {
+        let dir = tempfile::tempdir().unwrap();
+        let bbox = Bbox::new(0.0, 0.0, 10.0, 10.0);
+        let grid = burn(BurnRule::Sum);
+        for (data_type, name) in [(BurnType::Float64, "burn64.tif"), (BurnType::Float32, "burn32.tif")] {
+            let path = dir.path().join(name);
+            let clamped = grid.save(&path, &bbox, 1.0, data_type, &PngMetadata::default()).unwrap();
+            assert_eq!(clamped, 0);
+
+            let mut decoder = tiff::decoder::Decoder::new(File::open(&path).unwrap()).unwrap();
+            assert_eq!(decoder.dimensions().unwrap(), (10, 10));
+            let tiepoint = decoder.get_tag_f64_vec(Tag::ModelTiepointTag).unwrap();
+            assert_eq!(tiepoint[3..5], [0.0, 10.0]);
+            assert_eq!(decoder.get_tag_ascii_string(Tag::GdalNodata).unwrap(), "nan");
+            let values: Vec<f64> = match decoder.read_image().unwrap() {
+                tiff::decoder::DecodingResult::F64(values) => values,
+                tiff::decoder::DecodingResult::F32(values) => values.into_iter().map(f64::from).collect(),
+                _ => panic!("expected a float image"),
+            };
+            assert_eq!(values[4 * 10 + 5], 7.0);
+            assert!(values[8 * 10 + 8].is_nan());
+        }
+        assert!(!dir.path().join("burn64.tif.part").exists());
+    }
+
+    #[test]
+    fn test_save_png16() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("burn.png");
+        let grid = BurnGrid::new(3, 1, BurnRule::Last);
+        grid.burn(0..1, 0, 1234.4);
+        grid.burn(1..2, 0, 70000.0);
+        let metadata = PngMetadata {
+            xmp: None,
+            text: vec![("Layer".to_string(), "zones".to_string())],
+        };
+        let clamped = grid
+            .save(&path, &Bbox::new(0.0, 0.0, 3.0, 1.0), 1.0, BurnType::Uint16, &metadata)
+            .unwrap();
+        assert_eq!(clamped, 1);
+
+        let decoder = png::Decoder::new(std::io::BufReader::new(File::open(&path).unwrap()));
+        let mut reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.color_type, info.bit_depth), (png::ColorType::GrayscaleAlpha, png::BitDepth::Sixteen));
+        assert_eq!(info.uncompressed_latin1_text[0].text, "zones");
+        let mut data = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut data).unwrap();
+        let sample = |i: usize| u16::from_be_bytes([data[i * 2], data[i * 2 + 1]]);
+        assert_eq!((sample(0), sample(1)), (1234, u16::MAX));
+        assert_eq!((sample(2), sample(3)), (u16::MAX, u16::MAX));
+        // Nothing burned: transparent
+        assert_eq!(sample(5), 0);
+    }
+}
@@ -0,0 +1,184 @@
+//! Existing renders used as base canvases (`--into`).
+//!
+//! The grid of a canvas (bbox and resolution) comes from a world file next to
+//! the PNG (`.pgw`, `.pngw` or `.wld`), or else from the bbox embedded by
+//! `--geotag`. New layers can only be drawn on a canvas with the same grid.
+
+use image::RgbaImage;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use crate::error::{GpkgError, Result};
+use crate::geotag;
+use crate::math::{calculate_dimensions, Bbox};
+
+/// Extensions of world files looked up next to a canvas, in order.
+const WORLD_FILE_EXTENSIONS: [&str; 3] = ["pgw", "pngw", "wld"];
+
+/// Relative difference tolerated between two grids (rounding of printed values).
+const GRID_TOLERANCE: f64 = 1e-6;
+
+/// Pixel grid of a canvas.
+#[derive(Debug, Clone, Copy)]
+pub struct CanvasGrid {
+    pub bbox: Bbox,
+    /// Resolution in degrees per pixel.
+    pub resolution: f64,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CanvasGrid {
+    /// Checks that rendering `bbox` at `resolution` produces this grid.
+    pub fn check(&self, path: &Path, bbox: &Bbox, resolution: f64) -> Result<()> {
+        let mismatch = |detail: String| GpkgError::Canvas(path.display().to_string(), detail);
+        if ((resolution - self.resolution) / self.resolution).abs() > GRID_TOLERANCE {
+            return Err(mismatch(format!(
+                "resolution {} does not match the canvas resolution {}",
+                resolution, self.resolution
+            )));
+        }
+        let (width, height) = calculate_dimensions(bbox, resolution);
+        let corners = [
+            (bbox.min_lon, self.bbox.min_lon),
+            (bbox.min_lat, self.bbox.min_lat),
+            (bbox.max_lon, self.bbox.max_lon),
+            (bbox.max_lat, self.bbox.max_lat),
+        ];
+        // Corners may differ by less than a pixel when dimensions were rounded up
+        let misaligned = corners.iter().any(|(a, b)| (a - b).abs() >= resolution);
+        if (width, height) != (self.width, self.height) || misaligned {
+            return Err(mismatch(format!(
+                "bbox {},{},{},{} ({}x{}) does not match the canvas {},{},{},{} ({}x{})",
+                bbox.min_lon,
+                bbox.min_lat,
+                bbox.max_lon,
+                bbox.max_lat,
+                width,
+                height,
+                self.bbox.min_lon,
+                self.bbox.min_lat,
+                self.bbox.max_lon,
+                self.bbox.max_lat,
+                self.width,
+                self.height
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Reads the grid of the PNG at `path` without decoding its pixels.
+pub fn read_grid(path: &Path) -> Result<CanvasGrid> {
+    if !path.exists() {
+        return Err(GpkgError::FileNotFound(path.display().to_string()));
+    }
+    let (width, height) = image::image_dimensions(path)?;
+    let (bbox, resolution) = match world_file_path(path) {
+        Some(world) => parse_world_file(&std::fs::read_to_string(world)?, width, height)
+            .map_err(|detail| GpkgError::Canvas(path.display().to_string(), detail))?,
+        None => {
+            let bbox = read_geotag_bbox(path)?.ok_or_else(|| {
+                GpkgError::Canvas(
+                    path.display().to_string(),
+                    "no world file (.pgw) and no --geotag bbox to locate it".to_string(),
+                )
+            })?;
+            (bbox, bbox.width() / width as f64)
+        }
+    };
+    Ok(CanvasGrid {
+        bbox,
+        resolution,
+        width,
+        height,
+    })
+}
+
+/// Loads the pixels of a canvas.
+pub fn read_image(path: &Path) -> Result<RgbaImage> {
+    Ok(image::open(path)?.to_rgba8())
+}
+
+/// Returns the world file next to `png`, if any.
+pub fn world_file_path(png: &Path) -> Option<PathBuf> {
+    WORLD_FILE_EXTENSIONS
+        .iter()
+        .map(|ext| png.with_extension(ext))
+        .find(|path| path.exists())
+}
+
+/// Parses a world file into the bbox of a `width` x `height` image and its resolution.
+///
+/// The six lines are the pixel width, two rotation terms, the (negative)
+/// pixel height and the center of the upper-left pixel.
+fn parse_world_file(text: &str, width: u32, height: u32) -> std::result::Result<(Bbox, f64), String> {
+    let values = text
+        .split_whitespace()
+        .map(str::parse::<f64>)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| "world file holds a non-numeric value".to_string())?;
+    let [size_x, rot_y, rot_x, size_y, center_x, center_y] = values[..] else {
+        return Err(format!("world file needs 6 values, got {}", values.len()));
+    };
+    if rot_x != 0.0 || rot_y != 0.0 {
+        return Err("rotated world files are not supported".to_string());
+    }
+    if size_x <= 0.0 || ((size_x + size_y) / size_x).abs() > GRID_TOLERANCE {
+        return Err(format!("pixels must be square, got {} x {}", size_x, -size_y));
+    }
+    let min_lon = center_x - size_x / 2.0;
+    let max_lat = center_y + size_x / 2.0;
+    let bbox = Bbox::new(
+        min_lon,
+        max_lat - height as f64 * size_x,
+        min_lon + width as f64 * size_x,
+        max_lat,
+    );
+    Ok((bbox, size_x))
+}
+
+/// Reads the bbox written by `--geotag`, if the PNG has one.
+fn read_geotag_bbox(path: &Path) -> Result<Option<Bbox>> {
+    let decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    let reader = decoder.read_info()?;
+    let bbox = reader
+        .info()
+        .utf8_text
+        .iter()
+        .filter(|chunk| chunk.keyword == geotag::XMP_KEYWORD)
+        .filter_map(|chunk| chunk.get_text().ok())
+        .find_map(|xmp| geotag::parse_bbox(&xmp));
+    Ok(bbox)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_world_file() {
+        let (bbox, resolution) = parse_world_file("0.5\n0\n0\n-0.5\n10.25\n49.75\n", 4, 2).unwrap();
+        assert_eq!(resolution, 0.5);
+        assert_eq!((bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat), (10.0, 49.0, 12.0, 50.0));
+
+        assert!(parse_world_file("0.5 0 0 -0.25 10 50", 4, 2).is_err());
+        assert!(parse_world_file("0.5 0.1 0 -0.5 10 50", 4, 2).is_err());
+        assert!(parse_world_file("0.5 0 0 -0.5", 4, 2).is_err());
+    }
+
+    #[test]
+    fn test_grid_check() {
+        let grid = CanvasGrid {
+            bbox: Bbox::new(10.0, 49.0, 12.0, 50.0),
+            resolution: 0.5,
+            width: 4,
+            height: 2,
+        };
+        let path = Path::new("canvas.png");
+        assert!(grid.check(path, &Bbox::new(10.0, 49.0, 12.0, 50.0), 0.5).is_ok());
+        assert!(grid.check(path, &Bbox::new(10.0, 49.0, 12.0, 50.0), 0.25).is_err());
+        assert!(grid.check(path, &Bbox::new(10.5, 49.0, 12.5, 50.0), 0.5).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+//! What this binary was built with (`capabilities` subcommand).
+//!
+//! Services submitting jobs to the CLI read this listing to feature-detect:
+//! accepted inputs, produced outputs, usable commands, reprojection backends
+//! and the CRS each path works in all depend on the cargo features compiled in.
+
+use clap::CommandFactory;
+use serde_json::{json, Value};
+
+use crate::cli::Cli;
+
+/// Commands that need GeoPackage support (`gpkg` feature).
+const GPKG_COMMANDS: [&str; 4] = ["list-layers", "tiles", "serve", "animate"];
+
+/// Cargo features of the binary that change what it can do.
+pub fn enabled_features() -> Vec<&'static str> {
+    [
+        ("gpkg", cfg!(feature = "gpkg")),
+        ("reproject", cfg!(feature = "reproject")),
+        ("reproject-pure", cfg!(feature = "reproject-pure")),
+        ("bundled-proj-data", cfg!(feature = "bundled-proj-data")),
+    ]
+    .into_iter()
+    .filter_map(|(name, on)| on.then_some(name))
+    .collect()
+}
+
+/// Capabilities compiled into this binary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    pub version: &'static str,
+    pub features: Vec<&'static str>,
+    /// Commands usable in this build.
+    pub commands: Vec<String>,
+    /// Values accepted by `--format`.
+    pub input_formats: Vec<&'static str>,
+    pub output_formats: Vec<&'static str>,
+    /// Reprojection backends, in the order they are tried.
+    pub backends: Vec<&'static str>,
+    /// Whether WKT definitions are understood (otherwise only their EPSG code is used).
+    pub wkt: bool,
+    /// Whether datum grid shifts are applied.
+    pub datum_grids: bool,
+    /// CRS of the XYZ tiles and of WMS requests (empty without `gpkg`).
+    pub tile_crs: Vec<&'static str>,
+    pub wms_crs: Vec<&'static str>,
+}
+
+impl Capabilities {
+    /// Describes the running binary.
+    pub fn detect() -> Self {
+        let gpkg = cfg!(feature = "gpkg");
+        let commands = Cli::command()
+            .get_subcommands()
+            .map(|command| command.get_name().to_string())
+            .filter(|name| gpkg || !GPKG_COMMANDS.contains(&name.as_str()))
+            .collect();
+        let input_formats = if gpkg { vec!["gpkg", "geojson"] } else { vec!["geojson"] };
+        let backends = [
+            ("proj", cfg!(feature = "reproject")),
+            ("proj4rs", cfg!(feature = "reproject-pure")),
+        ]
+        .into_iter()
+        .filter_map(|(name, on)| on.then_some(name))
+        .collect();
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            features: enabled_features(),
+            commands,
+            input_formats,
+            output_formats: vec!["png"],
+            backends,
+            wkt: cfg!(feature = "reproject"),
+            datum_grids: cfg!(feature = "reproject"),
+            tile_crs: if gpkg { vec!["EPSG:3857"] } else { vec![] },
+            wms_crs: if gpkg { vec!["EPSG:4326", "CRS:84", "EPSG:3857"] } else { vec![] },
+        }
+    }
+
+    /// Whether inputs in another CRS than WGS84 can be rendered.
+    pub fn reprojects(&self) -> bool {
+        !self.backends.is_empty()
+    }
+
+    /// Serializes the capabilities as a JSON object.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "version": self.version,
+            "features": self.features,
+            "commands": self.commands,
+            "input_formats": self.input_formats,
+            "output_formats": self.output_formats,
+            "reprojection": {
+                "backends": self.backends,
+                "wkt": self.wkt,
+                "datum_grids": self.datum_grids,
+            },
+            "crs": {
+                // Renders are plate carrée images of a lon/lat bbox
+                "output": "EPSG:4326",
+                "inputs": if self.reprojects() { "any (reprojected to EPSG:4326)" } else { "EPSG:4326 only" },
+                "tiles": self.tile_crs,
+                "wms": self.wms_crs,
+            },
+        })
+    }
+
+    /// Human-readable summary, one line per topic.
+    pub fn lines(&self) -> Vec<String> {
+        let list = |items: &[&str]| if items.is_empty() { "none".to_string() } else { items.join(", ") };
+        let reprojection = if self.reprojects() {
+            let mut detail = list(&self.backends);
+            if !self.wkt {
+                detail.push_str(" (EPSG codes only, no datum grids)");
+            }
+            detail
+        } else {
+            "none (inputs must be in EPSG:4326)".to_string()
+        };
+        let commands: Vec<&str> = self.commands.iter().map(String::as_str).collect();
+        vec![
+            format!("Version: {}", self.version),
+            format!("Features: {}", list(&self.features)),
+            format!("Commands: {}", list(&commands)),
+            format!("Input formats: {}", list(&self.input_formats)),
+            format!("Output formats: {}", list(&self.output_formats)),
+            format!("Reprojection: {}", reprojection),
+            format!("Tile CRS: {}; WMS CRS: {}", list(&self.tile_crs), list(&self.wms_crs)),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let capabilities = Capabilities::detect();
+        assert!(capabilities.commands.iter().any(|c| c == "render"));
+        assert!(capabilities.commands.iter().any(|c| c == "capabilities"));
+        assert_eq!(capabilities.commands.iter().any(|c| c == "serve"), cfg!(feature = "gpkg"));
+        assert_eq!(capabilities.input_formats.contains(&"gpkg"), cfg!(feature = "gpkg"));
+
+        let json = capabilities.to_json();
+        assert_eq!(json["output_formats"], json!(["png"]));
+        assert_eq!(json["reprojection"]["backends"].as_array().unwrap().len(), capabilities.backends.len());
+        assert_eq!(json["crs"]["output"], "EPSG:4326");
+    }
+}
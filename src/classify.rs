@@ -0,0 +1,127 @@
+//! Classification of attribute values for choropleths (`--classify`).
+//!
+//! The values of a `--fill-column` are read in a pre-pass and split into
+//! classes; each feature then takes the ramp color of its class instead of a
+//! color proportional to its value.
+
+/// Above this many values, natural breaks are computed on an even sample of
+/// the sorted values: the optimization is quadratic in their number.
+const MAX_JENKS_VALUES: usize = 2000;
+
+/// How values are split into classes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Classification {
+    /// Classes of equal width between the smallest and largest value.
+    EqualInterval,
+    /// Classes holding the same number of values.
+    Quantile,
+    /// Jenks natural breaks: classes minimizing the variance within them.
+    Jenks,
+}
+
+impl Classification {
+    /// Returns the upper bounds of the first `classes - 1` classes of
+    /// `values`, in increasing order. A value equal to a bound belongs to the
+    /// lower class. Non-finite values are ignored; fewer values than classes
+    /// give fewer breaks.
+    pub fn breaks(self, values: &[f64], classes: usize) -> Vec<f64> {
+        let mut sorted: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len();
+        if n == 0 || classes < 2 {
+            return Vec::new();
+        }
+        match self {
+            Self::EqualInterval => {
+                let (min, max) = (sorted[0], sorted[n - 1]);
+                (1..classes).map(|i| min + (max - min) * i as f64 / classes as f64).collect()
+            }
+            Self::Quantile => (1..classes).map(|i| sorted[(i * n / classes).saturating_sub(1)]).collect(),
+            Self::Jenks => {
+                if n > MAX_JENKS_VALUES {
+                    let last = n - 1;
+                    sorted = (0..MAX_JENKS_VALUES)
+                        .map(|i| sorted[i * last / (MAX_JENKS_VALUES - 1)])
+                        .collect();
+                }
+                jenks_breaks(&sorted, classes)
+            }
+        }
+    }
+}
+
+/// Fisher's exact optimization of the natural breaks of `sorted`: dynamic
+/// programming over the best split of each prefix into each class count.
+fn jenks_breaks(sorted: &[f64], classes: usize) -> Vec<f64> {
+    let n = sorted.len();
+    let classes = classes.min(n);
+    // Sums of the centered values and their squares, for the variance of any run
+    let mean = sorted.iter().sum::<f64>() / n as f64;
+    let mut sums = vec![(0.0, 0.0); n + 1];
+    for (i, v) in sorted.iter().enumerate() {
+        let d = v - mean;
+        sums[i + 1] = (sums[i].0 + d, sums[i].1 + d * d);
+    }
+    // Sum of squared deviations of sorted[a..=b]
+    let deviation = |a: usize, b: usize| {
+        let (s, s2) = (sums[b + 1].0 - sums[a].0, sums[b + 1].1 - sums[a].1);
+        s2 - s * s / (b - a + 1) as f64
+    };
+
+    // cost[m]: smallest deviation of sorted[..=m] in the current class count;
+    // starts[j][m]: first value of the last class in the best split in j + 1 classes
+    let mut cost: Vec<f64> = (0..n).map(|m| deviation(0, m)).collect();
+    let mut starts = vec![vec![0; n]; classes];
+    for class_starts in starts.iter_mut().skip(1) {
+        let previous = cost.clone();
+        for m in (0..n).rev() {
+            let best = (1..=m)
+                .map(|i| (previous[i - 1] + deviation(i, m), i))
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            if let Some((total, start)) = best {
+                cost[m] = total;
+                class_starts[m] = start;
+            }
+        }
+    }
+
+    let mut breaks = Vec::with_capacity(classes - 1);
+    let mut last = n - 1;
+    for class_starts in starts[1..].iter().rev() {
+        let start = class_starts[last];
+        if start == 0 {
+            break;
+        }
+        breaks.push(sorted[start - 1]);
+        last = start - 1;
+    }
+    breaks.reverse();
+    breaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaks() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0];
+        assert_eq!(Classification::EqualInterval.breaks(&values, 3), vec![4.0, 7.0]);
+        assert_eq!(Classification::Quantile.breaks(&values, 5), vec![2.0, 4.0, 6.0, 8.0]);
+        assert_eq!(Classification::Quantile.breaks(&values[..3], 2), vec![1.0]);
+
+        // Natural breaks fall in the gaps between clusters
+        let values = [1.0, 1.5, 2.0, 10.0, 11.0, 12.0, 50.0, 52.0, f64::NAN];
+        assert_eq!(Classification::Jenks.breaks(&values, 3), vec![2.0, 12.0]);
+        assert_eq!(Classification::Jenks.breaks(&values, 2), vec![12.0]);
+        assert_eq!(Classification::Jenks.breaks(&[5.0, 1.0], 4), vec![1.0]);
+
+        // Large inputs are sampled
+        let values: Vec<f64> = (0..10_000).map(|i| if i < 5000 { i as f64 } else { 1e6 + i as f64 }).collect();
+        let breaks = Classification::Jenks.breaks(&values, 2);
+        assert!(breaks.len() == 1 && (4990.0..5000.0).contains(&breaks[0]), "{:?}", breaks);
+
+        assert!(Classification::Quantile.breaks(&[], 5).is_empty());
+    }
+}
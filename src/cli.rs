@@ -1,11 +1,35 @@
 //! CLI argument parsing and validation.
 
-use clap::Parser;
-use std::path::PathBuf;
-
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser};
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::attributes::AttributeFormat;
+use crate::burn::{BurnRule, BurnType};
+use crate::classify::Classification;
 use crate::error::{GpkgError, Result};
-use crate::logger::VerbosityLevel;
-use crate::math::Bbox;
+use crate::geojson::{self, GeojsonInput};
+use crate::gpkg::{validate_where_clause, FidSelection, SqlitePragmas, TempStore, DEFAULT_CURVE_TOLERANCE};
+use crate::logger::{ColorMode, VerbosityLevel};
+use crate::math::{Bbox, Padding};
+use crate::output::{self, CollisionPolicy, OutputLayout};
+use crate::progress::ProgressMode;
+use crate::ramp::Ramp;
+use crate::render::encode::{PngCompression, PngFilter, PngOptions};
+use crate::render::{Glow, MAX_DIMENSION};
+use crate::reproject;
+use crate::resample::Resample;
+use crate::style;
+use crate::warning::WarningCode;
+
+mod toml;
+
+use toml::Value;
+
+/// Options excluding each other that clap does not know about (checked by `validate`).
+const EXCLUSIVE_OPTIONS: [(&str, &str); 2] = [("verbose", "quiet"), ("resolution", "scale")];
 
 /// Input file format
 #[derive(Clone, Debug, clap::ValueEnum)]
@@ -14,23 +38,43 @@ pub enum Format {
     Geojson,
 }
 
-/// Command line arguments for gpkg-to-png.
+/// Command line interface of gpkg-to-png.
 #[derive(Parser, Debug)]
 #[command(name = "gpkg-to-png")]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, arg_required_else_help = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Arguments for the `render` command.
+#[derive(Parser, Debug)]
 pub struct Args {
-    /// Path to the input file (.gpkg or .geojson).
-    pub input: PathBuf,
+    /// Input files (.gpkg, .geojson, or .geojsonl/.ndjson text sequences); quoted globs like "data/*.gpkg" are expanded.
+    #[arg(required_unless_present = "config", value_name = "INPUT")]
+    pub inputs: Vec<PathBuf>,
 
-    /// Enable verbose output (display debug information).
-    #[arg(short, long)]
-    pub verbose: bool,
+    /// TOML file setting options and per-layer styles (options given on the command line take precedence).
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Styles of the `[layers.NAME]` tables of the config file.
+    #[arg(skip)]
+    pub layer_styles: Vec<(String, LayerStyle)>,
+
+    /// Verbose output: -v adds debug details, -vv also a line per chunk of geometries (env: GPKG_TO_PNG_VERBOSITY).
+    #[arg(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
 
     /// Suppress non-error output (quiet mode).
     #[arg(short, long)]
     pub quiet: bool,
 
-    /// Disable colored output (auto-detected by default).
+    /// When to color log output (auto: on a terminal, unless NO_COLOR is set).
+    #[arg(long, value_enum, env = "GPKG_TO_PNG_COLOR", default_value_t = ColorMode::Auto)]
+    pub color: ColorMode,
+
+    /// Disable colored output (same as --color never).
     #[arg(long)]
     pub no_color: bool,
 
@@ -38,10 +82,115 @@ pub struct Args {
     #[arg(short, long, default_value = ".")]
     pub output_dir: PathBuf,
 
+    /// How output files are arranged inside the output directory.
+    #[arg(long, value_enum, default_value_t = OutputLayout::Flat)]
+    pub output_layout: OutputLayout,
+
+    /// Name outputs by a hash of the input content, layer and settings (skips existing files).
+    #[arg(long)]
+    pub content_addressed: bool,
+
+    /// Skip the outputs completed by the interrupted or failed previous run into the output directory.
+    #[arg(long)]
+    pub resume: bool,
+
+    /// When renders of a run map to the same file (layer names equal once sanitized): suffix later ones or fail.
+    #[arg(long, value_enum, default_value_t = CollisionPolicy::Suffix)]
+    pub on_collision: CollisionPolicy,
+
+    /// Output file name pattern, e.g. "{layer}_{scale}m_{date}.png" (placeholders: layer, input, bbox, resolution, scale, date, time, timestamp).
+    #[arg(long, value_name = "TEMPLATE", conflicts_with_all = ["content_addressed", "into", "preview_scales"])]
+    pub name_template: Option<String>,
+
+    /// Draw onto this existing render (located by its world file or --geotag bbox) and save it in place.
+    #[arg(long, value_name = "PNG")]
+    pub into: Option<PathBuf>,
+
+    /// Embed the bbox and its center as XMP GPS metadata in output PNGs.
+    #[arg(long)]
+    pub geotag: bool,
+
+    /// Write a JSON list of the produced files, their grids and timings (default: <output-dir>/manifest.json).
+    #[arg(long, value_name = "PATH", num_args = 0..=1, require_equals = true)]
+    pub manifest: Option<Option<PathBuf>>,
+
+    /// Write a STAC Item (`<name>.stac.json`) next to every produced file.
+    #[arg(long, conflicts_with = "rotation")]
+    pub stac: bool,
+
+    /// Write a GeoJSON footprint (`<name>.footprint.geojson`) of the extent of every produced file.
+    #[arg(long)]
+    pub footprint: bool,
+
+    /// Compare every produced file with its approved version at the same path under DIR, and fail when one diverges.
+    #[arg(long, value_name = "DIR")]
+    pub verify_against: Option<PathBuf>,
+
+    /// Channel difference (0-255) still considered unchanged by --verify-against.
+    #[arg(long, value_name = "N", default_value = "0", requires = "verify_against")]
+    pub verify_tolerance: u8,
+
+    /// Share of changed pixels, in percent, still accepted by --verify-against.
+    #[arg(long, value_name = "PERCENT", default_value = "0", requires = "verify_against")]
+    pub verify_threshold: f64,
+
+    /// Leave out the text chunks describing the render (bbox, resolution, CRS, source, layer, command line).
+    #[arg(long)]
+    pub no_metadata: bool,
+
+    /// PNG compression effort: none and fast encode very large images much faster, best gives the smallest files.
+    #[arg(long, value_enum, default_value_t = PngCompression::Default)]
+    pub png_compression: PngCompression,
+
+    /// PNG row filter (adaptive tries each filter per row; a fixed filter encodes faster).
+    #[arg(long, value_enum, default_value_t = PngFilter::Adaptive)]
+    pub png_filter: PngFilter,
+
+    /// Write Adam7-interlaced PNGs, which display progressively while downloading (larger and slower to encode).
+    #[arg(long)]
+    pub interlace: bool,
+
+    /// Write paletted PNGs of at most N colors (2-256), much smaller for flat renders.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(2..=256))]
+    pub colors: Option<u16>,
+
+    /// Dither paletted PNGs (Floyd–Steinberg), so translucent overlaps and gradients do not band.
+    #[arg(long, requires = "colors")]
+    pub dither: bool,
+
+    /// On Ctrl-C, save the layer being rendered as `<name>.partial.png` instead of discarding it.
+    #[arg(long)]
+    pub save_partial: bool,
+
     /// Bounding box: "minLon,minLat,maxLon,maxLat" (auto-detected from GPKG if not provided).
     #[arg(short, long)]
     pub bbox: Option<String>,
 
+    /// CRS of the --bbox coordinates (e.g. "EPSG:2154"), reprojected to WGS84; defaults to WGS84 degrees.
+    #[arg(long, value_name = "CRS", requires = "bbox")]
+    pub bbox_crs: Option<String>,
+
+    /// Margin around the bbox (given or detected) on each side: "5%" of its size, "500m", or degrees like "0.01".
+    #[arg(long, value_name = "PADDING")]
+    pub padding: Option<String>,
+
+    /// Expand the bbox symmetrically to a width:height ratio, e.g. "16:9" or "1.5".
+    #[arg(long, value_name = "W:H")]
+    pub aspect: Option<String>,
+
+    /// Expand the bbox symmetrically to a square (same as --aspect 1:1).
+    #[arg(long, conflicts_with = "aspect")]
+    pub square: bool,
+
+    /// Rotate the map clockwise by DEGREES around the bbox center; the image grows to hold the rotated bbox.
+    #[arg(
+        long,
+        value_name = "DEGREES",
+        allow_negative_numbers = true,
+        conflicts_with_all = ["into", "geotag", "autocrop", "preview_scales"]
+    )]
+    pub rotation: Option<f64>,
+
     /// Pixel size in degrees (mutually exclusive with --scale).
     #[arg(short, long)]
     pub resolution: Option<f64>,
@@ -50,89 +199,915 @@ pub struct Args {
     #[arg(short, long)]
     pub scale: Option<f64>,
 
-    /// Fill color RGBA hex (e.g., "FF000080").
+    /// Output width in pixels, instead of --resolution or --scale (the height follows the bbox).
+    #[arg(
+        long,
+        value_name = "PIXELS",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with_all = ["resolution", "scale"]
+    )]
+    pub width: Option<u32>,
+
+    /// Output height in pixels, instead of --resolution or --scale; with --width, the image fits within both.
+    #[arg(
+        long,
+        value_name = "PIXELS",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with_all = ["resolution", "scale"]
+    )]
+    pub height: Option<u32>,
+
+    /// Size the image to fit a printed page: A0-A6, letter, legal, tabloid, or "WxH" in mm (or "8.5x11in").
+    #[arg(
+        long,
+        value_name = "SIZE",
+        conflicts_with_all = ["resolution", "scale", "width", "height", "preview_scales", "into"]
+    )]
+    pub paper: Option<String>,
+
+    /// Turn the --paper page to landscape.
+    #[arg(
+        long,
+        requires = "paper",
+        conflicts_with_all = ["resolution", "scale", "width", "height", "preview_scales", "into"]
+    )]
+    pub landscape: bool,
+
+    /// Blank margin on each side of the --paper page, in mm (or "0.5in", "1cm").
+    #[arg(
+        long,
+        value_name = "LENGTH",
+        requires = "paper",
+        conflicts_with_all = ["resolution", "scale", "width", "height", "preview_scales", "into"]
+    )]
+    pub margin: Option<String>,
+
+    /// Print resolution in dots per inch: sizes --paper pages (default 300) and is written in the PNG pHYs chunk.
+    #[arg(long, value_name = "DPI", value_parser = clap::value_parser!(u32).range(1..))]
+    pub dpi: Option<u32>,
+
+    /// Largest width or height of an image, in pixels.
+    #[arg(long, value_name = "PIXELS", default_value_t = MAX_DIMENSION, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_dimension: u32,
+
+    /// Coarsen the resolution until the image fits --max-dimension instead of failing.
+    #[arg(long, visible_alias = "auto-fit")]
+    pub auto_downscale: bool,
+
+    /// Trim borders whose alpha is at most ALPHA (default 0: fully transparent) and shrink the bbox to match.
+    #[arg(long, value_name = "ALPHA", num_args = 0..=1, default_missing_value = "0")]
+    pub autocrop: Option<u8>,
+
+    /// Instead of the full render, save a montage of the bbox center at several scales (m/px), e.g. "1,5,25".
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "SCALES",
+        conflicts_with_all = ["resolution", "scale", "width", "height"]
+    )]
+    pub preview_scales: Vec<f64>,
+
+    /// Fill color RGBA hex (e.g., "FF000080"), or "none" to draw outlines only.
     #[arg(long, default_value = "FF000080")]
     pub fill: String,
 
-    /// Stroke color RGB hex (e.g., "FF0000").
+    /// Stroke color RGB hex (e.g., "FF0000"), or "none" to draw interiors only.
     #[arg(long, default_value = "FF0000")]
     pub stroke: String,
 
+    /// Give each GeoPackage layer its own palette color; SEED shuffles the palette (default 0: palette order).
+    #[arg(
+        long,
+        value_name = "SEED",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0",
+        conflicts_with_all = ["fill", "stroke"]
+    )]
+    pub auto_colors: Option<u64>,
+
+    /// Fill each feature with a color hashed from its id (FID, or index in GeoJSON); SEED changes the colors (default 0).
+    #[arg(
+        long,
+        value_name = "SEED",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0",
+        conflicts_with = "auto_colors"
+    )]
+    pub color_per_feature: Option<u64>,
+
     /// Stroke width in pixels.
     #[arg(long, default_value = "1")]
     pub stroke_width: u32,
 
-    /// Specific layer to render (default: all).
+    /// Mark each ring vertex with a small cross (debugging aid).
+    #[arg(long)]
+    pub draw_vertices: bool,
+
+    /// Vertex marker color RGB hex, used with --draw-vertices.
+    #[arg(long, default_value = "0000FF", requires = "draw_vertices")]
+    pub vertex_color: String,
+
+    /// Kernel used when an image is downsampled (nearest keeps exact values for masks).
+    #[arg(long, value_enum, default_value_t = Resample::Box)]
+    pub resample: Resample,
+
+    /// Render N times finer and downsample with --resample, smoothing polygon edges.
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(2..=16),
+        conflicts_with_all = ["burn", "into", "preview_scales"]
+    )]
+    pub supersample: Option<u32>,
+
+    /// Also save LEVELS overviews, each half the size of the previous one, as `<name>.ov2.png`, `<name>.ov4.png`...
+    #[arg(
+        long,
+        value_name = "LEVELS",
+        default_value = "0",
+        value_parser = clap::value_parser!(u32).range(0..=16),
+        conflicts_with_all = ["burn", "preview_scales"]
+    )]
+    pub overviews: u32,
+
+    /// Also save the FID and non-geometry columns of the rendered features as `<name>.attributes.csv|json`.
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with_all = ["preview_scales", "raster_only"])]
+    pub export_attributes: Option<AttributeFormat>,
+
+    /// Draw features collapsing below one pixel in both directions as squares of this size instead of dropping their fill.
+    #[arg(long, value_name = "PIXELS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub min_feature_px: Option<u32>,
+
+    /// Draw a halo of this RGBA hex color (e.g. "FFFF00C0") fading out just outside the polygon boundaries.
+    #[arg(long, value_name = "COLOR")]
+    pub glow: Option<String>,
+
+    /// Distance in pixels over which the --glow halo fades out.
+    #[arg(
+        long,
+        value_name = "PIXELS",
+        default_value = "6",
+        value_parser = clap::value_parser!(u32).range(1..=256),
+        requires = "glow"
+    )]
+    pub glow_radius: u32,
+
+    /// Numeric attribute (0-1, or 0-100 when a value exceeds 1) setting each feature's fill alpha.
+    #[arg(long, value_name = "COLUMN")]
+    pub opacity_column: Option<String>,
+
+    /// Attribute holding each feature's stroke color (hex like "#FF8800" or a CSS color name).
+    #[arg(long, value_name = "COLUMN")]
+    pub stroke_color_column: Option<String>,
+
+    /// Numeric attribute picking each feature's fill color on --ramp, from its smallest to its largest value.
+    #[arg(long, value_name = "COLUMN", conflicts_with = "color_per_feature")]
+    pub fill_column: Option<String>,
+
+    /// Color ramp of --fill-column: a built-in name (viridis, magma, RdYlBu, Blues...; _r reverses it) or colors like "white,orange,#800026" or "0:white,0.2:orange,1:#800026".
+    #[arg(long, value_name = "RAMP", default_value = "viridis", requires = "fill_column")]
+    pub ramp: String,
+
+    /// Color --fill-column by classes instead of proportionally, with breaks computed from the values of the layer.
+    #[arg(long, value_enum, value_name = "METHOD", requires = "fill_column")]
+    pub classify: Option<Classification>,
+
+    /// Number of classes of --classify.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "5",
+        value_parser = clap::value_parser!(u32).range(2..=16),
+        requires = "classify"
+    )]
+    pub classes: u32,
+
+    /// Write the numeric COLUMN of each feature into the pixels it covers, as a Float64 GeoTIFF (.tif) instead of a PNG.
+    #[arg(
+        long,
+        value_name = "COLUMN",
+        conflicts_with_all = ["into", "preview_scales", "rotation", "autocrop", "content_addressed", "save_partial", "geotag"]
+    )]
+    pub burn: Option<String>,
+
+    /// How the --burn values of overlapping features combine in a pixel.
+    #[arg(long, value_enum, default_value_t = BurnRule::Last, requires = "burn")]
+    pub burn_rule: BurnRule,
+
+    /// Sample type of the --burn output: float GeoTIFF (.tif) or 16-bit grayscale PNG (uint16).
+    #[arg(long, value_enum, default_value_t = BurnType::Float64, requires = "burn")]
+    pub burn_type: BurnType,
+
+    /// Layer to render, or wildcard pattern such as "cadastre_*" (repeatable, default: all).
     #[arg(short, long)]
-    pub layer: Option<String>,
+    pub layer: Vec<String>,
+
+    /// Layer to leave out, or wildcard pattern such as "*_draft" (repeatable).
+    #[arg(long, value_name = "LAYER")]
+    pub exclude_layer: Vec<String>,
+
+    /// Tile layer of the GeoPackage drawn under every rendered polygon layer (GPKG only).
+    #[arg(long, value_name = "LAYER", conflicts_with_all = ["burn", "into", "preview_scales"])]
+    pub raster: Option<String>,
+
+    /// Save the --raster layer alone, as its own PNG, instead of rendering the polygon layers.
+    #[arg(long, requires = "raster", conflicts_with_all = ["layer", "exclude_layer", "fid"])]
+    pub raster_only: bool,
+
+    /// SQL condition on feature attributes, applied to every layer (GPKG only).
+    #[arg(long = "where", value_name = "CLAUSE")]
+    pub where_clause: Option<String>,
+
+    /// SQL condition for a single layer: "LAYER:CLAUSE" (GPKG only, repeatable).
+    #[arg(long, value_name = "LAYER:CLAUSE")]
+    pub layer_where: Vec<String>,
+
+    /// Maximum number of features to read per layer (GPKG only).
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub limit: Option<u64>,
+
+    /// Number of features to skip per layer before reading (GPKG only).
+    #[arg(long)]
+    pub offset: Option<u64>,
+
+    /// Name a layer's outputs by an alias: "SOURCE=ALIAS" (GPKG only, repeatable).
+    #[arg(long, value_name = "SOURCE=ALIAS")]
+    pub rename: Vec<String>,
+
+    /// Only render these feature IDs, comma-separated (GPKG only).
+    #[arg(long, value_name = "IDS", value_delimiter = ',')]
+    pub fid: Vec<String>,
+
+    /// Column matched by --fid instead of the primary key.
+    #[arg(long, value_name = "COLUMN", requires = "fid")]
+    pub fid_column: Option<String>,
+
+    /// Number of layers rendered concurrently, GPKG only (each holds its own image in memory).
+    #[arg(short, long, value_name = "N", default_value = "1", value_parser = clap::value_parser!(u32).range(1..))]
+    pub jobs: u32,
+
+    /// Largest angle, in degrees, spanned by one segment of a linearized arc (GPKG only).
+    #[arg(long, value_name = "DEGREES", default_value_t = DEFAULT_CURVE_TOLERANCE)]
+    pub curve_tolerance: f64,
+
+    /// Cache reprojected geometries in this directory for later runs (GPKG only).
+    #[arg(long, value_name = "DIR")]
+    pub reprojection_cache: Option<PathBuf>,
+
+    /// PROJ data directory (proj.db and datum grids), instead of PROJ_DATA or the bundled data.
+    #[arg(long, value_name = "DIR")]
+    pub proj_data: Option<PathBuf>,
+
+    /// Let PROJ download missing datum grids from its CDN (needs a libproj built with network support).
+    #[arg(long)]
+    pub proj_network: bool,
+
+    /// Fail when the best transformation cannot be used (e.g. its grid is missing) instead of falling back.
+    #[arg(long)]
+    pub proj_only_best: bool,
+
+    /// Fail when the transformation selected for a layer is not known to be accurate to METERS.
+    #[arg(long, value_name = "METERS")]
+    pub proj_accuracy: Option<f64>,
 
     /// Input file format
-    #[arg(short = 'f', long, value_enum)]
-    pub format: Format,
+    #[arg(short = 'f', long, value_enum, required_unless_present = "config")]
+    pub format: Option<Format>,
 
     /// Output PNG filename (GeoJSON only, default: input filename)
     #[arg(long)]
     pub output_name: Option<String>,
+
+    /// Another file of the input format merged into the render (repeatable).
+    #[arg(long, value_name = "FILE", conflicts_with = "raster_only")]
+    pub merge: Vec<PathBuf>,
+
+    /// CRS of GeoJSON coordinates, reprojected to WGS84: "CRS" for every input or "PATH=CRS" for one (repeatable).
+    #[arg(long, value_name = "[PATH=]CRS")]
+    pub source_crs: Vec<String>,
+
+    /// How progress is reported: terminal bars, or NDJSON events on stdout (logs move to stderr).
+    #[arg(long, value_enum, default_value_t = ProgressMode::Bar)]
+    pub progress: ProgressMode,
+
+    /// Maximum delay between progress refreshes in milliseconds (0 = every feature).
+    #[arg(long, value_name = "MS", default_value = "50")]
+    pub progress_interval: u64,
+
+    /// Turn a warning into an error (code like "W014" or slug, repeatable).
+    #[arg(long, value_name = "CODE")]
+    pub deny: Vec<String>,
+
+    /// SQLite memory-mapped I/O size in bytes, GPKG only (0 disables; default: auto for files >= 64 MiB).
+    #[arg(long, value_name = "BYTES")]
+    pub mmap_size: Option<u64>,
+
+    /// SQLite page cache size in KiB, GPKG only.
+    #[arg(long, value_name = "KIB")]
+    pub cache_size: Option<u64>,
+
+    /// Where SQLite stores temporary tables and indices, GPKG only.
+    #[arg(long, value_enum)]
+    pub temp_store: Option<TempStore>,
+}
+
+/// Commands; without one, arguments are those of `render`.
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    /// Render polygon layers to PNG images (the default command).
+    Render(Box<Args>),
+    /// Report what a render would produce (dimensions, resolution, bbox, SRS, memory) without rendering.
+    Info(Box<Args>),
+    /// List the feature layers of a GeoPackage: geometry type, SRS, feature count and extent.
+    ListLayers(ListLayersArgs),
+    /// Write the XYZ tiles of a GeoPackage over one or more zoom levels to a directory.
+    Tiles(TilesArgs),
+    /// Serve XYZ tiles and WMS GetMap images of a GeoPackage, rendered on demand.
+    Serve(ServeArgs),
+    /// Animate a GeoPackage over the values of an attribute, one frame per value (APNG or GIF).
+    Animate(AnimateArgs),
+    /// Compare two renders: their coverage (alpha channel) or every channel.
+    #[command(alias = "compare")]
+    CompareImages(CompareArgs),
+    /// Check that the bundled SQLite and PROJ data work on this machine.
+    Doctor,
+    /// Report polygon hole statistics and holes lying outside their exterior ring.
+    Stats(StatsArgs),
+    /// List the input formats, outputs, commands, reprojection backends and CRS compiled into this binary.
+    Capabilities(CapabilitiesArgs),
+}
+
+/// Arguments for the `capabilities` subcommand.
+#[derive(clap::Args, Debug)]
+pub struct CapabilitiesArgs {
+    /// Print a JSON object instead of one line per topic.
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Arguments for the `list-layers` subcommand.
+#[derive(clap::Args, Debug)]
+pub struct ListLayersArgs {
+    /// GeoPackage to list.
+    pub input: PathBuf,
+
+    /// Print a JSON array of layers instead of one line per layer.
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Arguments for the `tiles` subcommand.
+#[derive(clap::Args, Debug)]
+pub struct TilesArgs {
+    /// GeoPackage to tile.
+    pub input: PathBuf,
+
+    /// Directory receiving the `{z}/{x}/{y}.png` files.
+    #[arg(short, long, default_value = "tiles")]
+    pub output_dir: PathBuf,
+
+    /// Single zoom level of the tiles (0 = one tile for the whole world).
+    #[arg(
+        short,
+        long,
+        value_parser = clap::value_parser!(u8).range(0..=24),
+        required_unless_present_any = ["max_zoom", "scale"],
+        conflicts_with_all = ["min_zoom", "max_zoom", "scale"]
+    )]
+    pub zoom: Option<u8>,
+
+    /// Lowest zoom level of the pyramid.
+    #[arg(long, default_value = "0", value_parser = clap::value_parser!(u8).range(0..=24))]
+    pub min_zoom: u8,
+
+    /// Highest zoom level of the pyramid.
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=24), conflicts_with = "scale")]
+    pub max_zoom: Option<u8>,
+
+    /// Ground resolution in meters/pixel setting --max-zoom: the first zoom at least this detailed at the center of the layers.
+    #[arg(short, long, value_name = "METERS")]
+    pub scale: Option<f64>,
+
+    /// Simplify geometries with a tolerance in pixels (default 0.5), coarser on the ground at lower zooms.
+    #[arg(long, value_name = "PX", num_args = 0..=1, require_equals = true, default_missing_value = "0.5")]
+    pub simplify: Option<f64>,
+
+    /// Skip the tiles completed by the interrupted or failed previous run into the output directory.
+    #[arg(long)]
+    pub resume: bool,
+
+    /// Layers drawn, in order (repeatable, default: all polygon layers).
+    #[arg(short, long)]
+    pub layer: Vec<String>,
+
+    #[command(flatten)]
+    pub style: TileStyleArgs,
+}
+
+/// Arguments for the `serve` subcommand.
+#[derive(clap::Args, Debug)]
+pub struct ServeArgs {
+    /// GeoPackage to serve.
+    pub input: PathBuf,
+
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub bind: String,
+
+    /// Layers drawn, in order (repeatable, default: all polygon layers).
+    #[arg(short, long)]
+    pub layer: Vec<String>,
+
+    #[command(flatten)]
+    pub style: TileStyleArgs,
+
+    /// TOML file with `fill`, `stroke` and `stroke-width` overriding the style
+    /// options, applied again whenever it changes.
+    #[arg(long, value_name = "FILE")]
+    pub style_file: Option<PathBuf>,
+}
+
+/// Arguments for the `animate` subcommand.
+#[derive(clap::Args, Debug)]
+pub struct AnimateArgs {
+    /// GeoPackage to animate.
+    pub input: PathBuf,
+
+    /// Column with one frame per distinct value, in ascending order (e.g. a year).
+    #[arg(long, value_name = "COLUMN")]
+    pub by: String,
+
+    /// Animation file: APNG (.png) or GIF (.gif), from the extension.
+    #[arg(short, long, default_value = "animation.png")]
+    pub output: PathBuf,
+
+    /// Frame width in pixels (the height follows the extent of the layers).
+    #[arg(long, default_value = "800", value_parser = clap::value_parser!(u32).range(1..))]
+    pub width: u32,
+
+    /// Time each frame is shown, in milliseconds.
+    #[arg(long, value_name = "MS", default_value = "500")]
+    pub delay: u16,
+
+    /// Number of times the animation plays (0 = forever).
+    #[arg(long, value_name = "N", default_value = "0")]
+    pub loops: u32,
+
+    /// Layers drawn, in order (repeatable, default: all polygon layers).
+    #[arg(short, long)]
+    pub layer: Vec<String>,
+
+    #[command(flatten)]
+    pub style: TileStyleArgs,
+}
+
+/// Style of the tiles drawn by `tiles` and `serve`, and of `animate` frames.
+#[derive(clap::Args, Debug)]
+pub struct TileStyleArgs {
+    /// Fill color RGBA hex (e.g., "FF000080"), or "none" to draw outlines only.
+    #[arg(long, default_value = "FF000080")]
+    pub fill: String,
+
+    /// Stroke color RGB hex (e.g., "FF0000"), or "none" to draw interiors only.
+    #[arg(long, default_value = "FF0000")]
+    pub stroke: String,
+
+    /// Stroke width in pixels.
+    #[arg(long, default_value = "1")]
+    pub stroke_width: u32,
+}
+
+/// Fill (RGBA) and stroke (RGB) colors, None when set to "none".
+pub type Colors = (Option<[u8; 4]>, Option<[u8; 3]>);
+
+impl TileStyleArgs {
+    /// Parses the fill and stroke colors.
+    pub fn colors(&self) -> Result<Colors> {
+        Ok((parse_rgba(&self.fill)?, parse_rgb(&self.stroke)?))
+    }
+}
+
+/// Arguments for the `stats` subcommand.
+#[derive(clap::Args, Debug)]
+pub struct StatsArgs {
+    /// Input file (GeoPackage or GeoJSON).
+    pub input: PathBuf,
+
+    /// Input file format
+    #[arg(short = 'f', long, value_enum)]
+    pub format: Format,
+
+    /// Specific layer to analyze (GPKG only, default: all).
+    #[arg(short, long)]
+    pub layer: Option<String>,
+}
+
+/// Arguments for the `compare-images` subcommand.
+#[derive(clap::Args, Debug)]
+pub struct CompareArgs {
+    /// Reference image.
+    pub expected: PathBuf,
+
+    /// Image to compare against the reference.
+    pub actual: PathBuf,
+
+    /// Write a visual diff PNG to this path.
+    #[arg(long)]
+    pub diff: Option<PathBuf>,
+
+    /// What is compared: coverage (alpha channel only) or color (every channel).
+    #[arg(long, value_enum, default_value_t = CompareMode::Coverage)]
+    pub mode: CompareMode,
+
+    /// Channel difference (0-255) still considered unchanged.
+    #[arg(long, default_value = "0")]
+    pub tolerance: u8,
+
+    /// Exit with an error when any pixel changed.
+    #[arg(long)]
+    pub fail_on_diff: bool,
+}
+
+/// Channels compared by `compare-images`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CompareMode {
+    /// The alpha channel: which pixels are covered, whatever their color.
+    Coverage,
+    /// All four channels: the render must draw the same colors.
+    Color,
+}
+
+/// Style overrides of one layer, from a `[layers.NAME]` table of the config file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayerStyle {
+    /// Fill of the layer, when set; `Some(None)` (`fill = "none"`) removes it.
+    pub fill: Option<Option<[u8; 4]>>,
+    /// Stroke of the layer, when set; `Some(None)` (`stroke = "none"`) removes it.
+    pub stroke: Option<Option<[u8; 3]>>,
+    pub stroke_width: Option<u32>,
+}
+
+/// Approved images a run is checked against (`--verify-against`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verify {
+    pub dir: PathBuf,
+    /// Channel difference still considered unchanged.
+    pub tolerance: u8,
+    /// Share of changed pixels accepted, in percent.
+    pub threshold: f64,
 }
 
 /// Fully validated configuration object.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Config {
-    /// Path to the input GeoPackage.
+    /// Path to the input being processed.
     pub input: PathBuf,
+    /// Every input of the invocation, globs expanded, in order.
+    pub inputs: Vec<PathBuf>,
     /// Path to the output directory.
     pub output_dir: PathBuf,
+    /// Arrangement of output files inside the output directory.
+    pub output_layout: OutputLayout,
+    /// Whether to name outputs by content hash.
+    pub content_addressed: bool,
+    /// Whether to skip the outputs completed by an unfinished previous run.
+    pub resume: bool,
+    /// What to do when renders of the run map to the same output file.
+    pub on_collision: CollisionPolicy,
+    /// Pattern of output file names (None names them after the layer).
+    pub name_template: Option<String>,
+    /// Unix time the run started, shared by the `--name-template` dates.
+    pub started: u64,
+    /// Existing render drawn onto and overwritten (None writes new files).
+    pub into: Option<PathBuf>,
+    /// Whether to embed XMP geotags in output PNGs.
+    pub geotag: bool,
+    /// Whether to describe the render in PNG text chunks.
+    pub metadata: bool,
+    /// Compression, filter and interlacing of output PNGs.
+    pub png_options: PngOptions,
+    /// Where to write the run manifest (None writes none).
+    pub manifest: Option<PathBuf>,
+    /// Whether to write a STAC Item next to every produced file.
+    pub stac: bool,
+    /// Whether to write a footprint GeoJSON next to every produced file.
+    pub footprint: bool,
+    /// Approved images the produced files are checked against (None checks nothing).
+    pub verify: Option<Verify>,
+    /// Whether to keep the partially rendered layer when interrupted.
+    pub save_partial: bool,
     /// Bounding box (None means auto-detect from GPKG).
     pub bbox: Option<Bbox>,
+    /// Margin added around the bbox.
+    pub padding: Option<Padding>,
+    /// Width/height ratio the bbox is expanded to.
+    pub aspect: Option<f64>,
+    /// Clockwise rotation of the map in degrees (0 keeps north up).
+    pub rotation: f64,
     /// Resolution in degrees per pixel.
     pub resolution: Option<f64>,
     /// Scale in meters per pixel.
     pub scale: Option<f64>,
-    /// Fill color RGBA.
-    pub fill: [u8; 4],
-    /// Stroke color RGB.
-    pub stroke: [u8; 3],
+    /// Output width in pixels, the resolution being derived from the bbox.
+    pub width: Option<u32>,
+    /// Output height in pixels, the resolution being derived from the bbox.
+    pub height: Option<u32>,
+    /// Largest width or height of an image, in pixels.
+    pub max_dimension: u32,
+    /// Whether to coarsen a resolution that exceeds the maximum dimensions.
+    pub auto_downscale: bool,
+    /// Alpha at or below which borders are trimmed (None keeps the full bbox).
+    pub autocrop: Option<u8>,
+    /// Scales of a preview montage (empty for a normal render).
+    pub preview_scales: Vec<f64>,
+    /// Fill color RGBA (None with `--fill none`).
+    pub fill: Option<[u8; 4]>,
+    /// Stroke color RGB (None with `--stroke none`).
+    pub stroke: Option<[u8; 3]>,
+    /// Seed of the per-layer palette colors (None keeps --fill and --stroke).
+    pub auto_colors: Option<u64>,
+    /// Seed of the per-feature fill colors (None keeps the layer fill color).
+    pub color_per_feature: Option<u64>,
     /// Stroke width.
     pub stroke_width: u32,
-    /// Optional layer name filter.
-    pub layer: Option<String>,
+    /// Style overrides per layer, as (layer, style).
+    pub layer_styles: Vec<(String, LayerStyle)>,
+    /// Vertex marker color RGB (None when --draw-vertices is off).
+    pub vertex_color: Option<[u8; 3]>,
+    /// Kernel used when an image is downsampled.
+    pub resample: Resample,
+    /// Factor of the finer grid rendered and downsampled (None renders directly).
+    pub supersample: Option<u32>,
+    /// Number of half-size overview levels saved with each PNG.
+    pub overviews: u32,
+    /// Format of the attribute table saved with each render (None saves none).
+    pub export_attributes: Option<AttributeFormat>,
+    /// Side of the mark drawn for sub-pixel features (None drops their fill).
+    pub min_feature_px: Option<u32>,
+    /// Halo drawn outside the polygon boundaries (None disables it).
+    pub glow: Option<Glow>,
+    /// Attribute controlling each feature's fill alpha.
+    pub opacity_column: Option<String>,
+    /// Attribute holding each feature's stroke color.
+    pub stroke_color_column: Option<String>,
+    /// Attribute picking each feature's fill color on the ramp.
+    pub fill_column: Option<String>,
+    /// Color ramp of the fill column.
+    pub ramp: Ramp,
+    /// How the values of the fill column are split into classes (None colors them proportionally).
+    pub classify: Option<Classification>,
+    /// Number of classes.
+    pub classes: usize,
+    /// Attribute burned into a GeoTIFF instead of rendering a PNG.
+    pub burn: Option<String>,
+    /// How overlapping burned values combine.
+    pub burn_rule: BurnRule,
+    /// Sample type of the burned raster.
+    pub burn_type: BurnType,
+    /// Names or wildcard patterns of the layers to render (empty = all).
+    pub layer: Vec<String>,
+    /// Names or wildcard patterns of the layers left out.
+    pub exclude_layer: Vec<String>,
+    /// Tile layer drawn under the polygon layers.
+    pub raster: Option<String>,
+    /// Whether to save the tile layer alone instead of the polygon layers.
+    pub raster_only: bool,
+    /// SQL condition applied to every layer.
+    pub where_clause: Option<String>,
+    /// SQL conditions for specific layers, as (layer, clause).
+    pub layer_where: Vec<(String, String)>,
+    /// Maximum number of features read per layer.
+    pub limit: Option<u64>,
+    /// Number of features skipped per layer.
+    pub offset: Option<u64>,
+    /// Output names of layers, as (source layer, alias).
+    pub rename: Vec<(String, String)>,
+    /// Feature IDs to render (None = all features).
+    pub fids: Option<FidSelection>,
     /// Output filename for GeoJSON (None for GPKG).
     pub output_name: Option<String>,
+    /// Additional files merged into the render.
+    pub merge: Vec<PathBuf>,
+    /// Source CRS per GeoJSON input, as (path, CRS); a None path applies to all others.
+    pub source_crs: Vec<(Option<PathBuf>, String)>,
     /// Input format.
     pub format: Format,
     /// Verbosity level for output control.
     pub verbosity: VerbosityLevel,
-    /// Whether to disable colored output.
-    pub no_color: bool,
+    /// When to color log output.
+    pub color: ColorMode,
+    /// Warning codes that abort the run.
+    pub deny: Vec<WarningCode>,
+    /// How progress is reported.
+    pub progress: ProgressMode,
+    /// Maximum delay between progress refreshes.
+    pub progress_interval: Duration,
+    /// Maximum number of layers rendered concurrently.
+    pub jobs: usize,
+    /// Largest angle, in degrees, between two points of a linearized arc.
+    pub curve_tolerance: f64,
+    /// Directory of the on-disk cache of reprojected geometries.
+    pub reprojection_cache: Option<PathBuf>,
+    /// Accuracy in metres required from the transformations of reprojected layers.
+    pub proj_accuracy: Option<f64>,
+    /// SQLite pragmas for reading the GeoPackage.
+    pub sqlite: SqlitePragmas,
+}
+
+impl Config {
+    /// Returns the attribute conditions that apply to a layer.
+    pub fn where_clauses_for(&self, layer: &str) -> Vec<String> {
+        self.where_clause
+            .iter()
+            .cloned()
+            .chain(
+                self.layer_where
+                    .iter()
+                    .filter(|(name, _)| name == layer)
+                    .map(|(_, clause)| clause.clone()),
+            )
+            .collect()
+    }
+
+    /// Returns the configuration for processing one input of a batch.
+    pub fn for_input(&self, input: &Path) -> Config {
+        let mut config = self.clone();
+        config.input = input.to_path_buf();
+        if self.inputs.len() > 1 && config.output_name.is_some() {
+            config.output_name = Some(input_stem(input));
+        }
+        config
+    }
+
+    /// Returns the configuration for rendering one layer, with its style overrides.
+    ///
+    /// With `--auto-colors`, the layer takes the palette color of its `index`
+    /// (fill alpha unchanged) unless its `[layers.NAME]` table sets one.
+    pub fn for_layer(&self, layer: &str, index: usize) -> Config {
+        let mut config = self.clone();
+        if let Some(seed) = self.auto_colors {
+            let [r, g, b] = style::palette_color(index, seed);
+            config.fill = config.fill.map(|[_, _, _, a]| [r, g, b, a]);
+            config.stroke = config.stroke.map(|_| [r, g, b]);
+        }
+        if let Some((_, style)) = self.layer_styles.iter().find(|(name, _)| name == layer) {
+            config.fill = style.fill.unwrap_or(config.fill);
+            config.stroke = style.stroke.unwrap_or(config.stroke);
+            config.stroke_width = style.stroke_width.unwrap_or(config.stroke_width);
+        }
+        config
+    }
+
+    /// Returns true if `--layer` selects `layer` (every layer when not given)
+    /// and `--exclude-layer` does not leave it out.
+    pub fn selects_layer(&self, layer: &str) -> bool {
+        (self.layer.is_empty() || self.layer.iter().any(|pattern| wildcard_match(pattern, layer)))
+            && !self.exclude_layer.iter().any(|pattern| wildcard_match(pattern, layer))
+    }
+
+    /// Returns the name used for a layer's output files (its alias, if any).
+    pub fn output_name_for<'a>(&'a self, layer: &'a str) -> &'a str {
+        self.rename
+            .iter()
+            .find(|(source, _)| source == layer)
+            .map_or(layer, |(_, alias)| alias)
+    }
+
+    /// Returns the GeoJSON inputs to merge, in order, with their source CRS.
+    pub fn geojson_inputs(&self) -> Vec<GeojsonInput> {
+        let fallback = self.source_crs.iter().find(|(path, _)| path.is_none());
+        std::iter::once(&self.input)
+            .chain(&self.merge)
+            .map(|path| {
+                let crs = self
+                    .source_crs
+                    .iter()
+                    .find(|(p, _)| p.as_ref() == Some(path))
+                    .or(fallback)
+                    .map(|(_, crs)| crs.clone());
+                GeojsonInput {
+                    path: path.clone(),
+                    source_crs: crs,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Cli {
+    /// Parses `argv`, completing `render` arguments by the `--config` file they name.
+    ///
+    /// Without a command, arguments are those of `render`. Options of the
+    /// config file that are not given on the command line are turned into
+    /// arguments and parsed again, so both sources are checked by clap the
+    /// same way. Exits with clap's usage message on invalid arguments.
+    pub fn load(mut argv: Vec<OsString>) -> Result<Command> {
+        if argv.get(1).is_some_and(|arg| !selects_command(arg)) {
+            argv.insert(1, "render".into());
+        }
+        let matches = Cli::command().get_matches_from(argv.clone());
+        let mut command = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit()).command;
+        let (Some(args), Some((_, sub_matches))) = (command.render_args(), matches.subcommand()) else {
+            return Ok(command);
+        };
+        let Some(path) = &args.config else {
+            return Ok(command);
+        };
+
+        let fail = |msg: String| GpkgError::ConfigFile(path.display().to_string(), msg);
+        let text = std::fs::read_to_string(path).map_err(|e| fail(e.to_string()))?;
+        let table = toml::parse(&text).map_err(&fail)?;
+        let file = config_file_args(table, sub_matches).map_err(&fail)?;
+
+        // File arguments go first: a trailing command-line option may take an optional value
+        argv.splice(2..2, file.args);
+        let matches = Cli::command().get_matches_from(argv);
+        let mut command = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit()).command;
+        let args = command
+            .render_args()
+            .expect("arguments of the config file are parsed by `render` or `info`");
+        args.layer_styles = file.layer_styles;
+        Ok(command)
+    }
+}
+
+impl Command {
+    /// Render arguments of the commands taking them (`render` and `info`).
+    fn render_args(&mut self) -> Option<&mut Args> {
+        match self {
+            Command::Render(args) | Command::Info(args) => Some(args),
+            _ => None,
+        }
+    }
+}
+
+/// Returns whether `arg`, the first argument, names a command or asks for help or the version.
+fn selects_command(arg: &OsStr) -> bool {
+    let Some(arg) = arg.to_str() else {
+        return false;
+    };
+    matches!(arg, "help" | "-h" | "--help" | "-V" | "--version")
+        || Cli::command()
+            .get_subcommands()
+            .any(|command| command.get_name() == arg || command.get_all_aliases().any(|alias| alias == arg))
 }
 
 impl Args {
     /// Validates arguments and converts them to a structured `Config`.
     ///
     /// Checks for mutually exclusive options and parses color hex strings.
-    pub fn validate(self) -> Result<Config> {
+    pub fn validate(mut self) -> Result<Config> {
+        // Both are enforced by clap unless a config file is given
+        if self.inputs.is_empty() {
+            return Err(GpkgError::InvalidFormatOption("an input file is required".to_string()));
+        }
+        let inputs = expand_inputs(&self.inputs)?;
+        let input = inputs[0].clone();
+        let format = self.format.take().ok_or_else(|| {
+            GpkgError::InvalidFormatOption("--format is required".to_string())
+        })?;
+
         // Validate that verbose and quiet are mutually exclusive
-        if self.verbose && self.quiet {
+        if self.verbose > 0 && self.quiet {
             return Err(GpkgError::MutuallyExclusiveOptions(
                 "verbose".to_string(),
                 "quiet".to_string(),
             ));
         }
 
-        // Determine verbosity level
-        let verbosity = if self.quiet {
-            VerbosityLevel::Quiet
-        } else if self.verbose {
-            VerbosityLevel::Verbose
-        } else {
-            VerbosityLevel::Normal
+        // Determine verbosity level, flags taking precedence over the environment
+        let verbosity = match (self.quiet, self.verbose) {
+            (true, _) => VerbosityLevel::Quiet,
+            (false, 0) => verbosity_from_env()?,
+            (false, 1) => VerbosityLevel::Verbose,
+            (false, _) => VerbosityLevel::Trace,
         };
 
-        // Validate that at least one of resolution or scale is provided
-        if self.resolution.is_none() && self.scale.is_none() {
+        // Validate that at least one of resolution, scale or size is provided (previews and canvases bring their own)
+        if self.resolution.is_none()
+            && self.scale.is_none()
+            && self.width.is_none()
+            && self.height.is_none()
+            && self.paper.is_none()
+            && self.preview_scales.is_empty()
+            && self.into.is_none()
+        {
             return Err(GpkgError::MissingResolutionOrScale);
         }
 
+        let dpi = self.dpi.or(self.paper.as_ref().map(|_| DEFAULT_DPI));
+        let (width, height) = match &self.paper {
+            Some(paper) => {
+                let margin = self.margin.as_deref().map(parse_length).transpose()?.unwrap_or(0.0);
+                let size = parse_paper(paper)?;
+                let (width, height) = paper_pixels(size, self.landscape, margin, dpi.unwrap_or(DEFAULT_DPI))?;
+                (Some(width), Some(height))
+            }
+            None => (self.width, self.height),
+        };
+
         // Validate that resolution and scale are mutually exclusive
         if self.resolution.is_some() && self.scale.is_some() {
             return Err(GpkgError::MutuallyExclusiveOptions(
@@ -155,78 +1130,623 @@ impl Args {
             }
         }
 
+        if let Some(&scale) = self.preview_scales.iter().find(|&&s| s <= 0.0 || s.is_nan()) {
+            return Err(GpkgError::InvalidScale(scale));
+        }
+
         // Parse bbox if provided
-        let bbox = self.bbox.as_ref().map(|s| parse_bbox(s)).transpose()?;
+        let bbox = match &self.bbox_crs {
+            Some(crs) => self.bbox.as_ref().map(|s| parse_projected_bbox(s, crs)).transpose()?,
+            None => self.bbox.as_ref().map(|s| parse_bbox(s)).transpose()?,
+        };
+        let padding = self.padding.as_deref().map(parse_padding).transpose()?;
+        let rotation = self.rotation.unwrap_or(0.0);
+        if !rotation.is_finite() {
+            return Err(GpkgError::InvalidFormatOption(format!("--rotation must be a number of degrees, got {}", rotation)));
+        }
+        let aspect = match (&self.aspect, self.square) {
+            (Some(aspect), _) => Some(parse_aspect(aspect)?),
+            (None, true) => Some(1.0),
+            (None, false) => None,
+        };
 
         // Parse colors
         let fill = parse_rgba(&self.fill)?;
         let stroke = parse_rgb(&self.stroke)?;
+        let vertex_color = self
+            .draw_vertices
+            .then(|| parse_rgb(&self.vertex_color))
+            .transpose()?
+            .flatten();
+        let glow = self.glow.as_deref().map(parse_rgba).transpose()?.flatten().map(|color| Glow {
+            color,
+            radius: self.glow_radius,
+        });
+        let ramp = Ramp::parse(&self.ramp)?;
+        if fill.is_none()
+            && (self.color_per_feature.is_some() || self.opacity_column.is_some() || self.fill_column.is_some())
+        {
+            return Err(GpkgError::InvalidFormatOption(
+                "--fill none cannot be combined with --color-per-feature, --fill-column or --opacity-column"
+                    .to_string(),
+            ));
+        }
+        if stroke.is_none() && self.stroke_color_column.is_some() {
+            return Err(GpkgError::InvalidFormatOption(
+                "--stroke none cannot be combined with --stroke-color-column".to_string(),
+            ));
+        }
+        if fill.is_none() && (stroke.is_none() || self.stroke_width == 0) && vertex_color.is_none() && glow.is_none() {
+            return Err(GpkgError::InvalidFormatOption(
+                "--fill none leaves nothing to draw without a stroke".to_string(),
+            ));
+        }
 
         // Validate format-specific options
-        if matches!(self.format, Format::Geojson) && self.layer.is_some() {
+        if matches!(format, Format::Geojson) && !(self.layer.is_empty() && self.exclude_layer.is_empty()) {
             return Err(GpkgError::InvalidFormatOption(
-                "--layer cannot be used with geojson format".to_string(),
+                "--layer and --exclude-layer cannot be used with geojson format".to_string(),
             ));
         }
 
-        if matches!(self.format, Format::Gpkg) && self.output_name.is_some() {
+        if matches!(format, Format::Geojson)
+            && (self.where_clause.is_some() || !self.layer_where.is_empty())
+        {
             return Err(GpkgError::InvalidFormatOption(
-                "--output-name can only be used with geojson format".to_string(),
+                "--where and --layer-where can only be used with gpkg format".to_string(),
             ));
         }
 
-        // Determine output name for GeoJSON
-        let output_name = if matches!(self.format, Format::Geojson) {
-            Some(self.output_name.clone().unwrap_or_else(|| {
-                self.input
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("output")
-                    .to_string()
-            }))
-        } else {
-            None
-        };
+        if matches!(format, Format::Geojson) && (self.limit.is_some() || self.offset.is_some()) {
+            return Err(GpkgError::InvalidFormatOption(
+                "--limit and --offset can only be used with gpkg format".to_string(),
+            ));
+        }
 
-        Ok(Config {
-            input: self.input,
-            output_dir: self.output_dir,
-            bbox,
-            resolution: self.resolution,
-            scale: self.scale,
-            fill,
-            stroke,
-            stroke_width: self.stroke_width,
-            layer: self.layer,
-            output_name,
-            format: self.format,
-            verbosity,
-            no_color: self.no_color,
-        })
-    }
-}
+        if matches!(format, Format::Geojson) && self.jobs > 1 {
+            return Err(GpkgError::InvalidFormatOption(
+                "--jobs can only be used with gpkg format".to_string(),
+            ));
+        }
 
-fn parse_bbox(s: &str) -> Result<Bbox> {
-    let parts: Vec<&str> = s.split(',').collect();
-    if parts.len() != 4 {
-        return Err(GpkgError::InvalidBbox(format!(
-            "expected 4 comma-separated values, got {}",
-            parts.len()
-        )));
-    }
+        if !(self.curve_tolerance > 0.0 && self.curve_tolerance <= 90.0) {
+            return Err(GpkgError::InvalidFormatOption(format!(
+                "--curve-tolerance must be between 0 and 90 degrees, got {}",
+                self.curve_tolerance
+            )));
+        }
 
-    let values: std::result::Result<Vec<f64>, _> = parts.iter().map(|p| p.trim().parse()).collect();
-    let values = values.map_err(|_| GpkgError::InvalidBbox("invalid number format".to_string()))?;
+        if !cfg!(feature = "reproject") && (self.proj_data.is_some() || self.proj_network || self.proj_only_best) {
+            return Err(GpkgError::FeatureDisabled(
+                "--proj-data, --proj-network and --proj-only-best".to_string(),
+                "reproject".to_string(),
+            ));
+        }
+        if let Some(dir) = self.proj_data.as_ref().filter(|dir| !dir.is_dir()) {
+            return Err(GpkgError::FileNotFound(dir.display().to_string()));
+        }
+        if let Some(accuracy) = self.proj_accuracy.filter(|a| a.is_nan() || *a <= 0.0) {
+            return Err(GpkgError::InvalidFormatOption(format!(
+                "--proj-accuracy must be a positive number of meters, got {}",
+                accuracy
+            )));
+        }
 
-    let (min_lon, min_lat, max_lon, max_lat) = (values[0], values[1], values[2], values[3]);
+        if matches!(format, Format::Geojson) && self.raster.is_some() {
+            return Err(GpkgError::InvalidFormatOption(
+                "--raster can only be used with gpkg format".to_string(),
+            ));
+        }
 
-    // Validate that min < max for both lon and lat
-    if min_lon >= max_lon {
-        return Err(GpkgError::InvalidBbox(format!(
-            "min_lon ({}) must be less than max_lon ({})",
-            min_lon, max_lon
-        )));
-    }
+        if matches!(format, Format::Geojson) && !self.fid.is_empty() {
+            return Err(GpkgError::InvalidFormatOption(
+                "--fid can only be used with gpkg format".to_string(),
+            ));
+        }
+
+        if matches!(format, Format::Geojson) && !self.rename.is_empty() {
+            return Err(GpkgError::InvalidFormatOption(
+                "--rename can only be used with gpkg format (use --output-name)".to_string(),
+            ));
+        }
+        let rename = parse_renames(&self.rename)?;
+
+        if matches!(format, Format::Geojson) && self.auto_colors.is_some() {
+            return Err(GpkgError::InvalidFormatOption(
+                "--auto-colors can only be used with gpkg format".to_string(),
+            ));
+        }
+
+        if matches!(format, Format::Geojson) && !self.layer_styles.is_empty() {
+            return Err(GpkgError::InvalidFormatOption(
+                "[layers] styles of the config file can only be used with gpkg format".to_string(),
+            ));
+        }
+
+        if matches!(format, Format::Gpkg) && !self.source_crs.is_empty() {
+            return Err(GpkgError::InvalidFormatOption(
+                "--source-crs can only be used with geojson format".to_string(),
+            ));
+        }
+        let is_geojson = |path: &PathBuf| {
+            geojson::is_sequence(path)
+                || path
+                    .extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("geojson") || e.eq_ignore_ascii_case("json"))
+        };
+        if matches!(format, Format::Gpkg) && self.merge.iter().any(is_geojson) {
+            return Err(GpkgError::InvalidFormatOption(
+                "--merge files must be GeoPackages with gpkg format".to_string(),
+            ));
+        }
+
+        if inputs.len() > 1 && (!self.merge.is_empty() || self.output_name.is_some()) {
+            return Err(GpkgError::InvalidFormatOption(
+                "--merge and --output-name cannot be used with several inputs".to_string(),
+            ));
+        }
+        if self.into.is_some()
+            && (inputs.len() > 1
+                || self.content_addressed
+                || self.autocrop.is_some()
+                || self.auto_downscale
+                || self.padding.is_some()
+                || self.aspect.is_some()
+                || self.square
+                || !self.preview_scales.is_empty())
+        {
+            return Err(GpkgError::InvalidFormatOption(
+                "--into keeps the canvas grid and file: it cannot be used with several inputs, \
+                 --content-addressed, --autocrop, --auto-downscale, --padding, --aspect, --square \
+                 or --preview-scales"
+                    .to_string(),
+            ));
+        }
+
+        if let Some(template) = &self.name_template {
+            output::check_name_template(template)?;
+        }
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+
+        // Keep the outputs of each input apart
+        let output_layout = match self.output_layout {
+            OutputLayout::Flat if inputs.len() > 1 => OutputLayout::PerInputDir,
+            layout => layout,
+        };
+
+        let source_crs = self
+            .source_crs
+            .iter()
+            .map(|s| parse_source_crs(s, &input, &self.merge))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Parse feature IDs
+        let fid_values: Vec<String> = self.fid.iter().map(|v| v.trim().to_string()).collect();
+        if self.fid_column.is_none() {
+            if let Some(bad) = fid_values.iter().find(|v| v.parse::<i64>().is_err()) {
+                return Err(GpkgError::InvalidFormatOption(format!(
+                    "--fid values must be integers without --fid-column, got {}",
+                    bad
+                )));
+            }
+        }
+        let fids = (!fid_values.is_empty()).then(|| FidSelection {
+            column: self.fid_column.clone(),
+            values: fid_values,
+        });
+
+        // Validate attribute filters
+        if let Some(clause) = &self.where_clause {
+            validate_where_clause(clause)?;
+        }
+        let layer_where = self
+            .layer_where
+            .iter()
+            .map(|s| parse_layer_where(s))
+            .collect::<Result<Vec<_>>>()?;
+
+        if matches!(format, Format::Gpkg) && self.output_name.is_some() {
+            return Err(GpkgError::InvalidFormatOption(
+                "--output-name can only be used with geojson format".to_string(),
+            ));
+        }
+
+        // Parse denied warning codes
+        let deny = self
+            .deny
+            .iter()
+            .map(|s| s.parse())
+            .collect::<Result<Vec<WarningCode>>>()?;
+
+        // Determine output name for GeoJSON
+        let output_name = if matches!(format, Format::Geojson) {
+            Some(self.output_name.clone().unwrap_or_else(|| input_stem(&input)))
+        } else {
+            None
+        };
+
+        let manifest = self
+            .manifest
+            .map(|path| path.unwrap_or_else(|| self.output_dir.join("manifest.json")));
+
+        if !(0.0..=100.0).contains(&self.verify_threshold) {
+            return Err(GpkgError::InvalidFormatOption(format!(
+                "--verify-threshold must be a percentage between 0 and 100, got {}",
+                self.verify_threshold
+            )));
+        }
+        let verify = match self.verify_against {
+            Some(dir) if !dir.is_dir() => return Err(GpkgError::FileNotFound(dir.display().to_string())),
+            Some(dir) => Some(Verify {
+                dir,
+                tolerance: self.verify_tolerance,
+                threshold: self.verify_threshold,
+            }),
+            None => None,
+        };
+
+        Ok(Config {
+            input,
+            inputs,
+            output_dir: self.output_dir,
+            output_layout,
+            content_addressed: self.content_addressed,
+            resume: self.resume,
+            on_collision: self.on_collision,
+            name_template: self.name_template,
+            started,
+            into: self.into,
+            geotag: self.geotag,
+            metadata: !self.no_metadata,
+            png_options: PngOptions {
+                compression: self.png_compression,
+                filter: self.png_filter,
+                interlace: self.interlace,
+                colors: self.colors,
+                dither: self.dither,
+                dpi,
+            },
+            manifest,
+            stac: self.stac,
+            footprint: self.footprint,
+            verify,
+            save_partial: self.save_partial,
+            bbox,
+            padding,
+            aspect,
+            rotation,
+            resolution: self.resolution,
+            scale: self.scale,
+            width,
+            height,
+            max_dimension: self.max_dimension,
+            auto_downscale: self.auto_downscale,
+            autocrop: self.autocrop,
+            preview_scales: self.preview_scales,
+            fill,
+            stroke,
+            auto_colors: self.auto_colors,
+            color_per_feature: self.color_per_feature,
+            stroke_width: self.stroke_width,
+            layer_styles: self.layer_styles,
+            vertex_color,
+            resample: self.resample,
+            supersample: self.supersample,
+            overviews: self.overviews,
+            export_attributes: self.export_attributes,
+            min_feature_px: self.min_feature_px,
+            glow,
+            opacity_column: self.opacity_column,
+            stroke_color_column: self.stroke_color_column,
+            fill_column: self.fill_column,
+            ramp,
+            classify: self.classify,
+            classes: self.classes as usize,
+            burn: self.burn,
+            burn_rule: self.burn_rule,
+            burn_type: self.burn_type,
+            layer: self.layer,
+            exclude_layer: self.exclude_layer,
+            raster: self.raster,
+            raster_only: self.raster_only,
+            where_clause: self.where_clause,
+            layer_where,
+            limit: self.limit,
+            offset: self.offset,
+            rename,
+            fids,
+            output_name,
+            merge: self.merge,
+            source_crs,
+            format,
+            verbosity,
+            color: if self.no_color { ColorMode::Never } else { self.color },
+            deny,
+            progress: self.progress,
+            progress_interval: Duration::from_millis(self.progress_interval),
+            jobs: self.jobs as usize,
+            curve_tolerance: self.curve_tolerance,
+            reprojection_cache: self.reprojection_cache,
+            proj_accuracy: self.proj_accuracy,
+            sqlite: SqlitePragmas {
+                mmap_size: self.mmap_size,
+                cache_size_kib: self.cache_size,
+                temp_store: self.temp_store,
+            },
+        })
+    }
+}
+
+/// Contents of a config file missing from the command line.
+struct ConfigFileArgs {
+    /// Options as arguments, inputs first.
+    args: Vec<OsString>,
+    layer_styles: Vec<(String, LayerStyle)>,
+}
+
+/// Turns the options of a config file into arguments, skipping those given on
+/// the command line, and reads its `[layers.NAME]` styles.
+fn config_file_args(table: toml::Table, matches: &ArgMatches) -> std::result::Result<ConfigFileArgs, String> {
+    let command = Args::command();
+    let mut inputs: Vec<OsString> = Vec::new();
+    let mut options: Vec<OsString> = Vec::new();
+    let mut layer_styles = Vec::new();
+    for (key, value) in table {
+        if key == "layers" {
+            let Value::Table(layers) = value else {
+                return Err("`layers` must hold [layers.NAME] tables".to_string());
+            };
+            for (name, style) in layers {
+                let style = parse_layer_style(&name, style)?;
+                layer_styles.push((name, style));
+            }
+            continue;
+        }
+
+        let arg = command
+            .get_arguments()
+            .filter(|arg| arg.get_id() != "config")
+            .find(|arg| arg.get_long() == Some(key.as_str()) || (arg.is_positional() && arg.get_id() == key.as_str()))
+            .ok_or_else(|| format!("unknown option `{}`", key))?;
+        if overridden_by_command_line(&command, arg, matches) {
+            continue;
+        }
+        let values = match value {
+            Value::Array(items) if matches!(arg.get_action(), ArgAction::Append) => items,
+            Value::Array(_) => return Err(format!("`{}` takes a single value", key)),
+            value => vec![value],
+        };
+        for value in values {
+            let text = match (arg.get_action(), value) {
+                (ArgAction::SetTrue, Value::Boolean(set)) => {
+                    if set {
+                        options.push(format!("--{}", key).into());
+                    }
+                    continue;
+                }
+                (ArgAction::SetTrue, other) => {
+                    return Err(format!("`{}` must be true or false, got a {}", key, other.type_name()))
+                }
+                // `verbose = 2` is `-vv`, `verbose = true` is `-v`
+                (ArgAction::Count, Value::Integer(count)) if (0..=u8::MAX as i64).contains(&count) => {
+                    options.extend((0..count).map(|_| format!("--{}", key).into()));
+                    continue;
+                }
+                (ArgAction::Count, Value::Boolean(set)) => {
+                    if set {
+                        options.push(format!("--{}", key).into());
+                    }
+                    continue;
+                }
+                (ArgAction::Count, other) => {
+                    return Err(format!("`{}` must be a count or true/false, got a {}", key, other.type_name()))
+                }
+                (_, Value::String(s)) => s,
+                (_, Value::Integer(n)) => n.to_string(),
+                (_, Value::Float(x)) => x.to_string(),
+                (_, Value::Boolean(b)) => b.to_string(),
+                (_, other) => return Err(format!("`{}` cannot hold a {}", key, other.type_name())),
+            };
+            if arg.is_positional() {
+                inputs.push(text.into());
+            } else {
+                options.push(format!("--{}={}", key, text).into());
+            }
+        }
+    }
+    inputs.extend(options);
+    Ok(ConfigFileArgs {
+        args: inputs,
+        layer_styles,
+    })
+}
+
+/// Returns whether `arg`, or an option excluding it, is given on the command line.
+fn overridden_by_command_line(command: &clap::Command, arg: &clap::Arg, matches: &ArgMatches) -> bool {
+    let on_command_line = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    let id = arg.get_id().as_str();
+    let excludes = |a: &clap::Arg, b: &clap::Arg| {
+        command.get_arg_conflicts_with(a).iter().any(|c| c.get_id() == b.get_id())
+    };
+    on_command_line(id)
+        || command
+            .get_arguments()
+            .filter(|other| on_command_line(other.get_id().as_str()))
+            .any(|other| excludes(arg, other) || excludes(other, arg))
+        || EXCLUSIVE_OPTIONS
+            .iter()
+            .any(|&(a, b)| (id == a && on_command_line(b)) || (id == b && on_command_line(a)))
+}
+
+/// Reads the `fill`, `stroke` and `stroke-width` of a `[layers.NAME]` table.
+fn parse_layer_style(layer: &str, value: Value) -> std::result::Result<LayerStyle, String> {
+    let Value::Table(table) = value else {
+        return Err(format!("`layers.{}` must be a table", layer));
+    };
+    parse_style(table, Some(&format!("layers.{}", layer)))
+}
+
+/// Reads the style file of `serve --style-file`: `fill`, `stroke` and
+/// `stroke-width` keys, as in a `[layers.NAME]` table.
+pub fn read_style_file(path: &Path) -> Result<LayerStyle> {
+    let fail = |msg: String| GpkgError::ConfigFile(path.display().to_string(), msg);
+    let text = std::fs::read_to_string(path).map_err(|e| fail(e.to_string()))?;
+    let table = toml::parse(&text).map_err(&fail)?;
+    parse_style(table, None).map_err(fail)
+}
+
+/// Reads the style keys of `table`, named `table_name` in errors (None for the root).
+fn parse_style(table: toml::Table, table_name: Option<&str>) -> std::result::Result<LayerStyle, String> {
+    let name = |key: &str| match table_name {
+        Some(table) => format!("{}.{}", table, key),
+        None => key.to_string(),
+    };
+    let mut style = LayerStyle::default();
+    for (key, value) in table {
+        let invalid = |e: GpkgError| format!("`{}`: {}", name(&key), e);
+        match (key.as_str(), value) {
+            ("fill", Value::String(s)) => style.fill = Some(parse_rgba(&s).map_err(invalid)?),
+            ("stroke", Value::String(s)) => style.stroke = Some(parse_rgb(&s).map_err(invalid)?),
+            ("stroke-width", Value::Integer(width)) => {
+                style.stroke_width = Some(u32::try_from(width).map_err(|_| {
+                    format!("`{}` must be a non-negative integer", name("stroke-width"))
+                })?)
+            }
+            ("fill" | "stroke" | "stroke-width", other) => {
+                return Err(format!("`{}` cannot be a {}", name(&key), other.type_name()))
+            }
+            _ => {
+                return Err(format!(
+                    "unknown style `{}`{} (expected fill, stroke or stroke-width)",
+                    key,
+                    table_name.map(|table| format!(" in `{}`", table)).unwrap_or_default()
+                ))
+            }
+        }
+    }
+    Ok(style)
+}
+
+/// Name of the environment variable setting the verbosity when neither
+/// `--quiet` nor `--verbose` is given.
+const VERBOSITY_ENV: &str = "GPKG_TO_PNG_VERBOSITY";
+
+/// Reads the verbosity from `GPKG_TO_PNG_VERBOSITY` (Normal when unset).
+fn verbosity_from_env() -> Result<VerbosityLevel> {
+    parse_verbosity(std::env::var(VERBOSITY_ENV).ok())
+}
+
+fn parse_verbosity(value: Option<String>) -> Result<VerbosityLevel> {
+    let Some(value) = value else {
+        return Ok(VerbosityLevel::Normal);
+    };
+    <VerbosityLevel as clap::ValueEnum>::from_str(value.trim(), true).map_err(|_| {
+        GpkgError::InvalidFormatOption(format!(
+            "{} must be quiet, normal, verbose or trace, got \"{}\"",
+            VERBOSITY_ENV, value
+        ))
+    })
+}
+
+/// Default output name of a GeoJSON input.
+fn input_stem(input: &Path) -> String {
+    input
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output")
+        .to_string()
+}
+
+/// Expands `*` and `?` in the file name of inputs that do not exist as given.
+///
+/// Matches are sorted; a pattern matching nothing is a missing file.
+fn expand_inputs(patterns: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut inputs = Vec::new();
+    for pattern in patterns {
+        let name = pattern.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if pattern.exists() || !name.contains(['*', '?']) {
+            inputs.push(pattern.clone());
+            continue;
+        }
+        let dir = pattern.parent().filter(|p| !p.as_os_str().is_empty());
+        let not_found = || GpkgError::FileNotFound(pattern.display().to_string());
+        let entries = std::fs::read_dir(dir.unwrap_or(Path::new("."))).map_err(|_| not_found())?;
+        let mut matches: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .filter(|entry| entry.file_name().to_str().is_some_and(|n| wildcard_match(name, n)))
+            .map(|entry| dir.map_or_else(|| PathBuf::from(entry.file_name()), |_| entry.path()))
+            .collect();
+        if matches.is_empty() {
+            return Err(not_found());
+        }
+        matches.sort();
+        inputs.extend(matches);
+    }
+    Ok(inputs)
+}
+
+/// Matches `name` against a pattern where `*` is any run and `?` any character.
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Last `*` seen and the name position it currently covers up to
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Parses the four comma-separated numbers of a bbox.
+fn parse_bbox_values(s: &str) -> Result<(f64, f64, f64, f64)> {
+    let parts: Vec<&str> = s.split(',').collect();
+    if parts.len() != 4 {
+        return Err(GpkgError::InvalidBbox(format!(
+            "expected 4 comma-separated values, got {}",
+            parts.len()
+        )));
+    }
+
+    let values: std::result::Result<Vec<f64>, _> = parts.iter().map(|p| p.trim().parse()).collect();
+    let values = values.map_err(|_| GpkgError::InvalidBbox("invalid number format".to_string()))?;
+    Ok((values[0], values[1], values[2], values[3]))
+}
+
+fn parse_bbox(s: &str) -> Result<Bbox> {
+    let (min_lon, min_lat, max_lon, max_lat) = parse_bbox_values(s)?;
+
+    // min_lon > max_lon means the bbox crosses the antimeridian (e.g. 170,-20,-170,-10)
+    let lon_range = -180.0..=180.0;
+    if min_lon > max_lon && (!lon_range.contains(&min_lon) || !lon_range.contains(&max_lon)) {
+        return Err(GpkgError::InvalidBbox(format!(
+            "antimeridian-crossing bbox longitudes must be within [-180, 180], got {} and {}",
+            min_lon, max_lon
+        )));
+    }
+
+    // Validate that the bbox is not empty and min < max for lat
+    if min_lon == max_lon {
+        return Err(GpkgError::InvalidBbox(format!(
+            "min_lon ({}) must differ from max_lon ({})",
+            min_lon, max_lon
+        )));
+    }
     if min_lat >= max_lat {
         return Err(GpkgError::InvalidBbox(format!(
             "min_lat ({}) must be less than max_lat ({})",
@@ -234,10 +1754,207 @@ fn parse_bbox(s: &str) -> Result<Bbox> {
         )));
     }
 
+    // Crossing bboxes are stored unwrapped so the width stays positive
+    let max_lon = if min_lon > max_lon { max_lon + 360.0 } else { max_lon };
+
     Ok(Bbox::new(min_lon, min_lat, max_lon, max_lat))
 }
 
-fn parse_rgba(s: &str) -> Result<[u8; 4]> {
+/// Parses a bbox given in `crs` ("minX,minY,maxX,maxY") and reprojects it to
+/// the WGS84 bbox covering its outline.
+fn parse_projected_bbox(s: &str, crs: &str) -> Result<Bbox> {
+    let transformer = reproject::Transformer::new(crs, "EPSG:4326")?;
+    if transformer.backend() == reproject::Backend::Identity {
+        return parse_bbox(s);
+    }
+    let (min_x, min_y, max_x, max_y) = parse_bbox_values(s)?;
+    if min_x >= max_x || min_y >= max_y {
+        return Err(GpkgError::InvalidBbox(format!(
+            "minimum coordinates ({}, {}) must be less than maximum ones ({}, {}) in {}",
+            min_x, min_y, max_x, max_y, crs
+        )));
+    }
+    let (min_lon, min_lat, max_lon, max_lat) = reproject::reproject_bbox_to_wgs84(min_x, min_y, max_x, max_y, crs)
+        .ok_or_else(|| GpkgError::Reprojection(format!("--bbox {} is outside the area of {}", s, crs)))?;
+    Ok(Bbox::new(min_lon, min_lat, max_lon, max_lat))
+}
+
+/// Parses a padding: a percentage ("5%"), meters ("500m") or degrees ("0.01").
+fn parse_padding(s: &str) -> Result<Padding> {
+    let s = s.trim();
+    let (number, padding): (&str, fn(f64) -> Padding) = if let Some(percent) = s.strip_suffix('%') {
+        (percent, |v| Padding::Ratio(v / 100.0))
+    } else if let Some(meters) = s.strip_suffix('m') {
+        (meters, Padding::Meters)
+    } else {
+        (s, Padding::Degrees)
+    };
+    match number.trim().parse::<f64>() {
+        Ok(value) if value >= 0.0 && value.is_finite() => Ok(padding(value)),
+        _ => Err(GpkgError::InvalidPadding(format!(
+            "expected a non-negative percentage, meters or degrees (\"5%\", \"500m\", \"0.01\"), got \"{}\"",
+            s
+        ))),
+    }
+}
+
+/// Print resolution of `--paper` pages without `--dpi`.
+const DEFAULT_DPI: u32 = 300;
+
+/// Portrait page sizes of `--paper`, in mm.
+const PAPER_SIZES: [(&str, (f64, f64)); 10] = [
+    ("a0", (841.0, 1189.0)),
+    ("a1", (594.0, 841.0)),
+    ("a2", (420.0, 594.0)),
+    ("a3", (297.0, 420.0)),
+    ("a4", (210.0, 297.0)),
+    ("a5", (148.0, 210.0)),
+    ("a6", (105.0, 148.0)),
+    ("letter", (215.9, 279.4)),
+    ("legal", (215.9, 355.6)),
+    ("tabloid", (279.4, 431.8)),
+];
+
+/// Parses a length in mm: "10", "10mm", "1cm" or "0.5in".
+fn parse_length(s: &str) -> Result<f64> {
+    let s = s.trim();
+    let (number, unit) = if let Some(number) = s.strip_suffix("mm") {
+        (number, 1.0)
+    } else if let Some(number) = s.strip_suffix("cm") {
+        (number, 10.0)
+    } else if let Some(number) = s.strip_suffix("in") {
+        (number, 25.4)
+    } else {
+        (s, 1.0)
+    };
+    match number.trim().parse::<f64>() {
+        Ok(value) if value >= 0.0 && value.is_finite() => Ok(value * unit),
+        _ => Err(GpkgError::InvalidPaper(format!(
+            "expected a non-negative length in mm, cm or in (\"10\", \"1cm\", \"0.5in\"), got \"{}\"",
+            s
+        ))),
+    }
+}
+
+/// Parses a page size, named ("A4", "letter") or "WxH" ("210x297", "8.5x11in"), in mm.
+fn parse_paper(s: &str) -> Result<(f64, f64)> {
+    let name = s.trim().to_ascii_lowercase();
+    if let Some((_, size)) = PAPER_SIZES.iter().find(|(n, _)| *n == name) {
+        return Ok(*size);
+    }
+    let invalid = || {
+        GpkgError::InvalidPaper(format!(
+            "expected A0-A6, letter, legal, tabloid or \"WxH\" (\"210x297mm\", \"8.5x11in\"), got \"{}\"",
+            s
+        ))
+    };
+    let (width, height) = name.split_once('x').ok_or_else(invalid)?;
+    // The unit of the height applies to both sides
+    let unit = ["mm", "cm", "in"].into_iter().find(|u| height.ends_with(u)).unwrap_or("");
+    let width = parse_length(&format!("{}{}", width, unit)).map_err(|_| invalid())?;
+    let height = parse_length(height).map_err(|_| invalid())?;
+    if width == 0.0 || height == 0.0 {
+        return Err(invalid());
+    }
+    Ok((width, height))
+}
+
+/// Pixel size of the printable area of a page of `size` mm (portrait),
+/// inside `margin` mm on each side, at `dpi`.
+fn paper_pixels(size: (f64, f64), landscape: bool, margin: f64, dpi: u32) -> Result<(u32, u32)> {
+    let (width, height) = if landscape { (size.1, size.0) } else { size };
+    let pixels = |mm: f64| ((mm - 2.0 * margin) / 25.4 * dpi as f64).floor();
+    let (width_px, height_px) = (pixels(width), pixels(height));
+    if width_px < 1.0 || height_px < 1.0 {
+        return Err(GpkgError::InvalidPaper(format!(
+            "{} mm margins leave no printable area on a {}x{} mm page",
+            margin, width, height
+        )));
+    }
+    Ok((width_px.min(u32::MAX as f64) as u32, height_px.min(u32::MAX as f64) as u32))
+}
+
+/// Parses an aspect ratio: "W:H" or a decimal width/height ratio.
+fn parse_aspect(s: &str) -> Result<f64> {
+    let ratio = match s.split_once(':') {
+        Some((width, height)) => width.trim().parse::<f64>().and_then(|w| Ok(w / height.trim().parse::<f64>()?)),
+        None => s.trim().parse(),
+    };
+    match ratio {
+        Ok(ratio) if ratio > 0.0 && ratio.is_finite() => Ok(ratio),
+        _ => Err(GpkgError::InvalidAspect(format!(
+            "expected positive \"W:H\" or a ratio (\"16:9\", \"1.5\"), got \"{}\"",
+            s
+        ))),
+    }
+}
+
+/// Parses a "LAYER:CLAUSE" per-layer filter, splitting on the first colon.
+fn parse_layer_where(s: &str) -> Result<(String, String)> {
+    let (layer, clause) = s.split_once(':').ok_or_else(|| {
+        GpkgError::InvalidWhereClause(format!("expected LAYER:CLAUSE, got {}", s))
+    })?;
+    let layer = layer.trim();
+    if layer.is_empty() {
+        return Err(GpkgError::InvalidWhereClause(format!("missing layer name: {}", s)));
+    }
+    validate_where_clause(clause)?;
+    Ok((layer.to_string(), clause.trim().to_string()))
+}
+
+/// Parses "SOURCE=ALIAS" pairs; aliases must be unique file names.
+fn parse_renames(values: &[String]) -> Result<Vec<(String, String)>> {
+    let mut renames: Vec<(String, String)> = Vec::new();
+    for s in values {
+        let (source, alias) = s
+            .split_once('=')
+            .map(|(source, alias)| (source.trim(), alias.trim()))
+            .filter(|(source, alias)| !source.is_empty() && !alias.is_empty())
+            .ok_or_else(|| GpkgError::InvalidFormatOption(format!("--rename expects SOURCE=ALIAS, got {}", s)))?;
+        if alias.contains(['/', '\\']) || alias == "." || alias == ".." {
+            return Err(GpkgError::InvalidFormatOption(format!(
+                "--rename alias must be a plain file name, got {}",
+                alias
+            )));
+        }
+        if let Some((other, _)) = renames.iter().find(|(_, a)| a == alias) {
+            return Err(GpkgError::InvalidFormatOption(format!(
+                "--rename gives {} and {} the same alias {}",
+                other, source, alias
+            )));
+        }
+        renames.push((source.to_string(), alias.to_string()));
+    }
+    Ok(renames)
+}
+
+/// Parses "[PATH=]CRS"; PATH must be the input or one of the merged files.
+///
+/// PROJ strings ("+proj=...") contain '=' and are always taken whole.
+fn parse_source_crs(s: &str, input: &Path, merge: &[PathBuf]) -> Result<(Option<PathBuf>, String)> {
+    let (path, crs) = match s.split_once('=') {
+        Some((path, crs)) if !s.starts_with('+') => (Some(PathBuf::from(path.trim())), crs.trim()),
+        _ => (None, s.trim()),
+    };
+    if crs.is_empty() {
+        return Err(GpkgError::InvalidFormatOption(format!("--source-crs is missing a CRS: {}", s)));
+    }
+    if let Some(path) = &path {
+        if path != input && !merge.contains(path) {
+            return Err(GpkgError::InvalidFormatOption(format!(
+                "--source-crs names {}, which is neither the input nor a --merge file",
+                path.display()
+            )));
+        }
+    }
+    Ok((path, crs.to_string()))
+}
+
+/// Parses an RGBA hex color, or "none" (None) for no fill.
+fn parse_rgba(s: &str) -> Result<Option<[u8; 4]>> {
+    if s.trim().eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
     let bytes = hex::decode(s).map_err(|_| GpkgError::InvalidColor(s.to_string()))?;
     if bytes.len() != 4 {
         return Err(GpkgError::InvalidColor(format!(
@@ -245,10 +1962,14 @@ fn parse_rgba(s: &str) -> Result<[u8; 4]> {
             s.len()
         )));
     }
-    Ok([bytes[0], bytes[1], bytes[2], bytes[3]])
+    Ok(Some([bytes[0], bytes[1], bytes[2], bytes[3]]))
 }
 
-fn parse_rgb(s: &str) -> Result<[u8; 3]> {
+/// Parses an RGB hex color, or "none" (None) for no stroke.
+fn parse_rgb(s: &str) -> Result<Option<[u8; 3]>> {
+    if s.trim().eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
     let bytes = hex::decode(s).map_err(|_| GpkgError::InvalidColor(s.to_string()))?;
     if bytes.len() != 3 {
         return Err(GpkgError::InvalidColor(format!(
@@ -256,7 +1977,7 @@ fn parse_rgb(s: &str) -> Result<[u8; 3]> {
             s.len()
         )));
     }
-    Ok([bytes[0], bytes[1], bytes[2]])
+    Ok(Some([bytes[0], bytes[1], bytes[2]]))
 }
 
 #[cfg(test)]
@@ -293,7 +2014,8 @@ mod tests {
     #[test]
     fn test_parse_rgba_valid() {
         let color = parse_rgba("FF000080").unwrap();
-        assert_eq!(color, [255, 0, 0, 128]);
+        assert_eq!(color, Some([255, 0, 0, 128]));
+        assert_eq!(parse_rgba("None").unwrap(), None);
     }
 
     #[test]
@@ -311,7 +2033,8 @@ mod tests {
     #[test]
     fn test_parse_rgb_valid() {
         let color = parse_rgb("00FF00").unwrap();
-        assert_eq!(color, [0, 255, 0]);
+        assert_eq!(color, Some([0, 255, 0]));
+        assert_eq!(parse_rgb("none").unwrap(), None);
     }
 
     #[test]
@@ -322,11 +2045,6 @@ mod tests {
 
     #[test]
     fn test_parse_bbox_inverted() {
-        // Test inverted longitude (max < min)
-        let err = parse_bbox("-4.0,48.0,-4.5,48.5").unwrap_err();
-        assert!(err.to_string().contains("min_lon"));
-        assert!(err.to_string().contains("must be less than"));
-
         // Test inverted latitude (max < min)
         let err = parse_bbox("-4.5,48.5,-4.0,48.0").unwrap_err();
         assert!(err.to_string().contains("min_lat"));
@@ -337,25 +2055,215 @@ mod tests {
         assert!(err.to_string().contains("min_lon"));
     }
 
+    #[test]
+    fn test_parse_bbox_antimeridian() {
+        // Inverted longitudes describe a bbox crossing the dateline
+        let bbox = parse_bbox("170.0,-20.0,-170.0,-10.0").unwrap();
+        assert!(bbox.crosses_antimeridian());
+        assert!((bbox.min_lon - 170.0).abs() < 1e-10);
+        assert!((bbox.max_lon - 190.0).abs() < 1e-10);
+        assert!((bbox.width() - 20.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_parse_bbox_antimeridian_out_of_range() {
+        let err = parse_bbox("200.0,-20.0,-170.0,-10.0").unwrap_err();
+        assert!(err.to_string().contains("antimeridian"));
+    }
+
+    #[test]
+    fn test_parse_projected_bbox() {
+        // WGS84 coordinates keep the checks of --bbox
+        let bbox = parse_projected_bbox("170.0,-20.0,-170.0,-10.0", "EPSG:4326").unwrap();
+        assert!(bbox.crosses_antimeridian());
+
+        #[cfg(any(feature = "reproject", feature = "reproject-pure"))]
+        {
+            // Lambert-93 coordinates around Lyon
+            let bbox = parse_projected_bbox("840000,6510000,850000,6525000", "EPSG:2154").unwrap();
+            assert!(bbox.min_lon > 4.7 && bbox.max_lon < 5.0, "{:?}", bbox);
+            assert!(bbox.min_lat > 45.6 && bbox.max_lat < 45.9, "{:?}", bbox);
+            let err = parse_projected_bbox("850000,6510000,840000,6525000", "EPSG:2154").unwrap_err();
+            assert!(matches!(err, GpkgError::InvalidBbox(_)));
+        }
+        assert!(matches!(
+            parse_projected_bbox("0,0,1,1", "EPSG:999999").unwrap_err(),
+            GpkgError::Reprojection(_)
+        ));
+        assert!(Args::try_parse_from(["gpkg-to-png", "a.gpkg", "-f", "gpkg", "--bbox-crs", "EPSG:2154"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_padding() {
+        assert_eq!(parse_padding("5%").unwrap(), Padding::Ratio(0.05));
+        assert_eq!(parse_padding(" 500m").unwrap(), Padding::Meters(500.0));
+        assert_eq!(parse_padding("0.01").unwrap(), Padding::Degrees(0.01));
+        for invalid in ["", "-5%", "5km", "m", "NaN"] {
+            assert!(parse_padding(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_aspect() {
+        assert_eq!(parse_aspect("16:9").unwrap(), 16.0 / 9.0);
+        assert_eq!(parse_aspect(" 4 : 3 ").unwrap(), 4.0 / 3.0);
+        assert_eq!(parse_aspect("1.5").unwrap(), 1.5);
+        for invalid in ["", "16:", "0:9", "16:0", "-1", "a:b"] {
+            assert!(parse_aspect(invalid).is_err(), "{:?}", invalid);
+        }
+
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-s", "1", "--square"]);
+        assert_eq!(args.validate().unwrap().aspect, Some(1.0));
+    }
+
+    #[test]
+    fn test_validate_rotation() {
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-s", "1", "--rotation", "-30"]);
+        assert_eq!(args.validate().unwrap().rotation, -30.0);
+        assert_eq!(create_test_args(Some(0.001), None, None).validate().unwrap().rotation, 0.0);
+
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-s", "1", "--rotation", "inf"]);
+        assert!(args.validate().is_err());
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-s", "1", "--rotation", "30", "--geotag"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_paper() {
+        assert_eq!(parse_paper("A4").unwrap(), (210.0, 297.0));
+        assert_eq!(parse_paper("Letter").unwrap(), (215.9, 279.4));
+        assert_eq!(parse_paper("100x150").unwrap(), (100.0, 150.0));
+        assert_eq!(parse_paper("10x15cm").unwrap(), (100.0, 150.0));
+        for invalid in ["A9", "100", "0x150", "x150", "100x-150"] {
+            assert!(matches!(parse_paper(invalid), Err(GpkgError::InvalidPaper(_))), "{:?}", invalid);
+        }
+        assert_eq!(parse_length("1cm").unwrap(), 10.0);
+        assert_eq!(parse_length("0.5in").unwrap(), 12.7);
+
+        // The printable area of an A4 page at 300 dpi, inside 10 mm margins
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--paper", "a4", "--margin", "10"]);
+        let config = args.validate().unwrap();
+        assert_eq!((config.width, config.height), (Some(2244), Some(3271)));
+        assert_eq!(config.png_options.dpi, Some(300));
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--paper", "a4", "--landscape", "--dpi", "150"]);
+        let config = args.validate().unwrap();
+        assert_eq!((config.width, config.height), (Some(1753), Some(1240)));
+        assert_eq!(config.png_options.dpi, Some(150));
+
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--paper", "a6", "--margin", "3in"]);
+        assert!(matches!(args.validate(), Err(GpkgError::InvalidPaper(_))));
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--paper", "a4", "-s", "1"]).is_err());
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-s", "1", "--landscape"]).is_err());
+    }
+
     fn create_test_args(resolution: Option<f64>, scale: Option<f64>, bbox: Option<&str>) -> Args {
         Args {
-            input: PathBuf::from("test.gpkg"),
-            verbose: false,
+            inputs: vec![PathBuf::from("test.gpkg")],
+            config: None,
+            layer_styles: vec![],
+            verbose: 0,
             quiet: false,
+            color: ColorMode::Auto,
             no_color: false,
             output_dir: PathBuf::from("."),
+            output_layout: OutputLayout::Flat,
+            content_addressed: false,
+            resume: false,
+            on_collision: CollisionPolicy::Suffix,
+            name_template: None,
+            into: None,
+            geotag: false,
+            manifest: None,
+            stac: false,
+            footprint: false,
+            verify_against: None,
+            verify_tolerance: 0,
+            verify_threshold: 0.0,
+            no_metadata: false,
+            png_compression: PngCompression::Default,
+            png_filter: PngFilter::Adaptive,
+            interlace: false,
+            colors: None,
+            dither: false,
+            save_partial: false,
             bbox: bbox.map(|s| s.to_string()),
+            bbox_crs: None,
+            padding: None,
+            aspect: None,
+            square: false,
+            rotation: None,
             resolution,
             scale,
+            width: None,
+            height: None,
+            paper: None,
+            landscape: false,
+            margin: None,
+            dpi: None,
+            max_dimension: MAX_DIMENSION,
+            auto_downscale: false,
+            autocrop: None,
+            preview_scales: vec![],
             fill: "FF000080".to_string(),
             stroke: "FF0000".to_string(),
+            auto_colors: None,
+            color_per_feature: None,
             stroke_width: 1,
-            layer: None,
-            format: Format::Gpkg,
+            draw_vertices: false,
+            vertex_color: "0000FF".to_string(),
+            resample: Resample::Box,
+            supersample: None,
+            overviews: 0,
+            export_attributes: None,
+            min_feature_px: None,
+            glow: None,
+            glow_radius: 6,
+            opacity_column: None,
+            stroke_color_column: None,
+            fill_column: None,
+            ramp: "viridis".to_string(),
+            classify: None,
+            classes: 5,
+            burn: None,
+            burn_rule: BurnRule::Last,
+            burn_type: BurnType::Float64,
+            layer: vec![],
+            exclude_layer: vec![],
+            raster: None,
+            raster_only: false,
+            where_clause: None,
+            layer_where: vec![],
+            limit: None,
+            offset: None,
+            rename: vec![],
+            fid: vec![],
+            fid_column: None,
+            jobs: 1,
+            curve_tolerance: DEFAULT_CURVE_TOLERANCE,
+            reprojection_cache: None,
+            proj_data: None,
+            proj_network: false,
+            proj_only_best: false,
+            proj_accuracy: None,
+            format: Some(Format::Gpkg),
             output_name: None,
+            merge: vec![],
+            source_crs: vec![],
+            progress: ProgressMode::Bar,
+            progress_interval: 50,
+            deny: vec![],
+            mmap_size: None,
+            cache_size: None,
+            temp_store: None,
         }
     }
 
+    #[test]
+    fn test_create_test_args_matches_parsed_defaults() {
+        let parsed = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg"]);
+        let built = create_test_args(None, None, None);
+        assert_eq!(format!("{:?}", parsed), format!("{:?}", built));
+    }
+
     #[test]
     fn test_validate_resolution_only() {
         let args = create_test_args(Some(0.001), None, Some("-4.5,48.0,-4.0,48.5"));
@@ -382,6 +2290,29 @@ mod tests {
         assert!(err.to_string().contains("scale"));
     }
 
+    #[test]
+    fn test_validate_width_and_height() {
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--width", "2048"]);
+        let config = args.validate().unwrap();
+        assert_eq!((config.width, config.height), (Some(2048), None));
+        assert!(config.resolution.is_none() && config.scale.is_none());
+
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--height", "0"]).is_err());
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--width", "100", "-s", "10"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_max_dimension() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert_eq!(config.max_dimension, MAX_DIMENSION);
+        assert!(!config.auto_downscale);
+
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-s", "1", "--max-dimension", "4096", "--auto-fit"]);
+        let config = args.validate().unwrap();
+        assert_eq!(config.max_dimension, 4096);
+        assert!(config.auto_downscale);
+    }
+
     #[test]
     fn test_validate_both_resolution_and_scale() {
         let args = create_test_args(Some(0.001), Some(10.0), Some("-4.5,48.0,-4.0,48.5"));
@@ -430,44 +2361,46 @@ mod tests {
     #[test]
     fn test_validate_geojson_with_layer_option() {
         let args = Args {
-            input: PathBuf::from("test.geojson"),
-            verbose: false,
-            quiet: false,
-            no_color: false,
-            output_dir: PathBuf::from("."),
-            bbox: Some("-4.5,48.0,-4.0,48.5".to_string()),
-            resolution: Some(0.001),
-            scale: None,
-            fill: "FF000080".to_string(),
-            stroke: "FF0000".to_string(),
-            stroke_width: 1,
-            layer: Some("test_layer".to_string()),
-            format: Format::Geojson,
-            output_name: None,
+            inputs: vec![PathBuf::from("test.geojson")],
+            layer: vec!["test_layer".to_string()],
+            format: Some(Format::Geojson),
+            ..create_test_args(Some(0.001), None, Some("-4.5,48.0,-4.0,48.5"))
         };
         let err = args.validate().unwrap_err();
         assert!(err
             .to_string()
-            .contains("--layer cannot be used with geojson format"));
+            .contains("--layer and --exclude-layer cannot be used with geojson format"));
+    }
+
+    #[test]
+    fn test_selects_layer() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert!(config.selects_layer("roads"));
+
+        let args = ["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "1", "-l", "cadastre_*", "--layer", "roads"];
+        let config = Args::parse_from(args).validate().unwrap();
+        assert_eq!(config.layer, ["cadastre_*", "roads"]);
+        assert!(config.selects_layer("cadastre_2024"));
+        assert!(config.selects_layer("roads"));
+        assert!(!config.selects_layer("roads_old"));
+        assert!(!config.selects_layer("rivers"));
+
+        let args = ["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "1", "--exclude-layer", "*_draft"];
+        let config = Args::parse_from(args).validate().unwrap();
+        assert!(config.selects_layer("roads"));
+        assert!(!config.selects_layer("roads_draft"));
+        let args = ["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "1", "-l", "cadastre_*", "--exclude-layer", "cadastre_old"];
+        let config = Args::parse_from(args).validate().unwrap();
+        assert!(config.selects_layer("cadastre_2024"));
+        assert!(!config.selects_layer("cadastre_old"));
     }
 
     #[test]
     fn test_validate_geojson_default_output_name() {
         let args = Args {
-            input: PathBuf::from("test.geojson"),
-            verbose: false,
-            quiet: false,
-            no_color: false,
-            output_dir: PathBuf::from("."),
-            bbox: Some("-4.5,48.0,-4.0,48.5".to_string()),
-            resolution: Some(0.001),
-            scale: None,
-            fill: "FF000080".to_string(),
-            stroke: "FF0000".to_string(),
-            stroke_width: 1,
-            layer: None,
-            format: Format::Geojson,
-            output_name: None,
+            inputs: vec![PathBuf::from("test.geojson")],
+            format: Some(Format::Geojson),
+            ..create_test_args(Some(0.001), None, Some("-4.5,48.0,-4.0,48.5"))
         };
         let config = args.validate().unwrap();
         assert_eq!(config.output_name, Some("test".to_string()));
@@ -476,42 +2409,22 @@ mod tests {
     #[test]
     fn test_validate_geojson_custom_output_name() {
         let args = Args {
-            input: PathBuf::from("test.geojson"),
-            verbose: false,
-            quiet: false,
-            no_color: false,
-            output_dir: PathBuf::from("."),
-            bbox: Some("-4.5,48.0,-4.0,48.5".to_string()),
-            resolution: Some(0.001),
-            scale: None,
-            fill: "FF000080".to_string(),
-            stroke: "FF0000".to_string(),
-            stroke_width: 1,
-            layer: None,
-            format: Format::Geojson,
+            inputs: vec![PathBuf::from("test.geojson")],
+            format: Some(Format::Geojson),
             output_name: Some("custom".to_string()),
+            ..create_test_args(Some(0.001), None, Some("-4.5,48.0,-4.0,48.5"))
         };
         let config = args.validate().unwrap();
         assert_eq!(config.output_name, Some("custom".to_string()));
+        let input = config.input.clone();
+        assert_eq!(config.for_input(&input).output_name, Some("custom".to_string()));
     }
 
     #[test]
     fn test_validate_gpkg_with_output_name_option() {
         let args = Args {
-            input: PathBuf::from("test.gpkg"),
-            verbose: false,
-            quiet: false,
-            no_color: false,
-            output_dir: PathBuf::from("."),
-            bbox: Some("-4.5,48.0,-4.0,48.5".to_string()),
-            resolution: Some(0.001),
-            scale: None,
-            fill: "FF000080".to_string(),
-            stroke: "FF0000".to_string(),
-            stroke_width: 1,
-            layer: None,
-            format: Format::Gpkg,
             output_name: Some("custom".to_string()),
+            ..create_test_args(Some(0.001), None, Some("-4.5,48.0,-4.0,48.5"))
         };
         let err = args.validate().unwrap_err();
         assert!(err
@@ -519,6 +2432,30 @@ mod tests {
             .contains("--output-name can only be used with geojson format"));
     }
 
+    #[test]
+    fn test_parse_geojson_options() {
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            "test.geojson",
+            "-f",
+            "geojson",
+            "--bbox=-4.5,48.0,-4.0,48.5",
+            "--resolution=0.001",
+            "--output-name",
+            "custom",
+        ]);
+        assert_eq!(args.inputs, [PathBuf::from("test.geojson")]);
+        assert!(matches!(args.format, Some(Format::Geojson)));
+        assert_eq!(args.bbox.as_deref(), Some("-4.5,48.0,-4.0,48.5"));
+        assert_eq!(args.resolution, Some(0.001));
+        assert_eq!(args.output_name.as_deref(), Some("custom"));
+        assert_eq!(args.validate().unwrap().output_name, Some("custom".to_string()));
+
+        let args = Args::parse_from(["gpkg-to-png", "test.geojson", "-f", "geojson", "-r", "0.001", "--layer", "test_layer"]);
+        assert_eq!(args.layer, ["test_layer"]);
+        assert!(args.validate().is_err());
+    }
+
     #[test]
     fn test_validate_default_verbosity() {
         let args = create_test_args(Some(0.001), None, Some("-4.5,48.0,-4.0,48.5"));
@@ -529,9 +2466,90 @@ mod tests {
     #[test]
     fn test_validate_verbose_flag() {
         let mut args = create_test_args(Some(0.001), None, Some("-4.5,48.0,-4.0,48.5"));
-        args.verbose = true;
+        args.verbose = 1;
         let config = args.validate().unwrap();
         assert_eq!(config.verbosity, VerbosityLevel::Verbose);
+
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001", "-vv"]);
+        assert_eq!(args.verbose, 2);
+        assert_eq!(args.validate().unwrap().verbosity, VerbosityLevel::Trace);
+    }
+
+    #[test]
+    fn test_parse_verbosity() {
+        assert_eq!(parse_verbosity(None).unwrap(), VerbosityLevel::Normal);
+        assert_eq!(parse_verbosity(Some("Trace".to_string())).unwrap(), VerbosityLevel::Trace);
+        assert_eq!(parse_verbosity(Some("quiet".to_string())).unwrap(), VerbosityLevel::Quiet);
+        assert!(parse_verbosity(Some("loud".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_validate_color() {
+        let parse = |extra: &[&str]| {
+            let argv = ["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001"].iter().chain(extra);
+            Args::parse_from(argv).validate().unwrap().color
+        };
+        assert_eq!(parse(&["--color", "always"]), ColorMode::Always);
+        assert_eq!(parse(&["--no-color"]), ColorMode::Never);
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--color", "sometimes"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_fill_and_stroke_none() {
+        let parse = |extra: &[&str]| {
+            let argv = ["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001"].iter().chain(extra);
+            Args::parse_from(argv).validate()
+        };
+        let config = parse(&["--fill", "none"]).unwrap();
+        assert_eq!((config.fill, config.stroke), (None, Some([255, 0, 0])));
+        let config = parse(&["--stroke", "NONE"]).unwrap();
+        assert_eq!((config.fill, config.stroke), (Some([255, 0, 0, 128]), None));
+
+        assert!(parse(&["--fill", "none", "--stroke", "none"]).is_err());
+        assert!(parse(&["--fill", "none", "--stroke-width", "0"]).is_err());
+        assert!(parse(&["--fill", "none", "--stroke", "none", "--draw-vertices"]).is_ok());
+        assert!(parse(&["--fill", "none", "--opacity-column", "share"]).is_err());
+        assert!(parse(&["--stroke", "none", "--stroke-color-column", "color"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_glow() {
+        let parse = |extra: &[&str]| {
+            let argv = ["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001"].iter().chain(extra);
+            Args::parse_from(argv).validate()
+        };
+        // A halo alone is something to draw
+        let config = parse(&["--fill", "none", "--stroke", "none", "--glow", "FFFF00C0"]).unwrap();
+        assert_eq!(config.glow, Some(Glow { color: [255, 255, 0, 192], radius: 6 }));
+        assert_eq!(parse(&["--glow", "FFFF00C0", "--glow-radius", "2"]).unwrap().glow.unwrap().radius, 2);
+        assert!(parse(&["--glow", "yellow"]).is_err());
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "1", "--glow-radius", "2"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_fill_column() {
+        let parse = |extra: &[&str]| {
+            let argv = ["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001"].iter().chain(extra);
+            Args::try_parse_from(argv).map_err(|e| e.to_string()).and_then(|a| a.validate().map_err(|e| e.to_string()))
+        };
+        let config = parse(&["--fill-column", "density"]).unwrap();
+        assert_eq!(config.fill_column.as_deref(), Some("density"));
+        assert_eq!(config.ramp, Ramp::default());
+        let config = parse(&["--fill-column", "density", "--ramp", "blues_r"]).unwrap();
+        assert_eq!(config.ramp, Ramp::named("Blues").unwrap().reversed());
+
+        assert!(parse(&["--fill-column", "density", "--ramp", "rainbow"]).unwrap_err().contains("unknown ramp"));
+        assert!(parse(&["--ramp", "magma"]).is_err());
+        assert!(parse(&["--fill-column", "density", "--color-per-feature"]).is_err());
+        assert!(parse(&["--fill-column", "density", "--fill", "none"]).is_err());
+
+        let config = parse(&["--fill-column", "density", "--classify", "jenks"]).unwrap();
+        assert_eq!((config.classify, config.classes), (Some(Classification::Jenks), 5));
+        let config = parse(&["--fill-column", "density", "--classify", "equal-interval", "--classes", "7"]).unwrap();
+        assert_eq!((config.classify, config.classes), (Some(Classification::EqualInterval), 7));
+        assert!(parse(&["--fill-column", "density", "--classify", "quantile", "--classes", "1"]).is_err());
+        assert!(parse(&["--fill-column", "density", "--classes", "4"]).is_err());
+        assert!(parse(&["--classify", "quantile"]).is_err());
     }
 
     #[test]
@@ -545,11 +2563,883 @@ mod tests {
     #[test]
     fn test_validate_verbose_and_quiet_mutually_exclusive() {
         let mut args = create_test_args(Some(0.001), None, Some("-4.5,48.0,-4.0,48.5"));
-        args.verbose = true;
+        args.verbose = 1;
         args.quiet = true;
         let err = args.validate().unwrap_err();
         assert!(err.to_string().contains("mutually exclusive"));
         assert!(err.to_string().contains("verbose"));
         assert!(err.to_string().contains("quiet"));
     }
+
+    #[test]
+    fn test_validate_progress_interval() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert_eq!(config.progress_interval, Duration::from_millis(50));
+
+        let mut args = create_test_args(Some(0.001), None, None);
+        args.progress_interval = 0;
+        assert!(args.validate().unwrap().progress_interval.is_zero());
+    }
+
+    #[test]
+    fn test_parse_progress_mode() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert_eq!(config.progress, ProgressMode::Bar);
+
+        let args = Args::parse_from(["gpkg-to-png", "in.gpkg", "-f", "gpkg", "-r", "0.1", "--progress", "json"]);
+        assert_eq!(args.validate().unwrap().progress, ProgressMode::Json);
+    }
+
+    #[test]
+    fn test_validate_sqlite_pragmas() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert!(config.sqlite.mmap_size.is_none());
+        assert!(config.sqlite.temp_store.is_none());
+
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            "test.gpkg",
+            "-f",
+            "gpkg",
+            "-r",
+            "0.001",
+            "--mmap-size=0",
+            "--cache-size=65536",
+            "--temp-store=memory",
+        ]);
+        let config = args.validate().unwrap();
+        assert_eq!(config.sqlite.mmap_size, Some(0));
+        assert_eq!(config.sqlite.cache_size_kib, Some(65536));
+        assert_eq!(config.sqlite.temp_store, Some(TempStore::Memory));
+    }
+
+    #[test]
+    fn test_validate_where_clauses() {
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            "test.gpkg",
+            "-f",
+            "gpkg",
+            "-r",
+            "0.001",
+            "--where",
+            "population > 1000",
+            "--layer-where",
+            "roads:kind = 'a:b'",
+        ]);
+        let config = args.validate().unwrap();
+        assert_eq!(
+            config.where_clauses_for("roads"),
+            vec!["population > 1000".to_string(), "kind = 'a:b'".to_string()]
+        );
+        assert_eq!(config.where_clauses_for("parcels"), vec!["population > 1000".to_string()]);
+    }
+
+    #[test]
+    fn test_validate_where_rejects_injection() {
+        let mut args = create_test_args(Some(0.001), None, None);
+        args.where_clause = Some("1; DELETE FROM parcels".to_string());
+        assert!(matches!(args.validate(), Err(GpkgError::InvalidWhereClause(_))));
+
+        let mut args = create_test_args(Some(0.001), None, None);
+        args.layer_where = vec!["no colon here".to_string()];
+        assert!(matches!(args.validate(), Err(GpkgError::InvalidWhereClause(_))));
+    }
+
+    #[test]
+    fn test_validate_geojson_with_where() {
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            "test.geojson",
+            "-f",
+            "geojson",
+            "-r",
+            "0.001",
+            "--where",
+            "a = 1",
+        ]);
+        let err = args.validate().unwrap_err();
+        assert!(err.to_string().contains("only be used with gpkg"));
+    }
+
+    #[test]
+    fn test_validate_limit_offset() {
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            "test.gpkg",
+            "-f",
+            "gpkg",
+            "-r",
+            "0.001",
+            "--limit",
+            "100",
+            "--offset",
+            "500",
+        ]);
+        let config = args.validate().unwrap();
+        assert_eq!(config.limit, Some(100));
+        assert_eq!(config.offset, Some(500));
+
+        let result =
+            Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--limit", "0"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_validate_output_layout() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert_eq!(config.output_layout, OutputLayout::Flat);
+
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            "test.gpkg",
+            "-f",
+            "gpkg",
+            "-r",
+            "0.001",
+            "--output-layout",
+            "per-input-dir",
+        ]);
+        assert_eq!(args.validate().unwrap().output_layout, OutputLayout::PerInputDir);
+    }
+
+    #[test]
+    fn test_validate_name_template() {
+        let parse = |template: &str| {
+            Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001", "--name-template", template])
+                .validate()
+        };
+        let config = parse("{layer}_{date}.png").unwrap();
+        assert_eq!(config.name_template.as_deref(), Some("{layer}_{date}.png"));
+        assert!(config.started > 0);
+        assert!(matches!(parse("{zoom}"), Err(GpkgError::InvalidNameTemplate(_))));
+        assert!(matches!(parse("{layer"), Err(GpkgError::InvalidNameTemplate(_))));
+
+        let args = ["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "1", "--name-template", "{layer}"];
+        assert!(Args::try_parse_from(args.iter().chain(&["--content-addressed"])).is_err());
+    }
+
+    #[test]
+    fn test_validate_manifest() {
+        let parse = |extra: &[&str]| {
+            let argv = ["gpkg-to-png", "-f", "gpkg", "-r", "1", "-o", "out"].iter().chain(extra);
+            Args::parse_from(argv).validate().unwrap()
+        };
+        assert_eq!(parse(&["test.gpkg"]).manifest, None);
+        // A bare --manifest does not take the input as its path
+        let config = parse(&["--manifest", "test.gpkg"]);
+        assert_eq!(config.manifest, Some(PathBuf::from("out/manifest.json")));
+        assert_eq!(config.input, PathBuf::from("test.gpkg"));
+        assert_eq!(parse(&["test.gpkg", "--manifest=runs/1.json"]).manifest, Some(PathBuf::from("runs/1.json")));
+
+        assert!(!parse(&["test.gpkg"]).stac);
+        assert!(parse(&["test.gpkg", "--stac"]).stac);
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--stac", "--rotation", "10"]).is_err());
+        assert!(parse(&["test.gpkg", "--footprint", "--rotation", "10"]).footprint);
+    }
+
+    #[test]
+    fn test_validate_auto_colors() {
+        let parse = |extra: &[&str]| {
+            let argv = ["gpkg-to-png", "test.gpkg", "-r", "0.001"].iter().chain(extra);
+            Args::try_parse_from(argv)
+        };
+        let config = parse(&["-f", "gpkg", "--auto-colors"]).unwrap().validate().unwrap();
+        assert_eq!(config.auto_colors, Some(0));
+        let roads = config.for_layer("roads", 1);
+        assert_eq!(roads.fill, Some([0xF2, 0x8E, 0x2B, 0x80]));
+        assert_eq!(roads.stroke, Some([0xF2, 0x8E, 0x2B]));
+
+        let config = parse(&["-f", "gpkg", "--auto-colors=7"]).unwrap().validate().unwrap();
+        assert_eq!(config.auto_colors, Some(7));
+        assert!(parse(&["-f", "gpkg", "--auto-colors", "--fill", "00FF00FF"]).is_err());
+        assert!(parse(&["-f", "geojson", "--auto-colors"]).unwrap().validate().is_err());
+
+        let config = parse(&["-f", "geojson", "--color-per-feature=3"]).unwrap().validate().unwrap();
+        assert_eq!(config.color_per_feature, Some(3));
+        assert!(parse(&["-f", "gpkg", "--auto-colors", "--color-per-feature"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_png_options() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert_eq!(config.png_options, PngOptions::default());
+
+        let args = Args::parse_from([
+            "gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001",
+            "--png-compression", "fast", "--png-filter", "up", "--interlace",
+        ]);
+        let options = args.validate().unwrap().png_options;
+        assert_eq!(options.compression, PngCompression::Fast);
+        assert_eq!(options.filter, PngFilter::Up);
+        assert!(options.interlace);
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--png-compression", "9"]).is_err());
+
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001", "--colors", "16", "--dither"]);
+        let options = args.validate().unwrap().png_options;
+        assert_eq!((options.colors, options.dither), (Some(16), true));
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--colors", "300"]).is_err());
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--dither"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_burn() {
+        let parse = |extra: &[&str]| {
+            let argv = ["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001"].iter().chain(extra);
+            Args::try_parse_from(argv)
+        };
+        let config = parse(&["--burn", "population"]).unwrap().validate().unwrap();
+        assert_eq!(config.burn.as_deref(), Some("population"));
+        assert_eq!(config.burn_rule, BurnRule::Last);
+        let config = parse(&["--burn", "population", "--burn-rule", "sum"]).unwrap().validate().unwrap();
+        assert_eq!(config.burn_rule, BurnRule::Sum);
+        assert_eq!(config.burn_type, BurnType::Float64);
+        let config = parse(&["--burn", "population", "--burn-type", "uint16"]).unwrap().validate().unwrap();
+        assert_eq!(config.burn_type, BurnType::Uint16);
+
+        assert!(parse(&["--burn-rule", "max"]).is_err());
+        assert!(parse(&["--burn", "population", "--autocrop"]).is_err());
+        assert!(parse(&["--burn", "population", "--rotation", "10"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_on_collision() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert_eq!(config.on_collision, CollisionPolicy::Suffix);
+
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001", "--on-collision", "error"]);
+        assert_eq!(args.validate().unwrap().on_collision, CollisionPolicy::Error);
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--on-collision", "overwrite"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_fid() {
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            "test.gpkg",
+            "-f",
+            "gpkg",
+            "-r",
+            "0.001",
+            "--fid",
+            "12, 17,203",
+        ]);
+        let fids = args.validate().unwrap().fids.unwrap();
+        assert!(fids.column.is_none());
+        assert_eq!(fids.values, vec!["12", "17", "203"]);
+
+        let mut args = create_test_args(Some(0.001), None, None);
+        args.fid = vec!["parcel-7".to_string()];
+        assert!(args.validate().unwrap_err().to_string().contains("must be integers"));
+
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            "test.gpkg",
+            "-f",
+            "gpkg",
+            "-r",
+            "0.001",
+            "--fid",
+            "parcel-7",
+            "--fid-column",
+            "parcel_id",
+        ]);
+        let fids = args.validate().unwrap().fids.unwrap();
+        assert_eq!(fids.column.as_deref(), Some("parcel_id"));
+    }
+
+    #[test]
+    fn test_fid_column_requires_fid() {
+        let result = Args::try_parse_from([
+            "gpkg-to-png",
+            "test.gpkg",
+            "-f",
+            "gpkg",
+            "--fid-column",
+            "parcel_id",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_validate_deny_codes() {
+        let mut args = create_test_args(Some(0.001), None, Some("-4.5,48.0,-4.0,48.5"));
+        args.deny = vec!["W014".to_string(), "bbox-auto-detected".to_string()];
+        let config = args.validate().unwrap();
+        assert_eq!(
+            config.deny,
+            vec![
+                WarningCode::FeatureSkippedInvalidWkb,
+                WarningCode::BboxAutoDetected
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_deny_unknown_code() {
+        let mut args = create_test_args(Some(0.001), None, Some("-4.5,48.0,-4.0,48.5"));
+        args.deny = vec!["W999".to_string()];
+        let err = args.validate().unwrap_err();
+        assert!(err.to_string().contains("Unknown warning code"));
+    }
+
+    #[test]
+    fn test_parse_compare_images_subcommand() {
+        let cli = Cli::parse_from([
+            "gpkg-to-png",
+            "compare-images",
+            "a.png",
+            "b.png",
+            "--tolerance",
+            "4",
+        ]);
+        match cli.command {
+            Command::CompareImages(cmp) => {
+                assert_eq!(cmp.expected, PathBuf::from("a.png"));
+                assert_eq!(cmp.actual, PathBuf::from("b.png"));
+                assert_eq!(cmp.tolerance, 4);
+                assert_eq!(cmp.mode, CompareMode::Coverage);
+                assert!(cmp.diff.is_none());
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        let load = |argv: &[&str]| Cli::load(argv.iter().map(OsString::from).collect()).unwrap();
+        match load(&["gpkg-to-png", "compare", "a.png", "b.png", "--mode", "color"]) {
+            Command::CompareImages(cmp) => assert_eq!(cmp.mode, CompareMode::Color),
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validate_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let golden = dir.path().to_str().unwrap();
+        let parse = |extra: &[&str]| {
+            let argv = ["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001"].iter().chain(extra);
+            Args::try_parse_from(argv)
+        };
+        assert!(parse(&[]).unwrap().validate().unwrap().verify.is_none());
+        let config = parse(&["--verify-against", golden, "--verify-threshold", "0.5"]).unwrap().validate().unwrap();
+        assert_eq!(
+            config.verify,
+            Some(Verify {
+                dir: dir.path().to_path_buf(),
+                tolerance: 0,
+                threshold: 0.5,
+            })
+        );
+        assert!(parse(&["--verify-tolerance", "2"]).is_err());
+        let args = parse(&["--verify-against", golden, "--verify-threshold", "101"]).unwrap();
+        assert!(matches!(args.validate(), Err(GpkgError::InvalidFormatOption(_))));
+        let args = parse(&["--verify-against", "/nonexistent/approved"]).unwrap();
+        assert!(matches!(args.validate(), Err(GpkgError::FileNotFound(_))));
+    }
+
+    #[test]
+    fn test_validate_preview_scales() {
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--preview-scales", "1,5,25"]);
+        let config = args.validate().unwrap();
+        assert_eq!(config.preview_scales, vec![1.0, 5.0, 25.0]);
+        assert!(config.resolution.is_none() && config.scale.is_none());
+
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--preview-scales", "1,0"]);
+        assert!(matches!(args.validate(), Err(GpkgError::InvalidScale(_))));
+
+        assert!(Args::try_parse_from([
+            "gpkg-to-png",
+            "test.gpkg",
+            "-f",
+            "gpkg",
+            "--preview-scales",
+            "1",
+            "--scale",
+            "5",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_validate_jobs() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert_eq!(config.jobs, 1);
+
+        let mut args = create_test_args(Some(0.001), None, None);
+        args.jobs = 4;
+        assert_eq!(args.validate().unwrap().jobs, 4);
+
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-j", "0"]).is_err());
+
+        let mut args = create_test_args(Some(0.001), None, None);
+        args.format = Some(Format::Geojson);
+        args.jobs = 2;
+        assert!(matches!(args.validate(), Err(GpkgError::InvalidFormatOption(_))));
+    }
+
+    #[test]
+    fn test_validate_raster() {
+        let parse = |extra: &[&str]| {
+            let argv = ["gpkg-to-png", "test.gpkg", "-r", "0.001"].iter().chain(extra);
+            Args::try_parse_from(argv)
+        };
+        let config = parse(&["-f", "gpkg"]).unwrap().validate().unwrap();
+        assert_eq!(config.raster, None);
+        assert!(!config.raster_only);
+
+        let config = parse(&["-f", "gpkg", "--raster", "ortho", "--raster-only"]).unwrap().validate().unwrap();
+        assert_eq!(config.raster.as_deref(), Some("ortho"));
+        assert!(config.raster_only);
+
+        assert!(parse(&["-f", "gpkg", "--raster-only"]).is_err());
+        assert!(parse(&["-f", "gpkg", "--raster", "ortho", "--burn", "height"]).is_err());
+        assert!(parse(&["-f", "gpkg", "--raster", "ortho", "--raster-only", "--layer", "parcels"]).is_err());
+        let geojson = parse(&["-f", "geojson", "--raster", "ortho"]).unwrap();
+        assert!(matches!(geojson.validate(), Err(GpkgError::InvalidFormatOption(_))));
+    }
+
+    #[test]
+    fn test_validate_export_attributes() {
+        assert_eq!(create_test_args(Some(0.001), None, None).validate().unwrap().export_attributes, None);
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "geojson", "-r", "0.001", "--export-attributes", "json"]);
+        assert_eq!(args.validate().unwrap().export_attributes, Some(AttributeFormat::Json));
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--export-attributes", "xml"]).is_err());
+        let argv = ["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--export-attributes", "csv", "--preview-scales", "1,5"];
+        assert!(Args::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_validate_curve_tolerance() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert_eq!(config.curve_tolerance, DEFAULT_CURVE_TOLERANCE);
+
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001", "--curve-tolerance", "0.5"]);
+        assert_eq!(args.validate().unwrap().curve_tolerance, 0.5);
+
+        for tolerance in [0.0, -1.0, 91.0, f64::NAN] {
+            let mut args = create_test_args(Some(0.001), None, None);
+            args.curve_tolerance = tolerance;
+            assert!(matches!(args.validate(), Err(GpkgError::InvalidFormatOption(_))), "{}", tolerance);
+        }
+    }
+
+    #[test]
+    fn test_validate_proj_settings() {
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001", "--proj-accuracy", "2"]);
+        assert_eq!(args.validate().unwrap().proj_accuracy, Some(2.0));
+
+        for accuracy in [0.0, -1.0, f64::NAN] {
+            let mut args = create_test_args(Some(0.001), None, None);
+            args.proj_accuracy = Some(accuracy);
+            assert!(matches!(args.validate(), Err(GpkgError::InvalidFormatOption(_))), "{}", accuracy);
+        }
+
+        let mut args = create_test_args(Some(0.001), None, None);
+        args.proj_data = Some(PathBuf::from("/nonexistent/proj"));
+        let expected = if cfg!(feature = "reproject") { "FileNotFound" } else { "FeatureDisabled" };
+        assert!(format!("{:?}", args.validate().unwrap_err()).starts_with(expected));
+    }
+
+    #[test]
+    fn test_validate_draw_vertices() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert_eq!(config.vertex_color, None);
+
+        let mut args = create_test_args(Some(0.001), None, None);
+        args.draw_vertices = true;
+        assert_eq!(args.validate().unwrap().vertex_color, Some([0, 0, 255]));
+
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            "test.gpkg",
+            "-f",
+            "gpkg",
+            "-r",
+            "0.001",
+            "--draw-vertices",
+            "--vertex-color",
+            "00FF00",
+        ]);
+        assert_eq!(args.validate().unwrap().vertex_color, Some([0, 255, 0]));
+
+        // The color alone does nothing, so it is rejected
+        assert!(Args::try_parse_from([
+            "gpkg-to-png",
+            "test.gpkg",
+            "-f",
+            "gpkg",
+            "--vertex-color",
+            "00FF00",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_validate_min_feature_px() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert_eq!(config.min_feature_px, None);
+
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            "test.gpkg",
+            "-f",
+            "gpkg",
+            "-r",
+            "0.001",
+            "--min-feature-px",
+            "2",
+        ]);
+        assert_eq!(args.validate().unwrap().min_feature_px, Some(2));
+
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--min-feature-px", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_resample() {
+        let config = create_test_args(Some(0.001), None, None).validate().unwrap();
+        assert_eq!(config.resample, Resample::Box);
+
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001", "--resample", "nearest"]);
+        assert_eq!(args.validate().unwrap().resample, Resample::Nearest);
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--resample", "cubic"]).is_err());
+
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001", "--supersample", "4"]);
+        assert_eq!(args.validate().unwrap().supersample, Some(4));
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--supersample", "1"]).is_err());
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--supersample", "2", "--burn", "v"]).is_err());
+
+        assert_eq!(create_test_args(Some(0.001), None, None).validate().unwrap().overviews, 0);
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001", "--overviews", "3"]);
+        assert_eq!(args.validate().unwrap().overviews, 3);
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--overviews", "2", "--burn", "v"]).is_err());
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.gpkg", "zones.gpkg"));
+        assert!(wildcard_match("z?nes*", "zones.gpkg"));
+        assert!(wildcard_match("*a*b", "xaxxab"));
+        assert!(!wildcard_match("*.gpkg", "zones.geojson"));
+        assert!(!wildcard_match("a?", "a"));
+    }
+
+    #[test]
+    fn test_validate_batch_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b.geojson", "a.geojson", "notes.txt"] {
+            std::fs::write(dir.path().join(name), "{}").unwrap();
+        }
+        let pattern = dir.path().join("*.geojson");
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            pattern.to_str().unwrap(),
+            "extra.geojson",
+            "-f",
+            "geojson",
+            "-r",
+            "0.001",
+        ]);
+        let config = args.validate().unwrap();
+        assert_eq!(
+            config.inputs,
+            [dir.path().join("a.geojson"), dir.path().join("b.geojson"), PathBuf::from("extra.geojson")]
+        );
+        assert_eq!(config.output_layout, OutputLayout::PerInputDir);
+        let second = config.for_input(&config.inputs[1]);
+        assert_eq!(second.output_name.as_deref(), Some("b"));
+
+        let missing = dir.path().join("*.gpkg");
+        let args = Args::parse_from(["gpkg-to-png", missing.to_str().unwrap(), "-f", "gpkg", "-r", "0.001"]);
+        assert!(matches!(args.validate(), Err(GpkgError::FileNotFound(_))));
+    }
+
+    #[test]
+    fn test_validate_into() {
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--into", "base.png"]);
+        let config = args.validate().unwrap();
+        assert_eq!(config.into, Some(PathBuf::from("base.png")));
+        assert!(config.resolution.is_none() && config.scale.is_none());
+
+        let args = Args::parse_from(["gpkg-to-png", "test.gpkg", "-f", "gpkg", "--into", "base.png", "--autocrop"]);
+        assert!(args.validate().unwrap_err().to_string().contains("--into"));
+    }
+
+    #[test]
+    fn test_parse_autocrop() {
+        let parse = |extra: &[&str]| {
+            let mut argv = vec!["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001"];
+            argv.extend(extra);
+            Args::parse_from(argv).validate().unwrap().autocrop
+        };
+        assert_eq!(parse(&[]), None);
+        assert_eq!(parse(&["--autocrop"]), Some(0));
+        assert_eq!(parse(&["--autocrop=16"]), Some(16));
+    }
+
+    #[test]
+    fn test_validate_rename() {
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            "test.gpkg",
+            "-f",
+            "gpkg",
+            "-r",
+            "0.001",
+            "--rename",
+            "Very Long Source Table Name=parcels",
+        ]);
+        let config = args.validate().unwrap();
+        assert_eq!(config.output_name_for("Very Long Source Table Name"), "parcels");
+        assert_eq!(config.output_name_for("roads"), "roads");
+
+        for bad in ["parcels", "a=../parcels", "a=x"] {
+            let mut argv = vec!["gpkg-to-png", "test.gpkg", "-f", "gpkg", "-r", "0.001", "--rename", bad];
+            if bad == "a=x" {
+                argv.extend(["--rename", "b=x"]);
+            }
+            assert!(Args::parse_from(argv).validate().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_validate_merge_source_crs() {
+        let args = Args::parse_from([
+            "gpkg-to-png",
+            "a.geojson",
+            "-f",
+            "geojson",
+            "-r",
+            "0.001",
+            "--merge",
+            "b.geojson",
+            "--merge",
+            "c.geojson",
+            "--source-crs",
+            "b.geojson=EPSG:2154",
+            "--source-crs",
+            "+proj=longlat +datum=WGS84",
+        ]);
+        let config = args.validate().unwrap();
+        let inputs = config.geojson_inputs();
+        let crs: Vec<_> = inputs.iter().map(|i| i.source_crs.as_deref()).collect();
+        assert_eq!(inputs[1].path, PathBuf::from("b.geojson"));
+        assert_eq!(
+            crs,
+            [
+                Some("+proj=longlat +datum=WGS84"),
+                Some("EPSG:2154"),
+                Some("+proj=longlat +datum=WGS84")
+            ]
+        );
+
+        let args = Args::parse_from([
+            "gpkg-to-png", "a.geojson", "-f", "geojson", "-r", "0.001", "--source-crs", "x.geojson=EPSG:2154",
+        ]);
+        assert!(args.validate().unwrap_err().to_string().contains("neither the input"));
+
+        let args = Args::parse_from(["gpkg-to-png", "a.gpkg", "-f", "gpkg", "-r", "0.001", "--merge", "b.gpkg"]);
+        assert_eq!(args.validate().unwrap().merge, [PathBuf::from("b.gpkg")]);
+        let args = Args::parse_from(["gpkg-to-png", "a.gpkg", "-f", "gpkg", "-r", "0.001", "--merge", "b.geojson"]);
+        assert!(args.validate().unwrap_err().to_string().contains("--merge files must be GeoPackages"));
+        let args = Args::parse_from(["gpkg-to-png", "a.gpkg", "-f", "gpkg", "-r", "0.001", "--source-crs", "EPSG:2154"]);
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("--source-crs can only be used with geojson format"));
+    }
+
+    #[test]
+    fn test_parse_stats_subcommand() {
+        let cli = Cli::parse_from(["gpkg-to-png", "stats", "zones.gpkg", "-f", "gpkg", "-l", "parcels"]);
+        match cli.command {
+            Command::Stats(stats) => {
+                assert_eq!(stats.input, PathBuf::from("zones.gpkg"));
+                assert!(matches!(stats.format, Format::Gpkg));
+                assert_eq!(stats.layer.as_deref(), Some("parcels"));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_serve_subcommand() {
+        let cli = Cli::parse_from(["gpkg-to-png", "serve", "zones.gpkg", "--bind", "0.0.0.0:3000", "-l", "a", "-l", "b"]);
+        match cli.command {
+            Command::Serve(serve) => {
+                assert_eq!(serve.bind, "0.0.0.0:3000");
+                assert_eq!(serve.layer, ["a", "b"]);
+                assert_eq!(serve.style.colors().unwrap(), (Some([255, 0, 0, 128]), Some([255, 0, 0])));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_doctor_subcommand() {
+        let cli = Cli::parse_from(["gpkg-to-png", "doctor"]);
+        assert!(matches!(cli.command, Command::Doctor));
+    }
+
+    #[test]
+    fn test_parse_tiles_subcommand() {
+        let cli = Cli::parse_from(["gpkg-to-png", "tiles", "zones.gpkg", "-z", "12", "-o", "out", "--stroke-width", "0"]);
+        match cli.command {
+            Command::Tiles(tiles) => {
+                assert_eq!((tiles.zoom, tiles.output_dir), (Some(12), PathBuf::from("out")));
+                assert_eq!(tiles.simplify, None);
+                assert!(tiles.layer.is_empty());
+                assert_eq!(tiles.style.stroke_width, 0);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["gpkg-to-png", "tiles", "zones.gpkg", "-z", "25"]).is_err());
+
+        // A pyramid needs its highest zoom, given or derived from --scale
+        let parse = |argv: &[&str]| Cli::try_parse_from(["gpkg-to-png", "tiles", "zones.gpkg"].iter().chain(argv));
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["-z", "12", "--max-zoom", "14"]).is_err());
+        assert!(parse(&["--max-zoom", "14", "-s", "2"]).is_err());
+        match parse(&["--min-zoom", "4", "-s", "2.5", "--simplify"]).unwrap().command {
+            Command::Tiles(tiles) => {
+                assert_eq!((tiles.min_zoom, tiles.max_zoom, tiles.scale), (4, None, Some(2.5)));
+                assert_eq!(tiles.simplify, Some(0.5));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_animate_subcommand() {
+        let cli = Cli::parse_from(["gpkg-to-png", "animate", "zones.gpkg", "--by", "year", "-o", "zones.gif", "--delay", "200"]);
+        match cli.command {
+            Command::Animate(animate) => {
+                assert_eq!((animate.by.as_str(), animate.output), ("year", PathBuf::from("zones.gif")));
+                assert_eq!((animate.width, animate.delay, animate.loops), (800, 200, 0));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["gpkg-to-png", "animate", "zones.gpkg"]).is_err());
+        assert!(Cli::try_parse_from(["gpkg-to-png", "animate", "zones.gpkg", "--by", "year", "--width", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_load_defaults_to_render() {
+        let load = |argv: &[&str]| Cli::load(argv.iter().map(OsString::from).collect()).unwrap();
+        for argv in [
+            &["gpkg-to-png", "test.gpkg", "-f", "gpkg"][..],
+            &["gpkg-to-png", "render", "test.gpkg", "-f", "gpkg"],
+            &["gpkg-to-png", "-f", "gpkg", "test.gpkg"],
+        ] {
+            match load(argv) {
+                Command::Render(args) => assert_eq!(args.inputs, [PathBuf::from("test.gpkg")]),
+                other => panic!("unexpected command: {:?}", other),
+            }
+        }
+        assert!(matches!(load(&["gpkg-to-png", "list-layers", "zones.gpkg", "--json"]), Command::ListLayers(list) if list.json));
+        assert!(
+            matches!(load(&["gpkg-to-png", "info", "zones.gpkg", "-f", "gpkg", "-s", "10"]), Command::Info(args) if args.scale == Some(10.0))
+        );
+    }
+
+    #[test]
+    fn test_render_requires_input_and_format() {
+        assert!(Args::try_parse_from(["gpkg-to-png", "test.gpkg"]).is_err());
+        assert!(Args::try_parse_from(["gpkg-to-png", "-f", "gpkg"]).is_err());
+    }
+
+    /// Loads `argv` with a config file holding `toml`.
+    fn load_with_config(toml: &str, argv: &[&str]) -> Result<Args> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("render.toml");
+        std::fs::write(&path, toml).unwrap();
+        let mut full: Vec<OsString> = vec!["gpkg-to-png".into(), "--config".into(), path.into()];
+        full.extend(argv.iter().map(OsString::from));
+        match Cli::load(full)? {
+            Command::Render(args) => Ok(*args),
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_load_config_file() {
+        let toml = r#"
+inputs = ["zones.gpkg"]
+format = "gpkg"
+scale = 2.5
+bbox = "-4.5,48.0,-4.0,48.5"
+fill = "0000FFFF"
+stroke-width = 3
+geotag = true
+deny = ["W014", "W016"]
+
+[layers.parcels]
+fill = "00FF0080"
+stroke-width = 0
+"#;
+        let args = load_with_config(toml, &["--fill", "FF0000FF", "--deny", "W001"]).unwrap();
+        assert_eq!(args.inputs, [PathBuf::from("zones.gpkg")]);
+        assert_eq!(args.bbox.as_deref(), Some("-4.5,48.0,-4.0,48.5"));
+        assert_eq!(args.fill, "FF0000FF");
+        assert_eq!(args.deny, ["W001"]);
+
+        let config = args.validate().unwrap();
+        assert_eq!(config.scale, Some(2.5));
+        assert!(config.geotag);
+        assert_eq!((config.fill, config.stroke_width), (Some([255, 0, 0, 255]), 3));
+        let parcels = config.for_layer("parcels", 0);
+        assert_eq!((parcels.fill, parcels.stroke, parcels.stroke_width), (Some([0, 255, 0, 128]), Some([255, 0, 0]), 0));
+        assert_eq!(config.for_layer("roads", 1).fill, Some([255, 0, 0, 255]));
+
+        // Command-line inputs replace those of the file, and options excluding file ones drop them
+        let args = load_with_config(toml, &["other.gpkg", "--resolution", "0.001"]).unwrap();
+        assert_eq!(args.inputs, [PathBuf::from("other.gpkg")]);
+        assert_eq!((args.resolution, args.scale), (Some(0.001), None));
+        let args = load_with_config(toml, &["--preview-scales", "1,5"]).unwrap();
+        assert_eq!(args.scale, None);
+
+        // Counted flags take a count or a boolean
+        let args = load_with_config("verbose = 2\ncolor = \"never\"", &["zones.gpkg"]).unwrap();
+        assert_eq!((args.verbose, args.color), (2, ColorMode::Never));
+        assert_eq!(load_with_config("verbose = true", &["zones.gpkg"]).unwrap().verbose, 1);
+    }
+
+    #[test]
+    fn test_load_config_file_errors() {
+        let err = |toml: &str| load_with_config(toml, &["zones.gpkg"]).unwrap_err().to_string();
+        assert!(err("colour = \"FF0000\"").ends_with("unknown option `colour`"));
+        assert!(err("geotag = \"yes\"").ends_with("`geotag` must be true or false, got a string"));
+        assert!(err("scale = [1, 2]").ends_with("`scale` takes a single value"));
+        assert!(err("[layers.parcels]\nfill = \"red\"").contains("`layers.parcels.fill`"));
+        assert!(err("[layers.parcels]\nopacity = 1").contains("unknown style `opacity`"));
+        assert!(err("scale = 1\nscale = 2").ends_with("line 2: duplicate key `scale`"));
+
+        let args = load_with_config("[layers.parcels]\nfill = \"00FF0080\"", &["a.geojson", "-f", "geojson", "-s", "1"]).unwrap();
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_read_style_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("style.toml");
+        let read = |toml: &str| {
+            std::fs::write(&path, toml).unwrap();
+            read_style_file(&path)
+        };
+        let style = read("fill = \"none\"\nstroke = \"0000FF\"\nstroke-width = 2").unwrap();
+        assert_eq!(
+            style,
+            LayerStyle {
+                fill: Some(None),
+                stroke: Some(Some([0, 0, 255])),
+                stroke_width: Some(2),
+            }
+        );
+        assert_eq!(read("").unwrap(), LayerStyle::default());
+
+        let err = |toml: &str| read(toml).unwrap_err().to_string();
+        assert!(err("fill = \"red\"").contains("`fill`: "));
+        assert!(err("stroke-width = -1").ends_with("`stroke-width` must be a non-negative integer"));
+        assert!(err("scale = 2").ends_with("unknown style `scale` (expected fill, stroke or stroke-width)"));
+        assert!(read_style_file(&dir.path().join("missing.toml")).is_err());
+    }
 }
@@ -0,0 +1,359 @@
+//! Minimal TOML reader for `--config` files.
+//!
+//! Supports the subset render jobs need: comments, `[table]` headers with
+//! dotted and quoted keys, and key/value pairs holding strings, integers,
+//! floats, booleans and arrays (which may span lines). Inline tables, arrays
+//! of tables, dates and multi-line strings are rejected.
+
+use std::fmt::Display;
+
+/// A parsed value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+/// Keys and values of a table, in file order.
+pub type Table = Vec<(String, Value)>;
+
+impl Value {
+    /// Name of the value type, for error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Integer(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Boolean(_) => "boolean",
+            Value::Array(_) => "array",
+            Value::Table(_) => "table",
+        }
+    }
+}
+
+/// Parses a document into its root table; errors mention the line.
+pub fn parse(text: &str) -> Result<Table, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let mut root = Table::new();
+    let mut current: Vec<String> = Vec::new();
+    loop {
+        parser.skip_blank();
+        match parser.peek() {
+            None => return Ok(root),
+            Some('[') => {
+                parser.bump();
+                if parser.peek() == Some('[') {
+                    return Err(parser.error("arrays of tables are not supported"));
+                }
+                let path = parser.dotted_key()?;
+                if parser.bump() != Some(']') {
+                    return Err(parser.error("expected `]` after table name"));
+                }
+                table_at(&mut root, &path).map_err(|e| parser.error(e))?;
+                current = path;
+                parser.end_of_line()?;
+            }
+            Some(_) => {
+                let line = parser.line;
+                let keys = parser.dotted_key()?;
+                if parser.bump() != Some('=') {
+                    return Err(parser.error("expected `=` after key"));
+                }
+                parser.skip_spaces();
+                let value = parser.value()?;
+                parser.end_of_line()?;
+
+                let (name, parents) = keys.split_last().expect("keys are not empty");
+                let path = [current.as_slice(), parents].concat();
+                let table = table_at(&mut root, &path).map_err(|e| format!("line {}: {}", line, e))?;
+                if table.iter().any(|(key, _)| key == name) {
+                    return Err(format!("line {}: duplicate key `{}`", line, name));
+                }
+                table.push((name.clone(), value));
+            }
+        }
+    }
+}
+
+/// Returns the table at `path`, creating missing tables.
+fn table_at<'t>(table: &'t mut Table, path: &[String]) -> Result<&'t mut Table, String> {
+    let Some((first, rest)) = path.split_first() else {
+        return Ok(table);
+    };
+    let index = match table.iter().position(|(key, _)| key == first) {
+        Some(index) => index,
+        None => {
+            table.push((first.clone(), Value::Table(Table::new())));
+            table.len() - 1
+        }
+    };
+    match &mut table[index].1 {
+        Value::Table(child) => table_at(child, rest),
+        other => Err(format!("`{}` is a {}, not a table", first, other.type_name())),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, msg: impl Display) -> String {
+        format!("line {}: {}", self.line, msg)
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        while !matches!(self.peek(), None | Some('\n')) {
+            self.bump();
+        }
+    }
+
+    /// Skips whitespace, line breaks and comments.
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r' | '\n') => {
+                    self.bump();
+                }
+                Some('#') => self.skip_comment(),
+                _ => return,
+            }
+        }
+    }
+
+    /// Consumes the rest of a line, which may only hold a comment.
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_spaces();
+        if self.peek() == Some('#') {
+            self.skip_comment();
+        }
+        if self.peek() == Some('\r') {
+            self.bump();
+        }
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("unexpected `{}` at end of line", c))),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                    self.bump();
+                }
+                if start == self.pos {
+                    return Err(self.error("expected a key"));
+                }
+                Ok(self.chars[start..self.pos].iter().collect())
+            }
+        }
+    }
+
+    fn dotted_key(&mut self) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        loop {
+            self.skip_spaces();
+            keys.push(self.key()?);
+            self.skip_spaces();
+            if self.peek() != Some('.') {
+                return Ok(keys);
+            }
+            self.bump();
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => Err(self.error("inline tables are not supported")),
+            _ => self.scalar(),
+        }
+    }
+
+    fn scalar(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while !matches!(self.peek(), None | Some(',' | ']' | ' ' | '\t' | '\r' | '\n' | '#')) {
+            self.bump();
+        }
+        let token: String = self.chars[start..self.pos].iter().collect();
+        let number = token.replace('_', "");
+        match token.as_str() {
+            "" => Err(self.error("expected a value")),
+            "true" => Ok(Value::Boolean(true)),
+            "false" => Ok(Value::Boolean(false)),
+            _ => number
+                .parse()
+                .map(Value::Integer)
+                .or_else(|_| number.parse().map(Value::Float))
+                .map_err(|_| self.error(format!("invalid value `{}` (strings need quotes)", token))),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.bump();
+        if self.chars[self.pos..].starts_with(&['"', '"']) {
+            return Err(self.error("multi-line strings are not supported"));
+        }
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(s),
+                Some('\\') => s.push(self.escape()?),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        let digits = match self.bump() {
+            Some('b') => return Ok('\u{8}'),
+            Some('t') => return Ok('\t'),
+            Some('n') => return Ok('\n'),
+            Some('f') => return Ok('\u{c}'),
+            Some('r') => return Ok('\r'),
+            Some('"') => return Ok('"'),
+            Some('\\') => return Ok('\\'),
+            Some('u') => 4,
+            Some('U') => 8,
+            _ => return Err(self.error("invalid escape sequence")),
+        };
+        let code: String = (0..digits).filter_map(|_| self.bump()).collect();
+        u32::from_str_radix(&code, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(format!("invalid unicode escape `{}`", code)))
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.bump();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('\'') => return Ok(s),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.bump();
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return Err(self.error("expected `,` or `]` in array")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_document() {
+        let table = parse(
+            r#"
+# Render job
+inputs = ["a.gpkg", 'b.gpkg'] # trailing comment
+scale = 2.5
+stroke-width = 1_000
+geotag = true
+deny = [
+    "W014",
+    "W016",
+]
+
+[layers."Long Name"]
+fill = "00FF0080\t"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            table[0],
+            (
+                "inputs".to_string(),
+                Value::Array(vec![Value::String("a.gpkg".into()), Value::String("b.gpkg".into())])
+            )
+        );
+        assert_eq!(table[1].1, Value::Float(2.5));
+        assert_eq!(table[2].1, Value::Integer(1000));
+        assert_eq!(table[3].1, Value::Boolean(true));
+        assert!(matches!(&table[4].1, Value::Array(items) if items.len() == 2));
+        let Value::Table(layers) = &table[5].1 else {
+            panic!("layers is not a table");
+        };
+        assert_eq!(
+            layers[0],
+            (
+                "Long Name".to_string(),
+                Value::Table(vec![("fill".to_string(), Value::String("00FF0080\t".into()))])
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("scale = 1\nscale = 2").unwrap_err(), "line 2: duplicate key `scale`");
+        assert_eq!(
+            parse("fill = FF0000").unwrap_err(),
+            "line 1: invalid value `FF0000` (strings need quotes)"
+        );
+        assert!(parse("fill = \"FF0000").is_err());
+        assert!(parse("style = { fill = \"FF0000\" }").is_err());
+        assert!(parse("[[layers]]").is_err());
+        assert!(parse("scale = 1 2").is_err());
+        assert!(parse("scale = 1\n[scale]").is_err());
+    }
+}
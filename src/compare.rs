@@ -0,0 +1,267 @@
+//! Image comparison for regression checks.
+//!
+//! Renders are transparent overlays, so what matters most when validating a
+//! style or algorithm change is which pixels are covered. This module diffs
+//! the alpha channel of two images and produces a visual diff where lost
+//! coverage is red and gained coverage is green. Checking that a new version
+//! draws exactly the same output compares every channel instead.
+
+use image::{Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+
+use crate::error::{GpkgError, Result};
+
+/// Result of an alpha-channel comparison.
+#[derive(Debug)]
+pub struct AlphaDiff {
+    /// Total number of pixels compared.
+    pub total_pixels: u64,
+    /// Number of pixels whose alpha differs by more than the tolerance.
+    pub changed_pixels: u64,
+    /// Largest absolute alpha difference found.
+    pub max_delta: u8,
+    /// Mean absolute alpha difference over all pixels.
+    pub mean_delta: f64,
+    /// Visual diff: red where the second image lost coverage, green where it gained.
+    pub diff_image: RgbaImage,
+}
+
+impl AlphaDiff {
+    /// Returns the share of changed pixels, in percent.
+    pub fn changed_percent(&self) -> f64 {
+        if self.total_pixels == 0 {
+            0.0
+        } else {
+            self.changed_pixels as f64 * 100.0 / self.total_pixels as f64
+        }
+    }
+}
+
+/// Result of a comparison of all four channels.
+#[derive(Debug)]
+pub struct RgbaDiff {
+    /// Total number of pixels compared.
+    pub total_pixels: u64,
+    /// Number of pixels with a channel differing by more than the tolerance.
+    pub changed_pixels: u64,
+    /// Largest absolute difference of each channel (R, G, B, A).
+    pub max_delta: [u8; 4],
+    /// Mean absolute difference of each channel over all pixels.
+    pub mean_delta: [f64; 4],
+    /// Visual diff: magenta where pixels changed, brighter for larger changes.
+    pub diff_image: RgbaImage,
+}
+
+impl RgbaDiff {
+    /// Returns the share of changed pixels, in percent.
+    pub fn changed_percent(&self) -> f64 {
+        if self.total_pixels == 0 {
+            0.0
+        } else {
+            self.changed_pixels as f64 * 100.0 / self.total_pixels as f64
+        }
+    }
+}
+
+/// Compares the alpha channels of two images of identical dimensions.
+///
+/// Pixels whose alpha differs by at most `tolerance` are counted as unchanged.
+pub fn compare_alpha(a: &RgbaImage, b: &RgbaImage, tolerance: u8) -> Result<AlphaDiff> {
+    if a.dimensions() != b.dimensions() {
+        return Err(GpkgError::ImageSizeMismatch {
+            left: a.dimensions(),
+            right: b.dimensions(),
+        });
+    }
+
+    let (width, height) = a.dimensions();
+    let mut diff_image = RgbaImage::new(width, height);
+    let mut changed_pixels = 0u64;
+    let mut max_delta = 0u8;
+    let mut delta_sum = 0u64;
+
+    for ((pa, pb), out) in a.pixels().zip(b.pixels()).zip(diff_image.pixels_mut()) {
+        let (alpha_a, alpha_b) = (pa.0[3], pb.0[3]);
+        let delta = alpha_a.abs_diff(alpha_b);
+        delta_sum += delta as u64;
+        max_delta = max_delta.max(delta);
+
+        *out = if delta > tolerance {
+            changed_pixels += 1;
+            // Scale intensity with the delta but keep small changes visible
+            let intensity = 128 + delta / 2;
+            if alpha_a > alpha_b {
+                Rgba([intensity, 0, 0, 255])
+            } else {
+                Rgba([0, intensity, 0, 255])
+            }
+        } else {
+            // Unchanged coverage rendered as faint gray for context
+            Rgba([128, 128, 128, alpha_a / 4])
+        };
+    }
+
+    let total_pixels = width as u64 * height as u64;
+    let mean_delta = if total_pixels == 0 {
+        0.0
+    } else {
+        delta_sum as f64 / total_pixels as f64
+    };
+
+    Ok(AlphaDiff {
+        total_pixels,
+        changed_pixels,
+        max_delta,
+        mean_delta,
+        diff_image,
+    })
+}
+
+/// Compares every channel of two images of identical dimensions.
+///
+/// Pixels whose channels all differ by at most `tolerance` are counted as
+/// unchanged.
+pub fn compare_rgba(a: &RgbaImage, b: &RgbaImage, tolerance: u8) -> Result<RgbaDiff> {
+    if a.dimensions() != b.dimensions() {
+        return Err(GpkgError::ImageSizeMismatch {
+            left: a.dimensions(),
+            right: b.dimensions(),
+        });
+    }
+
+    let (width, height) = a.dimensions();
+    let mut diff_image = RgbaImage::new(width, height);
+    let mut changed_pixels = 0u64;
+    let mut max_delta = [0u8; 4];
+    let mut delta_sum = [0u64; 4];
+
+    for ((pa, pb), out) in a.pixels().zip(b.pixels()).zip(diff_image.pixels_mut()) {
+        let mut delta = 0;
+        for c in 0..4 {
+            let d = pa.0[c].abs_diff(pb.0[c]);
+            delta_sum[c] += d as u64;
+            max_delta[c] = max_delta[c].max(d);
+            delta = delta.max(d);
+        }
+        *out = if delta > tolerance {
+            changed_pixels += 1;
+            let intensity = 128 + delta / 2;
+            Rgba([intensity, 0, intensity, 255])
+        } else {
+            Rgba([128, 128, 128, pa.0[3] / 4])
+        };
+    }
+
+    let total_pixels = width as u64 * height as u64;
+    let mean_delta = delta_sum.map(|sum| if total_pixels == 0 { 0.0 } else { sum as f64 / total_pixels as f64 });
+
+    Ok(RgbaDiff {
+        total_pixels,
+        changed_pixels,
+        max_delta,
+        mean_delta,
+        diff_image,
+    })
+}
+
+/// Returns where the approved version of `output` lies under `golden_dir`:
+/// at its path relative to `output_dir`, or by file name when outside it.
+pub fn golden_path(output: &Path, output_dir: &Path, golden_dir: &Path) -> PathBuf {
+    match output.strip_prefix(output_dir) {
+        Ok(relative) => golden_dir.join(relative),
+        Err(_) => golden_dir.join(output.file_name().unwrap_or_default()),
+    }
+}
+
+/// Returns where the diff of an output diverging from its approved version is
+/// written: `<name>.diff.png`.
+pub fn diff_path(output: &Path) -> PathBuf {
+    output.with_extension("diff.png")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image_with_alpha(width: u32, height: u32, alpha: u8) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, alpha]))
+    }
+
+    #[test]
+    fn test_identical_images() {
+        let a = image_with_alpha(4, 4, 128);
+        let diff = compare_alpha(&a, &a.clone(), 0).unwrap();
+        assert_eq!(diff.changed_pixels, 0);
+        assert_eq!(diff.max_delta, 0);
+        assert_eq!(diff.total_pixels, 16);
+    }
+
+    #[test]
+    fn test_color_changes_are_ignored() {
+        let a = image_with_alpha(2, 2, 200);
+        let b = RgbaImage::from_pixel(2, 2, Rgba([0, 0, 255, 200]));
+        let diff = compare_alpha(&a, &b, 0).unwrap();
+        assert_eq!(diff.changed_pixels, 0);
+    }
+
+    #[test]
+    fn test_lost_and_gained_coverage() {
+        let a = image_with_alpha(2, 1, 255);
+        let mut b = a.clone();
+        b.put_pixel(0, 0, Rgba([255, 0, 0, 0]));
+        let diff = compare_alpha(&a, &b, 0).unwrap();
+        assert_eq!(diff.changed_pixels, 1);
+        assert_eq!(diff.max_delta, 255);
+        assert!((diff.changed_percent() - 50.0).abs() < 1e-10);
+        // Lost coverage is red
+        assert!(diff.diff_image.get_pixel(0, 0).0[0] > 0);
+        assert_eq!(diff.diff_image.get_pixel(0, 0).0[1], 0);
+
+        let diff = compare_alpha(&b, &a, 0).unwrap();
+        // Gained coverage is green
+        assert!(diff.diff_image.get_pixel(0, 0).0[1] > 0);
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let a = image_with_alpha(2, 2, 100);
+        let b = image_with_alpha(2, 2, 103);
+        assert_eq!(compare_alpha(&a, &b, 3).unwrap().changed_pixels, 0);
+        assert_eq!(compare_alpha(&a, &b, 2).unwrap().changed_pixels, 4);
+    }
+
+    #[test]
+    fn test_compare_rgba() {
+        let a = image_with_alpha(2, 2, 200);
+        let mut b = a.clone();
+        b.put_pixel(1, 0, Rgba([250, 0, 10, 200]));
+        let diff = compare_rgba(&a, &b, 0).unwrap();
+        assert_eq!(diff.changed_pixels, 1);
+        assert_eq!(diff.max_delta, [5, 0, 10, 0]);
+        assert!((diff.mean_delta[2] - 2.5).abs() < 1e-10);
+        assert_eq!(diff.diff_image.get_pixel(1, 0).0, [133, 0, 133, 255]);
+        assert_eq!(diff.diff_image.get_pixel(0, 0).0[3], 50);
+        assert_eq!(compare_rgba(&a, &b, 10).unwrap().changed_pixels, 0);
+        // Color changes that the coverage comparison ignores
+        assert_eq!(compare_alpha(&a, &b, 0).unwrap().changed_pixels, 0);
+        assert!(compare_rgba(&a, &image_with_alpha(3, 2, 0), 0).is_err());
+    }
+
+    #[test]
+    fn test_golden_path() {
+        let golden = Path::new("approved");
+        let path = golden_path(Path::new("out/city/roads.png"), Path::new("out"), golden);
+        assert_eq!(path, PathBuf::from("approved/city/roads.png"));
+        let path = golden_path(Path::new("elsewhere/roads.png"), Path::new("out"), golden);
+        assert_eq!(path, PathBuf::from("approved/roads.png"));
+        assert_eq!(diff_path(Path::new("out/roads.png")), PathBuf::from("out/roads.diff.png"));
+    }
+
+    #[test]
+    fn test_size_mismatch() {
+        let a = image_with_alpha(2, 2, 0);
+        let b = image_with_alpha(3, 2, 0);
+        let result = compare_alpha(&a, &b, 0);
+        assert!(matches!(result, Err(GpkgError::ImageSizeMismatch { .. })));
+    }
+}
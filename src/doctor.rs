@@ -0,0 +1,178 @@
+//! Self-diagnosis of the runtime environment (`doctor` subcommand).
+//!
+//! Self-contained builds are shipped to machines without system SQLite or
+//! PROJ installs. These checks confirm that what was bundled actually works:
+//! the SQLite engine, the PROJ data files and an EPSG reprojection.
+
+use crate::capabilities;
+use crate::reproject::{Backend, Transformer};
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    #[cfg_attr(all(feature = "gpkg", not(feature = "reproject")), allow(dead_code))]
+    Warn,
+    Fail,
+}
+
+impl Status {
+    /// Short label shown in front of each check.
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        }
+    }
+}
+
+/// Result of a diagnostic check.
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Lambert-93 origin (3°E, 46.5°N), used to exercise an EPSG lookup.
+const PROBE_SRS: &str = "EPSG:2154";
+const PROBE_POINT: (f64, f64) = (700000.0, 6600000.0);
+const PROBE_EXPECTED: (f64, f64) = (3.0, 46.5);
+
+/// Runs every check.
+pub async fn run() -> Vec<Check> {
+    vec![features(), sqlite().await, proj_data(), reprojection()]
+}
+
+/// Lists the cargo features compiled into this binary.
+fn features() -> Check {
+    let enabled = capabilities::enabled_features();
+    let detail = if enabled.is_empty() {
+        "none".to_string()
+    } else {
+        enabled.join(", ")
+    };
+    Check::new("features", Status::Ok, detail)
+}
+
+/// Opens an in-memory database with the linked SQLite.
+#[cfg(feature = "gpkg")]
+async fn sqlite() -> Check {
+    let version = async {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await?;
+        let version: String = sqlx::query_scalar("SELECT sqlite_version()")
+            .fetch_one(&pool)
+            .await?;
+        pool.close().await;
+        Ok::<_, sqlx::Error>(version)
+    };
+    match version.await {
+        Ok(version) => Check::new("sqlite", Status::Ok, format!("SQLite {} (bundled)", version)),
+        Err(e) => Check::new("sqlite", Status::Fail, e.to_string()),
+    }
+}
+
+#[cfg(not(feature = "gpkg"))]
+async fn sqlite() -> Check {
+    Check::new("sqlite", Status::Warn, "GeoPackage support not compiled in")
+}
+
+/// Reports where libproj resource files are loaded from.
+#[cfg(feature = "reproject")]
+fn proj_data() -> Check {
+    use crate::proj_data::{self, ProjDataSource, PROJ_DB};
+
+    let source = proj_data::init();
+    let Some(dir) = source.dir() else {
+        return Check::new(
+            "proj-data",
+            Status::Warn,
+            "no PROJ_DATA and no bundled data; relying on libproj's default search path",
+        );
+    };
+    let origin = match source {
+        ProjDataSource::Environment(_) => "from environment",
+        ProjDataSource::Embedded(_) => "embedded",
+        ProjDataSource::Bundled(_) => "next to executable",
+        ProjDataSource::Missing => unreachable!(),
+    };
+    if dir.join(PROJ_DB).is_file() {
+        Check::new("proj-data", Status::Ok, format!("{} ({})", dir.display(), origin))
+    } else {
+        Check::new(
+            "proj-data",
+            Status::Fail,
+            format!("{} has no {} ({})", dir.display(), PROJ_DB, origin),
+        )
+    }
+}
+
+#[cfg(not(feature = "reproject"))]
+fn proj_data() -> Check {
+    Check::new("proj-data", Status::Ok, "not needed (libproj not compiled in)")
+}
+
+/// Reprojects a known point from an EPSG code to WGS84.
+fn reprojection() -> Check {
+    let transformer = match Transformer::new(PROBE_SRS, "EPSG:4326") {
+        Ok(t) => t,
+        Err(e) => return Check::new("reprojection", Status::Fail, e.to_string()),
+    };
+    let backend = match transformer.backend() {
+        Backend::Identity => "identity",
+        Backend::Proj => "libproj",
+        Backend::Pure => "proj4rs",
+    };
+    match transformer.convert(PROBE_POINT) {
+        Some((lon, lat))
+            if (lon - PROBE_EXPECTED.0).abs() < 1e-6 && (lat - PROBE_EXPECTED.1).abs() < 1e-6 =>
+        {
+            Check::new("reprojection", Status::Ok, format!("{} -> EPSG:4326 via {}", PROBE_SRS, backend))
+        }
+        Some((lon, lat)) => Check::new(
+            "reprojection",
+            Status::Fail,
+            format!("{} origin reprojected to ({}, {}) via {}", PROBE_SRS, lon, lat, backend),
+        ),
+        None => Check::new("reprojection", Status::Fail, format!("conversion failed via {}", backend)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_features_check_lists_defaults() {
+        let check = features();
+        assert_eq!(check.status, Status::Ok);
+        #[cfg(feature = "gpkg")]
+        assert!(check.detail.contains("gpkg"));
+    }
+
+    #[cfg(any(feature = "reproject", feature = "reproject-pure"))]
+    #[test]
+    fn test_reprojection_check_passes() {
+        let check = reprojection();
+        assert_eq!(check.status, Status::Ok, "{}", check.detail);
+    }
+
+    #[cfg(feature = "gpkg")]
+    #[tokio::test]
+    async fn test_sqlite_check_passes() {
+        let check = sqlite().await;
+        assert_eq!(check.status, Status::Ok, "{}", check.detail);
+        assert!(check.detail.starts_with("SQLite 3."));
+    }
+}
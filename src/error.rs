@@ -5,16 +5,27 @@ pub enum GpkgError {
     #[error("File not found: {0}")]
     FileNotFound(String),
 
-    #[allow(dead_code)]
     #[error("No polygon layers found in the GeoPackage")]
     NoPolygonLayers,
 
     #[error("Layer '{0}' not found. Available layers: {1}")]
     LayerNotFound(String, String),
 
+    #[error("Column '{0}' not found in layer '{1}'")]
+    ColumnNotFound(String, String),
+
     #[error("Invalid bounding box format: {0}")]
     InvalidBbox(String),
 
+    #[error("Invalid padding: {0}")]
+    InvalidPadding(String),
+
+    #[error("Invalid aspect ratio: {0}")]
+    InvalidAspect(String),
+
+    #[error("Invalid paper size: {0}")]
+    InvalidPaper(String),
+
     #[error("Invalid color format: {0}")]
     InvalidColor(String),
 
@@ -24,7 +35,7 @@ pub enum GpkgError {
     #[error("Scale must be positive, got: {0}")]
     InvalidScale(f64),
 
-    #[error("Either --resolution or --scale must be provided")]
+    #[error("Either --resolution, --scale, --width, --height or --paper must be provided")]
     MissingResolutionOrScale,
 
     #[error("Options --{0} and --{1} are mutually exclusive")]
@@ -33,15 +44,64 @@ pub enum GpkgError {
     #[error("Image dimensions too large: {width}x{height} pixels (max: {max})")]
     ImageTooLarge { width: u32, height: u32, max: u32 },
 
+    #[error("Canvas {0}: {1}")]
+    Canvas(String, String),
+
+    #[error("{0}: output {1} is already written by this run (see --on-collision)")]
+    OutputCollision(String, String),
+
+    #[error("Image dimensions differ: {}x{} vs {}x{}", left.0, left.1, right.0, right.1)]
+    ImageSizeMismatch { left: (u32, u32), right: (u32, u32) },
+
+    #[error("Images differ: {changed} of {total} pixels changed")]
+    ImagesDiffer { changed: u64, total: u64 },
+
+    #[error("{diverged} output(s) diverge from the approved images, {missing} have none")]
+    VerificationFailed { diverged: usize, missing: usize },
+
+    #[error("Interrupted")]
+    Interrupted,
+
+    #[error("{0} doctor check(s) failed")]
+    DoctorFailed(usize),
+
     #[error("Failed to parse GeoJSON: {0}")]
     GeojsonParseError(String),
 
     #[error("No polygon geometries found in GeoJSON file")]
     EmptyGeojson,
 
+    #[error("Inputs cannot be merged: {0}")]
+    IncompatibleInputs(String),
+
+    #[error("Config file {0}: {1}")]
+    ConfigFile(String, String),
+
+    #[error("Layer '{0}' requires the GeoPackage extension {1}, which is not supported")]
+    UnsupportedExtension(String, String),
+
     #[error("Invalid format option: {0}")]
     InvalidFormatOption(String),
 
+    #[error("Invalid --where clause: {0}")]
+    InvalidWhereClause(String),
+
+    #[error("Invalid --name-template: {0}")]
+    InvalidNameTemplate(String),
+
+    #[error("Unknown warning code: {0}")]
+    InvalidWarningCode(String),
+
+    #[error("Warning {0} denied by --deny: {1}")]
+    DeniedWarning(String, String),
+
+    #[error("Reprojection error: {0}")]
+    Reprojection(String),
+
+    #[error("{0} support is not available: built without the `{1}` feature")]
+    FeatureDisabled(String, String),
+
+    #[cfg(feature = "gpkg")]
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
 
@@ -50,10 +110,86 @@ pub enum GpkgError {
 
     #[error("Image error: {0}")]
     Image(#[from] image::ImageError),
+
+    #[error("PNG encoding error: {0}")]
+    PngEncoding(#[from] png::EncodingError),
+
+    #[error("PNG decoding error: {0}")]
+    PngDecoding(#[from] png::DecodingError),
+
+    #[error("TIFF encoding error: {0}")]
+    TiffEncoding(#[from] tiff::TiffError),
 }
 
 pub type Result<T> = std::result::Result<T, GpkgError>;
 
+/// Exit statuses of the command-line tool, one per failure class.
+pub mod exit_code {
+    /// Any other failure (I/O, image encoding, reprojection).
+    pub const FAILURE: i32 = 1;
+    /// Invalid arguments or config file (clap also exits with 2).
+    pub const USAGE: i32 = 2;
+    /// An input file, layer or column does not exist.
+    pub const NOT_FOUND: i32 = 3;
+    /// An input exists but cannot be rendered as is.
+    pub const INVALID_INPUT: i32 = 4;
+    /// The GeoPackage could not be queried.
+    pub const DATABASE: i32 = 5;
+    /// The image would exceed the maximum dimensions.
+    pub const TOO_LARGE: i32 = 6;
+    /// Two renders of the run map to the same output file.
+    pub const OUTPUT_COLLISION: i32 = 7;
+    /// A warning was turned into an error by `--deny`.
+    pub const DENIED_WARNING: i32 = 8;
+    /// `compare-images` found differences or `doctor` failed a check.
+    pub const CHECK_FAILED: i32 = 9;
+    /// Stopped by Ctrl-C (128 + SIGINT).
+    pub const INTERRUPTED: i32 = 130;
+}
+
+impl GpkgError {
+    /// Exit status of the command-line tool for this error.
+    pub fn exit_code(&self) -> i32 {
+        use GpkgError::*;
+        match self {
+            InvalidBbox(_)
+            | InvalidPadding(_)
+            | InvalidAspect(_)
+            | InvalidPaper(_)
+            | InvalidColor(_)
+            | InvalidResolution(_)
+            | InvalidScale(_)
+            | MissingResolutionOrScale
+            | MutuallyExclusiveOptions(..)
+            | ConfigFile(..)
+            | InvalidFormatOption(_)
+            | InvalidWhereClause(_)
+            | InvalidNameTemplate(_)
+            | InvalidWarningCode(_)
+            | FeatureDisabled(..) => exit_code::USAGE,
+            FileNotFound(_) | LayerNotFound(..) | ColumnNotFound(..) => exit_code::NOT_FOUND,
+            NoPolygonLayers
+            | GeojsonParseError(_)
+            | EmptyGeojson
+            | IncompatibleInputs(_)
+            | Canvas(..)
+            | UnsupportedExtension(..) => exit_code::INVALID_INPUT,
+            #[cfg(feature = "gpkg")]
+            Database(_) => exit_code::DATABASE,
+            ImageTooLarge { .. } => exit_code::TOO_LARGE,
+            OutputCollision(..) => exit_code::OUTPUT_COLLISION,
+            DeniedWarning(..) => exit_code::DENIED_WARNING,
+            ImageSizeMismatch { .. } | ImagesDiffer { .. } | VerificationFailed { .. } | DoctorFailed(_) => {
+                exit_code::CHECK_FAILED
+            }
+            Interrupted => exit_code::INTERRUPTED,
+            Reprojection(_) | Io(_) | Image(_) | PngEncoding(_) | PngDecoding(_) | TiffEncoding(_) => {
+                exit_code::FAILURE
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -90,4 +226,31 @@ mod tests {
         let err = GpkgError::InvalidFormatOption("--layer cannot be used with geojson format".to_string());
         assert_eq!(err.to_string(), "Invalid format option: --layer cannot be used with geojson format");
     }
+
+    #[test]
+    fn test_denied_warning_display() {
+        let err = GpkgError::DeniedWarning("W014".to_string(), "2 features skipped".to_string());
+        assert_eq!(err.to_string(), "Warning W014 denied by --deny: 2 features skipped");
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(GpkgError::InvalidBbox("1,2".to_string()).exit_code(), exit_code::USAGE);
+        assert_eq!(GpkgError::FileNotFound("a.gpkg".to_string()).exit_code(), exit_code::NOT_FOUND);
+        assert_eq!(GpkgError::EmptyGeojson.exit_code(), exit_code::INVALID_INPUT);
+        let too_large = GpkgError::ImageTooLarge {
+            width: 30_000,
+            height: 10,
+            max: 20_000,
+        };
+        assert_eq!(too_large.exit_code(), exit_code::TOO_LARGE);
+        assert_eq!(GpkgError::Interrupted.exit_code(), 130);
+        assert_eq!(GpkgError::Io(std::io::Error::other("disk full")).exit_code(), exit_code::FAILURE);
+    }
+
+    #[test]
+    fn test_invalid_where_clause_display() {
+        let err = GpkgError::InvalidWhereClause("unbalanced parentheses".to_string());
+        assert_eq!(err.to_string(), "Invalid --where clause: unbalanced parentheses");
+    }
 }
@@ -0,0 +1,414 @@
+//! C ABI for embedding the renderer (feature `ffi`).
+//!
+//! A source is opened once, which reads and reprojects its geometries to
+//! WGS84, then rendered any number of times to an RGBA buffer or a PNG file.
+//! Functions return `0` on success and `-1` on error; the message of the last
+//! error on the calling thread is available from [`gpkg_to_png_last_error`].
+//! Panics are caught at the boundary and reported the same way, so none
+//! unwinds into the caller (with `panic = "abort"`, they abort instead).
+//! The matching declarations are in `include/gpkg_to_png.h`.
+
+use geo::MultiPolygon;
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+use crate::error::{GpkgError, Result};
+use crate::geojson::GeojsonReader;
+#[cfg(feature = "gpkg")]
+use crate::gpkg::{FeatureFilter, GpkgReader, SqlitePragmas};
+use crate::math::Bbox;
+use crate::render::{self, FeatureStyle, PngMetadata, RenderConfig, Renderer};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Geometries loaded from a GeoJSON file or a GeoPackage layer, in WGS84.
+pub struct GpkgToPngSource {
+    geometries: Vec<MultiPolygon<f64>>,
+}
+
+/// Render settings passed by C callers.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct GpkgToPngOptions {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+    /// Resolution in degrees per pixel.
+    pub resolution: f64,
+    /// Fill color in RGBA format.
+    pub fill: [u8; 4],
+    /// Stroke color in RGB format.
+    pub stroke: [u8; 3],
+    /// Stroke width in pixels (0 disables strokes).
+    pub stroke_width: u32,
+}
+
+/// An RGBA image owned by the library, freed with [`gpkg_to_png_image_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct GpkgToPngImage {
+    /// Row-major RGBA pixels, `width * height * 4` bytes.
+    pub data: *mut u8,
+    pub len: usize,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl From<&GpkgToPngOptions> for RenderConfig {
+    fn from(options: &GpkgToPngOptions) -> Self {
+        RenderConfig {
+            bbox: Bbox::new(options.min_lon, options.min_lat, options.max_lon, options.max_lat),
+            resolution: options.resolution,
+            fill: Some(options.fill),
+            stroke: Some(options.stroke),
+            stroke_width: options.stroke_width,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: render::MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        }
+    }
+}
+
+/// Records `message` as the last error of the thread and returns `-1`.
+fn fail(message: String) -> c_int {
+    let msg = CString::new(message).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(msg));
+    -1
+}
+
+/// Runs the body of an exported function, returning `0` on success and
+/// recording errors and panics as the last error (`-1`).
+fn call(body: impl FnOnce() -> Result<()>) -> c_int {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => 0,
+        Ok(Err(e)) => fail(e.to_string()),
+        Err(payload) => fail(format!("internal error: {}", panic_message(payload.as_ref()))),
+    }
+}
+
+/// Returns the message of a caught panic.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    match payload.downcast_ref::<&str>() {
+        Some(msg) => msg,
+        None => payload.downcast_ref::<String>().map_or("panic", String::as_str),
+    }
+}
+
+/// Returns the bbox of `options`. Like on the command line, `min_lon >
+/// max_lon` describes a bbox crossing the antimeridian, stored with `max_lon`
+/// past 180.
+fn options_bbox(options: &GpkgToPngOptions) -> Result<Bbox> {
+    let GpkgToPngOptions {
+        min_lon,
+        min_lat,
+        max_lon,
+        max_lat,
+        ..
+    } = *options;
+    if ![min_lon, min_lat, max_lon, max_lat].iter().all(|v| v.is_finite()) {
+        return Err(GpkgError::InvalidBbox("coordinates must be finite".to_string()));
+    }
+    let lon_range = -180.0..=180.0;
+    if min_lon > max_lon && (!lon_range.contains(&min_lon) || !lon_range.contains(&max_lon)) {
+        return Err(GpkgError::InvalidBbox(format!(
+            "antimeridian-crossing bbox longitudes must be within [-180, 180], got {} and {}",
+            min_lon, max_lon
+        )));
+    }
+    if min_lon == max_lon || min_lat >= max_lat {
+        return Err(GpkgError::InvalidBbox("min must be less than max".to_string()));
+    }
+    let max_lon = if min_lon > max_lon { max_lon + 360.0 } else { max_lon };
+    Ok(Bbox::new(min_lon, min_lat, max_lon, max_lat))
+}
+
+/// Reads a NUL-terminated UTF-8 argument.
+unsafe fn c_str<'a>(s: *const c_char, name: &str) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(GpkgError::InvalidFormatOption(format!("{} is null", name)));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| GpkgError::InvalidFormatOption(format!("{} is not valid UTF-8", name)))
+}
+
+/// Runs an async reader on a private runtime.
+fn block_on<F: std::future::Future>(future: F) -> Result<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    Ok(runtime.block_on(future))
+}
+
+/// Renders every geometry of `source` with `options`.
+fn render(source: &GpkgToPngSource, options: &GpkgToPngOptions) -> Result<Renderer> {
+    let bbox = options_bbox(options)?;
+    let config = RenderConfig {
+        bbox,
+        ..RenderConfig::from(options)
+    };
+    if !config.resolution.is_finite() || config.resolution <= 0.0 {
+        return Err(GpkgError::InvalidResolution(config.resolution));
+    }
+    let mut renderer = Renderer::new(config)?;
+    for batch in source.geometries.chunks(render::BATCH_SIZE) {
+        let features = batch.iter().map(|geom| (geom, FeatureStyle::default()));
+        crate::render_features(&mut renderer, features, &bbox);
+    }
+    Ok(renderer)
+}
+
+/// Stores a new source in `*out` or records the error.
+unsafe fn open_with(
+    out: *mut *mut GpkgToPngSource,
+    open: impl FnOnce() -> Result<Vec<MultiPolygon<f64>>>,
+) -> c_int {
+    call(|| {
+        if out.is_null() {
+            return Err(GpkgError::InvalidFormatOption("out is null".to_string()));
+        }
+        let geometries = open()?;
+        *out = Box::into_raw(Box::new(GpkgToPngSource { geometries }));
+        Ok(())
+    })
+}
+
+/// Opens a GeoJSON file, reprojected to WGS84 from the CRS it declares, and
+/// stores the source in `*out`.
+///
+/// # Safety
+/// `path` must be a NUL-terminated string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn gpkg_to_png_open_geojson(path: *const c_char, out: *mut *mut GpkgToPngSource) -> c_int {
+    open_with(out, || {
+        let path = c_str(path, "path")?;
+        let mut reader = block_on(GeojsonReader::open(Path::new(path)))??;
+        reader.to_wgs84(None, path)?;
+        Ok(reader.get_geometries().to_vec())
+    })
+}
+
+/// Opens a polygon layer of a GeoPackage, reprojected to WGS84, and stores
+/// the source in `*out`.
+///
+/// # Safety
+/// `path` and `layer` must be NUL-terminated strings and `out` a valid pointer.
+#[cfg(feature = "gpkg")]
+#[no_mangle]
+pub unsafe extern "C" fn gpkg_to_png_open_gpkg(
+    path: *const c_char,
+    layer: *const c_char,
+    out: *mut *mut GpkgToPngSource,
+) -> c_int {
+    open_with(out, || {
+        let path = c_str(path, "path")?;
+        let layer = c_str(layer, "layer")?;
+        block_on(async {
+            let reader = GpkgReader::open(Path::new(path), &SqlitePragmas::default()).await?;
+            let layers = reader.list_polygon_layers().await?;
+            let info = layers.iter().find(|l| l.name == layer).ok_or_else(|| {
+                let available = layers.iter().map(|l| l.name.as_str()).collect::<Vec<_>>();
+                GpkgError::LayerNotFound(layer.to_string(), available.join(", "))
+            })?;
+            reader.read_geometries_wgs84(info, &FeatureFilter::default()).await
+        })?
+    })
+}
+
+/// Releases a source. Null is ignored.
+///
+/// # Safety
+/// `source` must come from an open function and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn gpkg_to_png_source_free(source: *mut GpkgToPngSource) {
+    if !source.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(source))));
+    }
+}
+
+/// Renders the bbox of `options` to an RGBA buffer stored in `*out`.
+///
+/// # Safety
+/// `source`, `options` and `out` must be valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn gpkg_to_png_render_rgba(
+    source: *const GpkgToPngSource,
+    options: *const GpkgToPngOptions,
+    out: *mut GpkgToPngImage,
+) -> c_int {
+    call(|| {
+        if source.is_null() || options.is_null() || out.is_null() {
+            return Err(GpkgError::InvalidFormatOption("null argument".to_string()));
+        }
+        let image = render(&*source, &*options)?.into_image();
+        let (width, height) = image.dimensions();
+        let pixels = image.into_raw().into_boxed_slice();
+        let len = pixels.len();
+        *out = GpkgToPngImage {
+            data: Box::into_raw(pixels) as *mut u8,
+            len,
+            width,
+            height,
+        };
+        Ok(())
+    })
+}
+
+/// Releases the pixels of an image and resets it. Empty images are ignored.
+///
+/// # Safety
+/// `image` must be null or filled by [`gpkg_to_png_render_rgba`].
+#[no_mangle]
+pub unsafe extern "C" fn gpkg_to_png_image_free(image: *mut GpkgToPngImage) {
+    if image.is_null() || (*image).data.is_null() {
+        return;
+    }
+    let image = &mut *image;
+    let pixels = ptr::slice_from_raw_parts_mut(image.data, image.len);
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(pixels))));
+    image.data = ptr::null_mut();
+    image.len = 0;
+}
+
+/// Renders the bbox of `options` to a PNG file at `path`.
+///
+/// # Safety
+/// `source` and `options` must be valid pointers and `path` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gpkg_to_png_render_file(
+    source: *const GpkgToPngSource,
+    options: *const GpkgToPngOptions,
+    path: *const c_char,
+) -> c_int {
+    call(|| {
+        if source.is_null() || options.is_null() {
+            return Err(GpkgError::InvalidFormatOption("null argument".to_string()));
+        }
+        let path = c_str(path, "path")?;
+        let renderer = render(&*source, &*options)?;
+        renderer.save(Path::new(path), &PngMetadata::default())
+    })
+}
+
+/// Returns the message of the last error on this thread, or null.
+///
+/// The string stays valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn gpkg_to_png_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |msg| msg.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> GpkgToPngOptions {
+        GpkgToPngOptions {
+            min_lon: 0.0,
+            min_lat: 0.0,
+            max_lon: 10.0,
+            max_lat: 10.0,
+            resolution: 1.0,
+            fill: [255, 0, 0, 255],
+            stroke: [0, 0, 0],
+            stroke_width: 0,
+        }
+    }
+
+    #[test]
+    fn test_render_rgba_from_geojson() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("square.geojson");
+        std::fs::write(
+            &input,
+            r#"{"type": "Polygon", "coordinates": [[[2, 2], [8, 2], [8, 8], [2, 8], [2, 2]]]}"#,
+        )
+        .unwrap();
+        let path = CString::new(input.to_str().unwrap()).unwrap();
+
+        unsafe {
+            let mut source = ptr::null_mut();
+            assert_eq!(gpkg_to_png_open_geojson(path.as_ptr(), &mut source), 0);
+
+            let mut image = GpkgToPngImage {
+                data: ptr::null_mut(),
+                len: 0,
+                width: 0,
+                height: 0,
+            };
+            assert_eq!(gpkg_to_png_render_rgba(source, &options(), &mut image), 0);
+            assert_eq!((image.width, image.height, image.len), (10, 10, 400));
+            let pixels = std::slice::from_raw_parts(image.data, image.len);
+            let center = (5 * 10 + 5) * 4;
+            assert_eq!(&pixels[center..center + 4], &[255, 0, 0, 255]);
+            assert_eq!(&pixels[..4], &[0, 0, 0, 0]);
+
+            gpkg_to_png_image_free(&mut image);
+            assert!(image.data.is_null());
+
+            let output = CString::new(dir.path().join("square.png").to_str().unwrap()).unwrap();
+            assert_eq!(gpkg_to_png_render_file(source, &options(), output.as_ptr()), 0);
+            assert!(dir.path().join("square.png").exists());
+
+            gpkg_to_png_source_free(source);
+        }
+    }
+
+    #[test]
+    fn test_options_bbox() {
+        let corners = |options: GpkgToPngOptions| {
+            options_bbox(&options).map(|b| (b.min_lon, b.min_lat, b.max_lon, b.max_lat))
+        };
+        assert_eq!(corners(options()).unwrap(), (0.0, 0.0, 10.0, 10.0));
+        // Crossing the antimeridian
+        let crossing = GpkgToPngOptions {
+            min_lon: 170.0,
+            max_lon: -170.0,
+            ..options()
+        };
+        assert_eq!(corners(crossing).unwrap(), (170.0, 0.0, 190.0, 10.0));
+
+        for invalid in [
+            GpkgToPngOptions { min_lon: f64::NAN, ..options() },
+            GpkgToPngOptions { max_lat: f64::INFINITY, ..options() },
+            GpkgToPngOptions { min_lon: 10.0, ..options() },
+            GpkgToPngOptions { min_lat: 10.0, ..options() },
+            GpkgToPngOptions { min_lon: 200.0, max_lon: 190.0, ..options() },
+        ] {
+            assert!(corners(invalid).is_err(), "{:?}", invalid);
+        }
+        let nan_resolution = GpkgToPngOptions { resolution: f64::NAN, ..options() };
+        let source = GpkgToPngSource { geometries: Vec::new() };
+        assert!(render(&source, &nan_resolution).is_err());
+    }
+
+    #[test]
+    fn test_panics_are_caught() {
+        assert_eq!(call(|| panic!("boom")), -1);
+        let msg = unsafe { CStr::from_ptr(gpkg_to_png_last_error()) };
+        assert_eq!(msg.to_str().unwrap(), "internal error: boom");
+        let code = 3;
+        assert_eq!(call(|| panic!("code {}", code)), -1);
+        let msg = unsafe { CStr::from_ptr(gpkg_to_png_last_error()) };
+        assert_eq!(msg.to_str().unwrap(), "internal error: code 3");
+    }
+
+    #[test]
+    fn test_last_error() {
+        let path = CString::new("missing.geojson").unwrap();
+        unsafe {
+            let mut source = ptr::null_mut();
+            assert_eq!(gpkg_to_png_open_geojson(path.as_ptr(), &mut source), -1);
+            assert!(source.is_null());
+            let msg = CStr::from_ptr(gpkg_to_png_last_error()).to_str().unwrap();
+            assert_eq!(msg, "File not found: missing.geojson");
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Footprints of renders (`--footprint`).
+//!
+//! Every produced file gets a `<name>.footprint.geojson` next to it: a
+//! Feature whose polygon covers exactly the pixels of the image (turned with
+//! `--rotation`), with the grid as properties, so outputs can be indexed
+//! spatially and displayed as coverage footprints.
+
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::manifest::Entry;
+use crate::math::ScreenTransform;
+use crate::stac;
+
+/// Returns where the footprint of an output is written: `<name>.footprint.geojson`.
+pub fn footprint_path(path: &Path) -> PathBuf {
+    path.with_extension("footprint.geojson")
+}
+
+/// Builds the footprint Feature of `entry`, rendered turned by `rotation`
+/// degrees and dated `datetime` (Unix seconds).
+pub fn feature(entry: &Entry, rotation: f64, datetime: u64) -> Value {
+    let screen = ScreenTransform::new(&entry.bbox, entry.resolution, entry.height, rotation);
+    let (width, height) = (entry.width as f64, entry.height as f64);
+    // Image corners, counter-clockwise from the bottom-left one
+    let ring: Vec<(f64, f64)> = [(0.0, height), (width, height), (width, 0.0), (0.0, 0.0)]
+        .iter()
+        .map(|&(x, y)| screen.invert(x, y))
+        .collect();
+
+    let lons = ring.iter().map(|p| p.0);
+    let lats = ring.iter().map(|p| p.1);
+    let (west, east) = (lons.clone().fold(f64::MAX, f64::min), lons.fold(f64::MIN, f64::max));
+    let (south, north) = (lats.clone().fold(f64::MAX, f64::min), lats.fold(f64::MIN, f64::max));
+    // GeoJSON writes a bbox crossing the antimeridian with west > east
+    let (west, east) = match (west < -180.0, east > 180.0) {
+        (true, _) => (west + 360.0, east),
+        (_, true) => (west, east - 360.0),
+        _ => (west, east),
+    };
+
+    let image = entry.path.file_name().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    json!({
+        "type": "Feature",
+        "bbox": [west, south, east, north],
+        "geometry": geometry(&ring),
+        "properties": {
+            "image": image,
+            "layer": entry.layer,
+            "input": entry.input.display().to_string(),
+            "width": entry.width,
+            "height": entry.height,
+            "resolution": entry.resolution,
+            "rotation": rotation,
+            "features": entry.features,
+            "datetime": stac::rfc3339(datetime),
+        },
+    })
+}
+
+/// Writes the footprint of `entry` next to its file and returns its path.
+pub fn write(entry: &Entry, rotation: f64, datetime: u64) -> Result<PathBuf> {
+    let path = footprint_path(&entry.path);
+    std::fs::write(&path, format!("{:#}\n", feature(entry, rotation, datetime)))?;
+    Ok(path)
+}
+
+/// Polygon of the convex `ring` (not closed), split in two polygons when it
+/// crosses the antimeridian.
+fn geometry(ring: &[(f64, f64)]) -> Value {
+    let shift = |part: Vec<(f64, f64)>, offset: f64| part.into_iter().map(|(x, y)| (x + offset, y)).collect();
+    let parts: Vec<Vec<(f64, f64)>> = if ring.iter().any(|p| p.0 > 180.0) {
+        vec![clip(ring, 180.0, true), shift(clip(ring, 180.0, false), -360.0)]
+    } else if ring.iter().any(|p| p.0 < -180.0) {
+        vec![shift(clip(ring, -180.0, true), 360.0), clip(ring, -180.0, false)]
+    } else {
+        vec![ring.to_vec()]
+    };
+    let polygons: Vec<Value> = parts
+        .into_iter()
+        .filter(|part| part.len() >= 3)
+        .map(|part| {
+            let closed: Vec<[f64; 2]> = part.iter().chain(part.first()).map(|&(x, y)| [x, y]).collect();
+            json!([closed])
+        })
+        .collect();
+    match polygons.as_slice() {
+        [polygon] => json!({"type": "Polygon", "coordinates": polygon}),
+        _ => json!({"type": "MultiPolygon", "coordinates": polygons}),
+    }
+}
+
+/// Clips the convex `ring` to the longitudes west (or east) of `edge`.
+fn clip(ring: &[(f64, f64)], edge: f64, west: bool) -> Vec<(f64, f64)> {
+    let inside = |p: &(f64, f64)| if west { p.0 <= edge } else { p.0 >= edge };
+    let mut clipped = Vec::with_capacity(ring.len() + 2);
+    for (i, current) in ring.iter().enumerate() {
+        let previous = &ring[(i + ring.len() - 1) % ring.len()];
+        if inside(current) != inside(previous) {
+            let t = (edge - previous.0) / (current.0 - previous.0);
+            clipped.push((edge, previous.1 + t * (current.1 - previous.1)));
+        }
+        if inside(current) {
+            clipped.push(*current);
+        }
+    }
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Bbox;
+
+    #[test]
+    fn test_feature() {
+        // The grid extends past the top of the bbox by a partial pixel row
+        let entry = Entry {
+            path: PathBuf::from("out/roads.png"),
+            input: PathBuf::from("city.gpkg"),
+            layer: "roads".to_string(),
+            bbox: Bbox::new(-4.5, 48.0, -4.0, 48.4995),
+            width: 500,
+            height: 500,
+            resolution: 0.001,
+            features: Some(42),
+            timings: None,
+        };
+        let feature = feature(&entry, 0.0, 1_700_000_000);
+        assert_eq!(feature["geometry"]["type"], "Polygon");
+        let ring = feature["geometry"]["coordinates"][0].as_array().unwrap();
+        assert_eq!(ring.len(), 5);
+        assert_eq!(ring[0], json!([-4.5, 48.0]));
+        assert_eq!(ring[2], json!([-4.0, 48.5]));
+        assert_eq!(feature["properties"]["image"], "roads.png");
+        assert_eq!(feature["properties"]["features"], 42);
+        assert_eq!(feature["properties"]["datetime"], "2023-11-14T22:13:20Z");
+        assert_eq!(footprint_path(&entry.path), PathBuf::from("out/roads.footprint.geojson"));
+
+        // Turned a quarter, the corners of a 2:1 image swap their extents
+        let turned = Entry {
+            bbox: Bbox::new(0.0, 0.0, 2.0, 1.0),
+            width: 2000,
+            height: 1000,
+            ..entry.clone()
+        };
+        let feature = super::feature(&turned, 90.0, 0);
+        let bbox: Vec<f64> = serde_json::from_value(feature["bbox"].clone()).unwrap();
+        let expected = [0.5, -0.5, 1.5, 1.5];
+        assert!(bbox.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-9));
+
+        let fiji = Entry {
+            bbox: Bbox::new(177.0, -19.0, 182.0, -16.0),
+            width: 5000,
+            height: 3000,
+            ..entry
+        };
+        let feature = super::feature(&fiji, 0.0, 0);
+        assert_eq!(feature["geometry"]["type"], "MultiPolygon");
+        assert_eq!(feature["bbox"], json!([177.0, -19.0, -178.0, -16.0]));
+        let east = &feature["geometry"]["coordinates"][1][0];
+        assert_eq!(east[0], json!([-180.0, -19.0]));
+    }
+}
@@ -1,45 +1,232 @@
 //! GeoJSON file reading and parsing.
+//!
+//! Coordinates are WGS84 per RFC 7946; files declaring another CRS with a
+//! legacy `crs` member, or given a source CRS, are reprojected to WGS84 by
+//! [`GeojsonReader::to_wgs84`]. Several files can be merged into one render
+//! with [`read_merged`]: they are parsed and reprojected in parallel.
+//!
+//! GeoJSON text sequences (RFC 8142) and newline-delimited GeoJSON are read
+//! record by record with a [`SequenceReader`], one chunk of features at a time.
 
-use geo::{Coord, LineString, MultiPolygon, Polygon};
-use geojson::{GeoJson, Geometry, Value};
-use std::fs;
-use std::path::Path;
+use geo::{Coord, CoordsIter, LineString, MultiPolygon, Polygon};
+use geojson::{GeoJson, Geometry, JsonObject, JsonValue, Value};
+use rayon::prelude::*;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 
+use crate::antimeridian;
 use crate::error::{GpkgError, Result};
+use crate::logger;
 use crate::math::Bbox;
+use crate::reproject::{
+    check_operation, reproject_bbox_to_wgs84, reproject_multipolygon, with_thread_transformer, Backend, Transformer,
+};
+use crate::style::AttributeValue;
+use crate::warning::{self, WarningCode};
+
+/// CRS of GeoJSON coordinates per RFC 7946.
+pub const WGS84: &str = "EPSG:4326";
+
+/// Maximum number of features handed over at a time by [`SequenceReader::for_each_chunk`].
+pub const SEQUENCE_CHUNK_SIZE: usize = 4096;
+
+/// Extensions of GeoJSON text sequences and newline-delimited GeoJSON files.
+const SEQUENCE_EXTENSIONS: [&str; 5] = ["geojsonl", "geojsons", "geojsonseq", "ndjson", "jsonl"];
+
+/// Record separator starting each text of an RFC 8142 sequence.
+const RS: char = '\u{1e}';
+
+/// A GeoJSON file to read, with the CRS of its coordinates when known.
+#[derive(Debug, Clone)]
+pub struct GeojsonInput {
+    pub path: PathBuf,
+    /// CRS overriding the one declared in the file (e.g. "EPSG:2154").
+    pub source_crs: Option<String>,
+}
 
 /// Reader for GeoJSON files.
 ///
-/// Parses GeoJSON and extracts polygon geometries.
-/// Assumes WGS84 (EPSG:4326) coordinate reference system.
+/// Parses GeoJSON and extracts polygon geometries, in the CRS of the file
+/// until [`GeojsonReader::to_wgs84`] is called.
 pub struct GeojsonReader {
     geometries: Vec<MultiPolygon<f64>>,
+    /// Properties of the feature of each geometry (None for bare geometries).
+    properties: Vec<Option<JsonObject>>,
+    /// CRS named by a legacy `crs` member, normalized to "EPSG:XXXX" when possible.
+    crs: Option<String>,
+    /// CRS the geometries were read in, one per distinct CRS of merged inputs.
+    source_crs: Vec<String>,
+    /// Extent given by the `bbox` member of the document, in the CRS of the geometries.
+    bbox: Option<Bbox>,
 }
 
 impl GeojsonReader {
     /// Opens and parses a GeoJSON file.
     pub async fn open(path: &Path) -> Result<Self> {
-        let content = fs::read_to_string(path).map_err(|e| {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                GpkgError::FileNotFound(path.display().to_string())
-            } else {
-                GpkgError::Io(e)
-            }
-        })?;
+        Self::read(path)
+    }
 
-        let content = preprocess_geojson(&content);
+    /// Parses a GeoJSON file on the calling thread.
+    ///
+    /// Text sequences (see [`is_sequence`]) are read whole into memory.
+    pub fn read(path: &Path) -> Result<Self> {
+        if is_sequence(path) {
+            return Self::read_sequence(path);
+        }
+        let content = fs::read_to_string(path).map_err(|e| open_error(path, e))?;
+        Self::parse(&content)
+    }
 
-        let geojson: GeoJson = content.parse().map_err(|e| {
+    /// Reads every feature of a GeoJSON text sequence.
+    fn read_sequence(path: &Path) -> Result<Self> {
+        let reader = SequenceReader::open(&GeojsonInput {
+            path: path.to_path_buf(),
+            source_crs: None,
+        })?;
+        let mut geometries = Vec::new();
+        let mut properties = Vec::new();
+        let summary = reader.for_each_chunk(|chunk_geometries, chunk_properties| {
+            geometries.extend_from_slice(chunk_geometries);
+            properties.extend_from_slice(chunk_properties);
+            Ok(())
+        })?;
+        summary.warn(&path.display().to_string())?;
+        if geometries.is_empty() {
+            return Err(GpkgError::EmptyGeojson);
+        }
+        Ok(Self {
+            geometries,
+            properties,
+            crs: None,
+            source_crs: vec![WGS84.to_string()],
+            bbox: None,
+        })
+    }
+
+    /// Parses GeoJSON text.
+    pub fn parse(content: &str) -> Result<Self> {
+        let repaired = preprocess_geojson(content);
+        if repaired != content {
+            warning::emit(
+                WarningCode::GeojsonRepaired,
+                "Malformed GeoJSON was repaired before parsing",
+            )?;
+        }
+        let geojson: GeoJson = repaired.parse().map_err(|e| {
             GpkgError::GeojsonParseError(format!("{}", e))
         })?;
 
-        let geometries = extract_geometries(&geojson);
+        let (geometries, properties): (Vec<_>, Vec<_>) =
+            extract_features(&geojson).into_iter().unzip();
 
         if geometries.is_empty() {
             return Err(GpkgError::EmptyGeojson);
         }
 
-        Ok(Self { geometries })
+        let ignored = count_geometries(&geojson) - geometries.len();
+        if ignored > 0 {
+            warning::emit(
+                WarningCode::FeatureSkippedNonPolygon,
+                &format!("{} feature(s) skipped (non-polygon geometry)", ignored),
+            )?;
+        }
+
+        let crs = declared_crs(&geojson);
+        Ok(Self {
+            geometries,
+            properties,
+            source_crs: vec![crs.clone().unwrap_or_else(|| WGS84.to_string())],
+            crs,
+            bbox: declared_bbox(&geojson),
+        })
+    }
+
+    /// Returns the CRS declared by the file's legacy `crs` member, if any.
+    pub fn crs(&self) -> Option<&str> {
+        self.crs.as_deref()
+    }
+
+    /// Returns the CRS the geometries were read in (several for merged
+    /// inputs in different CRS).
+    pub fn source_crs(&self) -> &[String] {
+        &self.source_crs
+    }
+
+    /// Reprojects the geometries to WGS84 from `source_crs`, else from the CRS
+    /// declared by the file; nothing is done for WGS84 input.
+    pub fn to_wgs84(&mut self, source_crs: Option<&str>, name: &str) -> Result<()> {
+        let crs = match (source_crs, &self.crs) {
+            (Some(crs), _) => crs.to_string(),
+            (None, Some(declared)) if declared != WGS84 => {
+                logger::info(&format!("{}: reprojecting from declared {} to {}", name, declared, WGS84));
+                declared.clone()
+            }
+            (None, _) => return Ok(()),
+        };
+        self.reproject_to_wgs84(&crs, name)
+    }
+
+    /// Reprojects every geometry from `crs` to WGS84.
+    ///
+    /// Geometries that fail to reproject are dropped with a warning; `name`
+    /// identifies the input in messages.
+    pub fn reproject_to_wgs84(&mut self, crs: &str, name: &str) -> Result<()> {
+        self.source_crs = vec![crs.to_string()];
+        if !needs_reprojection(crs, name, self.bbox.map(|b| b.center()))? {
+            return Ok(());
+        }
+        let reprojected = reproject_all(&self.geometries, crs);
+        self.bbox = self
+            .bbox
+            .and_then(|b| reproject_bbox_to_wgs84(b.min_lon, b.min_lat, b.max_lon, b.max_lat, crs))
+            .map(|(min_lon, min_lat, max_lon, max_lat)| Bbox::new(min_lon, min_lat, max_lon, max_lat));
+
+        let total = reprojected.len();
+        let properties = std::mem::take(&mut self.properties);
+        let properties = properties.into_iter().map(Some).chain(std::iter::repeat(None));
+        (self.geometries, self.properties) = reprojected
+            .into_iter()
+            .zip(properties)
+            .filter_map(|(mp, props)| Some((mp?, props.flatten())))
+            .unzip();
+
+        let failed = total - self.geometries.len();
+        if failed > 0 {
+            warning::emit(
+                WarningCode::FeatureSkippedReprojection,
+                &format!("{}: {} feature(s) skipped (reprojection to WGS84 failed)", name, failed),
+            )?;
+        }
+        if self.geometries.is_empty() {
+            return Err(GpkgError::EmptyGeojson);
+        }
+        Ok(())
+    }
+
+    /// Returns true if every coordinate lies within the range of longitudes
+    /// and latitudes in degrees (unwrapped longitudes up to ±360° included).
+    fn is_geographic(&self) -> bool {
+        self.geometries
+            .par_iter()
+            .all(|mp| mp.coords_iter().all(|c| c.x.abs() <= 360.0 && c.y.abs() <= 90.0))
+    }
+
+    /// Appends the features of `other`.
+    fn append(&mut self, mut other: GeojsonReader) {
+        // Bare geometries have no properties entry: pad to keep indices aligned
+        self.properties.resize(self.geometries.len(), None);
+        self.geometries.append(&mut other.geometries);
+        self.properties.append(&mut other.properties);
+        self.bbox = match (self.bbox, other.bbox) {
+            (Some(a), Some(b)) => antimeridian::bboxes_union(&[a, b]),
+            _ => None,
+        };
+        for crs in other.source_crs {
+            if !self.source_crs.contains(&crs) {
+                self.source_crs.push(crs);
+            }
+        }
     }
 
     /// Returns all parsed geometries.
@@ -47,41 +234,388 @@ impl GeojsonReader {
         &self.geometries
     }
 
+    /// Returns the properties of the features, in the order of the geometries.
+    ///
+    /// Trailing bare geometries may have no entry.
+    pub fn get_properties(&self) -> &[Option<JsonObject>] {
+        &self.properties
+    }
+
+    /// Returns the values of `columns` in the properties of geometry `index`.
+    ///
+    /// Missing properties are `AttributeValue::Null`.
+    pub fn attributes(&self, index: usize, columns: &[String]) -> Vec<AttributeValue> {
+        property_attributes(self.properties.get(index).and_then(Option::as_ref), columns)
+    }
+
+    /// Returns the property names of all features, in first-seen order.
+    pub fn property_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for properties in self.properties.iter().flatten() {
+            for name in properties.keys() {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+        }
+        names
+    }
+
+    /// Returns the raw JSON values of `columns` in the properties of geometry
+    /// `index`; missing properties are null.
+    pub fn property_values(&self, index: usize, columns: &[String]) -> Vec<JsonValue> {
+        property_values(self.properties.get(index).and_then(Option::as_ref), columns)
+    }
+
+    /// Returns the extent given by the `bbox` member of the document (of every
+    /// merged document), if any.
+    pub fn declared_bbox(&self) -> Option<Bbox> {
+        self.bbox
+    }
+
     /// Computes the bounding box from all geometries.
+    ///
+    /// Longitudes are resolved on the circle, so data on both sides of the
+    /// antimeridian yields a crossing bbox instead of a world-spanning one.
     pub fn compute_bbox(&self) -> Option<Bbox> {
-        if self.geometries.is_empty() {
-            return None;
+        antimeridian::geometries_bbox(&self.geometries)
+    }
+}
+
+/// Returns the values of `columns` in `properties`; missing properties are
+/// `AttributeValue::Null`.
+pub fn property_attributes(properties: Option<&JsonObject>, columns: &[String]) -> Vec<AttributeValue> {
+    columns
+        .iter()
+        .map(|column| {
+            properties
+                .and_then(|p| p.get(column))
+                .map_or(AttributeValue::Null, AttributeValue::from_json)
+        })
+        .collect()
+}
+
+/// Returns the raw JSON values of `columns` in `properties`; missing
+/// properties are null.
+pub fn property_values(properties: Option<&JsonObject>, columns: &[String]) -> Vec<JsonValue> {
+    columns
+        .iter()
+        .map(|column| properties.and_then(|p| p.get(column)).cloned().unwrap_or(JsonValue::Null))
+        .collect()
+}
+
+/// Returns true if `path` is a GeoJSON text sequence, by its extension
+/// (`.geojsonl`, `.geojsons`, `.geojsonseq`, `.ndjson` or `.jsonl`).
+pub fn is_sequence(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SEQUENCE_EXTENSIONS.iter().any(|s| e.eq_ignore_ascii_case(s)))
+}
+
+/// Counts gathered by a pass over a GeoJSON text sequence.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceSummary {
+    /// Polygon features handed over.
+    pub features: usize,
+    /// Features skipped for their non-polygon geometry.
+    pub skipped: usize,
+    /// Features dropped because they failed to reproject.
+    pub failed: usize,
+    /// Whether malformed records were repaired before parsing.
+    pub repaired: bool,
+}
+
+impl SequenceSummary {
+    /// Emits the warnings of the pass; `name` identifies the input.
+    pub fn warn(&self, name: &str) -> Result<()> {
+        if self.repaired {
+            warning::emit(
+                WarningCode::GeojsonRepaired,
+                &format!("{}: malformed records were repaired before parsing", name),
+            )?;
+        }
+        if self.skipped > 0 {
+            warning::emit(
+                WarningCode::FeatureSkippedNonPolygon,
+                &format!("{}: {} feature(s) skipped (non-polygon geometry)", name, self.skipped),
+            )?;
         }
+        if self.failed > 0 {
+            warning::emit(
+                WarningCode::FeatureSkippedReprojection,
+                &format!("{}: {} feature(s) skipped (reprojection to WGS84 failed)", name, self.failed),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Streams the features of a GeoJSON text sequence.
+///
+/// Records are either one GeoJSON text per line (newline-delimited GeoJSON)
+/// or texts introduced by a record separator, which may span several lines
+/// (RFC 8142). Each pass reads the file again and holds a single chunk of
+/// features, so sequences of any length are rendered with bounded memory.
+pub struct SequenceReader {
+    path: PathBuf,
+    /// CRS the coordinates are reprojected from (None when already WGS84).
+    source_crs: Option<String>,
+}
+
+impl SequenceReader {
+    /// Prepares to read `input`, checking its `source_crs`.
+    pub fn open(input: &GeojsonInput) -> Result<Self> {
+        let name = input.path.display().to_string();
+        let source_crs = match &input.source_crs {
+            Some(crs) if needs_reprojection(crs, &name, None)? => Some(crs.clone()),
+            _ => None,
+        };
+        Ok(Self {
+            path: input.path.clone(),
+            source_crs,
+        })
+    }
+
+    /// Returns the path of the sequence.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the sequence and calls `on_chunk` with the polygon geometries of
+    /// successive chunks of at most [`SEQUENCE_CHUNK_SIZE`] features, in WGS84,
+    /// and the properties of their feature (None for bare geometries).
+    ///
+    /// Warnings are left to the caller, from the returned summary.
+    pub fn for_each_chunk<F>(&self, mut on_chunk: F) -> Result<SequenceSummary>
+    where
+        F: FnMut(&[MultiPolygon<f64>], &[Option<JsonObject>]) -> Result<()>,
+    {
+        let file = File::open(&self.path).map_err(|e| open_error(&self.path, e))?;
+        let mut summary = SequenceSummary::default();
+        let mut chunk = Vec::with_capacity(SEQUENCE_CHUNK_SIZE);
+        // Once a record separator is seen, lines without one continue the record
+        let mut delimited = false;
+        let mut pending: Option<(usize, String)> = None;
 
-        let mut min_lon = f64::MAX;
-        let mut min_lat = f64::MAX;
-        let mut max_lon = f64::MIN;
-        let mut max_lat = f64::MIN;
-
-        for mp in &self.geometries {
-            for poly in mp.iter() {
-                for coord in poly.exterior().coords() {
-                    min_lon = min_lon.min(coord.x);
-                    min_lat = min_lat.min(coord.y);
-                    max_lon = max_lon.max(coord.x);
-                    max_lat = max_lat.max(coord.y);
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            let record = match line.strip_prefix(RS) {
+                Some(text) => {
+                    delimited = true;
+                    pending.replace((index + 1, text.to_string()))
                 }
-                for interior in poly.interiors() {
-                    for coord in interior.coords() {
-                        min_lon = min_lon.min(coord.x);
-                        min_lat = min_lat.min(coord.y);
-                        max_lon = max_lon.max(coord.x);
-                        max_lat = max_lat.max(coord.y);
+                None if delimited => {
+                    if let Some((_, text)) = pending.as_mut() {
+                        text.push('\n');
+                        text.push_str(&line);
                     }
+                    None
                 }
+                None => Some((index + 1, line)),
+            };
+            if let Some((line_number, text)) = record {
+                parse_record(&text, line_number, &mut chunk, &mut summary)?;
             }
+            while chunk.len() >= SEQUENCE_CHUNK_SIZE {
+                self.hand_over(&mut chunk, &mut summary, &mut on_chunk)?;
+            }
+        }
+        if let Some((line_number, text)) = pending {
+            parse_record(&text, line_number, &mut chunk, &mut summary)?;
+        }
+        while !chunk.is_empty() {
+            self.hand_over(&mut chunk, &mut summary, &mut on_chunk)?;
+        }
+        Ok(summary)
+    }
+
+    /// Reprojects up to [`SEQUENCE_CHUNK_SIZE`] parsed features and hands them to `on_chunk`.
+    fn hand_over<F>(
+        &self,
+        chunk: &mut Vec<(MultiPolygon<f64>, Option<JsonObject>)>,
+        summary: &mut SequenceSummary,
+        on_chunk: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(&[MultiPolygon<f64>], &[Option<JsonObject>]) -> Result<()>,
+    {
+        // A record holding a collection can overfill the chunk
+        let rest = chunk.split_off(chunk.len().min(SEQUENCE_CHUNK_SIZE));
+        let (mut geometries, mut properties): (Vec<_>, Vec<_>) = std::mem::replace(chunk, rest).into_iter().unzip();
+        if let Some(crs) = &self.source_crs {
+            let total = geometries.len();
+            (geometries, properties) = reproject_all(&geometries, crs)
+                .into_iter()
+                .zip(properties)
+                .filter_map(|(mp, props)| Some((mp?, props)))
+                .unzip();
+            summary.failed += total - geometries.len();
+        }
+        summary.features += geometries.len();
+        if geometries.is_empty() {
+            return Ok(());
+        }
+        on_chunk(&geometries, &properties)
+    }
+}
+
+/// Parses one record of a text sequence into `chunk`; `line` locates it in errors.
+fn parse_record(
+    text: &str,
+    line: usize,
+    chunk: &mut Vec<(MultiPolygon<f64>, Option<JsonObject>)>,
+    summary: &mut SequenceSummary,
+) -> Result<()> {
+    let text = text.trim_start_matches(RS).trim();
+    if text.is_empty() {
+        return Ok(());
+    }
+    let repaired = preprocess_geojson(text);
+    summary.repaired |= repaired != text;
+    let geojson: GeoJson = repaired
+        .parse()
+        .map_err(|e| GpkgError::GeojsonParseError(format!("line {}: {}", line, e)))?;
+    let features = extract_features(&geojson);
+    summary.skipped += count_geometries(&geojson) - features.len();
+    chunk.extend(features);
+    Ok(())
+}
+
+/// Returns whether coordinates in `crs` must be reprojected to WGS84, warning
+/// when the pure backend will do it; `name` identifies the input.
+///
+/// With a `probe` point in `crs`, the operation used there is checked too.
+fn needs_reprojection(crs: &str, name: &str, probe: Option<(f64, f64)>) -> Result<bool> {
+    let transformer = Transformer::new(crs, WGS84)?;
+    match transformer.backend() {
+        Backend::Identity => return Ok(false),
+        Backend::Pure => warning::emit(
+            WarningCode::ReprojectionApproximate,
+            &format!("{}: reprojected with proj4rs (no datum grids, metre-level error possible)", name),
+        )?,
+        Backend::Proj => {}
+    }
+    if let Some(point) = probe {
+        check_operation(&transformer, point, name)?;
+    }
+    Ok(true)
+}
+
+/// Reprojects `geometries` from `crs` to WGS84 in parallel (None where it fails).
+fn reproject_all(geometries: &[MultiPolygon<f64>], crs: &str) -> Vec<Option<MultiPolygon<f64>>> {
+    // Transformer is not Sync: each rayon worker reuses its own
+    geometries
+        .par_iter()
+        .map(|mp| with_thread_transformer(crs, WGS84, |transformer| reproject_multipolygon(mp, transformer)).flatten())
+        .collect()
+}
+
+/// Maps an error opening `path` to [`GpkgError::FileNotFound`] when it is missing.
+fn open_error(path: &Path, e: std::io::Error) -> GpkgError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        GpkgError::FileNotFound(path.display().to_string())
+    } else {
+        GpkgError::Io(e)
+    }
+}
+
+/// Reads several GeoJSON files in parallel and merges their features, in order.
+///
+/// Each input is reprojected to WGS84 from its `source_crs`, else from the
+/// CRS it declares. Mixing files in degrees with files in projected units
+/// (undeclared) is rejected rather than producing a world-spanning bbox.
+pub fn read_merged(inputs: &[GeojsonInput]) -> Result<GeojsonReader> {
+    let mut readers = inputs
+        .par_iter()
+        .map(|input| GeojsonReader::read(&input.path))
+        .collect::<Result<Vec<_>>>()?;
+
+    readers
+        .par_iter_mut()
+        .zip(inputs)
+        .try_for_each(|(reader, input)| reader.to_wgs84(input.source_crs.as_deref(), &input.path.display().to_string()))?;
+
+    if readers.len() > 1 {
+        let geographic: Vec<bool> = readers.par_iter().map(GeojsonReader::is_geographic).collect();
+        if geographic.iter().any(|g| *g != geographic[0]) {
+            let units = inputs.iter().zip(&geographic).map(|(input, &g)| {
+                (input.path.as_path(), if g { "degrees" } else { "projected units" }.to_string())
+            });
+            return Err(GpkgError::IncompatibleInputs(format!(
+                "mixed coordinate units ({}); set --source-crs for the projected inputs",
+                describe_inputs(units)
+            )));
         }
+    }
+
+    let mut readers = readers.into_iter();
+    let mut merged = readers.next().ok_or(GpkgError::EmptyGeojson)?;
+    for reader in readers {
+        merged.append(reader);
+    }
+    Ok(merged)
+}
 
-        if min_lon == f64::MAX {
-            None
-        } else {
-            Some(Bbox::new(min_lon, min_lat, max_lon, max_lat))
+/// Formats `path: detail` pairs for error messages.
+fn describe_inputs<'a>(inputs: impl Iterator<Item = (&'a Path, String)>) -> String {
+    inputs
+        .map(|(path, detail)| format!("{}: {}", path.display(), detail))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the CRS named by the legacy `crs` member of the document, if any.
+fn declared_crs(geojson: &GeoJson) -> Option<String> {
+    let members = match geojson {
+        GeoJson::FeatureCollection(collection) => collection.foreign_members.as_ref(),
+        GeoJson::Feature(feature) => feature.foreign_members.as_ref(),
+        GeoJson::Geometry(geometry) => geometry.foreign_members.as_ref(),
+    }?;
+    let name = members.get("crs")?.get("properties")?.get("name")?.as_str()?;
+    Some(normalize_crs(name))
+}
+
+/// Returns the extent given by the top-level `bbox` member of the document.
+///
+/// 3D bboxes are read for their horizontal extent, and a west edge greater
+/// than the east one crosses the antimeridian (RFC 7946 section 5.2). Empty
+/// or malformed bboxes are ignored.
+fn declared_bbox(geojson: &GeoJson) -> Option<Bbox> {
+    let values = match geojson {
+        GeoJson::FeatureCollection(collection) => collection.bbox.as_ref(),
+        GeoJson::Feature(feature) => feature.bbox.as_ref(),
+        GeoJson::Geometry(geometry) => geometry.bbox.as_ref(),
+    }?;
+    let (west, south, east, north) = match values.as_slice() {
+        [west, south, east, north] | [west, south, _, east, north, _] => (*west, *south, *east, *north),
+        _ => {
+            logger::debug(&format!("Ignoring bbox member with {} values", values.len()));
+            return None;
         }
+    };
+    let crossing = west > east && west.abs() <= 180.0 && east.abs() <= 180.0;
+    if ![west, south, east, north].iter().all(|v| v.is_finite()) || south >= north || (west >= east && !crossing) {
+        logger::debug(&format!("Ignoring invalid bbox member {:?}", values));
+        return None;
+    }
+    // Crossing bboxes are stored unwrapped so the width stays positive
+    let east = if crossing { east + 360.0 } else { east };
+    Some(Bbox::new(west, south, east, north))
+}
+
+/// Normalizes an OGC CRS URN to "EPSG:XXXX" (CRS84 is WGS84).
+///
+/// `urn:ogc:def:crs:EPSG::2154` becomes `EPSG:2154`; other names are kept.
+pub fn normalize_crs(name: &str) -> String {
+    let name = name.trim();
+    let upper = name.to_ascii_uppercase();
+    if upper.ends_with(":CRS84") {
+        return WGS84.to_string();
+    }
+    match upper.strip_prefix("URN:OGC:DEF:CRS:EPSG:") {
+        // The version between the colons is usually empty
+        Some(rest) => format!("EPSG:{}", rest.rsplit(':').next().unwrap_or(rest)),
+        None => name.to_string(),
     }
 }
 
@@ -98,20 +632,20 @@ fn preprocess_geojson(content: &str) -> String {
     content.replace("\"\"", "\"")
 }
 
-/// Extract polygon geometries from GeoJSON.
-fn extract_geometries(geojson: &GeoJson) -> Vec<MultiPolygon<f64>> {
-    let mut geometries = Vec::new();
+/// Extract polygon geometries from GeoJSON, with the properties of their feature.
+fn extract_features(geojson: &GeoJson) -> Vec<(MultiPolygon<f64>, Option<JsonObject>)> {
+    let mut features = Vec::new();
 
     match geojson {
         GeoJson::Geometry(geom) => {
             if let Some(mp) = geometry_to_multipolygon(geom) {
-                geometries.push(mp);
+                features.push((mp, None));
             }
         }
         GeoJson::Feature(feature) => {
             if let Some(ref geom) = feature.geometry {
                 if let Some(mp) = geometry_to_multipolygon(geom) {
-                    geometries.push(mp);
+                    features.push((mp, feature.properties.clone()));
                 }
             }
         }
@@ -119,14 +653,33 @@ fn extract_geometries(geojson: &GeoJson) -> Vec<MultiPolygon<f64>> {
             for feature in &collection.features {
                 if let Some(ref geom) = feature.geometry {
                     if let Some(mp) = geometry_to_multipolygon(geom) {
-                        geometries.push(mp);
+                        features.push((mp, feature.properties.clone()));
                     }
                 }
             }
         }
     }
 
-    geometries
+    features
+}
+
+/// Extract polygon geometries from GeoJSON.
+#[cfg(test)]
+fn extract_geometries(geojson: &GeoJson) -> Vec<MultiPolygon<f64>> {
+    extract_features(geojson).into_iter().map(|(mp, _)| mp).collect()
+}
+
+/// Count all geometries in the document, regardless of type.
+fn count_geometries(geojson: &GeoJson) -> usize {
+    match geojson {
+        GeoJson::Geometry(_) => 1,
+        GeoJson::Feature(feature) => feature.geometry.is_some() as usize,
+        GeoJson::FeatureCollection(collection) => collection
+            .features
+            .iter()
+            .filter(|f| f.geometry.is_some())
+            .count(),
+    }
 }
 
 /// Convert a GeoJSON geometry to a MultiPolygon.
@@ -217,6 +770,33 @@ mod tests {
         assert_eq!(geometries.len(), 1);
     }
 
+    #[test]
+    fn test_attributes_follow_geometries() {
+        let json = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "properties": {"p": 0.5}, "geometry": {"type": "Point", "coordinates": [0.0, 0.0]}},
+                {"type": "Feature", "properties": {"p": 80}, "geometry": {"type": "Polygon", "coordinates": [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]]}},
+                {"type": "Feature", "properties": null, "geometry": {"type": "Polygon", "coordinates": [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]]}}
+            ]
+        }"#;
+
+        let geojson: GeoJson = json.parse().unwrap();
+        let (geometries, properties) = extract_features(&geojson).into_iter().unzip();
+        let reader = GeojsonReader {
+            geometries,
+            properties,
+            crs: None,
+            source_crs: Vec::new(),
+            bbox: None,
+        };
+        let columns = ["p".to_string()];
+        assert_eq!(reader.attributes(0, &columns), vec![AttributeValue::Number(80.0)]);
+        assert_eq!(reader.attributes(1, &columns), vec![AttributeValue::Null]);
+        assert_eq!(reader.property_names(), columns);
+        assert_eq!(reader.property_values(1, &columns), vec![JsonValue::Null]);
+    }
+
     #[test]
     fn test_parse_raw_polygon() {
         let json = r#"{
@@ -330,6 +910,7 @@ mod tests {
         let geojson: GeoJson = json.parse().unwrap();
         let geometries = extract_geometries(&geojson);
         assert_eq!(geometries.len(), 1);
+        assert_eq!(count_geometries(&geojson), 2);
     }
 
     #[test]
@@ -360,6 +941,10 @@ mod tests {
                 MultiPolygon::new(vec![poly1]),
                 MultiPolygon::new(vec![poly2]),
             ],
+            properties: vec![],
+            crs: None,
+            source_crs: Vec::new(),
+            bbox: None,
         };
 
         let bbox = reader.compute_bbox().unwrap();
@@ -368,4 +953,187 @@ mod tests {
         assert!((bbox.max_lon - 3.0).abs() < 1e-10);
         assert!((bbox.max_lat - 3.0).abs() < 1e-10);
     }
+
+    #[test]
+    fn test_compute_bbox_across_antimeridian() {
+        let west = Polygon::new(
+            LineString::from(vec![
+                Coord { x: -179.5, y: -17.0 },
+                Coord { x: -178.5, y: -17.0 },
+                Coord { x: -178.5, y: -16.0 },
+                Coord { x: -179.5, y: -17.0 },
+            ]),
+            vec![],
+        );
+        let east = Polygon::new(
+            LineString::from(vec![
+                Coord { x: 177.0, y: -18.0 },
+                Coord { x: 178.0, y: -18.0 },
+                Coord { x: 178.0, y: -17.0 },
+                Coord { x: 177.0, y: -18.0 },
+            ]),
+            vec![],
+        );
+
+        let reader = GeojsonReader {
+            geometries: vec![MultiPolygon::new(vec![west]), MultiPolygon::new(vec![east])],
+            properties: vec![],
+            crs: None,
+            source_crs: Vec::new(),
+            bbox: None,
+        };
+
+        let bbox = reader.compute_bbox().unwrap();
+        assert!(bbox.crosses_antimeridian());
+        assert!((bbox.min_lon - 177.0).abs() < 1e-10);
+        assert!((bbox.max_lon - 181.5).abs() < 1e-10);
+        assert!((bbox.min_lat - (-18.0)).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_normalize_crs() {
+        assert_eq!(normalize_crs("urn:ogc:def:crs:EPSG::2154"), "EPSG:2154");
+        assert_eq!(normalize_crs("urn:ogc:def:crs:OGC:1.3:CRS84"), "EPSG:4326");
+        assert_eq!(normalize_crs("EPSG:3857"), "EPSG:3857");
+    }
+
+    #[test]
+    fn test_read_merged() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, json: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, json).unwrap();
+            GeojsonInput {
+                path,
+                source_crs: None,
+            }
+        };
+        let degrees = write(
+            "degrees.geojson",
+            r#"{"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 0]]]}"#,
+        );
+        let metres = write(
+            "metres.geojson",
+            r#"{"type": "Feature", "properties": {"id": 1}, "geometry":
+                {"type": "Polygon", "coordinates": [[[222000, 0], [333000, 0], [333000, 111000], [222000, 0]]]}}"#,
+        );
+        let declared = write(
+            "declared.geojson",
+            r#"{"type": "FeatureCollection",
+                "crs": {"type": "name", "properties": {"name": "urn:ogc:def:crs:EPSG::3857"}},
+                "features": [{"type": "Feature", "properties": null, "geometry":
+                    {"type": "Polygon", "coordinates": [[[111319, 0], [222639, 0], [222639, 111325], [111319, 0]]]}}]}"#,
+        );
+
+        let err = read_merged(&[degrees.clone(), metres.clone()]).err().unwrap();
+        assert!(err.to_string().contains("mixed coordinate units"));
+
+        // The declared CRS is reprojected, which needs one of the reprojection backends
+        let merged = read_merged(&[degrees.clone(), declared]);
+        #[cfg(not(any(feature = "reproject", feature = "reproject-pure")))]
+        assert!(matches!(merged, Err(GpkgError::Reprojection(_))));
+        #[cfg(any(feature = "reproject", feature = "reproject-pure"))]
+        {
+            let merged = merged.unwrap();
+            assert_eq!(merged.source_crs(), ["EPSG:4326", "EPSG:3857"]);
+            let bbox = merged.compute_bbox().unwrap();
+            assert!((bbox.max_lon - 2.0).abs() < 0.01);
+            assert!((bbox.max_lat - 1.0).abs() < 0.01);
+
+            let reprojected = GeojsonInput {
+                source_crs: Some("EPSG:3857".to_string()),
+                ..metres
+            };
+            let merged = read_merged(&[degrees, reprojected]).unwrap();
+            assert_eq!(merged.get_geometries().len(), 2);
+            assert_eq!(merged.crs(), None);
+            assert_eq!(merged.source_crs(), ["EPSG:4326", "EPSG:3857"]);
+            let columns = ["id".to_string()];
+            assert!(matches!(merged.attributes(0, &columns)[0], AttributeValue::Null));
+            assert!(!matches!(merged.attributes(1, &columns)[0], AttributeValue::Null));
+            let bbox = merged.compute_bbox().unwrap();
+            assert!((bbox.max_lon - 2.99).abs() < 0.01);
+            assert!(bbox.max_lat < 1.1);
+        }
+    }
+
+    #[test]
+    fn test_read_sequence() {
+        let dir = tempfile::tempdir().unwrap();
+        let square = |x: usize| {
+            format!(
+                r#"{{"type": "Feature", "properties": {{"id": {x}}}, "geometry": {{"type": "Polygon", "coordinates": [[[{x}, 0], [{x}.5, 0], [{x}.5, 1], [{x}, 0]]]}}}}"#
+            )
+        };
+
+        // Newline-delimited, with blank lines and a point
+        let path = dir.path().join("zones.ndjson");
+        let point = r#"{"type": "Feature", "properties": null, "geometry": {"type": "Point", "coordinates": [0, 0]}}"#;
+        fs::write(&path, format!("{}\n\n{}\n{}\n", square(1), point, square(2))).unwrap();
+        assert!(is_sequence(&path));
+        assert!(!is_sequence(Path::new("zones.geojson")));
+        let reader = GeojsonReader::read(&path).unwrap();
+        assert_eq!(reader.get_geometries().len(), 2);
+        assert!(matches!(reader.attributes(1, &["id".to_string()])[0], AttributeValue::Number(n) if n == 2.0));
+
+        // RFC 8142 records may span lines
+        let path = dir.path().join("zones.geojsons");
+        let pretty = square(3).replace(", ", ",\n  ");
+        fs::write(&path, format!("\u{1e}{}\n\u{1e}{}\n", pretty, square(4))).unwrap();
+        let mut ids = Vec::new();
+        let summary = SequenceReader::open(&GeojsonInput {
+            path: path.clone(),
+            source_crs: None,
+        })
+        .unwrap()
+        .for_each_chunk(|geometries, properties| {
+            assert_eq!(geometries.len(), properties.len());
+            ids.extend(properties.iter().map(|p| p.as_ref().unwrap()["id"].clone()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(ids, [3, 4]);
+        assert_eq!(summary.features, 2);
+
+        // Chunks are bounded, and parse errors point at their line
+        let path = dir.path().join("many.geojsonl");
+        let lines: Vec<String> = (0..SEQUENCE_CHUNK_SIZE + 3).map(|i| square(i % 100)).collect();
+        fs::write(&path, lines.join("\n") + "\n{\"type\": \n").unwrap();
+        let reader = SequenceReader::open(&GeojsonInput {
+            path,
+            source_crs: None,
+        })
+        .unwrap();
+        let mut sizes = Vec::new();
+        let err = reader
+            .for_each_chunk(|geometries, _| {
+                sizes.push(geometries.len());
+                Ok(())
+            })
+            .unwrap_err();
+        assert_eq!(sizes, [SEQUENCE_CHUNK_SIZE]);
+        assert!(err.to_string().contains(&format!("line {}", SEQUENCE_CHUNK_SIZE + 4)));
+    }
+
+    #[test]
+    fn test_declared_bbox() {
+        let polygon = r#""features": [{"type": "Feature", "properties": null, "geometry":
+            {"type": "Polygon", "coordinates": [[[178, -18], [179, -18], [179, -17], [178, -18]]]}}]"#;
+        let parse = |bbox: &str| {
+            GeojsonReader::parse(&format!(r#"{{"type": "FeatureCollection", {} {}}}"#, bbox, polygon)).unwrap()
+        };
+
+        let reader = parse(r#""bbox": [177, -20, -178, -16],"#);
+        let bbox = reader.declared_bbox().unwrap();
+        assert_eq!((bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat), (177.0, -20.0, 182.0, -16.0));
+        assert!(bbox.crosses_antimeridian());
+
+        let reader = parse(r#""bbox": [170, -25, 0, 180, -10, 100],"#);
+        let bbox = reader.declared_bbox().unwrap();
+        assert_eq!((bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat), (170.0, -25.0, 180.0, -10.0));
+
+        assert!(parse("").declared_bbox().is_none());
+        assert!(parse(r#""bbox": [170, -10, 180, -25],"#).declared_bbox().is_none());
+        assert!(parse(r#""bbox": [170, -10, 180],"#).declared_bbox().is_none());
+    }
 }
@@ -0,0 +1,111 @@
+//! XMP geotagging of rendered images.
+//!
+//! Photo-management and GIS-lite tools can place an image on a map from its
+//! XMP GPS properties. The center of the render bbox is written as
+//! `exif:GPSLatitude`/`exif:GPSLongitude`, and the full bbox is recorded in a
+//! tool-specific namespace so it can be recovered exactly.
+
+use crate::math::Bbox;
+
+/// Keyword of the PNG iTXt chunk holding an XMP packet.
+pub const XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+
+/// Namespace for the bbox properties.
+const NAMESPACE: &str = "https://github.com/AdrienGras/gpkg-to-png/ns/1.0/";
+
+/// Normalizes a longitude to [-180, 180] (crossing bboxes store `max_lon > 180`).
+fn normalize_lon(lon: f64) -> f64 {
+    if lon > 180.0 {
+        lon - 360.0
+    } else {
+        lon
+    }
+}
+
+/// Formats a coordinate as XMP GPS "DDD,MM.mmmmmmR" (degrees, decimal minutes, reference).
+fn gps_coordinate(value: f64, positive: char, negative: char) -> String {
+    let reference = if value < 0.0 { negative } else { positive };
+    let abs = value.abs();
+    let degrees = abs.trunc();
+    let minutes = (abs - degrees) * 60.0;
+    format!("{},{:.6}{}", degrees as u32, minutes, reference)
+}
+
+/// Builds an XMP packet describing the geographic extent of a render.
+pub fn xmp_packet(bbox: &Bbox) -> String {
+    let center_lon = normalize_lon((bbox.min_lon + bbox.max_lon) / 2.0);
+    let center_lat = (bbox.min_lat + bbox.max_lat) / 2.0;
+
+    format!(
+        concat!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n",
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n",
+            " <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n",
+            "  <rdf:Description rdf:about=\"\"\n",
+            "    xmlns:exif=\"http://ns.adobe.com/exif/1.0/\"\n",
+            "    xmlns:gpkg2png=\"{ns}\"\n",
+            "    exif:GPSVersionID=\"2.3.0.0\"\n",
+            "    exif:GPSMapDatum=\"WGS-84\"\n",
+            "    exif:GPSLatitude=\"{lat}\"\n",
+            "    exif:GPSLongitude=\"{lon}\"\n",
+            "    gpkg2png:MinLon=\"{min_lon}\"\n",
+            "    gpkg2png:MinLat=\"{min_lat}\"\n",
+            "    gpkg2png:MaxLon=\"{max_lon}\"\n",
+            "    gpkg2png:MaxLat=\"{max_lat}\"/>\n",
+            " </rdf:RDF>\n",
+            "</x:xmpmeta>\n",
+            "<?xpacket end=\"r\"?>"
+        ),
+        ns = NAMESPACE,
+        lat = gps_coordinate(center_lat, 'N', 'S'),
+        lon = gps_coordinate(center_lon, 'E', 'W'),
+        min_lon = bbox.min_lon,
+        min_lat = bbox.min_lat,
+        max_lon = normalize_lon(bbox.max_lon),
+        max_lat = bbox.max_lat,
+    )
+}
+
+/// Recovers the bbox written by [`xmp_packet`], unwrapping crossing bboxes.
+pub fn parse_bbox(xmp: &str) -> Option<Bbox> {
+    let value = |name: &str| -> Option<f64> {
+        let start = xmp.find(&format!("gpkg2png:{}=\"", name))? + name.len() + 11;
+        let end = start + xmp[start..].find('"')?;
+        xmp[start..end].parse().ok()
+    };
+    let (min_lon, max_lon) = (value("MinLon")?, value("MaxLon")?);
+    let max_lon = if max_lon < min_lon { max_lon + 360.0 } else { max_lon };
+    Some(Bbox::new(min_lon, value("MinLat")?, max_lon, value("MaxLat")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gps_coordinate() {
+        assert_eq!(gps_coordinate(48.5, 'N', 'S'), "48,30.000000N");
+        assert_eq!(gps_coordinate(-4.25, 'E', 'W'), "4,15.000000W");
+    }
+
+    #[test]
+    fn test_xmp_packet_center_and_bbox() {
+        let xmp = xmp_packet(&Bbox::new(-4.5, 48.0, -4.0, 49.0));
+        assert!(xmp.contains("exif:GPSLatitude=\"48,30.000000N\""));
+        assert!(xmp.contains("exif:GPSLongitude=\"4,15.000000W\""));
+        assert!(xmp.contains("gpkg2png:MinLon=\"-4.5\""));
+        assert!(xmp.contains("gpkg2png:MaxLat=\"49\""));
+    }
+
+    #[test]
+    fn test_xmp_packet_across_antimeridian() {
+        // 170°E -> 170°W, stored as 170 -> 190; center is on the dateline
+        let xmp = xmp_packet(&Bbox::new(170.0, -10.0, 190.0, 10.0));
+        assert!(xmp.contains("exif:GPSLongitude=\"180,0.000000E\""));
+        assert!(xmp.contains("gpkg2png:MaxLon=\"-170\""));
+
+        let bbox = parse_bbox(&xmp).unwrap();
+        assert_eq!((bbox.min_lon, bbox.max_lon, bbox.max_lat), (170.0, 190.0, 10.0));
+        assert!(parse_bbox("<x:xmpmeta/>").is_none());
+    }
+}
@@ -1,12 +1,28 @@
-use geo::{Geometry, MapCoords, MultiPolygon};
-use proj::Proj;
-use rayon::prelude::*;
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
-use sqlx::Row;
-use std::path::Path;
-use std::str::FromStr;
+//! GeoPackage access.
+//!
+//! Layer metadata, read filters and connection settings live here so they can
+//! be configured without the `gpkg` feature; the SQLite-backed reader is in
+//! the `reader` submodule.
+
+use serde_json::json;
 
 use crate::error::{GpkgError, Result};
+use crate::math::Bbox;
+use crate::style::AttributeValue;
+
+#[cfg(feature = "gpkg")]
+mod cache;
+#[cfg(feature = "gpkg")]
+mod reader;
+#[cfg(feature = "gpkg")]
+mod wkb;
+
+#[cfg(feature = "gpkg")]
+pub use reader::GpkgReader;
+
+/// Default largest angle, in degrees, spanned by one segment when the arcs
+/// of curved geometries (CurvePolygon, MultiSurface) are linearized.
+pub const DEFAULT_CURVE_TOLERANCE: f64 = 4.0;
 
 /// Information about a polygon layer in the GeoPackage
 #[derive(Debug, Clone)]
@@ -16,262 +32,295 @@ pub struct LayerInfo {
     pub srs_id: i32,
 }
 
-/// Read GeoPackage and extract polygon layers
-pub struct GpkgReader {
-    pool: SqlitePool,
+/// Description of a feature layer of any geometry type, as listed by `list-layers`
+#[derive(Debug, Clone)]
+pub struct LayerSummary {
+    pub name: String,
+    /// Geometry type declared in gpkg_geometry_columns (e.g. "MULTIPOLYGON")
+    pub geometry_type: String,
+    pub srs_id: i32,
+    /// "ORGANIZATION:CODE" of the SRS (e.g. "EPSG:2154"), if it has one
+    pub srs: Option<String>,
+    pub feature_count: u64,
+    /// Extent from gpkg_contents, in the layer SRS: (min_x, min_y, max_x, max_y)
+    pub extent: Option<(f64, f64, f64, f64)>,
+    /// Extent reprojected to WGS84, as accepted by `--bbox`
+    pub wgs84_extent: Option<Bbox>,
 }
 
-impl GpkgReader {
-    /// Open a GeoPackage file
-    pub async fn open(path: &Path) -> Result<Self> {
-        if !path.exists() {
-            return Err(GpkgError::FileNotFound(path.display().to_string()));
-        }
-
-        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
-            .map_err(GpkgError::Database)?
-            .read_only(true);
-
-        let pool = SqlitePool::connect_with(options)
-            .await
-            .map_err(GpkgError::Database)?;
-
-        Ok(Self { pool })
+impl LayerSummary {
+    /// Whether the layer holds polygons (or curved surfaces) and can be rendered.
+    pub fn is_polygon(&self) -> bool {
+        let geometry_type = self.geometry_type.to_ascii_uppercase();
+        geometry_type.contains("POLYGON") || geometry_type.contains("SURFACE")
     }
 
-    /// List all polygon/multipolygon layers
-    pub async fn list_polygon_layers(&self) -> Result<Vec<LayerInfo>> {
-        let rows = sqlx::query(
-            r#"
-            SELECT c.table_name, g.column_name, g.srs_id
-            FROM gpkg_contents c
-            JOIN gpkg_geometry_columns g ON c.table_name = g.table_name
-            WHERE c.data_type = 'features'
-            AND (g.geometry_type_name LIKE '%POLYGON%' OR g.geometry_type_name LIKE '%polygon%')
-            "#,
-        )
-        .fetch_all(&self.pool)
-        .await?;
-
-        let layers: Vec<LayerInfo> = rows
-            .iter()
-            .map(|row| LayerInfo {
-                name: row.get("table_name"),
-                geometry_column: row.get("column_name"),
-                srs_id: row.get("srs_id"),
-            })
-            .collect();
-
-        Ok(layers)
+    /// Serializes the summary as a JSON object; extents are `[min_x, min_y, max_x, max_y]`.
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "name": self.name,
+            "geometry_type": self.geometry_type,
+            "polygon": self.is_polygon(),
+            "srs_id": self.srs_id,
+            "srs": self.srs,
+            "feature_count": self.feature_count,
+            "extent": self.extent.map(|(min_x, min_y, max_x, max_y)| [min_x, min_y, max_x, max_y]),
+            "wgs84_extent": self.wgs84_extent.map(|b| [b.min_lon, b.min_lat, b.max_lon, b.max_lat]),
+        })
     }
+}
 
-    /// Read geometries from a specific layer
-    pub async fn read_geometries(&self, layer: &LayerInfo) -> Result<Vec<MultiPolygon<f64>>> {
-        let query = format!("SELECT {} FROM \"{}\"", layer.geometry_column, layer.name);
-
-        let rows = sqlx::query(&query).fetch_all(&self.pool).await?;
+/// A raster layer stored as a tile pyramid (`data_type = 'tiles'`).
+#[derive(Debug, Clone)]
+pub struct TileLayerInfo {
+    pub name: String,
+    pub srs_id: i32,
+    /// Bounds of the tile matrix set, in the layer SRS: tile (0, 0) of every
+    /// zoom level starts at (min_x, max_y).
+    pub extent: (f64, f64, f64, f64),
+    /// Zoom levels, from the coarsest to the finest.
+    pub matrices: Vec<TileMatrix>,
+}
 
-        let mut geometries = Vec::new();
-        for row in rows {
-            let wkb_data: Vec<u8> = row.get(0);
+/// The tile grid of one zoom level of a tile layer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileMatrix {
+    pub zoom_level: i64,
+    pub matrix_width: i64,
+    pub matrix_height: i64,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    /// Size of a pixel in layer SRS units.
+    pub pixel_x_size: f64,
+    pub pixel_y_size: f64,
+}
 
-            // Skip GeoPackage header (first 8 bytes: magic, version, flags, srs_id, envelope)
-            // GeoPackage WKB has a header before the standard WKB
-            if let Some(geom) = parse_gpkg_wkb(&wkb_data) {
-                match geom {
-                    Geometry::Polygon(p) => geometries.push(MultiPolygon::new(vec![p])),
-                    Geometry::MultiPolygon(mp) => geometries.push(mp),
-                    _ => {} // Skip non-polygon geometries
-                }
-            }
-        }
+impl TileLayerInfo {
+    /// Returns the coarsest zoom level whose pixels are no larger than
+    /// `pixel_size` (in layer SRS units), or the finest one when all are.
+    pub fn matrix_for(&self, pixel_size: f64) -> Option<&TileMatrix> {
+        self.matrices
+            .iter()
+            .filter(|m| m.pixel_x_size.max(m.pixel_y_size) <= pixel_size)
+            .min_by(|a, b| b.pixel_x_size.total_cmp(&a.pixel_x_size))
+            .or_else(|| self.matrices.iter().min_by(|a, b| a.pixel_x_size.total_cmp(&b.pixel_x_size)))
+    }
 
-        Ok(geometries)
+    /// Returns the columns and rows of the `matrix` tiles covering an area
+    /// given in the layer SRS, or None when it is outside the matrix.
+    pub fn tile_range(
+        &self,
+        matrix: &TileMatrix,
+        (min_x, min_y, max_x, max_y): (f64, f64, f64, f64),
+    ) -> Option<((i64, i64), (i64, i64))> {
+        let (origin_x, _, _, origin_y) = self.extent;
+        let tile_x = matrix.tile_width as f64 * matrix.pixel_x_size;
+        let tile_y = matrix.tile_height as f64 * matrix.pixel_y_size;
+        let first_column = (((min_x - origin_x) / tile_x).floor() as i64).max(0);
+        let last_column = (((max_x - origin_x) / tile_x).floor() as i64).min(matrix.matrix_width - 1);
+        let first_row = (((origin_y - max_y) / tile_y).floor() as i64).max(0);
+        let last_row = (((origin_y - min_y) / tile_y).floor() as i64).min(matrix.matrix_height - 1);
+        (first_column <= last_column && first_row <= last_row).then_some(((first_column, last_column), (first_row, last_row)))
     }
+}
 
-    /// Get SRS definition for a layer
-    pub async fn get_srs_definition(&self, srs_id: i32) -> Result<String> {
-        let row = sqlx::query("SELECT definition FROM gpkg_spatial_ref_sys WHERE srs_id = ?")
-            .bind(srs_id)
-            .fetch_one(&self.pool)
-            .await?;
+/// Extensions that do not change what is drawn, or that the reader handles
+/// (curve geometry types are linearized, WebP tiles decoded).
+const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "gpkg_rtree_index",
+    "gpkg_geometry_type_trigger",
+    "gpkg_srs_id_trigger",
+    "gpkg_geom_CIRCULARSTRING",
+    "gpkg_geom_COMPOUNDCURVE",
+    "gpkg_geom_CURVEPOLYGON",
+    "gpkg_geom_MULTICURVE",
+    "gpkg_geom_MULTISURFACE",
+    "gpkg_geom_CURVE",
+    "gpkg_geom_SURFACE",
+    "gpkg_crs_wkt",
+    "gpkg_webp",
+    "gpkg_zoom_other",
+    "gpkg_metadata",
+    "gpkg_schema",
+    "gpkg_related_tables",
+    "related_tables",
+];
+
+/// A row of `gpkg_extensions`.
+#[derive(Debug, Clone)]
+pub struct Extension {
+    /// Table the extension applies to (None for the whole GeoPackage).
+    pub table_name: Option<String>,
+    pub column_name: Option<String>,
+    pub extension_name: String,
+    /// `read-write`, or `write-only` when readers may ignore it.
+    pub scope: String,
+}
 
-        Ok(row.get("definition"))
+impl Extension {
+    /// Returns true if the extension applies to `table` (or to every table).
+    pub fn applies_to(&self, table: &str) -> bool {
+        self.table_name.as_ref().is_none_or(|t| t.eq_ignore_ascii_case(table))
     }
 
-    /// Read and reproject geometries to WGS84.
-    ///
-    /// This method parallelizes the reprojection of geometries using `rayon`.
-    /// Each thread initializes its own `proj` context for thread safety.
-    pub async fn read_geometries_wgs84(&self, layer: &LayerInfo) -> Result<Vec<MultiPolygon<f64>>> {
-        let geometries = self.read_geometries(layer).await?;
-
-        if layer.srs_id == 4326 {
-            return Ok(geometries);
-        }
+    /// Returns true if reading without support for this extension may
+    /// produce wrong or incomplete output.
+    pub fn is_unsupported(&self) -> bool {
+        self.scope.eq_ignore_ascii_case("read-write")
+            && !SUPPORTED_EXTENSIONS.iter().any(|s| s.eq_ignore_ascii_case(&self.extension_name))
+    }
+}
 
-        let srs_def = self.get_srs_definition(layer.srs_id).await?;
+/// A polygon feature with the attribute values requested alongside it.
+#[derive(Debug, Clone)]
+pub struct Feature {
+    /// Primary key of the feature (`rowid` if none is declared).
+    pub fid: i64,
+    pub geometry: geo::MultiPolygon<f64>,
+    /// Values of the requested columns, in request order.
+    pub attributes: Vec<AttributeValue>,
+}
 
-        // Parallelize reprojection
-        let reprojected: Vec<MultiPolygon<f64>> = geometries
-            .into_par_iter()
-            .filter_map(|mp| {
-                // Proj is Send but not Sync, so we must create it per thread.
-                // Using a closure with Proj inside allows each thread to have its own.
-                let proj = Proj::new_known_crs(&srs_def, "EPSG:4326", None).ok()?;
-                reproject_multipolygon(&mp, &proj)
-            })
-            .collect();
+/// Restricts which features of a layer are read.
+#[derive(Debug, Clone, Default)]
+pub struct FeatureFilter {
+    /// Only read features whose RTree entry or header envelope intersects
+    /// this WGS84 bbox.
+    pub bbox: Option<Bbox>,
+    /// SQL conditions on attribute columns, combined with AND.
+    ///
+    /// Each clause must have been checked with `validate_where_clause`.
+    pub where_clauses: Vec<String>,
+    /// Maximum number of features to read.
+    pub limit: Option<u64>,
+    /// Number of matching features to skip before reading.
+    pub offset: Option<u64>,
+    /// Only read the features with these IDs.
+    pub fids: Option<FidSelection>,
+}
 
-        Ok(reprojected)
-    }
+/// A set of feature IDs to read.
+#[derive(Debug, Clone)]
+pub struct FidSelection {
+    /// Column holding the IDs (None = the layer primary key).
+    pub column: Option<String>,
+    /// Values to match; integers when matching the primary key.
+    pub values: Vec<String>,
+}
 
-    /// Get the bounding box of a layer in source CRS from gpkg_contents
-    pub async fn get_layer_bbox(&self, layer: &LayerInfo) -> Result<Option<(f64, f64, f64, f64)>> {
-        let row = sqlx::query(
-            "SELECT min_x, min_y, max_x, max_y FROM gpkg_contents WHERE table_name = ?",
-        )
-        .bind(&layer.name)
-        .fetch_one(&self.pool)
-        .await?;
-
-        let min_x: Option<f64> = row.get("min_x");
-        let min_y: Option<f64> = row.get("min_y");
-        let max_x: Option<f64> = row.get("max_x");
-        let max_y: Option<f64> = row.get("max_y");
-
-        match (min_x, min_y, max_x, max_y) {
-            (Some(min_x), Some(min_y), Some(max_x), Some(max_y)) => {
-                Ok(Some((min_x, min_y, max_x, max_y)))
-            }
-            _ => Ok(None),
-        }
-    }
+/// Files at least this large get memory-mapped I/O unless told otherwise.
+#[cfg(feature = "gpkg")]
+const AUTO_MMAP_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// Upper bound for the automatic mmap size (SQLite's default compile-time cap).
+#[cfg(feature = "gpkg")]
+const AUTO_MMAP_MAX: u64 = 0x7fff_0000;
+
+/// SQLite `PRAGMA temp_store` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum TempStore {
+    Default,
+    File,
+    Memory,
 }
 
-/// Reproject a bbox from source CRS to WGS84.
-///
-/// Returns `None` if the projection fails.
-/// Handles the projection by reprojecting all 4 corners and computing the new bounds.
-pub fn reproject_bbox_to_wgs84(
-    min_x: f64,
-    min_y: f64,
-    max_x: f64,
-    max_y: f64,
-    srs_def: &str,
-) -> Option<(f64, f64, f64, f64)> {
-    let proj = Proj::new_known_crs(srs_def, "EPSG:4326", None).ok()?;
-
-    // Reproject all 4 corners and find the new bounds
-    let corners = [
-        (min_x, min_y),
-        (max_x, min_y),
-        (max_x, max_y),
-        (min_x, max_y),
-    ];
-
-    let mut wgs84_min_lon = f64::MAX;
-    let mut wgs84_min_lat = f64::MAX;
-    let mut wgs84_max_lon = f64::MIN;
-    let mut wgs84_max_lat = f64::MIN;
-
-    for (x, y) in corners {
-        if let Ok((lon, lat)) = proj.convert((x, y)) {
-            wgs84_min_lon = wgs84_min_lon.min(lon);
-            wgs84_min_lat = wgs84_min_lat.min(lat);
-            wgs84_max_lon = wgs84_max_lon.max(lon);
-            wgs84_max_lat = wgs84_max_lat.max(lat);
+impl TempStore {
+    #[cfg(feature = "gpkg")]
+    fn pragma_value(self) -> &'static str {
+        match self {
+            TempStore::Default => "DEFAULT",
+            TempStore::File => "FILE",
+            TempStore::Memory => "MEMORY",
         }
     }
+}
 
-    if wgs84_min_lon == f64::MAX {
-        None
-    } else {
-        Some((wgs84_min_lon, wgs84_min_lat, wgs84_max_lon, wgs84_max_lat))
-    }
+/// SQLite pragmas applied to every connection used to read the GeoPackage.
+#[derive(Debug, Clone, Default)]
+pub struct SqlitePragmas {
+    /// `PRAGMA mmap_size` in bytes (None = automatic, 0 = disabled).
+    pub mmap_size: Option<u64>,
+    /// Page cache size in KiB (`PRAGMA cache_size = -N`).
+    pub cache_size_kib: Option<u64>,
+    /// `PRAGMA temp_store`.
+    pub temp_store: Option<TempStore>,
 }
 
-/// Reproject a MultiPolygon using proj.
-///
-/// Returns `None` if any coordinate transformation fails (results in NaN).
-fn reproject_multipolygon(mp: &MultiPolygon<f64>, proj: &Proj) -> Option<MultiPolygon<f64>> {
-    let reprojected = mp.map_coords(|coord| match proj.convert((coord.x, coord.y)) {
-        Ok((x, y)) => geo::Coord { x, y },
-        Err(_) => geo::Coord {
-            x: f64::NAN,
-            y: f64::NAN,
-        },
-    });
-
-    // Check if any coordinates failed (became NaN)
-    let has_nan = reprojected.iter().any(|poly| {
-        poly.exterior()
-            .coords()
-            .any(|c| c.x.is_nan() || c.y.is_nan())
-            || poly
-                .interiors()
-                .iter()
-                .any(|ring| ring.coords().any(|c| c.x.is_nan() || c.y.is_nan()))
-    });
-
-    if has_nan {
-        None
-    } else {
-        Some(reprojected)
+impl SqlitePragmas {
+    /// Returns the mmap size to use for a file of `file_len` bytes.
+    ///
+    /// Without an explicit value, large files are mapped entirely (up to
+    /// `AUTO_MMAP_MAX`) and small files use SQLite's default.
+    #[cfg(feature = "gpkg")]
+    fn effective_mmap_size(&self, file_len: u64) -> Option<u64> {
+        match self.mmap_size {
+            Some(size) => Some(size),
+            None if file_len >= AUTO_MMAP_THRESHOLD => Some(file_len.min(AUTO_MMAP_MAX)),
+            None => None,
+        }
     }
 }
 
-/// Parse GeoPackage WKB (with header) to geo Geometry.
+
+/// Checks that a user-provided WHERE clause is a single expression.
 ///
-/// GeoPackage WKB format extends ISO WKB with a specific header:
-/// - Magic number: "GP" (2 bytes)
-/// - Version: 1 byte
-/// - Flags: 1 byte (includes envelope type and byte order)
-/// - SRS ID: 4 bytes
-/// - Optional envelope data
-/// - Standard ISO WKB
-fn parse_gpkg_wkb(data: &[u8]) -> Option<Geometry<f64>> {
-    // GeoPackage uses a header before standard WKB
-    // Header: magic (2 bytes), version (1 byte), flags (1 byte), srs_id (4 bytes)
-    // Then optional envelope, then standard WKB
-
-    if data.len() < 8 {
-        return None;
+/// The clause is inserted into the feature query, so statement separators,
+/// comments and unbalanced parentheses or quotes (which could escape the
+/// surrounding expression) are rejected. Column names and syntax are checked
+/// by SQLite when the query runs.
+pub fn validate_where_clause(clause: &str) -> Result<()> {
+    let invalid = |msg: &str| Err(GpkgError::InvalidWhereClause(format!("{}: {}", msg, clause)));
+
+    if clause.trim().is_empty() {
+        return invalid("empty clause");
     }
 
-    // Check magic number "GP"
-    if data[0] != 0x47 || data[1] != 0x50 {
-        // Try parsing as standard WKB
-        return wkb::wkb_to_geom(&mut std::io::Cursor::new(data)).ok();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    let mut chars = clause.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            if c == q {
+                // A doubled quote is an escaped quote inside the literal
+                if chars.peek() == Some(&q) {
+                    chars.next();
+                } else {
+                    quote = None;
+                }
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' | '`' => quote = Some(c),
+            '[' => quote = Some(']'),
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth < 0 {
+                    return invalid("unbalanced parentheses");
+                }
+            }
+            ';' => return invalid("statement separators are not allowed"),
+            '-' if chars.peek() == Some(&'-') => return invalid("comments are not allowed"),
+            '/' if chars.peek() == Some(&'*') => return invalid("comments are not allowed"),
+            _ => {}
+        }
     }
 
-    let flags = data[3];
-    let envelope_indicator = (flags >> 1) & 0x07;
-
-    // Calculate envelope size based on indicator
-    let envelope_size = match envelope_indicator {
-        0 => 0,
-        1 => 32, // 4 doubles (minx, maxx, miny, maxy)
-        2 => 48, // 6 doubles (+ minz, maxz)
-        3 => 48, // 6 doubles (+ minm, maxm)
-        4 => 64, // 8 doubles (all)
-        _ => return None,
-    };
-
-    let wkb_start = 8 + envelope_size;
-    if data.len() <= wkb_start {
-        return None;
+    if quote.is_some() {
+        return invalid("unterminated quote");
     }
-
-    wkb::wkb_to_geom(&mut std::io::Cursor::new(&data[wkb_start..])).ok()
+    if depth != 0 {
+        return invalid("unbalanced parentheses");
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    // Note: Integration tests with real GPKG file in tests/ directory
-
     #[test]
     fn test_layer_info_creation() {
         let layer = LayerInfo {
@@ -284,85 +333,109 @@ mod tests {
     }
 
     #[test]
-    fn test_reproject_identity() {
-        use geo::{coord, LineString, Polygon};
-
-        // Test that reprojection with identity proj works
-        let poly = Polygon::new(
-            LineString::from(vec![
-                coord! { x: 0.0, y: 0.0 },
-                coord! { x: 1.0, y: 0.0 },
-                coord! { x: 1.0, y: 1.0 },
-                coord! { x: 0.0, y: 1.0 },
-                coord! { x: 0.0, y: 0.0 },
-            ]),
-            vec![],
-        );
-        let mp = MultiPolygon::new(vec![poly]);
-
-        // Create an identity-like projection (WGS84 to WGS84)
-        if let Ok(proj) = Proj::new_known_crs("EPSG:4326", "EPSG:4326", None) {
-            let result = reproject_multipolygon(&mp, &proj);
-            assert!(result.is_some());
-        }
+    fn test_layer_summary_json() {
+        let summary = LayerSummary {
+            name: "parcels".to_string(),
+            geometry_type: "MULTIPOLYGON".to_string(),
+            srs_id: 2154,
+            srs: Some("EPSG:2154".to_string()),
+            feature_count: 12,
+            extent: Some((100.0, 6000.0, 200.0, 6100.0)),
+            wgs84_extent: None,
+        };
+        let json = summary.to_json();
+        assert_eq!(json["polygon"], true);
+        assert_eq!(json["srs"], "EPSG:2154");
+        assert_eq!(json["extent"], json!([100.0, 6000.0, 200.0, 6100.0]));
+        assert!(json["wgs84_extent"].is_null());
+
+        let points = LayerSummary {
+            geometry_type: "POINT".to_string(),
+            ..summary
+        };
+        assert!(!points.is_polygon());
     }
 
     #[test]
-    fn test_reproject_bbox_to_wgs84() {
-        // Test reprojection from WGS84 to WGS84 (should be identity-like)
-        let result = reproject_bbox_to_wgs84(-4.5, 48.0, -4.0, 48.5, "EPSG:4326");
-        assert!(result.is_some());
-        let (min_lon, min_lat, max_lon, max_lat) = result.unwrap();
-        assert!((min_lon - (-4.5)).abs() < 0.001);
-        assert!((min_lat - 48.0).abs() < 0.001);
-        assert!((max_lon - (-4.0)).abs() < 0.001);
-        assert!((max_lat - 48.5).abs() < 0.001);
+    fn test_tile_layer_matrices() {
+        // A 256 px Web Mercator-like pyramid over a 1024 x 1024 unit square
+        let matrix = |zoom_level: i64| {
+            let tiles = 1 << zoom_level;
+            TileMatrix {
+                zoom_level,
+                matrix_width: tiles,
+                matrix_height: tiles,
+                tile_width: 256,
+                tile_height: 256,
+                pixel_x_size: 4.0 / tiles as f64,
+                pixel_y_size: 4.0 / tiles as f64,
+            }
+        };
+        let layer = TileLayerInfo {
+            name: "ortho".to_string(),
+            srs_id: 3857,
+            extent: (0.0, 0.0, 1024.0, 1024.0),
+            matrices: (0..3).map(matrix).collect(),
+        };
+        assert_eq!(layer.matrix_for(4.0).unwrap().zoom_level, 0);
+        assert_eq!(layer.matrix_for(1.5).unwrap().zoom_level, 2);
+        assert_eq!(layer.matrix_for(3.0).unwrap().zoom_level, 1);
+        assert_eq!(layer.matrix_for(0.1).unwrap().zoom_level, 2);
+
+        // Rows count down from the top of the extent
+        let zoom_2 = matrix(2);
+        assert_eq!(layer.tile_range(&zoom_2, (0.0, 0.0, 1024.0, 1024.0)), Some(((0, 3), (0, 3))));
+        assert_eq!(layer.tile_range(&zoom_2, (300.0, 10.0, 400.0, 200.0)), Some(((1, 1), (3, 3))));
+        assert_eq!(layer.tile_range(&zoom_2, (2000.0, 0.0, 3000.0, 10.0)), None);
+    }
+
+    #[test]
+    fn test_extension_support() {
+        let extension = |table: Option<&str>, name: &str, scope: &str| Extension {
+            table_name: table.map(str::to_string),
+            column_name: None,
+            extension_name: name.to_string(),
+            scope: scope.to_string(),
+        };
+        let rtree = extension(Some("Parcels"), "gpkg_rtree_index", "write-only");
+        assert!(rtree.applies_to("parcels"));
+        assert!(!rtree.applies_to("roads"));
+        assert!(!rtree.is_unsupported());
+        assert!(!extension(Some("parcels"), "GPKG_GEOM_CURVEPOLYGON", "read-write").is_unsupported());
+
+        let coverage = extension(Some("dem"), "gpkg_2d_gridded_coverage", "read-write");
+        assert!(coverage.is_unsupported());
+        assert!(!extension(None, "vendor_cache", "write-only").is_unsupported());
+        assert!(extension(None, "vendor_encryption", "read-write").applies_to("parcels"));
     }
 
     #[test]
-    fn test_reproject_bbox_from_lambert93() {
-        // Test reprojection from EPSG:2154 (Lambert-93) to WGS84
-        // Using approximate Lambert-93 coordinates for Brittany, France
-        // These coords are approximate - main test is that reprojection works
-        // and returns valid ordered bbox values
-        let result = reproject_bbox_to_wgs84(860000.0, 6250000.0, 880000.0, 6280000.0, "EPSG:2154");
-        assert!(result.is_some());
-        let (min_lon, min_lat, max_lon, max_lat) = result.unwrap();
-        // Check that we got valid values (not NaN/Inf)
-        assert!(!min_lon.is_nan() && !min_lon.is_infinite());
-        assert!(!min_lat.is_nan() && !min_lat.is_infinite());
-        assert!(!max_lon.is_nan() && !max_lon.is_infinite());
-        assert!(!max_lat.is_nan() && !max_lat.is_infinite());
-        // Check ordering
-        assert!(
-            min_lon < max_lon,
-            "min_lon {} should be < max_lon {}",
-            min_lon,
-            max_lon
-        );
-        assert!(
-            min_lat < max_lat,
-            "min_lat {} should be < max_lat {}",
-            min_lat,
-            max_lat
-        );
-        // Check values are in plausible range for France (roughly -10 to 10 lon, 40 to 52 lat)
-        assert!(
-            min_lon > -10.0 && min_lon < 10.0,
-            "min_lon {} should be in France",
-            min_lon
-        );
-        assert!(
-            min_lat > 40.0 && min_lat < 52.0,
-            "min_lat {} should be in France",
-            min_lat
-        );
+    fn test_validate_where_clause() {
+        assert!(validate_where_clause("population > 1000").is_ok());
+        assert!(validate_where_clause("name = 'O''Brien; (x' AND (a OR b)").is_ok());
+        assert!(validate_where_clause("\"odd;col\" IS NOT NULL").is_ok());
+
+        assert!(validate_where_clause("  ").is_err());
+        assert!(validate_where_clause("1; DROP TABLE parcels").is_err());
+        assert!(validate_where_clause("1) OR (1").is_err());
+        assert!(validate_where_clause("(a = 1").is_err());
+        assert!(validate_where_clause("name = 'open").is_err());
+        assert!(validate_where_clause("1 -- comment").is_err());
+        assert!(validate_where_clause("1 /* comment */").is_err());
     }
 
     #[test]
-    fn test_reproject_bbox_invalid_crs() {
-        // Test with invalid CRS - should return None
-        let result = reproject_bbox_to_wgs84(0.0, 0.0, 1.0, 1.0, "INVALID:CRS");
-        assert!(result.is_none());
+    #[cfg(feature = "gpkg")]
+    fn test_effective_mmap_size() {
+        let auto = SqlitePragmas::default();
+        assert_eq!(auto.effective_mmap_size(1024), None);
+        assert_eq!(auto.effective_mmap_size(AUTO_MMAP_THRESHOLD), Some(AUTO_MMAP_THRESHOLD));
+        assert_eq!(auto.effective_mmap_size(u64::MAX), Some(AUTO_MMAP_MAX));
+
+        let disabled = SqlitePragmas {
+            mmap_size: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(disabled.effective_mmap_size(u64::MAX), Some(0));
     }
 }
@@ -0,0 +1,246 @@
+//! On-disk cache of reprojected geometries (`--reprojection-cache`).
+//!
+//! Reprojection dominates repeated renders of a layer stored in another CRS
+//! than WGS84. The WGS84 geometries of the features read are saved by FID,
+//! in one file per GeoPackage and layer, and reused by later runs as long as
+//! the GeoPackage file (size and modification time), the SRS definition and
+//! the curve tolerance are unchanged. Features missing from the cache (read
+//! for another extent or filter) are reprojected and added to it.
+
+use geo::{Coord, LineString, MultiPolygon, Polygon};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::error::Result;
+use crate::logger;
+
+/// First bytes of a cache file, with the version of its layout.
+const MAGIC: &[u8; 16] = b"gpkg-to-png-rc01";
+
+/// WGS84 geometries of the features of one layer, by FID.
+pub(super) struct ReprojectionCache {
+    path: PathBuf,
+    /// What the geometries were computed from; a different key empties the cache.
+    key: String,
+    geometries: HashMap<i64, MultiPolygon<f64>>,
+    /// Number of geometries added since the file was read.
+    added: usize,
+}
+
+impl ReprojectionCache {
+    /// Opens the cache of `layer` of the GeoPackage `gpkg` in `dir`.
+    ///
+    /// A missing or unreadable file, or one made from another version of the
+    /// GeoPackage, SRS or tolerance, gives an empty cache.
+    pub fn open(dir: &Path, gpkg: &Path, layer: &str, srs_def: &str, curve_tolerance: f64) -> Result<Self> {
+        let gpkg = gpkg.canonicalize()?;
+        let metadata = std::fs::metadata(&gpkg)?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos());
+        let key = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            gpkg.display(),
+            layer,
+            metadata.len(),
+            modified,
+            srs_def,
+            curve_tolerance
+        );
+        let name = Sha256::digest(format!("{}\n{}", gpkg.display(), layer).as_bytes());
+        let path = dir.join(format!("{}.cache", hex::encode(&name[..16])));
+
+        let geometries = match File::open(&path) {
+            Ok(file) => match read_entries(&mut BufReader::new(file), &key) {
+                Ok(Some(geometries)) => {
+                    logger::debug(&format!(
+                        "Layer {}: {} reprojected geometries read from {}",
+                        layer,
+                        geometries.len(),
+                        path.display()
+                    ));
+                    geometries
+                }
+                Ok(None) => {
+                    logger::debug(&format!("Layer {}: stale reprojection cache {}", layer, path.display()));
+                    HashMap::new()
+                }
+                Err(e) => {
+                    logger::debug(&format!("Layer {}: unreadable reprojection cache {}: {}", layer, path.display(), e));
+                    HashMap::new()
+                }
+            },
+            Err(_) => HashMap::new(),
+        };
+        Ok(Self {
+            path,
+            key,
+            geometries,
+            added: 0,
+        })
+    }
+
+    /// Returns the WGS84 geometry of feature `fid`, if cached.
+    pub fn get(&self, fid: i64) -> Option<&MultiPolygon<f64>> {
+        self.geometries.get(&fid)
+    }
+
+    /// Adds the WGS84 geometry of feature `fid`.
+    pub fn insert(&mut self, fid: i64, geometry: MultiPolygon<f64>) {
+        self.geometries.insert(fid, geometry);
+        self.added += 1;
+    }
+
+    /// Returns where the cache is stored.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the cache back when geometries were added; returns whether it did.
+    ///
+    /// The file is written with a `.part` suffix and renamed once complete.
+    pub fn save(&self) -> Result<bool> {
+        if self.added == 0 {
+            return Ok(false);
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let part = self.path.with_extension("cache.part");
+        let written = File::create(&part)
+            .map_err(Into::into)
+            .and_then(|file| write_entries(&mut BufWriter::new(file), &self.key, &self.geometries));
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&part);
+            return Err(e);
+        }
+        std::fs::rename(&part, &self.path)?;
+        Ok(true)
+    }
+}
+
+fn write_entries<W: Write>(out: &mut W, key: &str, geometries: &HashMap<i64, MultiPolygon<f64>>) -> Result<()> {
+    out.write_all(MAGIC)?;
+    write_u32(out, key.len() as u32)?;
+    out.write_all(key.as_bytes())?;
+    out.write_all(&(geometries.len() as u64).to_le_bytes())?;
+    for (fid, geometry) in geometries {
+        out.write_all(&fid.to_le_bytes())?;
+        write_u32(out, geometry.0.len() as u32)?;
+        for polygon in geometry {
+            write_u32(out, 1 + polygon.interiors().len() as u32)?;
+            for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+                write_u32(out, ring.0.len() as u32)?;
+                for c in &ring.0 {
+                    out.write_all(&c.x.to_le_bytes())?;
+                    out.write_all(&c.y.to_le_bytes())?;
+                }
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Reads the geometries of a cache file, or None when it was made for another key.
+fn read_entries<R: Read>(input: &mut R, key: &str) -> std::io::Result<Option<HashMap<i64, MultiPolygon<f64>>>> {
+    let mut magic = [0; 16];
+    input.read_exact(&mut magic)?;
+    let mut stored = vec![0; read_u32(input)? as usize];
+    input.read_exact(&mut stored)?;
+    if &magic != MAGIC || stored != key.as_bytes() {
+        return Ok(None);
+    }
+
+    let count = read_u64(input)?;
+    let mut geometries = HashMap::new();
+    for _ in 0..count {
+        let fid = read_u64(input)? as i64;
+        let polygons = (0..read_u32(input)?)
+            .map(|_| {
+                let mut rings = (0..read_u32(input)?)
+                    .map(|_| {
+                        let coords = (0..read_u32(input)?)
+                            .map(|_| {
+                                let x = f64::from_bits(read_u64(input)?);
+                                let y = f64::from_bits(read_u64(input)?);
+                                Ok(Coord { x, y })
+                            })
+                            .collect::<std::io::Result<Vec<_>>>()?;
+                        Ok(LineString(coords))
+                    })
+                    .collect::<std::io::Result<Vec<_>>>()?;
+                if rings.is_empty() {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "polygon without rings"));
+                }
+                let exterior = rings.remove(0);
+                Ok(Polygon::new(exterior, rings))
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        geometries.insert(fid, MultiPolygon(polygons));
+    }
+    Ok(Some(geometries))
+}
+
+fn write_u32<W: Write>(out: &mut W, value: u32) -> std::io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+fn read_u32<R: Read>(input: &mut R) -> std::io::Result<u32> {
+    let mut bytes = [0; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(input: &mut R) -> std::io::Result<u64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::polygon;
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let gpkg = dir.path().join("city.gpkg");
+        std::fs::write(&gpkg, b"data").unwrap();
+        let caches = dir.path().join("cache");
+        let square = polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0), (x: 0.0, y: 0.0)];
+        let holed = Polygon::new(square.exterior().clone(), vec![square.exterior().clone()]);
+
+        let mut cache = ReprojectionCache::open(&caches, &gpkg, "roads", "EPSG:2154", 4.0).unwrap();
+        assert!(cache.get(7).is_none());
+        assert!(!cache.save().unwrap());
+        cache.insert(7, MultiPolygon(vec![square.clone(), holed.clone()]));
+        assert!(cache.save().unwrap());
+        assert!(cache.path().starts_with(&caches));
+
+        let cache = ReprojectionCache::open(&caches, &gpkg, "roads", "EPSG:2154", 4.0).unwrap();
+        assert_eq!(cache.get(7), Some(&MultiPolygon(vec![square, holed])));
+        // Another layer, SRS or tolerance does not reuse the geometries
+        assert!(ReprojectionCache::open(&caches, &gpkg, "rivers", "EPSG:2154", 4.0).unwrap().get(7).is_none());
+        assert!(ReprojectionCache::open(&caches, &gpkg, "roads", "EPSG:3857", 4.0).unwrap().get(7).is_none());
+        assert!(ReprojectionCache::open(&caches, &gpkg, "roads", "EPSG:2154", 1.0).unwrap().get(7).is_none());
+
+        // Nor does a modified GeoPackage
+        std::fs::write(&gpkg, b"more data").unwrap();
+        assert!(ReprojectionCache::open(&caches, &gpkg, "roads", "EPSG:2154", 4.0).unwrap().get(7).is_none());
+
+        // A truncated file is ignored
+        let path = cache.path().to_path_buf();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 3]).unwrap();
+        std::fs::write(&gpkg, b"data").unwrap();
+        let _ = ReprojectionCache::open(&caches, &gpkg, "roads", "EPSG:2154", 4.0).unwrap();
+    }
+}
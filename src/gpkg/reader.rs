@@ -0,0 +1,1468 @@
+//! SQLite-backed GeoPackage reader.
+
+use futures_util::TryStreamExt;
+use geo::{Geometry, MultiPolygon};
+use rayon::prelude::*;
+use sqlx::query::Query;
+use sqlx::sqlite::{Sqlite, SqliteArguments, SqliteConnectOptions, SqlitePool};
+use sqlx::{Row, TypeInfo, ValueRef};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::cache::ReprojectionCache;
+use super::{
+    wkb, Extension, Feature, FeatureFilter, FidSelection, LayerInfo, LayerSummary, SqlitePragmas, TileLayerInfo, TileMatrix,
+    DEFAULT_CURVE_TOLERANCE,
+};
+use crate::error::{GpkgError, Result};
+use crate::logger;
+use crate::math::Bbox;
+use crate::antimeridian;
+use crate::raster::TileMosaic;
+use crate::reproject::{
+    check_operation, reproject_bbox_from_wgs84, reproject_bbox_to_wgs84, reproject_multipolygon, with_thread_transformer,
+    Backend, Transformer,
+};
+use crate::style::AttributeValue;
+use crate::warning::{self, WarningCode};
+
+/// Maximum number of features held in memory between reading and rendering.
+pub const READ_CHUNK_SIZE: usize = 4096;
+
+/// Read GeoPackage and extract polygon layers
+///
+/// Clones share the same connection pool.
+#[derive(Clone)]
+pub struct GpkgReader {
+    pool: SqlitePool,
+    path: PathBuf,
+    /// Largest angle, in degrees, spanned by one segment of a linearized arc.
+    curve_tolerance: f64,
+    /// Directory of the on-disk cache of reprojected geometries, if any.
+    reprojection_cache: Option<PathBuf>,
+}
+
+impl GpkgReader {
+    /// Open a GeoPackage file with the given connection pragmas
+    pub async fn open(path: &Path, pragmas: &SqlitePragmas) -> Result<Self> {
+        if !path.exists() {
+            return Err(GpkgError::FileNotFound(path.display().to_string()));
+        }
+
+        let mut options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+            .map_err(GpkgError::Database)?
+            .read_only(true);
+
+        let file_len = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        if let Some(mmap_size) = pragmas.effective_mmap_size(file_len) {
+            logger::debug(&format!("SQLite mmap_size: {} bytes", mmap_size));
+            options = options.pragma("mmap_size", mmap_size.to_string());
+        }
+        if let Some(kib) = pragmas.cache_size_kib {
+            // Negative values are interpreted by SQLite as KiB instead of pages
+            options = options.pragma("cache_size", format!("-{}", kib));
+        }
+        if let Some(temp_store) = pragmas.temp_store {
+            options = options.pragma("temp_store", temp_store.pragma_value());
+        }
+
+        let pool = SqlitePool::connect_with(options)
+            .await
+            .map_err(GpkgError::Database)?;
+
+        Ok(Self {
+            pool,
+            path: path.to_path_buf(),
+            curve_tolerance: DEFAULT_CURVE_TOLERANCE,
+            reprojection_cache: None,
+        })
+    }
+
+    /// Linearize arcs of curved geometries with segments spanning at most
+    /// `degrees` instead of [`DEFAULT_CURVE_TOLERANCE`].
+    pub fn set_curve_tolerance(&mut self, degrees: f64) {
+        self.curve_tolerance = degrees;
+    }
+
+    /// Keep the WGS84 geometries of layers in another CRS in a cache file
+    /// under `dir`, reused by later runs until the GeoPackage changes.
+    pub fn set_reprojection_cache(&mut self, dir: Option<PathBuf>) {
+        self.reprojection_cache = dir;
+    }
+
+    /// List all polygon/multipolygon layers
+    pub async fn list_polygon_layers(&self) -> Result<Vec<LayerInfo>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT c.table_name, g.column_name, g.srs_id
+            FROM gpkg_contents c
+            JOIN gpkg_geometry_columns g ON c.table_name = g.table_name
+            WHERE c.data_type = 'features'
+            AND (g.geometry_type_name LIKE '%POLYGON%' OR g.geometry_type_name LIKE '%polygon%'
+                 OR g.geometry_type_name LIKE '%SURFACE%')
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let layers: Vec<LayerInfo> = rows
+            .iter()
+            .map(|row| LayerInfo {
+                name: row.get("table_name"),
+                geometry_column: row.get("column_name"),
+                srs_id: row.get("srs_id"),
+            })
+            .collect();
+
+        Ok(layers)
+    }
+
+    /// List the rows of `gpkg_extensions` (empty when the table is absent)
+    pub async fn list_extensions(&self) -> Result<Vec<Extension>> {
+        let exists: i64 =
+            sqlx::query("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'gpkg_extensions'")
+                .fetch_one(&self.pool)
+                .await?
+                .get(0);
+        if exists == 0 {
+            return Ok(Vec::new());
+        }
+        let rows = sqlx::query("SELECT table_name, column_name, extension_name, scope FROM gpkg_extensions")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| Extension {
+                table_name: row.get("table_name"),
+                column_name: row.get("column_name"),
+                extension_name: row.get("extension_name"),
+                scope: row.get("scope"),
+            })
+            .collect())
+    }
+
+    /// List all raster layers stored as tile pyramids, with their zoom levels
+    pub async fn list_tile_layers(&self) -> Result<Vec<TileLayerInfo>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT c.table_name, s.srs_id, s.min_x, s.min_y, s.max_x, s.max_y
+            FROM gpkg_contents c
+            JOIN gpkg_tile_matrix_set s ON c.table_name = s.table_name
+            WHERE c.data_type = 'tiles'
+            ORDER BY c.table_name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut layers = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let name: String = row.get("table_name");
+            let matrices = sqlx::query(
+                r#"
+                SELECT zoom_level, matrix_width, matrix_height, tile_width, tile_height,
+                       pixel_x_size, pixel_y_size
+                FROM gpkg_tile_matrix
+                WHERE table_name = ?
+                ORDER BY zoom_level
+                "#,
+            )
+            .bind(&name)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|m| TileMatrix {
+                zoom_level: m.get("zoom_level"),
+                matrix_width: m.get("matrix_width"),
+                matrix_height: m.get("matrix_height"),
+                tile_width: m.get::<i64, _>("tile_width") as u32,
+                tile_height: m.get::<i64, _>("tile_height") as u32,
+                pixel_x_size: m.get("pixel_x_size"),
+                pixel_y_size: m.get("pixel_y_size"),
+            })
+            .collect();
+            layers.push(TileLayerInfo {
+                name,
+                srs_id: row.get("srs_id"),
+                extent: (row.get("min_x"), row.get("min_y"), row.get("max_x"), row.get("max_y")),
+                matrices,
+            });
+        }
+        Ok(layers)
+    }
+
+    /// Read and decode the `matrix` tiles of a tile layer within the given
+    /// columns and rows (inclusive).
+    ///
+    /// Tiles are decoded in parallel; those that are not a supported image
+    /// (PNG, JPEG, WebP) are skipped with a warning.
+    pub async fn read_tile_mosaic(
+        &self,
+        layer: &TileLayerInfo,
+        matrix: &TileMatrix,
+        (first_column, last_column): (i64, i64),
+        (first_row, last_row): (i64, i64),
+    ) -> Result<TileMosaic> {
+        let query = format!(
+            "SELECT tile_column, tile_row, tile_data FROM {} \
+             WHERE zoom_level = ? AND tile_column BETWEEN ? AND ? AND tile_row BETWEEN ? AND ?",
+            quote_ident(&layer.name)
+        );
+        let rows = sqlx::query(&query)
+            .bind(matrix.zoom_level)
+            .bind(first_column)
+            .bind(last_column)
+            .bind(first_row)
+            .bind(last_row)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let total = rows.len();
+        let decoded: Vec<(i64, i64, image::RgbaImage)> = rows
+            .par_iter()
+            .filter_map(|row| {
+                let data: &[u8] = row.get("tile_data");
+                let tile = image::load_from_memory(data).ok()?.into_rgba8();
+                Some((row.get("tile_column"), row.get("tile_row"), tile))
+            })
+            .collect();
+        if decoded.len() < total {
+            warning::emit(
+                WarningCode::TileSkippedInvalidImage,
+                &format!(
+                    "Layer {}: {} tile(s) skipped (undecodable image)",
+                    layer.name,
+                    total - decoded.len()
+                ),
+            )?;
+        }
+
+        let mut mosaic = TileMosaic::new(layer, *matrix);
+        for (column, row, tile) in decoded {
+            mosaic.insert(column, row, tile);
+        }
+        Ok(mosaic)
+    }
+
+    /// Describe every feature layer, whatever its geometry type
+    pub async fn list_feature_layers(&self) -> Result<Vec<LayerSummary>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT c.table_name, g.column_name, g.geometry_type_name, g.srs_id,
+                   s.organization, s.organization_coordsys_id
+            FROM gpkg_contents c
+            JOIN gpkg_geometry_columns g ON c.table_name = g.table_name
+            LEFT JOIN gpkg_spatial_ref_sys s ON s.srs_id = g.srs_id
+            WHERE c.data_type = 'features'
+            ORDER BY c.table_name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut layers = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let layer = LayerInfo {
+                name: row.get("table_name"),
+                geometry_column: row.get("column_name"),
+                srs_id: row.get("srs_id"),
+            };
+            let srs = srs_name(row.get("organization"), row.get("organization_coordsys_id"));
+            layers.push(LayerSummary {
+                geometry_type: row.get("geometry_type_name"),
+                srs_id: layer.srs_id,
+                srs,
+                feature_count: self.count_features(&layer, &FeatureFilter::default()).await?,
+                extent: self.get_layer_bbox(&layer).await?,
+                wgs84_extent: self.get_layers_bbox_wgs84(std::slice::from_ref(&layer)).await?,
+                name: layer.name,
+            });
+        }
+        Ok(layers)
+    }
+
+    /// Returns the "ORGANIZATION:CODE" name of an SRS (e.g. "EPSG:2154"), or
+    /// None when it is undefined or not in the GeoPackage.
+    pub async fn get_srs_name(&self, srs_id: i32) -> Result<Option<String>> {
+        let row = sqlx::query(
+            "SELECT organization, organization_coordsys_id FROM gpkg_spatial_ref_sys WHERE srs_id = ?",
+        )
+        .bind(srs_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.and_then(|row| srs_name(row.get("organization"), row.get("organization_coordsys_id"))))
+    }
+
+    /// Builds the SELECT returning the primary key, the geometry column and the
+    /// `columns` of the features matching `filter`, with its positional parameters.
+    ///
+    /// When the filter carries a bbox and the layer has an RTree index
+    /// (`rtree_<table>_<column>`), only intersecting features are selected.
+    async fn feature_query(
+        &self,
+        layer: &LayerInfo,
+        filter: &FeatureFilter,
+        columns: &[String],
+    ) -> Result<(String, Vec<SqlValue>)> {
+        let pk = self.primary_key(layer).await?;
+        let mut selected = vec![
+            format!("t.{}", quote_ident(&pk)),
+            format!("t.{}", quote_ident(&layer.geometry_column)),
+        ];
+        for column in columns {
+            if !self.has_column(layer, column).await? {
+                return Err(GpkgError::ColumnNotFound(column.clone(), layer.name.clone()));
+            }
+            selected.push(format!("t.{}", quote_ident(column)));
+        }
+        let mut query = format!(
+            "SELECT {} FROM {} AS t",
+            selected.join(", "),
+            quote_ident(&layer.name)
+        );
+        let mut conditions: Vec<String> = Vec::new();
+        let mut binds: Vec<SqlValue> = Vec::new();
+
+        if let Some(bbox) = &filter.bbox {
+            if let Some(clause) = self.rtree_clause(layer, bbox).await? {
+                conditions.push(clause.sql);
+                binds.extend(clause.binds);
+            }
+        }
+        if let Some(fids) = &filter.fids {
+            let clause = self.fid_clause(layer, fids).await?;
+            conditions.push(clause.sql);
+            binds.extend(clause.binds);
+        }
+        conditions.extend(filter.where_clauses.iter().map(|c| format!("({})", c)));
+
+        if !conditions.is_empty() {
+            query.push_str(" WHERE ");
+            query.push_str(&conditions.join(" AND "));
+        }
+
+        if filter.limit.is_some() || filter.offset.is_some() {
+            // A stable order makes successive batches (--offset) disjoint
+            // SQLite needs a LIMIT for OFFSET; -1 means no limit
+            let limit = filter.limit.map_or(-1, |l| l.min(i64::MAX as u64) as i64);
+            query.push_str(&format!(
+                " ORDER BY t.{} LIMIT {} OFFSET {}",
+                quote_ident(&pk),
+                limit,
+                filter.offset.unwrap_or(0)
+            ));
+        }
+
+        Ok((query, binds))
+    }
+
+    /// Count the features matching `filter`, without reading their geometries
+    pub async fn count_features(&self, layer: &LayerInfo, filter: &FeatureFilter) -> Result<u64> {
+        let (query, binds) = self.feature_query(layer, filter, &[]).await?;
+        let query = format!("SELECT COUNT(*) FROM ({})", query);
+        let row = bind_all(sqlx::query(&query), binds)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| query_error(e, layer, filter))?;
+        let count: i64 = row.get(0);
+        Ok(count.max(0) as u64)
+    }
+
+    /// Total size in bytes of the geometry blobs of the features matching
+    /// `filter`, without reading them
+    pub async fn geometry_bytes(&self, layer: &LayerInfo, filter: &FeatureFilter) -> Result<u64> {
+        let (query, binds) = self.feature_query(layer, filter, &[]).await?;
+        let query = format!(
+            "SELECT TOTAL(LENGTH({})) FROM ({})",
+            quote_ident(&layer.geometry_column),
+            query
+        );
+        let row = bind_all(sqlx::query(&query), binds)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| query_error(e, layer, filter))?;
+        let bytes: f64 = row.get(0);
+        Ok(bytes.max(0.0) as u64)
+    }
+
+    /// Returns the smallest and largest numeric values of `column` among the
+    /// features matching `filter` (None if no value is numeric)
+    pub async fn value_range(
+        &self,
+        layer: &LayerInfo,
+        filter: &FeatureFilter,
+        column: &str,
+    ) -> Result<Option<(f64, f64)>> {
+        let (query, binds) = self.feature_query(layer, filter, &[column.to_string()]).await?;
+        let value = format!(
+            "CASE WHEN typeof({0}) IN ('integer', 'real') THEN CAST({0} AS REAL) END",
+            quote_ident(column)
+        );
+        let query = format!("SELECT MIN({0}), MAX({0}) FROM ({1})", value, query);
+        let row = bind_all(sqlx::query(&query), binds)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| query_error(e, layer, filter))?;
+        let (min, max): (Option<f64>, Option<f64>) = (row.get(0), row.get(1));
+        Ok(min.zip(max))
+    }
+
+    /// Returns the numeric values of `column` among the features matching
+    /// `filter`, skipping the others
+    pub async fn numeric_values(&self, layer: &LayerInfo, filter: &FeatureFilter, column: &str) -> Result<Vec<f64>> {
+        let (query, binds) = self.feature_query(layer, filter, &[column.to_string()]).await?;
+        let query = format!(
+            "SELECT CAST({0} AS REAL) FROM ({1}) WHERE typeof({0}) IN ('integer', 'real')",
+            quote_ident(column),
+            query
+        );
+        let rows = bind_all(sqlx::query(&query), binds)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| query_error(e, layer, filter))?;
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    /// Stream features from a specific layer, in the layer CRS, with the
+    /// values of `columns`.
+    ///
+    /// Rows are fetched and parsed incrementally, and handed to `on_chunk` in
+    /// batches of at most [`READ_CHUNK_SIZE`] features, so memory use does not
+    /// grow with the layer size. Skipped features are reported once, after the
+    /// whole layer has been read.
+    ///
+    /// With a `filter` bbox, features whose header envelope lies outside it
+    /// are dropped before their WKB is decoded.
+    pub async fn for_each_chunk<F>(
+        &self,
+        layer: &LayerInfo,
+        filter: &FeatureFilter,
+        columns: &[String],
+        mut on_chunk: F,
+    ) -> Result<()>
+    where
+        F: FnMut(Vec<Feature>) -> Result<()>,
+    {
+        let (query, binds) = self.feature_query(layer, filter, columns).await?;
+        let area = match &filter.bbox {
+            Some(bbox) if !bbox.crosses_antimeridian() => self.source_bbox(layer, bbox).await?,
+            _ => None,
+        };
+        let mut rows = bind_all(sqlx::query(&query), binds).fetch(&self.pool);
+
+        let mut chunk = Vec::with_capacity(READ_CHUNK_SIZE);
+        let mut outside = 0usize;
+        let mut invalid_wkb = 0usize;
+        let mut non_polygon = 0usize;
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .map_err(|e| query_error(e, layer, filter))?
+        {
+            let fid: i64 = row.get(0);
+            let wkb_data: &[u8] = row.get(1);
+
+            if let (Some(area), Some(envelope)) = (area, gpkg_envelope(wkb_data)) {
+                if !bbox_intersects(envelope, area) {
+                    outside += 1;
+                    continue;
+                }
+            }
+
+            // Skip GeoPackage header (first 8 bytes: magic, version, flags, srs_id, envelope)
+            // GeoPackage WKB has a header before the standard WKB
+            let geometry = match parse_gpkg_wkb(wkb_data, self.curve_tolerance) {
+                Some(Geometry::Polygon(p)) => MultiPolygon::new(vec![p]),
+                Some(Geometry::MultiPolygon(mp)) => mp,
+                Some(_) => {
+                    non_polygon += 1; // Skip non-polygon geometries
+                    continue;
+                }
+                None => {
+                    invalid_wkb += 1;
+                    continue;
+                }
+            };
+            let attributes = (2..columns.len() + 2).map(|i| attribute_value(&row, i)).collect();
+            chunk.push(Feature {
+                fid,
+                geometry,
+                attributes,
+            });
+
+            if chunk.len() == READ_CHUNK_SIZE {
+                on_chunk(std::mem::replace(&mut chunk, Vec::with_capacity(READ_CHUNK_SIZE)))?;
+            }
+        }
+        if !chunk.is_empty() {
+            on_chunk(chunk)?;
+        }
+
+        if outside > 0 {
+            logger::debug(&format!(
+                "Layer {}: {} feature(s) outside the bbox skipped (header envelope)",
+                layer.name, outside
+            ));
+        }
+        if invalid_wkb > 0 {
+            warning::emit(
+                WarningCode::FeatureSkippedInvalidWkb,
+                &format!(
+                    "Layer {}: {} feature(s) skipped (invalid WKB)",
+                    layer.name, invalid_wkb
+                ),
+            )?;
+        }
+        if non_polygon > 0 {
+            warning::emit(
+                WarningCode::FeatureSkippedNonPolygon,
+                &format!(
+                    "Layer {}: {} feature(s) skipped (non-polygon geometry)",
+                    layer.name, non_polygon
+                ),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Read all geometries from a specific layer, in the layer CRS
+    #[cfg(test)]
+    pub async fn read_geometries(
+        &self,
+        layer: &LayerInfo,
+        filter: &FeatureFilter,
+    ) -> Result<Vec<MultiPolygon<f64>>> {
+        let mut geometries = Vec::new();
+        self.for_each_chunk(layer, filter, &[], |chunk| {
+            geometries.extend(chunk.into_iter().map(|f| f.geometry));
+            Ok(())
+        })
+        .await?;
+        Ok(geometries)
+    }
+
+    /// Builds the RTree filter clause for a layer, if an index is available.
+    ///
+    /// Returns `None` (full scan) when the layer has no RTree index, the bbox
+    /// crosses the antimeridian, or the bbox can't be expressed in the layer CRS.
+    async fn rtree_clause(&self, layer: &LayerInfo, bbox: &Bbox) -> Result<Option<SqlClause>> {
+        let rtree = format!("rtree_{}_{}", layer.name, layer.geometry_column);
+        let exists: i64 =
+            sqlx::query("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+                .bind(&rtree)
+                .fetch_one(&self.pool)
+                .await?
+                .get(0);
+        if exists == 0 {
+            logger::debug(&format!("Layer {}: no RTree index, full scan", layer.name));
+            return Ok(None);
+        }
+
+        if bbox.crosses_antimeridian() {
+            logger::debug(&format!(
+                "Layer {}: bbox crosses the antimeridian, RTree index not used",
+                layer.name
+            ));
+            return Ok(None);
+        }
+
+        let Some((min_x, min_y, max_x, max_y)) = self.source_bbox(layer, bbox).await? else {
+            logger::debug(&format!(
+                "Layer {}: could not reproject bbox to layer CRS, full scan",
+                layer.name
+            ));
+            return Ok(None);
+        };
+
+        let pk = self.primary_key(layer).await?;
+        logger::debug(&format!("Layer {}: using RTree index {}", layer.name, rtree));
+
+        Ok(Some(SqlClause {
+            sql: format!(
+                "t.{} IN (SELECT id FROM {} WHERE minx <= ? AND maxx >= ? AND miny <= ? AND maxy >= ?)",
+                quote_ident(&pk),
+                quote_ident(&rtree)
+            ),
+            binds: [max_x, min_x, max_y, min_y].into_iter().map(SqlValue::Real).collect(),
+        }))
+    }
+
+    /// Converts a WGS84 bbox to the layer CRS, as (min_x, min_y, max_x, max_y).
+    ///
+    /// Returns `None` when the bbox can't be expressed in the layer CRS.
+    async fn source_bbox(&self, layer: &LayerInfo, bbox: &Bbox) -> Result<Option<(f64, f64, f64, f64)>> {
+        if layer.srs_id == 4326 {
+            return Ok(Some((bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat)));
+        }
+        let srs_def = self.get_srs_definition(layer.srs_id).await?;
+        Ok(reproject_bbox_from_wgs84(bbox, &srs_def))
+    }
+
+    /// Builds the `IN (...)` clause selecting features by ID.
+    async fn fid_clause(&self, layer: &LayerInfo, fids: &FidSelection) -> Result<SqlClause> {
+        let (column, binds) = match &fids.column {
+            Some(column) => {
+                if !self.has_column(layer, column).await? {
+                    return Err(GpkgError::ColumnNotFound(column.clone(), layer.name.clone()));
+                }
+                // Text values are converted by SQLite according to the column affinity
+                let binds = fids.values.iter().cloned().map(SqlValue::Text).collect();
+                (column.clone(), binds)
+            }
+            None => {
+                let binds = fids
+                    .values
+                    .iter()
+                    .map(|v| {
+                        v.parse().map(SqlValue::Integer).map_err(|_| {
+                            GpkgError::InvalidFormatOption(format!(
+                                "--fid values must be integers without --fid-column, got {}",
+                                v
+                            ))
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                (self.primary_key(layer).await?, binds)
+            }
+        };
+
+        let placeholders = vec!["?"; fids.values.len()].join(", ");
+        Ok(SqlClause {
+            sql: format!("t.{} IN ({})", quote_ident(&column), placeholders),
+            binds,
+        })
+    }
+
+    /// Returns true if the layer table has the given column.
+    async fn has_column(&self, layer: &LayerInfo, column: &str) -> Result<bool> {
+        let count: i64 = sqlx::query("SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?")
+            .bind(&layer.name)
+            .bind(column)
+            .fetch_one(&self.pool)
+            .await?
+            .get(0);
+        Ok(count > 0)
+    }
+
+    /// Returns the non-geometry columns of a layer, without its primary key,
+    /// in table order.
+    pub async fn attribute_columns(&self, layer: &LayerInfo) -> Result<Vec<String>> {
+        let rows = sqlx::query("SELECT name FROM pragma_table_info(?) WHERE pk = 0 AND name <> ? ORDER BY cid")
+            .bind(&layer.name)
+            .bind(&layer.geometry_column)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(|r| r.get::<String, _>("name")).collect())
+    }
+
+    /// Returns the integer primary key column of a layer (`rowid` if none is declared).
+    async fn primary_key(&self, layer: &LayerInfo) -> Result<String> {
+        let row = sqlx::query("SELECT name FROM pragma_table_info(?) WHERE pk > 0 ORDER BY pk")
+            .bind(&layer.name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row
+            .map(|r| r.get::<String, _>("name"))
+            .unwrap_or_else(|| "rowid".to_string()))
+    }
+
+    /// Stream every polygon feature of a layer with its primary key, in the layer CRS.
+    ///
+    /// Empty, invalid and non-polygon geometries are skipped.
+    pub async fn for_each_feature<F>(&self, layer: &LayerInfo, mut on_feature: F) -> Result<()>
+    where
+        F: FnMut(i64, MultiPolygon<f64>),
+    {
+        let pk = self.primary_key(layer).await?;
+        let query = format!(
+            "SELECT t.{}, t.{} FROM {} AS t",
+            quote_ident(&pk),
+            quote_ident(&layer.geometry_column),
+            quote_ident(&layer.name)
+        );
+        let mut rows = sqlx::query(&query).fetch(&self.pool);
+        while let Some(row) = rows.try_next().await? {
+            let fid: i64 = row.get(0);
+            let wkb_data: Option<&[u8]> = row.get(1);
+            match wkb_data.and_then(|data| parse_gpkg_wkb(data, self.curve_tolerance)) {
+                Some(Geometry::Polygon(p)) => on_feature(fid, MultiPolygon::new(vec![p])),
+                Some(Geometry::MultiPolygon(mp)) => on_feature(fid, mp),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Get SRS definition for a layer
+    pub async fn get_srs_definition(&self, srs_id: i32) -> Result<String> {
+        let row = sqlx::query("SELECT definition FROM gpkg_spatial_ref_sys WHERE srs_id = ?")
+            .bind(srs_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get("definition"))
+    }
+
+    /// Stream features reprojected to WGS84, chunk by chunk.
+    ///
+    /// Each chunk from [`Self::for_each_chunk`] is reprojected in parallel
+    /// with `rayon` before being handed to `on_chunk`. Each worker thread
+    /// keeps its own `proj` context, created once for the whole read. With a
+    /// reprojection cache, features found in it are not reprojected again.
+    pub async fn for_each_chunk_wgs84<F>(
+        &self,
+        layer: &LayerInfo,
+        filter: &FeatureFilter,
+        columns: &[String],
+        mut on_chunk: F,
+    ) -> Result<()>
+    where
+        F: FnMut(Vec<Feature>) -> Result<()>,
+    {
+        if layer.srs_id == 4326 {
+            return self.for_each_chunk(layer, filter, columns, on_chunk).await;
+        }
+
+        let srs_def = self.get_srs_definition(layer.srs_id).await?;
+
+        if let Ok(transformer) = Transformer::new(&srs_def, "EPSG:4326") {
+            if transformer.backend() == Backend::Pure {
+                warning::emit(
+                    WarningCode::ReprojectionApproximate,
+                    &format!(
+                        "Layer {}: reprojected with proj4rs (no datum grids, metre-level error possible)",
+                        layer.name
+                    ),
+                )?;
+            }
+            if let Some((min_x, min_y, max_x, max_y)) = self.get_layer_bbox(layer).await? {
+                let center = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+                check_operation(&transformer, center, &format!("Layer {}", layer.name))?;
+            }
+        }
+
+        let mut cache = match &self.reprojection_cache {
+            Some(dir) => Some(ReprojectionCache::open(
+                dir,
+                &self.path,
+                &layer.name,
+                &srs_def,
+                self.curve_tolerance,
+            )?),
+            None => None,
+        };
+
+        let mut failed = 0usize;
+        let mut cached = 0usize;
+        self.for_each_chunk(layer, filter, columns, |chunk| {
+            let total = chunk.len();
+            // Parallelize reprojection; the flag tells features reprojected now
+            let reprojected: Vec<(Feature, bool)> = chunk
+                .into_par_iter()
+                .filter_map(|feature| {
+                    if let Some(geometry) = cache.as_ref().and_then(|c| c.get(feature.fid)) {
+                        return Some((Feature { geometry: geometry.clone(), ..feature }, false));
+                    }
+                    // Transformer is not Sync: each thread reuses its own
+                    let geometry = with_thread_transformer(&srs_def, "EPSG:4326", |transformer| {
+                        reproject_multipolygon(&feature.geometry, transformer)
+                    })??;
+                    Some((Feature { geometry, ..feature }, true))
+                })
+                .collect();
+            failed += total - reprojected.len();
+            let reprojected: Vec<Feature> = reprojected
+                .into_iter()
+                .map(|(feature, new)| {
+                    match (&mut cache, new) {
+                        (Some(cache), true) => cache.insert(feature.fid, feature.geometry.clone()),
+                        _ => cached += 1,
+                    }
+                    feature
+                })
+                .collect();
+            if reprojected.is_empty() {
+                Ok(())
+            } else {
+                on_chunk(reprojected)
+            }
+        })
+        .await?;
+
+        if failed > 0 {
+            warning::emit(
+                WarningCode::FeatureSkippedReprojection,
+                &format!(
+                    "Layer {}: {} feature(s) skipped (reprojection to WGS84 failed)",
+                    layer.name, failed
+                ),
+            )?;
+        }
+
+        if let Some(cache) = cache {
+            logger::debug(&format!(
+                "Layer {}: {} geometries taken from the reprojection cache",
+                layer.name, cached
+            ));
+            if let Err(e) = cache.save() {
+                warning::emit(
+                    WarningCode::ReprojectionCacheUnavailable,
+                    &format!(
+                        "Layer {}: reprojection cache {} not written: {}",
+                        layer.name,
+                        cache.path().display(),
+                        e
+                    ),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read and reproject all geometries to WGS84
+    pub async fn read_geometries_wgs84(
+        &self,
+        layer: &LayerInfo,
+        filter: &FeatureFilter,
+    ) -> Result<Vec<MultiPolygon<f64>>> {
+        let mut geometries = Vec::new();
+        self.for_each_chunk_wgs84(layer, filter, &[], |chunk| {
+            geometries.extend(chunk.into_iter().map(|f| f.geometry));
+            Ok(())
+        })
+        .await?;
+        Ok(geometries)
+    }
+
+    /// Get the bounding box of a layer in source CRS from gpkg_contents
+    pub async fn get_layer_bbox(&self, layer: &LayerInfo) -> Result<Option<(f64, f64, f64, f64)>> {
+        let row = sqlx::query(
+            "SELECT min_x, min_y, max_x, max_y FROM gpkg_contents WHERE table_name = ?",
+        )
+        .bind(&layer.name)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let min_x: Option<f64> = row.get("min_x");
+        let min_y: Option<f64> = row.get("min_y");
+        let max_x: Option<f64> = row.get("max_x");
+        let max_y: Option<f64> = row.get("max_y");
+
+        match (min_x, min_y, max_x, max_y) {
+            (Some(min_x), Some(min_y), Some(max_x), Some(max_y)) => {
+                Ok(Some((min_x, min_y, max_x, max_y)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Get the WGS84 extent of several layers from gpkg_contents
+    ///
+    /// Longitudes are resolved on the circle, so layers on both sides of the
+    /// antimeridian give a bbox crossing it. Returns None when no layer has
+    /// a usable extent.
+    pub async fn get_layers_bbox_wgs84(&self, layers: &[LayerInfo]) -> Result<Option<Bbox>> {
+        let mut lon_intervals = Vec::new();
+        let mut lat_range: Option<(f64, f64)> = None;
+
+        for layer in layers {
+            if let Some((min_x, min_y, max_x, max_y)) = self.get_layer_bbox(layer).await? {
+                let wgs84 = if layer.srs_id == 4326 {
+                    Some((min_x, min_y, max_x, max_y))
+                } else {
+                    let srs_def = self.get_srs_definition(layer.srs_id).await?;
+                    reproject_bbox_to_wgs84(min_x, min_y, max_x, max_y, &srs_def)
+                };
+
+                if let Some((lon_min, lat_min, lon_max, lat_max)) = wgs84 {
+                    lon_intervals.push((lon_min, lon_max));
+                    lat_range = Some(match lat_range {
+                        None => (lat_min, lat_max),
+                        Some((lo, hi)) => (lo.min(lat_min), hi.max(lat_max)),
+                    });
+                }
+            }
+        }
+
+        let (Some((min_lat, max_lat)), Some((min_lon, max_lon))) =
+            (lat_range, antimeridian::minimal_lon_extent(&lon_intervals))
+        else {
+            return Ok(None);
+        };
+        Ok(Some(Bbox::new(min_lon, min_lat, max_lon, max_lat)))
+    }
+}
+
+/// A positional SQL parameter.
+enum SqlValue {
+    Real(f64),
+    Integer(i64),
+    Text(String),
+}
+
+/// A SQL condition with its positional parameters.
+struct SqlClause {
+    sql: String,
+    binds: Vec<SqlValue>,
+}
+
+/// Decodes column `index` of a row according to the SQLite type of its value.
+fn attribute_value(row: &sqlx::sqlite::SqliteRow, index: usize) -> AttributeValue {
+    let type_name = match row.try_get_raw(index) {
+        Ok(value) if !value.is_null() => value.type_info().name().to_string(),
+        _ => return AttributeValue::Null,
+    };
+    match type_name.as_str() {
+        "INTEGER" => row.try_get::<i64, _>(index).map(|v| AttributeValue::Number(v as f64)),
+        "REAL" => row.try_get::<f64, _>(index).map(AttributeValue::Number),
+        "TEXT" => row.try_get::<String, _>(index).map(AttributeValue::Text),
+        _ => return AttributeValue::Null,
+    }
+    .unwrap_or(AttributeValue::Null)
+}
+
+/// Binds positional parameters to a query, in order.
+fn bind_all<'q>(
+    mut query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    binds: Vec<SqlValue>,
+) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+    for value in binds {
+        query = match value {
+            SqlValue::Real(v) => query.bind(v),
+            SqlValue::Integer(v) => query.bind(v),
+            SqlValue::Text(v) => query.bind(v),
+        };
+    }
+    query
+}
+
+/// Maps a feature query error, attributing database errors to the user clauses if any.
+fn query_error(error: sqlx::Error, layer: &LayerInfo, filter: &FeatureFilter) -> GpkgError {
+    match error {
+        // Unknown columns or functions in a user clause are reported by SQLite here
+        sqlx::Error::Database(db) if !filter.where_clauses.is_empty() => {
+            GpkgError::InvalidWhereClause(format!("layer {}: {}", layer.name, db.message()))
+        }
+        other => GpkgError::Database(other),
+    }
+}
+
+/// Quote an SQLite identifier (table or column name).
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Parse GeoPackage WKB (with header) to geo Geometry.
+///
+/// GeoPackage WKB format extends ISO WKB with a specific header:
+/// - Magic number: "GP" (2 bytes)
+/// - Version: 1 byte
+/// - Flags: 1 byte (includes envelope type and byte order)
+/// - SRS ID: 4 bytes
+/// - Optional envelope data
+/// - Standard ISO WKB, possibly with Z/M ordinates (dropped) or curves
+///   (linearized with segments spanning at most `curve_tolerance` degrees)
+fn parse_gpkg_wkb(data: &[u8], curve_tolerance: f64) -> Option<Geometry<f64>> {
+    // GeoPackage uses a header before standard WKB
+    // Header: magic (2 bytes), version (1 byte), flags (1 byte), srs_id (4 bytes)
+    // Then optional envelope, then standard WKB
+
+    if data.len() < 8 {
+        return None;
+    }
+
+    // Check magic number "GP"
+    if data[0] != 0x47 || data[1] != 0x50 {
+        // Try parsing as standard WKB
+        return wkb::parse(data, curve_tolerance);
+    }
+
+    let flags = data[3];
+    let envelope_indicator = (flags >> 1) & 0x07;
+
+    // Calculate envelope size based on indicator
+    let envelope_size = match envelope_indicator {
+        0 => 0,
+        1 => 32, // 4 doubles (minx, maxx, miny, maxy)
+        2 => 48, // 6 doubles (+ minz, maxz)
+        3 => 48, // 6 doubles (+ minm, maxm)
+        4 => 64, // 8 doubles (all)
+        _ => return None,
+    };
+
+    let wkb_start = 8 + envelope_size;
+    if data.len() <= wkb_start {
+        return None;
+    }
+
+    wkb::parse(&data[wkb_start..], curve_tolerance)
+}
+
+/// Reads the envelope of a GeoPackage geometry header, as
+/// (min_x, min_y, max_x, max_y).
+///
+/// Returns `None` when the header has no envelope, is malformed or flags an
+/// empty geometry.
+fn gpkg_envelope(data: &[u8]) -> Option<(f64, f64, f64, f64)> {
+    if data.len() < 40 || data[0] != 0x47 || data[1] != 0x50 {
+        return None;
+    }
+    let flags = data[3];
+    // Bit 4: empty geometry; bits 1-3: envelope contents (0 = none)
+    if flags & 0x10 != 0 || !(1..=4).contains(&((flags >> 1) & 0x07)) {
+        return None;
+    }
+    let little_endian = flags & 0x01 == 1;
+    // The envelope starts with min_x, max_x, min_y, max_y
+    let value = |i: usize| {
+        let bytes: [u8; 8] = data[8 + i * 8..16 + i * 8].try_into().unwrap();
+        if little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        }
+    };
+    Some((value(0), value(2), value(1), value(3)))
+}
+
+/// Whether two (min_x, min_y, max_x, max_y) boxes overlap or touch.
+///
+/// NaN bounds never exclude anything.
+fn bbox_intersects(a: (f64, f64, f64, f64), b: (f64, f64, f64, f64)) -> bool {
+    !(a.2 < b.0 || a.0 > b.2 || a.3 < b.1 || a.1 > b.3)
+}
+
+/// Formats "ORGANIZATION:CODE", None for the undefined systems (organization NONE).
+fn srs_name(organization: Option<String>, code: Option<i64>) -> Option<String> {
+    match (organization, code) {
+        (Some(organization), Some(code)) if !organization.eq_ignore_ascii_case("NONE") => {
+            Some(format!("{}:{}", organization.to_ascii_uppercase(), code))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpkg::TempStore;
+
+    // Note: Integration tests with real GPKG file in tests/ directory
+
+    #[test]
+    fn test_quote_ident() {
+        assert_eq!(quote_ident("parcels"), "\"parcels\"");
+        assert_eq!(quote_ident("odd\"name"), "\"odd\"\"name\"");
+    }
+
+    /// Little-endian WKB for an axis-aligned square, wrapped in a minimal GeoPackage header.
+    fn square_blob(x: f64, y: f64, size: f64) -> Vec<u8> {
+        let mut blob = vec![0x47, 0x50, 0, 0x01];
+        blob.extend_from_slice(&4326i32.to_le_bytes());
+        blob.push(1);
+        blob.extend_from_slice(&3u32.to_le_bytes());
+        blob.extend_from_slice(&1u32.to_le_bytes());
+        blob.extend_from_slice(&5u32.to_le_bytes());
+        for (px, py) in [(x, y), (x + size, y), (x + size, y + size), (x, y + size), (x, y)] {
+            blob.extend_from_slice(&px.to_le_bytes());
+            blob.extend_from_slice(&py.to_le_bytes());
+        }
+        blob
+    }
+
+    #[tokio::test]
+    async fn test_read_geometries_uses_rtree() {
+        logger::Logger::init_for_tests();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rtree.gpkg");
+
+        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+            .unwrap()
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await.unwrap();
+        sqlx::query("CREATE TABLE parcels (fid INTEGER PRIMARY KEY, geom BLOB)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        // A plain table with the RTree columns is enough for the lookup
+        sqlx::query("CREATE TABLE rtree_parcels_geom (id INTEGER, minx REAL, maxx REAL, miny REAL, maxy REAL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        for i in 0..10 {
+            let x = i as f64 * 10.0;
+            sqlx::query("INSERT INTO parcels (fid, geom) VALUES (?, ?)")
+                .bind(i)
+                .bind(square_blob(x, 0.0, 1.0))
+                .execute(&pool)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO rtree_parcels_geom VALUES (?, ?, ?, 0.0, 1.0)")
+                .bind(i)
+                .bind(x)
+                .bind(x + 1.0)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        pool.close().await;
+
+        let reader = GpkgReader::open(&path, &SqlitePragmas::default()).await.unwrap();
+        let layer = LayerInfo {
+            name: "parcels".to_string(),
+            geometry_column: "geom".to_string(),
+            srs_id: 4326,
+        };
+
+        let all = reader.read_geometries(&layer, &FeatureFilter::default()).await.unwrap();
+        assert_eq!(all.len(), 10);
+
+        let mut filter = FeatureFilter {
+            bbox: Some(Bbox::new(5.0, -1.0, 25.0, 2.0)),
+            ..Default::default()
+        };
+        let filtered = reader.read_geometries(&layer, &filter).await.unwrap();
+        assert_eq!(filtered.len(), 2);
+
+        // Attribute clauses are combined with the RTree condition
+        filter.where_clauses = vec!["fid >= 2".to_string()];
+        let filtered = reader.read_geometries(&layer, &filter).await.unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(reader.count_features(&layer, &filter).await.unwrap(), 1);
+
+        // Limit and offset page through the matching features in key order
+        let page = FeatureFilter {
+            limit: Some(3),
+            offset: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(reader.read_geometries(&layer, &page).await.unwrap().len(), 2);
+        assert_eq!(reader.count_features(&layer, &page).await.unwrap(), 2);
+        let page = FeatureFilter {
+            limit: Some(3),
+            ..Default::default()
+        };
+        let first = reader.read_geometries(&layer, &page).await.unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[0].0[0].exterior().0[0].x, 0.0);
+
+        // Feature IDs match the primary key, or a given column
+        let by_fid = FeatureFilter {
+            fids: Some(FidSelection {
+                column: None,
+                values: vec!["1".to_string(), "4".to_string(), "42".to_string()],
+            }),
+            ..Default::default()
+        };
+        assert_eq!(reader.read_geometries(&layer, &by_fid).await.unwrap().len(), 2);
+        let by_column = FeatureFilter {
+            fids: Some(FidSelection {
+                column: Some("geom".to_string()),
+                values: vec!["none".to_string()],
+            }),
+            ..Default::default()
+        };
+        assert!(reader.read_geometries(&layer, &by_column).await.unwrap().is_empty());
+        let missing_column = FeatureFilter {
+            fids: Some(FidSelection {
+                column: Some("parcel_id".to_string()),
+                values: vec!["1".to_string()],
+            }),
+            ..Default::default()
+        };
+        let err = reader.read_geometries(&layer, &missing_column).await.unwrap_err();
+        assert!(matches!(err, GpkgError::ColumnNotFound(..)));
+
+        filter.where_clauses = vec!["missing_column > 1".to_string()];
+        let err = reader.read_geometries(&layer, &filter).await.unwrap_err();
+        assert!(matches!(err, GpkgError::InvalidWhereClause(_)));
+    }
+
+    #[tokio::test]
+    async fn test_for_each_chunk_culls_by_envelope() {
+        logger::Logger::init_for_tests();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("envelopes.gpkg");
+        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+            .unwrap()
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await.unwrap();
+        sqlx::query("CREATE TABLE parcels (fid INTEGER PRIMARY KEY, geom BLOB)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        // Squares at x = 0, 10, 20..., the last one without an envelope
+        for i in 0..5 {
+            let x = i as f64 * 10.0;
+            let mut blob = square_blob(x, 0.0, 1.0);
+            if i < 4 {
+                blob[3] = 0x03;
+                let envelope: Vec<u8> = [x, x + 1.0, 0.0, 1.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+                blob.splice(8..8, envelope);
+            }
+            sqlx::query("INSERT INTO parcels (fid, geom) VALUES (?, ?)")
+                .bind(i)
+                .bind(blob)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        pool.close().await;
+
+        let reader = GpkgReader::open(&path, &SqlitePragmas::default()).await.unwrap();
+        let layer = LayerInfo {
+            name: "parcels".to_string(),
+            geometry_column: "geom".to_string(),
+            srs_id: 4326,
+        };
+        assert_eq!(reader.read_geometries(&layer, &FeatureFilter::default()).await.unwrap().len(), 5);
+
+        // Without an RTree, only the envelopes keep the far squares out
+        let filter = FeatureFilter {
+            bbox: Some(Bbox::new(5.0, -1.0, 20.0, 2.0)),
+            ..Default::default()
+        };
+        let mut fids = Vec::new();
+        reader
+            .for_each_chunk(&layer, &filter, &[], |chunk| {
+                fids.extend(chunk.iter().map(|f| f.fid));
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(fids, vec![1, 2, 4]);
+
+        let mut blob = square_blob(0.0, 0.0, 1.0);
+        assert_eq!(gpkg_envelope(&blob), None);
+        blob[3] = 0x02;
+        blob.splice(8..8, [-1.0f64, 1.0, -2.0, 2.0].iter().flat_map(|v| v.to_be_bytes()));
+        assert_eq!(gpkg_envelope(&blob), Some((-1.0, -2.0, 1.0, 2.0)));
+        blob[3] = 0x12;
+        assert_eq!(gpkg_envelope(&blob), None);
+    }
+
+    #[tokio::test]
+    async fn test_for_each_chunk_bounds_chunk_size() {
+        logger::Logger::init_for_tests();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chunks.gpkg");
+        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+            .unwrap()
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await.unwrap();
+        sqlx::query("CREATE TABLE parcels (fid INTEGER PRIMARY KEY, geom BLOB)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let total = READ_CHUNK_SIZE + 10;
+        let mut tx = pool.begin().await.unwrap();
+        for i in 0..total {
+            sqlx::query("INSERT INTO parcels (geom) VALUES (?)")
+                .bind(square_blob(i as f64, 0.0, 1.0))
+                .execute(&mut *tx)
+                .await
+                .unwrap();
+        }
+        tx.commit().await.unwrap();
+        pool.close().await;
+
+        let reader = GpkgReader::open(&path, &SqlitePragmas::default()).await.unwrap();
+        let layer = LayerInfo {
+            name: "parcels".to_string(),
+            geometry_column: "geom".to_string(),
+            srs_id: 4326,
+        };
+
+        let mut sizes = Vec::new();
+        reader
+            .for_each_chunk_wgs84(&layer, &FeatureFilter::default(), &[], |chunk| {
+                sizes.push(chunk.len());
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(sizes, vec![READ_CHUNK_SIZE, 10]);
+        assert_eq!(
+            reader.count_features(&layer, &FeatureFilter::default()).await.unwrap(),
+            total as u64
+        );
+    }
+
+    #[tokio::test]
+    async fn test_for_each_chunk_reads_attributes() {
+        logger::Logger::init_for_tests();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("attributes.gpkg");
+        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+            .unwrap()
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await.unwrap();
+        sqlx::query("CREATE TABLE parcels (fid INTEGER PRIMARY KEY, geom BLOB, p)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        for (i, p) in ["0.25", "40", "NULL", "'high'"].iter().enumerate() {
+            sqlx::query(&format!("INSERT INTO parcels (geom, p) VALUES (?, {})", p))
+                .bind(square_blob(i as f64, 0.0, 1.0))
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        pool.close().await;
+
+        let reader = GpkgReader::open(&path, &SqlitePragmas::default()).await.unwrap();
+        let layer = LayerInfo {
+            name: "parcels".to_string(),
+            geometry_column: "geom".to_string(),
+            srs_id: 4326,
+        };
+        let filter = FeatureFilter::default();
+
+        let mut values = Vec::new();
+        reader
+            .for_each_chunk(&layer, &filter, &["p".to_string()], |chunk| {
+                values.extend(chunk.into_iter().flat_map(|f| f.attributes));
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                AttributeValue::Number(0.25),
+                AttributeValue::Number(40.0),
+                AttributeValue::Null,
+                AttributeValue::Text("high".to_string()),
+            ]
+        );
+        assert_eq!(reader.value_range(&layer, &filter, "p").await.unwrap(), Some((0.25, 40.0)));
+        assert_eq!(reader.numeric_values(&layer, &filter, "p").await.unwrap(), vec![0.25, 40.0]);
+        assert_eq!(reader.attribute_columns(&layer).await.unwrap(), vec!["p".to_string()]);
+
+        let err = reader.value_range(&layer, &filter, "missing").await.unwrap_err();
+        assert!(matches!(err, GpkgError::ColumnNotFound(..)));
+    }
+
+    #[tokio::test]
+    async fn test_open_applies_pragmas() {
+        logger::Logger::init_for_tests();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pragmas.gpkg");
+        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+            .unwrap()
+            .create_if_missing(true);
+        SqlitePool::connect_with(options).await.unwrap().close().await;
+
+        let pragmas = SqlitePragmas {
+            mmap_size: None,
+            cache_size_kib: Some(8192),
+            temp_store: Some(TempStore::Memory),
+        };
+        let reader = GpkgReader::open(&path, &pragmas).await.unwrap();
+        let cache_size: i64 = sqlx::query("PRAGMA cache_size")
+            .fetch_one(&reader.pool)
+            .await
+            .unwrap()
+            .get(0);
+        let temp_store: i64 = sqlx::query("PRAGMA temp_store")
+            .fetch_one(&reader.pool)
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(cache_size, -8192);
+        assert_eq!(temp_store, 2);
+    }
+
+    #[tokio::test]
+    async fn test_list_extensions() {
+        logger::Logger::init_for_tests();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("extensions.gpkg");
+        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+            .unwrap()
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await.unwrap();
+        sqlx::query("CREATE TABLE parcels (fid INTEGER PRIMARY KEY, geom BLOB)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // The table is optional
+        let reader = GpkgReader::open(&path, &SqlitePragmas::default()).await.unwrap();
+        assert!(reader.list_extensions().await.unwrap().is_empty());
+        reader.pool.close().await;
+
+        for statement in [
+            "CREATE TABLE gpkg_extensions (table_name TEXT, column_name TEXT, extension_name TEXT NOT NULL, \
+             definition TEXT NOT NULL, scope TEXT NOT NULL)",
+            "INSERT INTO gpkg_extensions VALUES ('parcels', 'geom', 'gpkg_rtree_index', 'GeoPackage 1.0', 'write-only')",
+            "INSERT INTO gpkg_extensions VALUES ('dem', 'tile_data', 'gpkg_2d_gridded_coverage', 'OGC 17-066r1', 'read-write')",
+        ] {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        pool.close().await;
+        let reader = GpkgReader::open(&path, &SqlitePragmas::default()).await.unwrap();
+        let extensions = reader.list_extensions().await.unwrap();
+        assert_eq!(extensions.len(), 2);
+        assert_eq!(extensions[0].column_name.as_deref(), Some("geom"));
+        let unsupported: Vec<_> = extensions.iter().filter(|e| e.is_unsupported()).collect();
+        assert_eq!(unsupported.len(), 1);
+        assert!(unsupported[0].applies_to("dem"));
+    }
+
+    #[tokio::test]
+    async fn test_read_tile_mosaic() {
+        logger::Logger::init_for_tests();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiles.gpkg");
+        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+            .unwrap()
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await.unwrap();
+        for statement in [
+            "CREATE TABLE gpkg_contents (table_name TEXT, data_type TEXT)",
+            "CREATE TABLE gpkg_tile_matrix_set (table_name TEXT, srs_id INTEGER, \
+             min_x REAL, min_y REAL, max_x REAL, max_y REAL)",
+            "CREATE TABLE gpkg_tile_matrix (table_name TEXT, zoom_level INTEGER, matrix_width INTEGER, \
+             matrix_height INTEGER, tile_width INTEGER, tile_height INTEGER, pixel_x_size REAL, pixel_y_size REAL)",
+            "CREATE TABLE ortho (id INTEGER PRIMARY KEY, zoom_level INTEGER, tile_column INTEGER, \
+             tile_row INTEGER, tile_data BLOB)",
+            "INSERT INTO gpkg_contents VALUES ('ortho', 'tiles')",
+            "INSERT INTO gpkg_tile_matrix_set VALUES ('ortho', 4326, 0, 0, 4, 2)",
+            "INSERT INTO gpkg_tile_matrix VALUES ('ortho', 1, 4, 2, 2, 2, 0.5, 0.5)",
+            "INSERT INTO gpkg_tile_matrix VALUES ('ortho', 0, 2, 1, 2, 2, 1, 1)",
+        ] {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(2, 2, image::Rgba([0, 128, 0, 255]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        for (column, data) in [(0, png), (1, b"not an image".to_vec())] {
+            sqlx::query("INSERT INTO ortho (zoom_level, tile_column, tile_row, tile_data) VALUES (0, ?, 0, ?)")
+                .bind(column)
+                .bind(data)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        pool.close().await;
+
+        let reader = GpkgReader::open(&path, &SqlitePragmas::default()).await.unwrap();
+        let layers = reader.list_tile_layers().await.unwrap();
+        assert_eq!(layers.len(), 1);
+        let layer = &layers[0];
+        assert_eq!(layer.extent, (0.0, 0.0, 4.0, 2.0));
+        assert_eq!(layer.matrices.iter().map(|m| m.zoom_level).collect::<Vec<_>>(), vec![0, 1]);
+
+        // The undecodable tile is skipped
+        let matrix = layer.matrix_for(1.0).unwrap();
+        let mosaic = reader.read_tile_mosaic(layer, matrix, (0, 1), (0, 0)).await.unwrap();
+        assert_eq!(mosaic.len(), 1);
+        assert_eq!(mosaic.sample(1.0, 1.0), image::Rgba([0, 128, 0, 255]));
+        assert_eq!(mosaic.sample(3.0, 1.0), image::Rgba([0, 0, 0, 0]));
+    }
+}
@@ -0,0 +1,412 @@
+//! Well-Known Binary decoding.
+//!
+//! Reads both byte orders and the Z, M and ZM variants of every geometry
+//! type, as ISO type codes (1003, 2003, 3003...) or EWKB flag bits. Only x
+//! and y are rendered, so the extra ordinates are read and dropped.
+//!
+//! Curved types (CircularString, CompoundCurve, CurvePolygon, MultiCurve and
+//! MultiSurface) are linearized into their straight counterparts, each arc
+//! split in segments spanning at most the given angle.
+
+use std::f64::consts::TAU;
+
+use geo::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon,
+};
+
+/// EWKB flag of geometries with a Z ordinate.
+const EWKB_Z: u32 = 0x8000_0000;
+/// EWKB flag of geometries with an M ordinate.
+const EWKB_M: u32 = 0x4000_0000;
+/// EWKB flag of geometries followed by an SRID.
+const EWKB_SRID: u32 = 0x2000_0000;
+
+/// Decodes a WKB geometry, None when it is malformed or of an unknown type.
+///
+/// Arcs are linearized with segments spanning at most `arc_step` degrees.
+/// Trailing bytes after the geometry are ignored.
+pub(crate) fn parse(data: &[u8], arc_step: f64) -> Option<Geometry<f64>> {
+    Reader {
+        data,
+        pos: 0,
+        arc_step: arc_step.to_radians(),
+    }
+    .geometry()
+}
+
+/// Cursor over WKB bytes.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// Largest angle spanned by one segment of a linearized arc, in radians.
+    arc_step: f64,
+}
+
+/// Byte order and coordinate size of the geometry being read.
+#[derive(Clone, Copy)]
+struct Layout {
+    little_endian: bool,
+    /// Ordinates per coordinate: 2 to 4.
+    dimensions: usize,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let bytes = self.data.get(self.pos..self.pos + N)?.try_into().ok()?;
+        self.pos += N;
+        Some(bytes)
+    }
+
+    fn u32(&mut self, layout: Layout) -> Option<u32> {
+        let bytes = self.bytes()?;
+        Some(if layout.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    fn f64(&mut self, layout: Layout) -> Option<f64> {
+        let bytes = self.bytes()?;
+        Some(if layout.little_endian { f64::from_le_bytes(bytes) } else { f64::from_be_bytes(bytes) })
+    }
+
+    /// Reads a count, rejecting counts the remaining bytes cannot hold so a
+    /// corrupt blob does not trigger a huge allocation.
+    fn count(&mut self, layout: Layout, min_item_size: usize) -> Option<usize> {
+        let count = self.u32(layout)? as usize;
+        (count.checked_mul(min_item_size)? <= self.data.len() - self.pos).then_some(count)
+    }
+
+    fn coord(&mut self, layout: Layout) -> Option<Coord<f64>> {
+        let x = self.f64(layout)?;
+        let y = self.f64(layout)?;
+        for _ in 2..layout.dimensions {
+            self.f64(layout)?;
+        }
+        Some(Coord { x, y })
+    }
+
+    fn line_string(&mut self, layout: Layout) -> Option<LineString<f64>> {
+        let count = self.count(layout, layout.dimensions * 8)?;
+        (0..count).map(|_| self.coord(layout)).collect::<Option<Vec<_>>>().map(LineString)
+    }
+
+    fn polygon(&mut self, layout: Layout) -> Option<Polygon<f64>> {
+        let count = self.count(layout, 4)?;
+        let mut rings = (0..count).map(|_| self.line_string(layout)).collect::<Option<Vec<_>>>()?;
+        // A polygon without rings is empty
+        let exterior = if rings.is_empty() { LineString(Vec::new()) } else { rings.remove(0) };
+        Some(Polygon::new(exterior, rings))
+    }
+
+    /// Reads a CircularString: arcs through every three points, the last
+    /// point of an arc starting the next.
+    fn circular_string(&mut self, layout: Layout) -> Option<LineString<f64>> {
+        let points = self.line_string(layout)?.0;
+        let Some(&first) = points.first() else {
+            return Some(LineString(points));
+        };
+        if points.len() % 2 == 0 {
+            return None;
+        }
+        let mut coords = vec![first];
+        for arc in points.windows(3).step_by(2) {
+            linearize_arc(arc[0], arc[1], arc[2], self.arc_step, &mut coords);
+        }
+        Some(LineString(coords))
+    }
+
+    /// Reads a CompoundCurve: line strings and circular strings joined end to end.
+    fn compound_curve(&mut self, layout: Layout) -> Option<LineString<f64>> {
+        let mut coords: Vec<Coord<f64>> = Vec::new();
+        for part in self.members(layout, curve)? {
+            // Each part starts where the previous one ended
+            let skip = usize::from(coords.last().is_some() && coords.last() == part.0.first());
+            coords.extend(part.0.into_iter().skip(skip));
+        }
+        Some(LineString(coords))
+    }
+
+    /// Reads a CurvePolygon, whose rings may be any curve.
+    fn curve_polygon(&mut self, layout: Layout) -> Option<Polygon<f64>> {
+        let mut rings = self.members(layout, curve)?;
+        let exterior = if rings.is_empty() { LineString(Vec::new()) } else { rings.remove(0) };
+        Some(Polygon::new(exterior, rings))
+    }
+
+    /// Reads the members of a multi-geometry, each a full WKB geometry
+    /// extracted by `member`.
+    fn members<T>(&mut self, layout: Layout, member: impl Fn(Geometry<f64>) -> Option<T>) -> Option<Vec<T>> {
+        let count = self.count(layout, 5)?;
+        (0..count).map(|_| member(self.geometry()?)).collect()
+    }
+
+    fn geometry(&mut self) -> Option<Geometry<f64>> {
+        let little_endian = match self.bytes::<1>()?[0] {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+        let mut layout = Layout {
+            little_endian,
+            dimensions: 2,
+        };
+        let code = self.u32(layout)?;
+        if code & EWKB_SRID != 0 {
+            self.u32(layout)?;
+        }
+        let flags = (code & EWKB_Z != 0) as usize + (code & EWKB_M != 0) as usize;
+        let code = code & 0x0FFF_FFFF;
+        // ISO codes add 1000 for Z, 2000 for M and 3000 for ZM
+        let iso = match code / 1000 {
+            0 => 0,
+            1 | 2 => 1,
+            3 => 2,
+            _ => return None,
+        };
+        layout.dimensions = 2 + flags.max(iso);
+
+        Some(match code % 1000 {
+            1 => Geometry::Point(Point(self.coord(layout)?)),
+            2 => Geometry::LineString(self.line_string(layout)?),
+            3 => Geometry::Polygon(self.polygon(layout)?),
+            4 => Geometry::MultiPoint(MultiPoint(self.members(layout, |g| match g {
+                Geometry::Point(p) => Some(p),
+                _ => None,
+            })?)),
+            5 => Geometry::MultiLineString(MultiLineString(self.members(layout, |g| match g {
+                Geometry::LineString(l) => Some(l),
+                _ => None,
+            })?)),
+            6 => Geometry::MultiPolygon(MultiPolygon(self.members(layout, |g| match g {
+                Geometry::Polygon(p) => Some(p),
+                _ => None,
+            })?)),
+            7 => Geometry::GeometryCollection(GeometryCollection(self.members(layout, Some)?)),
+            8 => Geometry::LineString(self.circular_string(layout)?),
+            9 => Geometry::LineString(self.compound_curve(layout)?),
+            10 => Geometry::Polygon(self.curve_polygon(layout)?),
+            11 => Geometry::MultiLineString(MultiLineString(self.members(layout, curve)?)),
+            12 => Geometry::MultiPolygon(MultiPolygon(self.members(layout, |g| match g {
+                Geometry::Polygon(p) => Some(p),
+                _ => None,
+            })?)),
+            _ => return None,
+        })
+    }
+}
+
+/// A linearized curve (LineString, CircularString or CompoundCurve).
+fn curve(geometry: Geometry<f64>) -> Option<LineString<f64>> {
+    match geometry {
+        Geometry::LineString(l) => Some(l),
+        _ => None,
+    }
+}
+
+/// Appends the points of the circular arc from `start` through `middle` to
+/// `end`, after `start`, with segments spanning at most `step` radians.
+///
+/// Collinear points are joined by straight segments; an arc ending where it
+/// starts is the full circle whose diameter joins `start` and `middle`.
+fn linearize_arc(start: Coord<f64>, middle: Coord<f64>, end: Coord<f64>, step: f64, coords: &mut Vec<Coord<f64>>) {
+    let angle = |p: Coord<f64>, center: Coord<f64>| (p.y - center.y).atan2(p.x - center.x);
+    let (center, sweep) = if start == end {
+        let center = Coord {
+            x: (start.x + middle.x) / 2.0,
+            y: (start.y + middle.y) / 2.0,
+        };
+        (center, TAU)
+    } else {
+        // Circumcenter of the three points
+        let (bx, by) = (middle.x - start.x, middle.y - start.y);
+        let (cx, cy) = (end.x - start.x, end.y - start.y);
+        let d = 2.0 * (bx * cy - by * cx);
+        if d.abs() <= f64::EPSILON * (bx * bx + by * by + cx * cx + cy * cy) {
+            coords.extend([middle, end]);
+            return;
+        }
+        let (b2, c2) = (bx * bx + by * by, cx * cx + cy * cy);
+        let center = Coord {
+            x: start.x + (cy * b2 - by * c2) / d,
+            y: start.y + (bx * c2 - cx * b2) / d,
+        };
+        // The arc runs counter-clockwise when start, middle and end turn left
+        let counter_clockwise = d > 0.0;
+        let mut sweep = (angle(end, center) - angle(start, center)).rem_euclid(TAU);
+        if !counter_clockwise {
+            sweep -= TAU;
+        }
+        (center, sweep)
+    };
+    let radius = ((start.x - center.x).powi(2) + (start.y - center.y).powi(2)).sqrt();
+    let start_angle = angle(start, center);
+    let segments = (sweep.abs() / step).ceil().max(1.0) as usize;
+    for i in 1..segments {
+        let a = start_angle + sweep * i as f64 / segments as f64;
+        coords.push(Coord {
+            x: center.x + radius * a.cos(),
+            y: center.y + radius * a.sin(),
+        });
+    }
+    coords.push(end);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// WKB of the unit square polygon with type `code` and `extra` ordinates
+    /// per coordinate, in either byte order.
+    fn square(code: u32, extra: usize, little_endian: bool) -> Vec<u8> {
+        let u32_bytes = |v: u32| if little_endian { v.to_le_bytes() } else { v.to_be_bytes() };
+        let f64_bytes = |v: f64| if little_endian { v.to_le_bytes() } else { v.to_be_bytes() };
+        let mut wkb = vec![little_endian as u8];
+        wkb.extend(u32_bytes(code));
+        wkb.extend(u32_bytes(1));
+        wkb.extend(u32_bytes(5));
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.0, 0.0)] {
+            wkb.extend(f64_bytes(x));
+            wkb.extend(f64_bytes(y));
+            for _ in 0..extra {
+                wkb.extend(f64_bytes(42.0));
+            }
+        }
+        wkb
+    }
+
+    fn expected() -> Geometry<f64> {
+        Geometry::Polygon(Polygon::new(
+            LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.0, 0.0)]),
+            vec![],
+        ))
+    }
+
+    #[test]
+    fn test_parse_dimensions() {
+        for little_endian in [true, false] {
+            // 2D, ISO Z/M/ZM, then EWKB Z/M/ZM
+            for (code, extra) in [
+                (3, 0),
+                (1003, 1),
+                (2003, 1),
+                (3003, 2),
+                (3 | EWKB_Z, 1),
+                (3 | EWKB_M, 1),
+                (3 | EWKB_Z | EWKB_M, 2),
+            ] {
+                assert_eq!(parse(&square(code, extra, little_endian), 4.0), Some(expected()), "type {:#x}", code);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_multi_polygon_z() {
+        let mut wkb = vec![1];
+        wkb.extend(1006u32.to_le_bytes());
+        wkb.extend(2u32.to_le_bytes());
+        wkb.extend(square(1003, 1, true));
+        wkb.extend(square(1003, 1, false));
+        match parse(&wkb, 4.0) {
+            Some(Geometry::MultiPolygon(mp)) => assert_eq!(mp.0.len(), 2),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed() {
+        let wkb = square(3, 0, true);
+        assert_eq!(parse(&wkb[..wkb.len() - 1], 4.0), None);
+        assert_eq!(parse(&square(4003, 0, true), 4.0), None);
+        assert_eq!(parse(&square(99, 0, true), 4.0), None);
+        assert_eq!(parse(&[2, 3, 0, 0, 0], 4.0), None);
+        // A ring count the blob cannot hold
+        let mut huge = vec![1];
+        huge.extend(3u32.to_le_bytes());
+        huge.extend(u32::MAX.to_le_bytes());
+        assert_eq!(parse(&huge, 4.0), None);
+    }
+
+    /// Little-endian WKB of a curve of type `code` through `points`.
+    fn curve_wkb(code: u32, points: &[(f64, f64)]) -> Vec<u8> {
+        let mut wkb = vec![1];
+        wkb.extend(code.to_le_bytes());
+        wkb.extend((points.len() as u32).to_le_bytes());
+        for &(x, y) in points {
+            wkb.extend(x.to_le_bytes());
+            wkb.extend(y.to_le_bytes());
+        }
+        wkb
+    }
+
+    /// Little-endian WKB of a geometry of type `code` made of `members`.
+    fn collection(code: u32, members: &[Vec<u8>]) -> Vec<u8> {
+        let mut wkb = vec![1];
+        wkb.extend(code.to_le_bytes());
+        wkb.extend((members.len() as u32).to_le_bytes());
+        for member in members {
+            wkb.extend(member);
+        }
+        wkb
+    }
+
+    #[test]
+    fn test_parse_circular_string() {
+        // Upper half of the unit circle, counter-clockwise
+        let wkb = curve_wkb(8, &[(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0)]);
+        let Some(Geometry::LineString(line)) = parse(&wkb, 4.0) else {
+            panic!("not a line string");
+        };
+        // 180 degrees in 4 degree steps
+        assert_eq!(line.0.len(), 46);
+        assert_eq!(line.0[0], Coord { x: 1.0, y: 0.0 });
+        assert_eq!(line.0[45], Coord { x: -1.0, y: 0.0 });
+        for c in &line.0 {
+            assert!((c.x.hypot(c.y) - 1.0).abs() < 1e-12);
+            assert!(c.y >= 0.0);
+        }
+
+        // Clockwise through the lower half, with a coarser tolerance
+        let wkb = curve_wkb(8, &[(1.0, 0.0), (0.0, -1.0), (-1.0, 0.0)]);
+        let Some(Geometry::LineString(line)) = parse(&wkb, 45.0) else {
+            panic!("not a line string");
+        };
+        assert_eq!(line.0.len(), 5);
+        assert!(line.0.iter().all(|c| c.y <= 0.0));
+
+        // Collinear points stay straight; an even point count is invalid
+        let wkb = curve_wkb(8, &[(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]);
+        assert_eq!(parse(&wkb, 4.0), Some(Geometry::LineString(LineString::from(vec![(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]))));
+        assert_eq!(parse(&curve_wkb(8, &[(0.0, 0.0), (1.0, 1.0)]), 4.0), None);
+    }
+
+    #[test]
+    fn test_parse_curve_polygon() {
+        // Full circle of radius 1 around (1, 0), as an exterior ring
+        let circle = curve_wkb(8, &[(0.0, 0.0), (2.0, 0.0), (0.0, 0.0)]);
+        let Some(Geometry::Polygon(polygon)) = parse(&collection(10, &[circle]), 10.0) else {
+            panic!("not a polygon");
+        };
+        let ring = &polygon.exterior().0;
+        assert_eq!(ring.len(), 37);
+        assert_eq!(ring.first(), ring.last());
+        assert!(ring.iter().all(|c| ((c.x - 1.0).hypot(c.y) - 1.0).abs() < 1e-12));
+
+        // CompoundCurve: a semicircle closed by a straight line, sharing endpoints
+        let compound = collection(
+            9,
+            &[
+                curve_wkb(8, &[(-1.0, 0.0), (0.0, 1.0), (1.0, 0.0)]),
+                curve_wkb(2, &[(1.0, 0.0), (-1.0, 0.0)]),
+            ],
+        );
+        let Some(Geometry::MultiPolygon(surface)) = parse(&collection(12, &[collection(10, &[compound])]), 90.0) else {
+            panic!("not a multi polygon");
+        };
+        let ring = &surface.0[0].exterior().0;
+        assert_eq!(ring.len(), 4);
+        assert_eq!(ring.first(), ring.last());
+        assert!(ring[1].x.abs() < 1e-12 && (ring[1].y - 1.0).abs() < 1e-12);
+        assert_eq!(ring[2], Coord { x: 1.0, y: 0.0 });
+
+        // Surfaces cannot hold points
+        assert_eq!(parse(&collection(10, &[curve_wkb(1, &[])]), 4.0), None);
+    }
+}
@@ -0,0 +1,59 @@
+//! Ctrl-C handling.
+//!
+//! The first SIGINT only raises a flag: the layer being rendered stops at its
+//! next chunk and is discarded (or saved with `--save-partial`), and files
+//! already being written are completed. A second SIGINT exits immediately.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Exit status of a run stopped by Ctrl-C (128 + SIGINT).
+pub const EXIT_CODE: i32 = crate::error::exit_code::INTERRUPTED;
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Installs the SIGINT handler.
+#[cfg(unix)]
+pub fn install() {
+    extern "C" fn on_sigint(_: libc::c_int) {
+        // Only async-signal-safe calls here
+        if !request(&REQUESTED) {
+            unsafe { libc::_exit(EXIT_CODE) };
+        }
+    }
+    let handler: extern "C" fn(libc::c_int) = on_sigint;
+    unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+    }
+}
+
+/// Other platforms keep the default Ctrl-C behavior.
+#[cfg(not(unix))]
+pub fn install() {}
+
+/// Raises `flag`; returns false when it was already raised, meaning the run
+/// must exit immediately.
+#[cfg(any(unix, test))]
+fn request(flag: &AtomicBool) -> bool {
+    !flag.swap(true, Ordering::SeqCst)
+}
+
+/// Returns true once Ctrl-C has been pressed.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The process-wide flag is left alone: raising it would interrupt the
+    // renders of the other tests
+    #[test]
+    fn test_second_request_exits() {
+        let flag = AtomicBool::new(false);
+        assert!(request(&flag));
+        assert!(flag.load(Ordering::SeqCst));
+        assert!(!request(&flag));
+        assert!(!requested());
+    }
+}
@@ -0,0 +1,227 @@
+//! Rasterize GeoPackage and GeoJSON polygons to PNG.
+//!
+//! The `gpkg-to-png` binary is a thin command-line wrapper around this crate:
+//! with the `cli` feature, [`cli`] parses its arguments and [`app`] runs them.
+//! Readers ([`GpkgReader`], [`GeojsonReader`]) load geometries and reproject
+//! them to WGS84, a [`Renderer`] configured by a [`RenderConfig`] rasterizes
+//! them, and [`render_to_png`] chains both steps for the common case:
+//!
+//! ```no_run
+//! use gpkg_to_png::render::MAX_DIMENSION;
+//! use gpkg_to_png::{render_to_png, Bbox, RenderConfig, Source};
+//!
+//! # async fn example() -> gpkg_to_png::Result<()> {
+//! let config = RenderConfig {
+//!     bbox: Bbox::new(-4.5, 48.0, -4.0, 48.5),
+//!     resolution: 0.0001,
+//!     fill: Some([255, 0, 0, 128]),
+//!     stroke: Some([255, 0, 0]),
+//!     stroke_width: 1,
+//!     vertex_color: None,
+//!     min_feature_px: None,
+//!     max_dimension: MAX_DIMENSION,
+//!     rotation: 0.0,
+//!     glow: None,
+//! };
+//! let source = Source::Geojson("zones.geojson".into());
+//! render_to_png(&source, config, "zones.png".as_ref()).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Messages and warnings are only printed once [`logger::Logger`] has been
+//! initialized.
+
+#[cfg(all(feature = "cli", feature = "gpkg"))]
+mod animate;
+pub mod antimeridian;
+#[cfg(feature = "cli")]
+pub mod app;
+pub mod attributes;
+pub mod burn;
+pub mod canvas;
+#[cfg(feature = "cli")]
+mod capabilities;
+pub mod classify;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod compare;
+#[cfg(feature = "cli")]
+mod doctor;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod footprint;
+pub mod geojson;
+pub mod geotag;
+pub mod gpkg;
+#[cfg(feature = "cli")]
+mod interrupt;
+pub mod logger;
+pub mod manifest;
+pub mod math;
+pub mod output;
+pub mod preview;
+pub mod progress;
+#[cfg(feature = "reproject")]
+pub mod proj_data;
+pub mod ramp;
+pub mod raster;
+pub mod render;
+pub mod reproject;
+pub mod resample;
+#[cfg(feature = "cli")]
+mod resume;
+#[cfg(all(feature = "cli", feature = "gpkg"))]
+mod serve;
+pub mod stac;
+pub mod stats;
+pub mod style;
+#[cfg(all(feature = "cli", feature = "gpkg"))]
+mod tiles;
+pub mod warning;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+use geo::MultiPolygon;
+use std::path::{Path, PathBuf};
+
+// Images are returned as `image` buffers; re-exported so callers match its version
+pub use image;
+
+pub use error::{GpkgError, Result};
+pub use geojson::GeojsonReader;
+#[cfg(feature = "gpkg")]
+pub use gpkg::GpkgReader;
+pub use math::Bbox;
+pub use render::{FeatureStyle, PngMetadata, RenderConfig, Renderer, SpanShader};
+
+#[cfg(feature = "gpkg")]
+use gpkg::{FeatureFilter, SqlitePragmas};
+
+/// Input rendered by [`render_to_png`].
+#[derive(Debug, Clone)]
+pub enum Source {
+    /// A GeoJSON file (WGS84).
+    Geojson(PathBuf),
+    /// One polygon layer of a GeoPackage, reprojected to WGS84.
+    #[cfg(feature = "gpkg")]
+    Gpkg { path: PathBuf, layer: String },
+}
+
+/// Renders every polygon of `source` inside `config.bbox` to a PNG at `output`.
+///
+/// Geometries are rendered with the layer style in one sweep per chunk, and
+/// wrapped across the antimeridian like the CLI does. Returns the image
+/// dimensions.
+pub async fn render_to_png(source: &Source, config: RenderConfig, output: &Path) -> Result<(u32, u32)> {
+    let bbox = config.bbox;
+    let mut renderer = Renderer::new(config)?;
+
+    match source {
+        Source::Geojson(path) => {
+            let reader = GeojsonReader::open(path).await?;
+            for batch in reader.get_geometries().chunks(render::BATCH_SIZE) {
+                let features = batch.iter().map(|geom| (geom, FeatureStyle::default()));
+                render_features(&mut renderer, features, &bbox);
+            }
+        }
+        #[cfg(feature = "gpkg")]
+        Source::Gpkg { path, layer } => {
+            let reader = GpkgReader::open(path, &SqlitePragmas::default()).await?;
+            let layers = reader.list_polygon_layers().await?;
+            let info = layers.iter().find(|l| l.name == *layer).ok_or_else(|| {
+                let available = layers.iter().map(|l| l.name.as_str()).collect::<Vec<_>>();
+                GpkgError::LayerNotFound(layer.clone(), available.join(", "))
+            })?;
+            let filter = FeatureFilter {
+                bbox: Some(bbox),
+                ..Default::default()
+            };
+            reader
+                .for_each_chunk_wgs84(info, &filter, &[], |chunk| {
+                    let features = chunk.iter().map(|f| (&f.geometry, FeatureStyle::default()));
+                    render_features(&mut renderer, features, &bbox);
+                    Ok(())
+                })
+                .await?;
+        }
+    }
+
+    output::ensure_parent_dir(output)?;
+    renderer.save(output, &PngMetadata::default())?;
+    Ok(renderer.dimensions())
+}
+
+/// Renders WGS84 features in one sweep, wrapping each geometry for the bbox.
+///
+/// Every copy of a geometry keeps the style of its feature.
+pub fn render_features<'a, I>(renderer: &mut Renderer, features: I, bbox: &Bbox)
+where
+    I: IntoIterator<Item = (&'a MultiPolygon<f64>, FeatureStyle)>,
+{
+    let (copies, styles): (Vec<_>, Vec<_>) = features
+        .into_iter()
+        .flat_map(|(geom, style)| {
+            antimeridian::wrap_for_bbox(geom, bbox)
+                .into_iter()
+                .map(move |copy| (copy, style))
+        })
+        .unzip();
+    renderer.render_styled_layer(&copies, &styles);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_render_geojson_to_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("square.geojson");
+        std::fs::write(
+            &input,
+            r#"{"type": "Polygon", "coordinates": [[[2, 2], [8, 2], [8, 8], [2, 8], [2, 2]]]}"#,
+        )
+        .unwrap();
+        let output = dir.path().join("out").join("square.png");
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 1.0,
+            fill: Some([255, 0, 0, 255]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: render::MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+
+        let dimensions = render_to_png(&Source::Geojson(input), config, &output).await.unwrap();
+        assert_eq!(dimensions, (10, 10));
+
+        let image = image::open(&output).unwrap().to_rgba8();
+        assert_eq!(image.get_pixel(5, 5).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_render_missing_input() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 1.0, 1.0),
+            resolution: 0.1,
+            fill: Some([255, 0, 0, 255]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: render::MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let source = Source::Geojson(PathBuf::from("missing.geojson"));
+        let err = render_to_png(&source, config, Path::new("unused.png")).await.unwrap_err();
+        assert!(matches!(err, GpkgError::FileNotFound(_)));
+    }
+}
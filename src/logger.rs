@@ -1,16 +1,25 @@
 //! Logging and verbosity control for the application.
 //!
-//! Provides a global logger with three verbosity levels:
+//! Provides a global logger with four verbosity levels:
 //! - Quiet: Only file paths output
 //! - Normal: Progress messages without prefixes (default)
 //! - Verbose: Timestamped colored logs with details
+//! - Trace: Verbose, plus a line per chunk of geometries
+//!
+//! When stdout is reserved for machine-readable output (`--progress json`),
+//! every message goes to stderr instead.
+//!
+//! Messages logged before the logger is initialized are dropped, so library
+//! callers stay silent unless they opt in.
 
+use std::fmt;
 use std::io::Write;
 use std::sync::OnceLock;
 use std::time::Instant;
 
 /// Verbosity level for controlling output.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 pub enum VerbosityLevel {
     /// Only file paths output
     Quiet,
@@ -18,6 +27,21 @@ pub enum VerbosityLevel {
     Normal,
     /// Timestamped colored logs with details
     Verbose,
+    /// Verbose, plus a line per chunk of geometries
+    Trace,
+}
+
+/// When to color log output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ColorMode {
+    /// Color a terminal, unless `NO_COLOR` is set.
+    #[default]
+    Auto,
+    /// Always color, even when redirected.
+    Always,
+    /// Never color.
+    Never,
 }
 
 /// Global logger instance.
@@ -31,6 +55,22 @@ static START_TIME: OnceLock<Instant> = OnceLock::new();
 pub struct Logger {
     level: VerbosityLevel,
     colors_enabled: bool,
+    /// Write messages to stderr, leaving stdout to machine-readable output.
+    stderr: bool,
+}
+
+/// Returns true if stderr (or stdout) is a terminal.
+///
+/// Terminal detection comes with the `cli` feature; without it, `auto`
+/// leaves the output uncolored.
+#[cfg(feature = "cli")]
+fn is_terminal(stderr: bool) -> bool {
+    atty::is(if stderr { atty::Stream::Stderr } else { atty::Stream::Stdout })
+}
+
+#[cfg(not(feature = "cli"))]
+fn is_terminal(_stderr: bool) -> bool {
+    false
 }
 
 impl Logger {
@@ -38,17 +78,45 @@ impl Logger {
     ///
     /// # Panics
     /// Panics if called more than once.
-    pub fn init(level: VerbosityLevel, no_color: bool) {
-        let colors_enabled = !no_color
-            && std::env::var("NO_COLOR").is_err()
-            && atty::is(atty::Stream::Stdout);
+    pub fn init(level: VerbosityLevel, color: ColorMode) {
+        Self::init_with(level, color, false);
+    }
+
+    /// Initialize the global logger, writing every message to stderr.
+    ///
+    /// # Panics
+    /// Panics if called more than once.
+    pub fn init_on_stderr(level: VerbosityLevel, color: ColorMode) {
+        Self::init_with(level, color, true);
+    }
+
+    fn init_with(level: VerbosityLevel, color: ColorMode, stderr: bool) {
+        let colors_enabled = match color {
+            ColorMode::Auto => std::env::var("NO_COLOR").is_err() && is_terminal(stderr),
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        };
 
         START_TIME.set(Instant::now()).ok();
         LOGGER
-            .set(Logger { level, colors_enabled })
+            .set(Logger {
+                level,
+                colors_enabled,
+                stderr,
+            })
             .expect("Logger already initialized");
     }
 
+    /// Initialize a quiet logger for unit tests (safe to call repeatedly).
+    #[cfg(test)]
+    pub fn init_for_tests() {
+        LOGGER.get_or_init(|| Logger {
+            level: VerbosityLevel::Quiet,
+            colors_enabled: false,
+            stderr: false,
+        });
+    }
+
     /// Get the global logger instance.
     ///
     /// # Panics
@@ -65,10 +133,9 @@ impl Logger {
             .unwrap_or(0.0)
     }
 
-    /// Returns true if verbose mode is enabled.
-    #[allow(dead_code)]
+    /// Returns true if verbose (or trace) mode is enabled.
     pub fn is_verbose(&self) -> bool {
-        self.level == VerbosityLevel::Verbose
+        self.level >= VerbosityLevel::Verbose
     }
 
     /// Returns true if quiet mode is enabled.
@@ -77,11 +144,19 @@ impl Logger {
     }
 
     /// Returns the current verbosity level.
-    #[allow(dead_code)]
     pub fn level(&self) -> VerbosityLevel {
         self.level
     }
 
+    /// Print a line to the message stream.
+    fn print(&self, line: fmt::Arguments) {
+        if self.stderr {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+
     /// Log with level prefix and timestamp (verbose mode).
     fn log_with_level(&self, level: &str, msg: &str) {
         let elapsed = self.elapsed();
@@ -90,21 +165,21 @@ impl Logger {
                 "ERROR" => "\x1b[31m",
                 "WARN" => "\x1b[33m",
                 "INFO" => "\x1b[34m",
-                "DEBUG" => "\x1b[90m",
+                "DEBUG" | "TRACE" => "\x1b[90m",
                 _ => "",
             };
-            println!(
+            self.print(format_args!(
                 "\x1b[90m[{:.2}s]\x1b[0m {}[{}]\x1b[0m {}",
                 elapsed, level_color, level, msg
-            );
+            ));
         } else {
-            println!("[{:.2}s] [{}] {}", elapsed, level, msg);
+            self.print(format_args!("[{:.2}s] [{}] {}", elapsed, level, msg));
         }
     }
 
     /// Log an error message (always displayed).
     pub fn error(&self, msg: &str) {
-        if self.level == VerbosityLevel::Verbose {
+        if self.is_verbose() {
             let elapsed = self.elapsed();
             if self.colors_enabled {
                 eprintln!(
@@ -123,17 +198,19 @@ impl Logger {
     pub fn warn(&self, msg: &str) {
         match self.level {
             VerbosityLevel::Quiet => {}
-            VerbosityLevel::Normal => println!("{}", msg),
-            VerbosityLevel::Verbose => self.log_with_level("WARN", msg),
+            VerbosityLevel::Normal => self.print(format_args!("{}", msg)),
+            VerbosityLevel::Verbose | VerbosityLevel::Trace => self.log_with_level("WARN", msg),
         }
     }
 
     /// Output a file path (quiet: just path, normal: message, verbose: with prefix).
     pub fn output(&self, path: &str) {
         match self.level {
-            VerbosityLevel::Quiet => println!("{}", path),
-            VerbosityLevel::Normal => println!("Saved: {}", path),
-            VerbosityLevel::Verbose => self.log_with_level("INFO", &format!("Saved: {}", path)),
+            VerbosityLevel::Quiet => self.print(format_args!("{}", path)),
+            VerbosityLevel::Normal => self.print(format_args!("Saved: {}", path)),
+            VerbosityLevel::Verbose | VerbosityLevel::Trace => {
+                self.log_with_level("INFO", &format!("Saved: {}", path))
+            }
         }
     }
 
@@ -141,21 +218,27 @@ impl Logger {
     pub fn info(&self, msg: &str) {
         match self.level {
             VerbosityLevel::Quiet => {}
-            VerbosityLevel::Normal => println!("{}", msg),
-            VerbosityLevel::Verbose => self.log_with_level("INFO", msg),
+            VerbosityLevel::Normal => self.print(format_args!("{}", msg)),
+            VerbosityLevel::Verbose | VerbosityLevel::Trace => self.log_with_level("INFO", msg),
         }
     }
 
     /// Log a debug message (displayed only in verbose mode).
     pub fn debug(&self, msg: &str) {
-        if self.level == VerbosityLevel::Verbose {
+        if self.is_verbose() {
             self.log_with_level("DEBUG", msg);
         }
     }
 
+    /// Log a trace message (displayed only in trace mode).
+    pub fn trace(&self, msg: &str) {
+        if self.level == VerbosityLevel::Trace {
+            self.log_with_level("TRACE", msg);
+        }
+    }
+
     /// Write a message directly to stdout without newline (for progress bars).
     /// Only writes in normal mode.
-    #[allow(dead_code)]
     pub fn write(&self, msg: &str) {
         if self.level == VerbosityLevel::Normal {
             print!("{}", msg);
@@ -165,41 +248,55 @@ impl Logger {
 }
 
 /// Log an error message (always displayed).
-#[allow(dead_code)]
 pub fn error(msg: &str) {
-    Logger::instance().error(msg);
+    if let Some(logger) = LOGGER.get() {
+        logger.error(msg);
+    }
 }
 
 /// Log a warning message (normal and verbose modes).
 pub fn warn(msg: &str) {
-    Logger::instance().warn(msg);
+    if let Some(logger) = LOGGER.get() {
+        logger.warn(msg);
+    }
 }
 
 /// Output a file path.
 pub fn output(path: &str) {
-    Logger::instance().output(path);
+    if let Some(logger) = LOGGER.get() {
+        logger.output(path);
+    }
 }
 
 /// Log an info message (displayed in normal mode and above).
 pub fn info(msg: &str) {
-    Logger::instance().info(msg);
+    if let Some(logger) = LOGGER.get() {
+        logger.info(msg);
+    }
 }
 
 /// Log a debug message (displayed only in verbose mode).
 pub fn debug(msg: &str) {
-    Logger::instance().debug(msg);
+    if let Some(logger) = LOGGER.get() {
+        logger.debug(msg);
+    }
 }
 
-/// Returns true if verbose mode is enabled.
-#[allow(dead_code)]
+/// Log a trace message (displayed only in trace mode).
+pub fn trace(msg: &str) {
+    if let Some(logger) = LOGGER.get() {
+        logger.trace(msg);
+    }
+}
+
+/// Returns true if verbose (or trace) mode is enabled.
 pub fn is_verbose() -> bool {
-    Logger::instance().is_verbose()
+    LOGGER.get().is_some_and(Logger::is_verbose)
 }
 
 /// Returns true if quiet mode is enabled.
-#[allow(dead_code)]
 pub fn is_quiet() -> bool {
-    Logger::instance().is_quiet()
+    LOGGER.get().is_some_and(Logger::is_quiet)
 }
 
 #[cfg(test)]
@@ -210,6 +307,7 @@ mod tests {
     fn test_verbosity_level_ordering() {
         assert!((VerbosityLevel::Quiet as i32) < (VerbosityLevel::Normal as i32));
         assert!((VerbosityLevel::Normal as i32) < (VerbosityLevel::Verbose as i32));
+        assert!(VerbosityLevel::Verbose < VerbosityLevel::Trace);
     }
 
     #[test]
@@ -217,19 +315,29 @@ mod tests {
         let quiet_logger = Logger {
             level: VerbosityLevel::Quiet,
             colors_enabled: false,
+            stderr: false,
         };
         let normal_logger = Logger {
             level: VerbosityLevel::Normal,
             colors_enabled: false,
+            stderr: false,
         };
         let verbose_logger = Logger {
             level: VerbosityLevel::Verbose,
             colors_enabled: false,
+            stderr: false,
+        };
+
+        let trace_logger = Logger {
+            level: VerbosityLevel::Trace,
+            colors_enabled: false,
+            stderr: false,
         };
 
         assert!(!quiet_logger.is_verbose());
         assert!(!normal_logger.is_verbose());
         assert!(verbose_logger.is_verbose());
+        assert!(trace_logger.is_verbose());
     }
 
     #[test]
@@ -237,14 +345,17 @@ mod tests {
         let quiet_logger = Logger {
             level: VerbosityLevel::Quiet,
             colors_enabled: false,
+            stderr: false,
         };
         let normal_logger = Logger {
             level: VerbosityLevel::Normal,
             colors_enabled: false,
+            stderr: false,
         };
         let verbose_logger = Logger {
             level: VerbosityLevel::Verbose,
             colors_enabled: false,
+            stderr: false,
         };
 
         assert!(quiet_logger.is_quiet());
@@ -257,6 +368,7 @@ mod tests {
         let quiet_logger = Logger {
             level: VerbosityLevel::Quiet,
             colors_enabled: false,
+            stderr: false,
         };
 
         assert_eq!(quiet_logger.level(), VerbosityLevel::Quiet);
@@ -267,6 +379,7 @@ mod tests {
         let logger = Logger {
             level: VerbosityLevel::Verbose,
             colors_enabled: false,
+            stderr: false,
         };
         // Without START_TIME set, elapsed returns 0.0
         let elapsed = logger.elapsed();
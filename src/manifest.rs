@@ -0,0 +1,141 @@
+//! Run manifest (`--manifest`).
+//!
+//! Renders record each file they produce; at the end of the run the list is
+//! written as JSON, so downstream jobs can find the outputs and their grids
+//! without parsing the human-oriented log.
+
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::math::Bbox;
+
+/// Version of the manifest layout, bumped on incompatible changes.
+pub const MANIFEST_VERSION: u32 = 1;
+
+static ENTRIES: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+/// A file written by the run, or found up to date with `--content-addressed`.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub input: PathBuf,
+    pub layer: String,
+    /// Bbox of the saved image (after `--autocrop`).
+    pub bbox: Bbox,
+    pub width: u32,
+    pub height: u32,
+    /// Degrees per pixel.
+    pub resolution: f64,
+    /// Features rendered, None when the file was up to date.
+    pub features: Option<u64>,
+    /// Time spent on the file, None when it was up to date.
+    pub timings: Option<Timings>,
+}
+
+/// Time spent reading, rendering and saving one file.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timings {
+    pub read: Duration,
+    pub render: Duration,
+    pub save: Duration,
+    pub total: Duration,
+}
+
+/// Records a produced file. Safe to call from concurrent layer renders.
+pub fn record(entry: Entry) {
+    ENTRIES.lock().unwrap_or_else(PoisonError::into_inner).push(entry);
+}
+
+/// Files recorded so far, in completion order.
+pub fn entries() -> Vec<Entry> {
+    ENTRIES.lock().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// Builds the manifest of a run that took `duration`; `complete` is false
+/// when it was interrupted or failed part way.
+pub fn to_json(entries: &[Entry], command: &str, complete: bool, duration: Duration) -> Value {
+    let millis = |d: Duration| (d.as_secs_f64() * 1e6).round() / 1e3;
+    let outputs: Vec<Value> = entries
+        .iter()
+        .map(|entry| {
+            let bbox = entry.bbox;
+            json!({
+                "path": entry.path.display().to_string(),
+                "input": entry.input.display().to_string(),
+                "layer": entry.layer,
+                "status": if entry.timings.is_some() { "rendered" } else { "up_to_date" },
+                "bbox": [bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat],
+                "width": entry.width,
+                "height": entry.height,
+                "resolution": entry.resolution,
+                "features": entry.features,
+                "timings_ms": entry.timings.map(|t| json!({
+                    "read": millis(t.read),
+                    "render": millis(t.render),
+                    "save": millis(t.save),
+                    "total": millis(t.total),
+                })),
+            })
+        })
+        .collect();
+    json!({
+        "manifest_version": MANIFEST_VERSION,
+        "version": env!("CARGO_PKG_VERSION"),
+        "command": command,
+        "complete": complete,
+        "duration_ms": millis(duration),
+        "outputs": outputs,
+    })
+}
+
+/// Writes the manifest of the recorded files to `path`.
+pub fn write(path: &Path, command: &str, complete: bool, duration: Duration) -> Result<()> {
+    let manifest = to_json(&entries(), command, complete, duration);
+    crate::output::ensure_parent_dir(path)?;
+    std::fs::write(path, format!("{:#}\n", manifest))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        let rendered = Entry {
+            path: PathBuf::from("out/roads.png"),
+            input: PathBuf::from("city.gpkg"),
+            layer: "roads".to_string(),
+            bbox: Bbox::new(-4.5, 48.0, -4.0, 48.5),
+            width: 500,
+            height: 500,
+            resolution: 0.001,
+            features: Some(42),
+            timings: Some(Timings {
+                read: Duration::from_micros(1500),
+                total: Duration::from_millis(3),
+                ..Timings::default()
+            }),
+        };
+        let up_to_date = Entry {
+            features: None,
+            timings: None,
+            ..rendered.clone()
+        };
+        let manifest = to_json(&[rendered, up_to_date], "gpkg-to-png city.gpkg", true, Duration::from_secs(1));
+
+        assert_eq!(manifest["complete"], true);
+        assert_eq!(manifest["duration_ms"], 1000.0);
+        let outputs = manifest["outputs"].as_array().unwrap();
+        assert_eq!(outputs[0]["path"], "out/roads.png");
+        assert_eq!(outputs[0]["status"], "rendered");
+        assert_eq!(outputs[0]["bbox"], json!([-4.5, 48.0, -4.0, 48.5]));
+        assert_eq!(outputs[0]["features"], 42);
+        assert_eq!(outputs[0]["timings_ms"]["read"], 1.5);
+        assert_eq!(outputs[1]["status"], "up_to_date");
+        assert!(outputs[1]["timings_ms"].is_null());
+    }
+}
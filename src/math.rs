@@ -33,13 +33,141 @@ impl Bbox {
     pub fn height(&self) -> f64 {
         self.max_lat - self.min_lat
     }
+
+    /// Returns the bbox grown by `padding` on each side.
+    ///
+    /// Latitudes stop at the poles, and a bbox growing past 360° of longitude
+    /// becomes the whole world.
+    pub fn padded(&self, padding: Padding) -> Bbox {
+        let (dx, dy) = match padding {
+            Padding::Ratio(ratio) => (self.width() * ratio, self.height() * ratio),
+            Padding::Degrees(degrees) => (degrees, degrees),
+            Padding::Meters(meters) => {
+                let center_lat = (self.min_lat + self.max_lat) / 2.0;
+                (meters / (111319.0 * center_lat.to_radians().cos()), meters / 111319.0)
+            }
+        };
+        let min_lat = (self.min_lat - dy).max(-90.0);
+        let max_lat = (self.max_lat + dy).min(90.0);
+        if self.width() + 2.0 * dx >= 360.0 {
+            return Bbox::new(-180.0, min_lat, 180.0, max_lat);
+        }
+        let (mut min_lon, mut max_lon) = (self.min_lon - dx, self.max_lon + dx);
+        // Keep the crossing representation: min_lon in range, max_lon past 180
+        if min_lon < -180.0 {
+            min_lon += 360.0;
+            max_lon += 360.0;
+        }
+        Bbox::new(min_lon, min_lat, max_lon, max_lat)
+    }
+
+    /// Returns the bbox expanded symmetrically to a width/height `ratio`.
+    ///
+    /// A bbox reaching past a pole is shifted back; one growing past 360° of
+    /// longitude or 180° of latitude is clamped and misses the ratio.
+    pub fn with_aspect(&self, ratio: f64) -> Bbox {
+        let (width, height) = (self.width(), self.height());
+        let (dx, dy) = if width < height * ratio {
+            ((height * ratio - width) / 2.0, 0.0)
+        } else {
+            (0.0, (width / ratio - height) / 2.0)
+        };
+        let (mut min_lat, mut max_lat) = (self.min_lat - dy, self.max_lat + dy);
+        let shift = (-90.0 - min_lat).max(0.0) - (max_lat - 90.0).max(0.0);
+        min_lat = (min_lat + shift).max(-90.0);
+        max_lat = (max_lat + shift).min(90.0);
+        if width + 2.0 * dx >= 360.0 {
+            return Bbox::new(-180.0, min_lat, 180.0, max_lat);
+        }
+        let (mut min_lon, mut max_lon) = (self.min_lon - dx, self.max_lon + dx);
+        if min_lon < -180.0 {
+            min_lon += 360.0;
+            max_lon += 360.0;
+        }
+        Bbox::new(min_lon, min_lat, max_lon, max_lat)
+    }
+
+    /// Returns the bbox, around the same center, that contains this one
+    /// rotated by `degrees`.
+    pub fn rotated_envelope(&self, degrees: f64) -> Bbox {
+        if degrees % 360.0 == 0.0 {
+            return *self;
+        }
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let (width, height) = (self.width(), self.height());
+        let half_width = (width * cos.abs() + height * sin.abs()) / 2.0;
+        let half_height = (width * sin.abs() + height * cos.abs()) / 2.0;
+        let (center_lon, center_lat) = self.center();
+        Bbox::new(
+            center_lon - half_width,
+            center_lat - half_height,
+            center_lon + half_width,
+            center_lat + half_height,
+        )
+    }
+
+    /// Returns the center of the bbox (longitude, latitude).
+    pub fn center(&self) -> (f64, f64) {
+        ((self.min_lon + self.max_lon) / 2.0, (self.min_lat + self.max_lat) / 2.0)
+    }
+
+    /// Returns true if the bbox crosses the antimeridian.
+    ///
+    /// Crossing bboxes are stored with `max_lon > 180` so the width stays positive.
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.max_lon > 180.0
+    }
+}
+
+/// Margin added on each side of a bbox.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Padding {
+    /// Fraction of the bbox width (left and right) and height (top and bottom).
+    Ratio(f64),
+    /// Degrees of longitude and latitude.
+    Degrees(f64),
+    /// Meters, converted at the bbox center latitude.
+    Meters(f64),
 }
 
 /// Calculate image dimensions (width, height) from bbox and resolution.
 pub fn calculate_dimensions(bbox: &Bbox, resolution: f64) -> (u32, u32) {
-    let width = (bbox.width() / resolution).ceil() as u32;
-    let height = (bbox.height() / resolution).ceil() as u32;
-    (width, height)
+    // Rounding noise must not add a pixel to an exact fit (e.g. 1080.0000001)
+    let pixels = |degrees: f64| {
+        let exact = degrees / resolution;
+        (exact * (1.0 - 1e-9)).ceil() as u32
+    };
+    (pixels(bbox.width()), pixels(bbox.height()))
+}
+
+/// Convert a scale in meters per pixel to a resolution in degrees per pixel,
+/// using the length of a degree of longitude at the bbox center latitude.
+pub fn scale_to_resolution(scale: f64, bbox: &Bbox) -> f64 {
+    let center_lat = (bbox.min_lat + bbox.max_lat) / 2.0;
+    scale / (111319.0 * center_lat.to_radians().cos())
+}
+
+/// Convert a resolution in degrees per pixel back to a scale in meters per
+/// pixel, the inverse of [`scale_to_resolution`].
+pub fn resolution_to_scale(resolution: f64, bbox: &Bbox) -> f64 {
+    let center_lat = (bbox.min_lat + bbox.max_lat) / 2.0;
+    resolution * 111319.0 * center_lat.to_radians().cos()
+}
+
+/// Resolution at which `bbox` is `width` and/or `height` pixels.
+///
+/// With both, the image fits within them and the other side follows the
+/// bbox aspect ratio.
+pub fn size_to_resolution(bbox: &Bbox, width: Option<u32>, height: Option<u32>) -> f64 {
+    let x = width.map_or(0.0, |w| bbox.width() / w as f64);
+    let y = height.map_or(0.0, |h| bbox.height() / h as f64);
+    let mut resolution = x.max(y);
+    // Rounding must not push `calculate_dimensions` one pixel over
+    let fits = |(w, h): (u32, u32)| w <= width.unwrap_or(u32::MAX) && h <= height.unwrap_or(u32::MAX);
+    while !fits(calculate_dimensions(bbox, resolution)) {
+        resolution *= 1.0 + f64::EPSILON;
+    }
+    resolution
 }
 
 /// Convert WGS84 coordinates to pixel coordinates
@@ -56,10 +184,87 @@ pub fn world_to_screen(
     (x, y)
 }
 
+/// Maps WGS84 coordinates to the pixels of a render whose frame, `bbox`,
+/// is rotated clockwise by `rotation` degrees around its center.
+#[derive(Debug, Clone, Copy)]
+pub struct ScreenTransform {
+    bbox: Bbox,
+    resolution: f64,
+    height: u32,
+    /// Sine and cosine of the rotation, None when north is up.
+    rotation: Option<(f64, f64)>,
+}
+
+impl ScreenTransform {
+    /// Creates the transform of an image `height` pixels high.
+    pub fn new(bbox: &Bbox, resolution: f64, height: u32, rotation: f64) -> Self {
+        Self {
+            bbox: *bbox,
+            resolution,
+            height,
+            rotation: (rotation % 360.0 != 0.0).then(|| rotation.to_radians().sin_cos()),
+        }
+    }
+
+    /// Converts a coordinate to screen space.
+    pub fn apply(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let Some((sin, cos)) = self.rotation else {
+            return world_to_screen(lon, lat, &self.bbox, self.resolution, self.height);
+        };
+        // North turns towards east: clockwise on screen, where north is up
+        let (center_lon, center_lat) = self.bbox.center();
+        let (dx, dy) = (lon - center_lon, lat - center_lat);
+        world_to_screen(
+            center_lon + dx * cos + dy * sin,
+            center_lat - dx * sin + dy * cos,
+            &self.bbox,
+            self.resolution,
+            self.height,
+        )
+    }
+
+    /// Returns the screen-space extent of `bbox` as (min x, min y, max x,
+    /// max y): the extent of its corners, which covers it when rotated.
+    pub fn apply_extent(&self, bbox: &Bbox) -> (f64, f64, f64, f64) {
+        let corners = [
+            (bbox.min_lon, bbox.min_lat),
+            (bbox.max_lon, bbox.min_lat),
+            (bbox.max_lon, bbox.max_lat),
+            (bbox.min_lon, bbox.max_lat),
+        ];
+        corners.iter().map(|&(lon, lat)| self.apply(lon, lat)).fold(
+            (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            |(min_x, min_y, max_x, max_y), (x, y)| (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+        )
+    }
+
+    /// Converts a screen position back to WGS84 coordinates.
+    pub fn invert(&self, x: f64, y: f64) -> (f64, f64) {
+        let lon = self.bbox.min_lon + x * self.resolution;
+        let lat = self.bbox.min_lat + (self.height as f64 - y) * self.resolution;
+        let Some((sin, cos)) = self.rotation else {
+            return (lon, lat);
+        };
+        let (center_lon, center_lat) = self.bbox.center();
+        let (dx, dy) = (lon - center_lon, lat - center_lat);
+        (center_lon + dx * cos - dy * sin, center_lat + dx * sin + dy * cos)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_scale_to_resolution() {
+        let equator = Bbox::new(0.0, -1.0, 1.0, 1.0);
+        assert!((scale_to_resolution(111319.0, &equator) - 1.0).abs() < 1e-9);
+        // A degree of longitude is half as long at 60°
+        let north = Bbox::new(0.0, 59.0, 1.0, 61.0);
+        assert!((scale_to_resolution(111319.0, &north) - 2.0).abs() < 1e-3);
+        assert!((resolution_to_scale(scale_to_resolution(50.0, &north), &north) - 50.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_bbox_dimensions() {
         let bbox = Bbox::new(-4.5, 48.0, -4.0, 48.5);
@@ -67,6 +272,84 @@ mod tests {
         assert!((bbox.height() - 0.5).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_bbox_crossing_antimeridian() {
+        let bbox = Bbox::new(170.0, -20.0, 190.0, -10.0);
+        assert!(bbox.crosses_antimeridian());
+        assert!((bbox.width() - 20.0).abs() < 1e-10);
+        assert!(!Bbox::new(-180.0, -90.0, 180.0, 90.0).crosses_antimeridian());
+    }
+
+    #[test]
+    fn test_padded() {
+        let bbox = Bbox::new(-4.5, 48.0, -4.0, 48.5);
+        let padded = bbox.padded(Padding::Ratio(0.1));
+        assert!((padded.min_lon + 4.55).abs() < 1e-10 && (padded.max_lat - 48.55).abs() < 1e-10);
+        let padded = bbox.padded(Padding::Degrees(1.0));
+        assert_eq!((padded.min_lon, padded.min_lat, padded.max_lon, padded.max_lat), (-5.5, 47.0, -3.0, 49.5));
+        // 1 km is wider in degrees of longitude than of latitude away from the equator
+        let padded = bbox.padded(Padding::Meters(1000.0));
+        assert!(bbox.min_lon - padded.min_lon > bbox.min_lat - padded.min_lat);
+
+        // Growing past -180 keeps a crossing bbox, past the poles stops there
+        let padded = Bbox::new(-179.5, 80.0, -170.0, 89.5).padded(Padding::Degrees(1.0));
+        assert!(padded.crosses_antimeridian());
+        assert_eq!((padded.min_lon, padded.max_lon, padded.max_lat), (179.5, 191.0, 90.0));
+        let padded = Bbox::new(-180.0, -10.0, 180.0, 10.0).padded(Padding::Ratio(0.05));
+        assert_eq!((padded.min_lon, padded.max_lon), (-180.0, 180.0));
+    }
+
+    #[test]
+    fn test_with_aspect() {
+        let bbox = Bbox::new(-4.5, 48.0, -4.0, 48.5);
+        let wide = bbox.with_aspect(16.0 / 9.0);
+        assert!((wide.width() / wide.height() - 16.0 / 9.0).abs() < 1e-10);
+        // Expanded symmetrically around the same center
+        assert!((wide.min_lon + wide.max_lon + 8.5).abs() < 1e-10);
+        assert_eq!((wide.min_lat, wide.max_lat), (48.0, 48.5));
+        let tall = bbox.with_aspect(0.5);
+        assert_eq!((tall.min_lon, tall.max_lon, tall.min_lat, tall.max_lat), (-4.5, -4.0, 47.75, 48.75));
+
+        // Shifted back below the pole
+        let arctic = Bbox::new(0.0, 85.0, 20.0, 89.0).with_aspect(1.0);
+        assert_eq!((arctic.min_lat, arctic.max_lat), (70.0, 90.0));
+    }
+
+    #[test]
+    fn test_rotated_envelope() {
+        let bbox = Bbox::new(0.0, 0.0, 4.0, 2.0);
+        assert_eq!(bbox.rotated_envelope(0.0).width(), 4.0);
+        let quarter = bbox.rotated_envelope(90.0);
+        assert!((quarter.width() - 2.0).abs() < 1e-10 && (quarter.height() - 4.0).abs() < 1e-10);
+        assert!((quarter.min_lon - 1.0).abs() < 1e-10);
+        let diagonal = Bbox::new(0.0, 0.0, 2.0, 2.0).rotated_envelope(-45.0);
+        assert!((diagonal.width() - 8.0_f64.sqrt()).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_screen_transform() {
+        let bbox = Bbox::new(0.0, 0.0, 10.0, 10.0);
+        let north_up = ScreenTransform::new(&bbox, 1.0, 10, 0.0);
+        assert_eq!(north_up.apply(2.0, 3.0), world_to_screen(2.0, 3.0, &bbox, 1.0, 10));
+
+        // A quarter turn clockwise brings north to the right
+        let rotated = ScreenTransform::new(&bbox, 1.0, 10, 90.0);
+        let (x, y) = rotated.apply(5.0, 8.0);
+        assert!((x - 8.0).abs() < 1e-10 && (y - 5.0).abs() < 1e-10);
+        let (x, y) = rotated.apply(5.0, 5.0);
+        assert!((x - 5.0).abs() < 1e-10 && (y - 5.0).abs() < 1e-10);
+
+        let extent = rotated.apply_extent(&Bbox::new(4.0, 6.0, 5.0, 8.0));
+        let rounded = |v: f64| (v * 1e9).round() / 1e9;
+        assert_eq!([extent.0, extent.1, extent.2, extent.3].map(rounded), [6.0, 4.0, 8.0, 5.0]);
+
+        for transform in [north_up, rotated, ScreenTransform::new(&bbox, 1.0, 10, -30.0)] {
+            let (x, y) = transform.apply(2.0, 7.5);
+            let (lon, lat) = transform.invert(x, y);
+            assert!((lon - 2.0).abs() < 1e-10 && (lat - 7.5).abs() < 1e-10);
+        }
+    }
+
     #[test]
     fn test_calculate_dimensions() {
         let bbox = Bbox::new(-4.5, 48.0, -4.0, 48.5);
@@ -76,6 +359,13 @@ mod tests {
         assert_eq!(height, 500);
     }
 
+    #[test]
+    fn test_calculate_dimensions_ignores_rounding_noise() {
+        let bbox = Bbox::new(-4.5, 48.0, -3.5, 48.5625);
+        let resolution = size_to_resolution(&bbox, Some(1920), None);
+        assert_eq!(calculate_dimensions(&bbox, resolution), (1920, 1080));
+    }
+
     #[test]
     fn test_calculate_dimensions_rounds_up() {
         let bbox = Bbox::new(0.0, 0.0, 1.0, 1.0);
@@ -85,6 +375,25 @@ mod tests {
         assert_eq!(height, 4);
     }
 
+    #[test]
+    fn test_size_to_resolution() {
+        let bbox = Bbox::new(-4.5, 48.0, -4.0, 48.25);
+        let resolution = size_to_resolution(&bbox, Some(2048), None);
+        assert_eq!(calculate_dimensions(&bbox, resolution), (2048, 1024));
+        let resolution = size_to_resolution(&bbox, None, Some(300));
+        assert_eq!(calculate_dimensions(&bbox, resolution), (600, 300));
+        // Both: the bbox fits within the requested size
+        let resolution = size_to_resolution(&bbox, Some(1000), Some(1000));
+        assert_eq!(calculate_dimensions(&bbox, resolution), (1000, 500));
+
+        // Sizes that do not divide the bbox evenly are not exceeded
+        let bbox = Bbox::new(0.1, 0.2, 0.7, 0.3);
+        for width in 1..500 {
+            let resolution = size_to_resolution(&bbox, Some(width), None);
+            assert_eq!(calculate_dimensions(&bbox, resolution).0, width);
+        }
+    }
+
     #[test]
     fn test_world_to_screen_origin() {
         let bbox = Bbox::new(0.0, 0.0, 1.0, 1.0);
@@ -0,0 +1,463 @@
+//! Output file layout.
+//!
+//! Decides where each rendered PNG goes inside the output directory, so batch
+//! runs over many inputs and layers can produce an organized tree. With
+//! content addressing, file names are derived from a hash of the input
+//! content, the layer and the render settings, so identical renders share a
+//! name and can be skipped or deduplicated.
+//!
+//! Layer names are sanitized into file names, so distinct layers may map to
+//! the same file: [`OutputClaims`] tracks the files of a run and resolves
+//! such collisions by the `--on-collision` policy.
+//!
+//! `--name-template` replaces the file name with a pattern such as
+//! `{layer}_{scale}m_{date}.png`, expanded by [`expand_name_template`].
+
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::error::{GpkgError, Result};
+use crate::math::Bbox;
+use crate::warning::{self, WarningCode};
+
+/// Number of hex characters kept from the SHA-256 for content-addressed names.
+const CONTENT_ID_LEN: usize = 32;
+
+/// How output files are arranged inside the output directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OutputLayout {
+    /// `<output-dir>/<layer>.png`
+    #[default]
+    Flat,
+    /// `<output-dir>/<layer>/<input>.png`
+    PerLayerDir,
+    /// `<output-dir>/<input>/<layer>.png`
+    PerInputDir,
+}
+
+/// What to do when two renders of a run resolve to the same output file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum CollisionPolicy {
+    /// Append `-1`, `-2`, ... to the later file names, with a warning.
+    #[default]
+    Suffix,
+    /// Fail instead of overwriting an output of the run.
+    Error,
+}
+
+/// Output files of a run, to keep renders from overwriting each other.
+///
+/// Paths are compared case-insensitively, as macOS and Windows file systems do.
+#[derive(Debug, Default)]
+pub struct OutputClaims {
+    claimed: HashSet<String>,
+}
+
+impl OutputClaims {
+    /// Claims `path` for the render `name` and returns the path to write.
+    ///
+    /// A path already claimed in the run is an error with
+    /// [`CollisionPolicy::Error`]; with [`CollisionPolicy::Suffix`], the first
+    /// free `<stem>-N.<ext>` is claimed instead.
+    pub fn claim(&mut self, path: PathBuf, name: &str, policy: CollisionPolicy) -> Result<PathBuf> {
+        if self.claimed.insert(claim_key(&path)) {
+            return Ok(path);
+        }
+        if policy == CollisionPolicy::Error {
+            return Err(GpkgError::OutputCollision(name.to_string(), path.display().to_string()));
+        }
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("output").to_string();
+        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("png").to_string();
+        let suffixed = (1..)
+            .map(|n| path.with_file_name(format!("{}-{}.{}", stem, n, extension)))
+            .find(|candidate| self.claimed.insert(claim_key(candidate)))
+            .expect("suffixes are unbounded");
+        warning::emit(
+            WarningCode::OutputNameCollision,
+            &format!(
+                "{}: {} is already an output of this run, saving to {}",
+                name,
+                path.display(),
+                suffixed.display()
+            ),
+        )?;
+        Ok(suffixed)
+    }
+}
+
+fn claim_key(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+/// Turns a layer name into a file name: path separators, characters reserved
+/// on Windows and control characters become `_`, and trailing dots and
+/// spaces are dropped.
+pub fn sanitize_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let sanitized = sanitized.trim_end_matches(['.', ' ']);
+    if sanitized.is_empty() {
+        "_".to_string()
+    } else {
+        sanitized.to_string()
+    }
+}
+
+/// Returns the input file name without extension, used to name directories and files.
+fn input_stem(input: &Path) -> &str {
+    input
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output")
+}
+
+/// Computes the output path of a render named `name` (layer or output name).
+///
+/// A `file_stem` (content id or expanded `--name-template`) replaces the
+/// file name in every layout.
+pub fn output_path(
+    output_dir: &Path,
+    layout: OutputLayout,
+    input: &Path,
+    name: &str,
+    file_stem: Option<&str>,
+) -> PathBuf {
+    let file_name = |default: &str| format!("{}.png", file_stem.unwrap_or(default));
+    match layout {
+        OutputLayout::Flat => output_dir.join(file_name(name)),
+        OutputLayout::PerLayerDir => output_dir.join(name).join(file_name(input_stem(input))),
+        OutputLayout::PerInputDir => output_dir.join(input_stem(input)).join(file_name(name)),
+    }
+}
+
+/// Placeholders accepted by `--name-template`.
+pub const NAME_PLACEHOLDERS: &[&str] = &[
+    "layer",
+    "input",
+    "bbox",
+    "resolution",
+    "scale",
+    "date",
+    "time",
+    "timestamp",
+];
+
+/// Values of the `--name-template` placeholders for one render.
+#[derive(Debug, Clone)]
+pub struct NameFields<'a> {
+    /// Output name of the layer (sanitized).
+    pub layer: &'a str,
+    pub input: &'a Path,
+    pub bbox: &'a Bbox,
+    /// Degrees per pixel.
+    pub resolution: f64,
+    /// Meters per pixel at the bbox center.
+    pub scale: f64,
+    /// Unix time of the run, shared by all its outputs.
+    pub timestamp: u64,
+}
+
+/// Checks that `template` only uses known placeholders and balanced braces,
+/// and names a file inside the output directory.
+pub fn check_name_template(template: &str) -> Result<()> {
+    let fields = NameFields {
+        layer: "",
+        input: Path::new(""),
+        bbox: &Bbox::new(0.0, 0.0, 0.0, 0.0),
+        resolution: 0.0,
+        scale: 0.0,
+        timestamp: 0,
+    };
+    let stem = expand_name_template(template, &fields)?;
+    if stem.is_empty() {
+        return Err(GpkgError::InvalidNameTemplate("the file name is empty".to_string()));
+    }
+    if Path::new(&stem).has_root() {
+        return Err(GpkgError::InvalidNameTemplate(format!(
+            "\"{}\" must be relative to the output directory",
+            template
+        )));
+    }
+    Ok(())
+}
+
+/// Expands the placeholders of `template` into a file stem (a trailing
+/// `.png` is dropped, [`output_path`] adds it back).
+///
+/// Dates are UTC; `{time}` is `HHMMSS` so names stay valid on Windows.
+pub fn expand_name_template(template: &str, fields: &NameFields) -> Result<String> {
+    let template = template.strip_suffix(".png").unwrap_or(template);
+    let invalid = |msg: String| GpkgError::InvalidNameTemplate(msg);
+    let (date, time) = utc_date_time(fields.timestamp);
+    let mut stem = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        stem.push_str(&rest[..start]);
+        if rest[start..].starts_with('}') {
+            return Err(invalid(format!("unmatched `}}` in \"{}\"", template)));
+        }
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| invalid(format!("unclosed `{{` in \"{}\"", template)))?;
+        let bbox = fields.bbox;
+        let value = match &rest[start + 1..start + end] {
+            "layer" => fields.layer.to_string(),
+            "input" => input_stem(fields.input).to_string(),
+            "bbox" => format!("{},{},{},{}", bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat),
+            "resolution" => fields.resolution.to_string(),
+            "scale" => format_scale(fields.scale),
+            "date" => date.clone(),
+            "time" => time.clone(),
+            "timestamp" => fields.timestamp.to_string(),
+            name => {
+                return Err(invalid(format!(
+                    "unknown placeholder {{{}}}, expected one of {}",
+                    name,
+                    NAME_PLACEHOLDERS.join(", ")
+                )))
+            }
+        };
+        stem.push_str(&sanitize_name(&value));
+        rest = &rest[start + end + 1..];
+    }
+    stem.push_str(rest);
+    Ok(stem)
+}
+
+/// Scale rounded to the centimeter, without trailing zeros.
+fn format_scale(scale: f64) -> String {
+    let scale = format!("{:.2}", scale);
+    scale.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Formats a Unix time as UTC `YYYY-MM-DD` and `HHMMSS`.
+pub(crate) fn utc_date_time(timestamp: u64) -> (String, String) {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+    // Days to civil date (proleptic Gregorian), after Howard Hinnant
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (
+        format!("{:04}-{:02}-{:02}", year, month, day),
+        format!("{:02}{:02}{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60),
+    )
+}
+
+/// Returns the SHA-256 of a file's content, as hex.
+pub fn file_digest(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Derives a content-addressed name from the input digest, the render name
+/// and a canonical description of the render settings.
+pub fn content_id(input_digest: &str, name: &str, settings: &[(&str, String)]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(input_digest.as_bytes());
+    hasher.update([0]);
+    hasher.update(name.as_bytes());
+    for (key, value) in settings {
+        hasher.update([0]);
+        hasher.update(key.as_bytes());
+        hasher.update(b"=");
+        hasher.update(value.as_bytes());
+    }
+    let mut id = hex::encode(hasher.finalize());
+    id.truncate(CONTENT_ID_LEN);
+    id
+}
+
+/// Returns where a render interrupted by `--save-partial` is saved: `<name>.partial.png`.
+pub fn partial_path(path: &Path) -> PathBuf {
+    path.with_extension("partial.png")
+}
+
+/// Returns where the `--overviews` level reduced `factor` times is saved: `<name>.ov<factor>.png`.
+pub fn overview_path(path: &Path, factor: u32) -> PathBuf {
+    path.with_extension(format!("ov{}.png", factor))
+}
+
+/// Creates the parent directory of an output path if needed.
+pub fn ensure_parent_dir(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_path() {
+        assert_eq!(partial_path(Path::new("out/roads.png")), PathBuf::from("out/roads.partial.png"));
+    }
+
+    #[test]
+    fn test_overview_path() {
+        assert_eq!(overview_path(Path::new("out/roads.png"), 4), PathBuf::from("out/roads.ov4.png"));
+    }
+
+    #[test]
+    fn test_flat_layout() {
+        let path = output_path(
+            Path::new("out"),
+            OutputLayout::Flat,
+            Path::new("data/city.gpkg"),
+            "roads",
+            None,
+        );
+        assert_eq!(path, PathBuf::from("out/roads.png"));
+    }
+
+    #[test]
+    fn test_per_layer_dir_layout() {
+        let path = output_path(
+            Path::new("out"),
+            OutputLayout::PerLayerDir,
+            Path::new("data/city.gpkg"),
+            "roads",
+            None,
+        );
+        assert_eq!(path, PathBuf::from("out/roads/city.png"));
+    }
+
+    #[test]
+    fn test_per_input_dir_layout() {
+        let path = output_path(
+            Path::new("out"),
+            OutputLayout::PerInputDir,
+            Path::new("data/city.gpkg"),
+            "roads",
+            None,
+        );
+        assert_eq!(path, PathBuf::from("out/city/roads.png"));
+    }
+
+    #[test]
+    fn test_content_id_replaces_file_name() {
+        let path = output_path(
+            Path::new("out"),
+            OutputLayout::PerLayerDir,
+            Path::new("data/city.gpkg"),
+            "roads",
+            Some("abc123"),
+        );
+        assert_eq!(path, PathBuf::from("out/roads/abc123.png"));
+    }
+
+    #[test]
+    fn test_content_id_is_stable() {
+        let settings = [("resolution", "0.001".to_string())];
+        let id = content_id("digest", "roads", &settings);
+        assert_eq!(id.len(), CONTENT_ID_LEN);
+        assert_eq!(id, content_id("digest", "roads", &settings));
+        assert_ne!(id, content_id("digest", "rivers", &settings));
+        assert_ne!(id, content_id("other", "roads", &settings));
+        assert_ne!(id, content_id("digest", "roads", &[("resolution", "0.002".to_string())]));
+    }
+
+    #[test]
+    fn test_file_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.txt");
+        std::fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            file_digest(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_ensure_parent_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a/b/layer.png");
+        ensure_parent_dir(&path).unwrap();
+        assert!(dir.path().join("a/b").is_dir());
+    }
+
+    #[test]
+    fn test_sanitize_name() {
+        assert_eq!(sanitize_name("roads"), "roads");
+        assert_eq!(sanitize_name("main/roads: 2024?"), "main_roads_ 2024_");
+        assert_eq!(sanitize_name("draft. "), "draft");
+        assert_eq!(sanitize_name(".."), "_");
+    }
+
+    #[test]
+    fn test_output_claims() {
+        let mut claims = OutputClaims::default();
+        let claim = |claims: &mut OutputClaims, path: &str, policy| claims.claim(PathBuf::from(path), "roads", policy);
+        assert_eq!(claim(&mut claims, "out/roads.png", CollisionPolicy::Suffix).unwrap(), PathBuf::from("out/roads.png"));
+        assert_eq!(claim(&mut claims, "out/Roads.png", CollisionPolicy::Suffix).unwrap(), PathBuf::from("out/Roads-1.png"));
+        assert_eq!(claim(&mut claims, "out/roads.png", CollisionPolicy::Suffix).unwrap(), PathBuf::from("out/roads-2.png"));
+        assert_eq!(claim(&mut claims, "other/roads.png", CollisionPolicy::Error).unwrap(), PathBuf::from("other/roads.png"));
+        assert!(matches!(
+            claim(&mut claims, "out/roads.png", CollisionPolicy::Error),
+            Err(GpkgError::OutputCollision(..))
+        ));
+    }
+
+    #[test]
+    fn test_expand_name_template() {
+        let bbox = Bbox::new(-4.5, 48.0, -4.0, 48.5);
+        let fields = NameFields {
+            layer: "roads",
+            input: Path::new("data/city.gpkg"),
+            bbox: &bbox,
+            resolution: 0.001,
+            scale: 74.62500001,
+            timestamp: 1_760_536_530,
+        };
+        let expand = |template: &str| expand_name_template(template, &fields);
+        assert_eq!(expand("{layer}_{scale}m_{date}.png").unwrap(), "roads_74.63m_2025-10-15");
+        assert_eq!(expand("{input}-{layer}-{time}").unwrap(), "city-roads-135530");
+        assert_eq!(expand("{bbox}@{resolution}").unwrap(), "-4.5,48,-4,48.5@0.001");
+        assert_eq!(expand("{timestamp}").unwrap(), "1760536530");
+        assert_eq!(expand("plain").unwrap(), "plain");
+
+        assert!(expand("{layer").is_err());
+        assert!(expand("layer}").is_err());
+        assert!(expand("{zoom}").unwrap_err().to_string().contains("unknown placeholder {zoom}"));
+        assert!(check_name_template("{layer}_{date}.png").is_ok());
+        assert!(check_name_template(".png").is_err());
+        assert!(check_name_template("/tmp/{layer}").is_err());
+    }
+
+    #[test]
+    fn test_utc_date_time() {
+        assert_eq!(utc_date_time(0), ("1970-01-01".to_string(), "000000".to_string()));
+        assert_eq!(utc_date_time(951_782_400), ("2000-02-29".to_string(), "000000".to_string()));
+        assert_eq!(utc_date_time(1_767_225_599), ("2025-12-31".to_string(), "235959".to_string()));
+    }
+}
@@ -0,0 +1,82 @@
+//! Scale preview montages (`--preview-scales`).
+//!
+//! Before launching a huge render, users can compare several candidate
+//! scales side by side. Each panel shows a window of at most
+//! `PANEL_SIZE`x`PANEL_SIZE` pixels around the bbox center, rendered at its
+//! scale with the final pixel size, so the level of detail is what the full
+//! render would produce.
+
+use image::{Rgba, RgbaImage};
+
+use crate::math::Bbox;
+
+/// Maximum width and height of a preview panel, in pixels.
+pub const PANEL_SIZE: u32 = 512;
+
+/// Transparent gap between panels, in pixels.
+const GAP: u32 = 8;
+
+/// Returns the window of a panel: `PANEL_SIZE` pixels at `resolution` around
+/// the bbox center, clipped to the bbox.
+pub fn panel_bbox(bbox: &Bbox, resolution: f64) -> Bbox {
+    let half = PANEL_SIZE as f64 * resolution / 2.0;
+    let half_lon = half.min(bbox.width() / 2.0);
+    let half_lat = half.min(bbox.height() / 2.0);
+    let center_lon = (bbox.min_lon + bbox.max_lon) / 2.0;
+    let center_lat = (bbox.min_lat + bbox.max_lat) / 2.0;
+    Bbox::new(
+        center_lon - half_lon,
+        center_lat - half_lat,
+        center_lon + half_lon,
+        center_lat + half_lat,
+    )
+}
+
+/// Lays panels out left to right, top-aligned, separated by a transparent gap.
+pub fn montage(panels: &[RgbaImage]) -> RgbaImage {
+    let width = panels.iter().map(|p| p.width()).sum::<u32>()
+        + GAP * panels.len().saturating_sub(1) as u32;
+    let height = panels.iter().map(|p| p.height()).max().unwrap_or(0);
+
+    let mut image = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 0]));
+    let mut x = 0i64;
+    for panel in panels {
+        image::imageops::replace(&mut image, panel, x, 0);
+        x += (panel.width() + GAP) as i64;
+    }
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panel_bbox_is_centered() {
+        let bbox = Bbox::new(0.0, 0.0, 10.0, 10.0);
+        let window = panel_bbox(&bbox, 0.001);
+        assert!((window.min_lon - (5.0 - 0.256)).abs() < 1e-9);
+        assert!((window.max_lat - (5.0 + 0.256)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_panel_bbox_clipped_to_bbox() {
+        let bbox = Bbox::new(0.0, 0.0, 10.0, 1.0);
+        let window = panel_bbox(&bbox, 0.01);
+        assert!((window.min_lat - 0.0).abs() < 1e-9);
+        assert!((window.max_lat - 1.0).abs() < 1e-9);
+        assert!((window.width() - 5.12).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_montage_layout() {
+        let red = RgbaImage::from_pixel(3, 2, Rgba([255, 0, 0, 255]));
+        let blue = RgbaImage::from_pixel(2, 4, Rgba([0, 0, 255, 255]));
+        let image = montage(&[red, blue]);
+        assert_eq!(image.dimensions(), (3 + GAP + 2, 4));
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(0, 3).0, [0, 0, 0, 0]);
+        assert_eq!(image.get_pixel(3, 0).0, [0, 0, 0, 0]);
+        assert_eq!(image.get_pixel(3 + GAP, 3).0, [0, 0, 255, 255]);
+    }
+}
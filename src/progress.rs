@@ -0,0 +1,219 @@
+//! Progress reporting.
+//!
+//! Calling `ProgressBar::set_position` for every feature adds measurable
+//! overhead on layers with millions of tiny geometries. `ProgressThrottle`
+//! batches updates so the bar is refreshed at most every N features
+//! (derived from the feature count) or every configured interval.
+//!
+//! With `--progress json`, bars are replaced by [`ProgressEvent`]s written
+//! to stdout as NDJSON (one object per line), for GUIs and web backends.
+
+use serde_json::json;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How progress is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ProgressMode {
+    /// Progress bars on the terminal.
+    #[default]
+    Bar,
+    /// NDJSON events on stdout; log messages move to stderr.
+    Json,
+}
+
+/// A machine-readable progress event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent<'a> {
+    /// An input of a batch is about to be processed (`index` starts at 1).
+    InputStarted { input: &'a Path, index: usize, total: usize },
+    /// A layer is about to be read and rendered.
+    LayerStarted {
+        layer: &'a str,
+        features: u64,
+        width: u32,
+        height: u32,
+    },
+    /// A chunk of geometries was read.
+    GeometriesRead { layer: &'a str, read: u64, total: u64 },
+    /// Rendering progress of a layer.
+    Rendered { layer: &'a str, percent: f64 },
+    /// A layer produced no image.
+    LayerSkipped { layer: &'a str, reason: &'a str },
+    /// An output file was written.
+    FileSaved { layer: &'a str, path: &'a Path },
+}
+
+impl ProgressEvent<'_> {
+    /// Serializes the event as a single-line JSON object.
+    pub fn to_json(&self) -> String {
+        let value = match self {
+            Self::InputStarted { input, index, total } => json!({
+                "event": "input_started",
+                "input": input.display().to_string(),
+                "index": index,
+                "total": total,
+            }),
+            Self::LayerStarted {
+                layer,
+                features,
+                width,
+                height,
+            } => json!({
+                "event": "layer_started",
+                "layer": layer,
+                "features": features,
+                "width": width,
+                "height": height,
+            }),
+            Self::GeometriesRead { layer, read, total } => json!({
+                "event": "geometries_read",
+                "layer": layer,
+                "read": read,
+                "total": total,
+            }),
+            Self::Rendered { layer, percent } => json!({
+                "event": "rendered",
+                "layer": layer,
+                "percent": (percent * 10.0).round() / 10.0,
+            }),
+            Self::LayerSkipped { layer, reason } => json!({
+                "event": "layer_skipped",
+                "layer": layer,
+                "reason": reason,
+            }),
+            Self::FileSaved { layer, path } => json!({
+                "event": "file_saved",
+                "layer": layer,
+                "path": path.display().to_string(),
+            }),
+        };
+        value.to_string()
+    }
+
+    /// Writes the event to stdout in JSON mode; does nothing otherwise.
+    pub fn emit(&self, mode: ProgressMode) {
+        if mode == ProgressMode::Json {
+            let mut stdout = std::io::stdout().lock();
+            writeln!(stdout, "{}", self.to_json()).ok();
+            stdout.flush().ok();
+        }
+    }
+}
+
+/// Maximum number of count-based updates over a full run.
+const MAX_UPDATES: u64 = 1000;
+
+/// Decides when a progress position is worth pushing to the display.
+#[derive(Debug)]
+pub struct ProgressThrottle {
+    every: u64,
+    interval: Duration,
+    last_pos: u64,
+    last_time: Instant,
+}
+
+impl ProgressThrottle {
+    /// Creates a throttle for `total` items, refreshing every thousandth of
+    /// `total` and whenever `interval` has elapsed since the last refresh, so
+    /// that a slow stream is not left without updates.
+    ///
+    /// An interval of zero disables throttling (every position is reported).
+    pub fn new(total: u64, interval: Duration) -> Self {
+        let every = if interval.is_zero() {
+            1
+        } else {
+            (total / MAX_UPDATES).max(1)
+        };
+        Self {
+            every,
+            interval,
+            last_pos: 0,
+            last_time: Instant::now(),
+        }
+    }
+
+    /// Returns true if `pos` should be displayed, recording it as the last update.
+    pub fn tick(&mut self, pos: u64) -> bool {
+        if pos - self.last_pos >= self.every || self.last_time.elapsed() >= self.interval {
+            self.last_pos = pos;
+            self.last_time = Instant::now();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_totals_update_every_item() {
+        let mut throttle = ProgressThrottle::new(10, Duration::from_millis(50));
+        assert!((1..=10).all(|pos| throttle.tick(pos)));
+    }
+
+    #[test]
+    fn test_large_totals_are_chunked() {
+        let mut throttle = ProgressThrottle::new(1_000_000, Duration::from_secs(3600));
+        let updates = (1..=1_000_000).filter(|&pos| throttle.tick(pos)).count();
+        assert_eq!(updates as u64, MAX_UPDATES);
+    }
+
+    #[test]
+    fn test_slow_streams_refresh_after_interval() {
+        let mut throttle = ProgressThrottle::new(1_000_000, Duration::from_millis(20));
+        assert!(!throttle.tick(1));
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(throttle.tick(2));
+        assert!(!throttle.tick(3));
+    }
+
+    #[test]
+    fn test_event_json() {
+        let event = ProgressEvent::GeometriesRead {
+            layer: "parcels",
+            read: 4096,
+            total: 10000,
+        };
+        assert_eq!(
+            event.to_json(),
+            r#"{"event":"geometries_read","layer":"parcels","read":4096,"total":10000}"#
+        );
+
+        let event = ProgressEvent::Rendered {
+            layer: "parcels",
+            percent: 100.0 / 3.0,
+        };
+        assert_eq!(event.to_json(), r#"{"event":"rendered","layer":"parcels","percent":33.3}"#);
+
+        let event = ProgressEvent::InputStarted {
+            input: Path::new("data/a.gpkg"),
+            index: 1,
+            total: 3,
+        };
+        assert_eq!(
+            event.to_json(),
+            r#"{"event":"input_started","index":1,"input":"data/a.gpkg","total":3}"#
+        );
+
+        let event = ProgressEvent::FileSaved {
+            layer: "a\"b",
+            path: Path::new("out/a.png"),
+        };
+        assert_eq!(
+            event.to_json(),
+            r#"{"event":"file_saved","layer":"a\"b","path":"out/a.png"}"#
+        );
+    }
+
+    #[test]
+    fn test_zero_interval_disables_throttling() {
+        let mut throttle = ProgressThrottle::new(1_000_000, Duration::ZERO);
+        assert!((1..=5000).all(|pos| throttle.tick(pos)));
+    }
+}
@@ -0,0 +1,163 @@
+//! Location of PROJ resource files for self-contained builds.
+//!
+//! `libproj` is linked statically (`bundled_proj`), but it still needs its
+//! `proj.db` database at runtime to resolve EPSG codes. When neither
+//! `PROJ_DATA` nor `PROJ_LIB` is set, the data is looked up — in order — in a
+//! copy embedded at build time (`bundled-proj-data` feature), then next to
+//! the executable (`<exe dir>/proj` or `<exe dir>/../share/proj`). The first
+//! hit is exported as `PROJ_DATA` before any projection is created.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Name of the PROJ database file.
+pub const PROJ_DB: &str = "proj.db";
+
+/// Where the PROJ data used by this process comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjDataSource {
+    /// `PROJ_DATA` or `PROJ_LIB` was set by the user.
+    Environment(PathBuf),
+    /// Extracted from the copy embedded in the binary.
+    #[cfg_attr(not(feature = "bundled-proj-data"), allow(dead_code))]
+    Embedded(PathBuf),
+    /// Found next to the executable.
+    Bundled(PathBuf),
+    /// Not found; `libproj` falls back to its compiled-in search path.
+    Missing,
+}
+
+impl ProjDataSource {
+    /// Returns the data directory, if one was found.
+    pub fn dir(&self) -> Option<&Path> {
+        match self {
+            Self::Environment(dir) | Self::Embedded(dir) | Self::Bundled(dir) => Some(dir),
+            Self::Missing => None,
+        }
+    }
+}
+
+static SOURCE: OnceLock<ProjDataSource> = OnceLock::new();
+
+/// Locates the PROJ data and exports it as `PROJ_DATA` (once per process).
+///
+/// Must run before the first `proj` context is created, while the process is
+/// still effectively single-threaded.
+pub fn init() -> &'static ProjDataSource {
+    SOURCE.get_or_init(|| {
+        let source = locate();
+        if let ProjDataSource::Embedded(dir) | ProjDataSource::Bundled(dir) = &source {
+            env::set_var("PROJ_DATA", dir);
+        }
+        source
+    })
+}
+
+/// Exports the PROJ settings of the command line to the environment
+/// variables libproj reads when creating a context: `--proj-data` as
+/// `PROJ_DATA`, `--proj-network` as `PROJ_NETWORK` and `--proj-only-best` as
+/// `PROJ_ONLY_BEST_DEFAULT`.
+///
+/// Must run before [`init`], so `--proj-data` takes precedence over the
+/// environment and the bundled data.
+pub fn configure(data_dir: Option<&Path>, network: bool, only_best: bool) {
+    if let Some(dir) = data_dir {
+        env::set_var("PROJ_DATA", dir);
+    }
+    if network {
+        env::set_var("PROJ_NETWORK", "ON");
+    }
+    if only_best {
+        env::set_var("PROJ_ONLY_BEST_DEFAULT", "YES");
+    }
+}
+
+/// Finds the PROJ data directory without touching the environment.
+fn locate() -> ProjDataSource {
+    for var in ["PROJ_DATA", "PROJ_LIB"] {
+        if let Some(dir) = env::var_os(var).filter(|v| !v.is_empty()) {
+            return ProjDataSource::Environment(PathBuf::from(dir));
+        }
+    }
+
+    // Runs before the logger is set up; an unwritable temp dir falls through
+    #[cfg(feature = "bundled-proj-data")]
+    if let Ok(dir) = embedded::extract() {
+        return ProjDataSource::Embedded(dir);
+    }
+
+    env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().and_then(bundled_dir))
+        .map_or(ProjDataSource::Missing, ProjDataSource::Bundled)
+}
+
+/// Returns the PROJ data directory shipped alongside an executable in `exe_dir`.
+fn bundled_dir(exe_dir: &Path) -> Option<PathBuf> {
+    [exe_dir.join("proj"), exe_dir.join("../share/proj")]
+        .into_iter()
+        .find(|dir| dir.join(PROJ_DB).is_file())
+}
+
+#[cfg(feature = "bundled-proj-data")]
+mod embedded {
+    use std::io;
+    use std::path::PathBuf;
+
+    /// `proj.db` embedded at build time.
+    static PROJ_DB_BYTES: &[u8] = include_bytes!(env!(
+        "GPKG_TO_PNG_PROJ_DB",
+        "set GPKG_TO_PNG_PROJ_DB to the proj.db to embed (bundled-proj-data feature)"
+    ));
+
+    /// Writes the embedded database to a per-version cache directory and returns it.
+    ///
+    /// The file is written once, then reused by later runs.
+    pub fn extract() -> io::Result<PathBuf> {
+        let dir = std::env::temp_dir()
+            .join(format!("gpkg-to-png-{}-proj", env!("CARGO_PKG_VERSION")));
+        let path = dir.join(super::PROJ_DB);
+        let up_to_date = std::fs::metadata(&path)
+            .map(|m| m.len() == PROJ_DB_BYTES.len() as u64)
+            .unwrap_or(false);
+        if !up_to_date {
+            std::fs::create_dir_all(&dir)?;
+            // Rename into place so concurrent runs never see a partial file
+            let tmp = dir.join(format!("{}.{}.tmp", super::PROJ_DB, std::process::id()));
+            std::fs::write(&tmp, PROJ_DB_BYTES)?;
+            std::fs::rename(&tmp, &path)?;
+        }
+        Ok(dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_dir_next_to_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        assert_eq!(bundled_dir(&bin), None);
+
+        let share = dir.path().join("share/proj");
+        std::fs::create_dir_all(&share).unwrap();
+        std::fs::write(share.join(PROJ_DB), b"").unwrap();
+        assert_eq!(bundled_dir(&bin), Some(bin.join("../share/proj")));
+
+        let local = bin.join("proj");
+        std::fs::create_dir_all(&local).unwrap();
+        std::fs::write(local.join(PROJ_DB), b"").unwrap();
+        assert_eq!(bundled_dir(&bin), Some(local));
+    }
+
+    #[test]
+    fn test_source_dir() {
+        assert_eq!(ProjDataSource::Missing.dir(), None);
+        let source = ProjDataSource::Bundled(PathBuf::from("/opt/proj"));
+        assert_eq!(source.dir(), Some(Path::new("/opt/proj")));
+    }
+}
@@ -0,0 +1,204 @@
+//! Color ramps for fills driven by a numeric attribute (`--ramp`).
+//!
+//! A ramp maps a position from 0 to 1 to a color, interpolating linearly
+//! between its stops. Perceptual (viridis, magma) and ColorBrewer ramps are
+//! built in and selected by name, `_r` reversing them; other ramps are given
+//! as lists of colors, evenly spaced or at explicit positions.
+
+use crate::error::{GpkgError, Result};
+use crate::style::parse_color;
+
+/// Built-in ramps and their evenly spaced stops.
+const BUILT_IN: [(&str, &[u32]); 12] = [
+    (
+        "viridis",
+        &[0x440154, 0x472D7B, 0x3B528B, 0x2C728E, 0x21908C, 0x27AD81, 0x5DC863, 0xAADC32, 0xFDE725],
+    ),
+    (
+        "magma",
+        &[0x000004, 0x1D1147, 0x51127C, 0x822681, 0xB63679, 0xE65164, 0xFB8861, 0xFEC287, 0xFCFDBF],
+    ),
+    (
+        "RdYlBu",
+        &[
+            0xA50026, 0xD73027, 0xF46D43, 0xFDAE61, 0xFEE090, 0xFFFFBF, 0xE0F3F8, 0xABD9E9, 0x74ADD1, 0x4575B4,
+            0x313695,
+        ],
+    ),
+    (
+        "RdYlGn",
+        &[
+            0xA50026, 0xD73027, 0xF46D43, 0xFDAE61, 0xFEE08B, 0xFFFFBF, 0xD9EF8B, 0xA6D96A, 0x66BD63, 0x1A9850,
+            0x006837,
+        ],
+    ),
+    (
+        "RdBu",
+        &[
+            0x67001F, 0xB2182B, 0xD6604D, 0xF4A582, 0xFDDBC7, 0xF7F7F7, 0xD1E5F0, 0x92C5DE, 0x4393C3, 0x2166AC,
+            0x053061,
+        ],
+    ),
+    (
+        "Spectral",
+        &[
+            0x9E0142, 0xD53E4F, 0xF46D43, 0xFDAE61, 0xFEE08B, 0xFFFFBF, 0xE6F598, 0xABDDA4, 0x66C2A5, 0x3288BD,
+            0x5E4FA2,
+        ],
+    ),
+    (
+        "Blues",
+        &[0xF7FBFF, 0xDEEBF7, 0xC6DBEF, 0x9ECAE1, 0x6BAED6, 0x4292C6, 0x2171B5, 0x08519C, 0x08306B],
+    ),
+    (
+        "Greens",
+        &[0xF7FCF5, 0xE5F5E0, 0xC7E9C0, 0xA1D99B, 0x74C476, 0x41AB5D, 0x238B45, 0x006D2C, 0x00441B],
+    ),
+    (
+        "Reds",
+        &[0xFFF5F0, 0xFEE0D2, 0xFCBBA1, 0xFC9272, 0xFB6A4A, 0xEF3B2C, 0xCB181D, 0xA50F15, 0x67000D],
+    ),
+    (
+        "Greys",
+        &[0xFFFFFF, 0xF0F0F0, 0xD9D9D9, 0xBDBDBD, 0x969696, 0x737373, 0x525252, 0x252525, 0x000000],
+    ),
+    (
+        "YlOrRd",
+        &[0xFFFFCC, 0xFFEDA0, 0xFED976, 0xFEB24C, 0xFD8D3C, 0xFC4E2A, 0xE31A1C, 0xBD0026, 0x800026],
+    ),
+    (
+        "YlGnBu",
+        &[0xFFFFD9, 0xEDF8B1, 0xC7E9B4, 0x7FCDBB, 0x41B6C4, 0x1D91C0, 0x225EA8, 0x253494, 0x081D58],
+    ),
+];
+
+/// A color ramp: colors at increasing positions from 0 to 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ramp {
+    stops: Vec<(f64, [u8; 3])>,
+}
+
+impl Ramp {
+    /// Returns the built-in ramp `name` (case-insensitive), reversed with a
+    /// `_r` suffix.
+    pub fn named(name: &str) -> Option<Ramp> {
+        let (base, reversed) = match name.strip_suffix("_r") {
+            Some(base) => (base, true),
+            None => (name, false),
+        };
+        let (_, colors) = BUILT_IN.iter().find(|(n, _)| n.eq_ignore_ascii_case(base))?;
+        let colors = colors.iter().map(|c| {
+            let [_, r, g, b] = c.to_be_bytes();
+            [r, g, b]
+        });
+        let ramp = Ramp::even(colors.collect());
+        Some(if reversed { ramp.reversed() } else { ramp })
+    }
+
+    /// Names of the built-in ramps.
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        BUILT_IN.iter().map(|(name, _)| *name)
+    }
+
+    /// Parses a built-in ramp name, or a list of at least two colors (hex or
+    /// CSS names): evenly spaced ("#FFFFCC,orange,#800026") or each at a
+    /// position from 0 to 1 ("0:white,0.2:orange,1:darkred").
+    pub fn parse(s: &str) -> Result<Ramp> {
+        if let Some(ramp) = Ramp::named(s.trim()) {
+            return Ok(ramp);
+        }
+        if !s.contains(',') {
+            return Err(GpkgError::InvalidColor(format!(
+                "unknown ramp \"{}\" (expected one of {}, optionally with _r, or a list of colors)",
+                s,
+                Ramp::names().collect::<Vec<_>>().join(", ")
+            )));
+        }
+
+        let invalid = |stop: &str| GpkgError::InvalidColor(format!("invalid ramp stop \"{}\" in \"{}\"", stop, s));
+        let stops = s.split(',').map(str::trim).collect::<Vec<_>>();
+        if stops.iter().all(|stop| !stop.contains(':')) {
+            let colors = stops.iter().map(|stop| parse_color(stop).ok_or_else(|| invalid(stop)));
+            return Ok(Ramp::even(colors.collect::<Result<_>>()?));
+        }
+
+        let mut positioned = Vec::with_capacity(stops.len());
+        for stop in stops {
+            let (position, color) = stop.split_once(':').ok_or_else(|| invalid(stop))?;
+            let position: f64 = position.trim().parse().map_err(|_| invalid(stop))?;
+            let color = parse_color(color).ok_or_else(|| invalid(stop))?;
+            if !(0.0..=1.0).contains(&position) || positioned.last().is_some_and(|&(last, _)| position < last) {
+                return Err(GpkgError::InvalidColor(format!(
+                    "ramp positions must increase from 0 to 1, got \"{}\"",
+                    s
+                )));
+            }
+            positioned.push((position, color));
+        }
+        Ok(Ramp { stops: positioned })
+    }
+
+    /// A ramp through `colors` at evenly spaced positions.
+    fn even(colors: Vec<[u8; 3]>) -> Ramp {
+        let last = colors.len().saturating_sub(1).max(1) as f64;
+        Ramp {
+            stops: colors.into_iter().enumerate().map(|(i, c)| (i as f64 / last, c)).collect(),
+        }
+    }
+
+    /// The same ramp running the other way.
+    pub fn reversed(&self) -> Ramp {
+        Ramp {
+            stops: self.stops.iter().rev().map(|&(position, color)| (1.0 - position, color)).collect(),
+        }
+    }
+
+    /// Returns the color at `position`, clamped to 0-1.
+    pub fn color(&self, position: f64) -> [u8; 3] {
+        let position = if position.is_nan() { 0.0 } else { position.clamp(0.0, 1.0) };
+        let upper = self.stops.iter().position(|&(p, _)| p >= position);
+        match upper {
+            Some(0) => self.stops[0].1,
+            Some(i) => {
+                let ((p0, c0), (p1, c1)) = (self.stops[i - 1], self.stops[i]);
+                let t = (position - p0) / (p1 - p0);
+                std::array::from_fn(|k| (c0[k] as f64 + (c1[k] as f64 - c0[k] as f64) * t).round() as u8)
+            }
+            None => self.stops[self.stops.len() - 1].1,
+        }
+    }
+}
+
+impl Default for Ramp {
+    fn default() -> Self {
+        Ramp::named("viridis").expect("viridis is built in")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ramps() {
+        let viridis = Ramp::named("Viridis").unwrap();
+        assert_eq!(viridis.color(0.0), [0x44, 0x01, 0x54]);
+        assert_eq!(viridis.color(1.0), [0xFD, 0xE7, 0x25]);
+        assert_eq!(viridis.color(0.5), [0x21, 0x90, 0x8C]);
+        assert_eq!(viridis.color(-1.0), viridis.color(0.0));
+        assert_eq!(Ramp::parse("viridis_r").unwrap().color(0.0), [0xFD, 0xE7, 0x25]);
+        assert_eq!(Ramp::names().count(), BUILT_IN.len());
+
+        // Evenly spaced and positioned stops
+        let ramp = Ramp::parse("#000000, white").unwrap();
+        assert_eq!(ramp.color(0.5), [128, 128, 128]);
+        let ramp = Ramp::parse("0:black,0.25:#FF0000,1:#FF0000").unwrap();
+        assert_eq!(ramp.color(0.125), [128, 0, 0]);
+        assert_eq!(ramp.color(0.6), [255, 0, 0]);
+        assert_eq!(ramp.reversed().color(0.0), [255, 0, 0]);
+
+        for invalid in ["rainbow", "#000000", "red,nocolor", "0:red,red", "0.5:red,0.2:blue", "0:red,2:blue"] {
+            assert!(Ramp::parse(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! GeoPackage raster layers (`--raster`).
+//!
+//! Tiles of one zoom level are decoded into a [`TileMosaic`], which is then
+//! resampled onto the pixel grid of a render (nearest neighbour): every pixel
+//! center is converted to WGS84, reprojected to the layer SRS and looked up
+//! in the tile it falls in.
+
+use image::{Rgba, RgbaImage};
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+use crate::gpkg::{TileLayerInfo, TileMatrix};
+use crate::math::ScreenTransform;
+use crate::render::RenderConfig;
+
+/// Decoded tiles of one zoom level of a tile layer.
+pub struct TileMosaic {
+    matrix: TileMatrix,
+    /// Top-left corner of tile (0, 0), in the layer SRS.
+    origin: (f64, f64),
+    tiles: HashMap<(i64, i64), RgbaImage>,
+}
+
+impl TileMosaic {
+    /// Creates an empty mosaic of the `matrix` zoom level of `layer`.
+    pub fn new(layer: &TileLayerInfo, matrix: TileMatrix) -> Self {
+        let (min_x, _, _, max_y) = layer.extent;
+        Self {
+            matrix,
+            origin: (min_x, max_y),
+            tiles: HashMap::new(),
+        }
+    }
+
+    /// Adds the tile at `column`, `row` (counted from the top).
+    pub fn insert(&mut self, column: i64, row: i64, tile: RgbaImage) {
+        self.tiles.insert((column, row), tile);
+    }
+
+    /// Number of tiles in the mosaic.
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Whether the mosaic has no tiles.
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Returns the pixel at (x, y) in the layer SRS; transparent outside the
+    /// tiles, where tiles are missing or smaller than declared.
+    pub fn sample(&self, x: f64, y: f64) -> Rgba<u8> {
+        let m = &self.matrix;
+        let px = ((x - self.origin.0) / m.pixel_x_size).floor();
+        let py = ((self.origin.1 - y) / m.pixel_y_size).floor();
+        if !(px >= 0.0 && py >= 0.0) {
+            return Rgba([0, 0, 0, 0]);
+        }
+        let (px, py) = (px as i64, py as i64);
+        let (tile_width, tile_height) = (m.tile_width as i64, m.tile_height as i64);
+        let key = (px / tile_width, py / tile_height);
+        let (tx, ty) = ((px % tile_width) as u32, (py % tile_height) as u32);
+        match self.tiles.get(&key) {
+            Some(tile) if tx < tile.width() && ty < tile.height() => *tile.get_pixel(tx, ty),
+            _ => Rgba([0, 0, 0, 0]),
+        }
+    }
+}
+
+/// Resamples `mosaic` onto the pixel grid of `config`.
+///
+/// `make_transform` creates, once per worker thread, the conversion of WGS84
+/// coordinates to the layer SRS (None for pixels that fail to convert).
+pub fn composite<T, F>(mosaic: &TileMosaic, config: &RenderConfig, width: u32, height: u32, make_transform: F) -> RgbaImage
+where
+    F: Fn() -> T + Sync + Send,
+    T: Fn(f64, f64) -> Option<(f64, f64)>,
+{
+    let screen = ScreenTransform::new(&config.bbox, config.resolution, height, config.rotation);
+    let mut image = RgbaImage::new(width, height);
+    image
+        .par_chunks_mut(width as usize * 4)
+        .enumerate()
+        .for_each_init(make_transform, |transform, (y, row)| {
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let (lon, lat) = screen.invert(x as f64 + 0.5, y as f64 + 0.5);
+                // Frames crossing the antimeridian continue past 180°
+                let lon = if lon > 180.0 { lon - 360.0 } else { lon };
+                if let Some((layer_x, layer_y)) = transform(lon, lat) {
+                    pixel.copy_from_slice(&mosaic.sample(layer_x, layer_y).0);
+                }
+            }
+        });
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Bbox;
+    use crate::render::MAX_DIMENSION;
+
+    #[test]
+    fn test_composite() {
+        // Two 2 x 2 tiles side by side over lon 0..4, lat 0..2
+        let matrix = TileMatrix {
+            zoom_level: 0,
+            matrix_width: 2,
+            matrix_height: 1,
+            tile_width: 2,
+            tile_height: 2,
+            pixel_x_size: 1.0,
+            pixel_y_size: 1.0,
+        };
+        let layer = TileLayerInfo {
+            name: "ortho".to_string(),
+            srs_id: 4326,
+            extent: (0.0, 0.0, 4.0, 2.0),
+            matrices: vec![matrix],
+        };
+        let mut mosaic = TileMosaic::new(&layer, matrix);
+        let mut left = RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255]));
+        left.put_pixel(0, 0, Rgba([0, 0, 255, 255]));
+        mosaic.insert(0, 0, left);
+        assert_eq!(mosaic.len(), 1);
+        assert_eq!(mosaic.sample(0.5, 1.5), Rgba([0, 0, 255, 255]));
+        assert_eq!(mosaic.sample(1.5, 0.5), Rgba([255, 0, 0, 255]));
+        // The right tile is missing, and nothing is left of the origin
+        assert_eq!(mosaic.sample(3.0, 1.0), Rgba([0, 0, 0, 0]));
+        assert_eq!(mosaic.sample(-0.5, 1.0), Rgba([0, 0, 0, 0]));
+
+        // Rendered at twice the tile resolution, shifted by half a degree
+        let config = RenderConfig {
+            bbox: Bbox::new(-0.5, 0.0, 2.5, 2.0),
+            resolution: 0.5,
+            fill: Some([0; 4]),
+            stroke: Some([0; 3]),
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let image = composite(&mosaic, &config, 6, 4, || |lon, lat| Some((lon, lat)));
+        assert_eq!(image.get_pixel(0, 0).0[3], 0);
+        assert_eq!(image.get_pixel(1, 0), &Rgba([0, 0, 255, 255]));
+        assert_eq!(image.get_pixel(2, 1), &Rgba([0, 0, 255, 255]));
+        assert_eq!(image.get_pixel(3, 0), &Rgba([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(4, 3), &Rgba([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(5, 3).0[3], 0);
+    }
+}
@@ -4,20 +4,59 @@
 //! and uses Bresenham's algorithm for stroke rendering. It supports alpha blending
 //! for overlapping geometries.
 
-use geo::{Coord, MultiPolygon};
+use geo::{BoundingRect, MultiPolygon, Rect};
 use image::{ImageBuffer, Rgba, RgbaImage};
-use rayon::iter::ParallelBridge;
 use rayon::prelude::*;
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::borrow::Borrow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 pub mod edge;
+pub mod encode;
 
 use crate::error::{GpkgError, Result};
-use crate::math::{calculate_dimensions, world_to_screen, Bbox};
+use crate::geotag;
+use crate::math::{calculate_dimensions, Bbox, ScreenTransform};
 use edge::{Edge, ScanlineTable};
+use encode::PngOptions;
 
-const MAX_DIMENSION: u32 = 20000;
+/// Default maximum width or height of a rendered image, in pixels.
+pub const MAX_DIMENSION: u32 = 20000;
+
+/// Number of geometries callers rasterize per `render_layer` sweep when
+/// they need to report progress in between.
+pub const BATCH_SIZE: usize = 4096;
+
+/// A line segment in screen coordinates.
+type Segment = ((f64, f64), (f64, f64));
+
+/// Returns the finest resolution, no finer than `resolution`, whose image
+/// fits within `max_dimension` pixels on both axes.
+pub fn fit_resolution(bbox: &Bbox, resolution: f64, max_dimension: u32) -> f64 {
+    let mut fitted = resolution
+        .max(bbox.width() / max_dimension as f64)
+        .max(bbox.height() / max_dimension as f64);
+    // Division rounding can leave the ceiling one pixel over the limit
+    loop {
+        let (width, height) = calculate_dimensions(bbox, fitted);
+        if width <= max_dimension && height <= max_dimension {
+            return fitted;
+        }
+        fitted *= 1.0 + f64::EPSILON * 4.0;
+    }
+}
+
+/// Returns the largest supersampling factor, at most `factor`, whose canvas
+/// for a `width` x `height` image fits within `max_dimension` pixels (1 when
+/// even a factor of 2 does not).
+pub fn fit_supersample(width: u32, height: u32, factor: u32, max_dimension: u32) -> u32 {
+    factor.min(max_dimension / width.max(height).max(1)).max(1)
+}
+
+/// Half-length in pixels of the arms of a vertex marker cross.
+const VERTEX_MARKER_RADIUS: i32 = 2;
 
 /// Render configuration for a layer.
 #[derive(Debug, Clone)]
@@ -26,193 +65,250 @@ pub struct RenderConfig {
     pub bbox: Bbox,
     /// Resolution in degrees per pixel.
     pub resolution: f64,
-    /// Fill color in RGBA format.
-    pub fill: [u8; 4],
-    /// Stroke color in RGB format.
-    pub stroke: [u8; 3],
+    /// Fill color in RGBA format (None draws outlines only).
+    pub fill: Option<[u8; 4]>,
+    /// Stroke color in RGB format (None draws interiors only).
+    pub stroke: Option<[u8; 3]>,
     /// Stroke width in pixels.
     pub stroke_width: u32,
+    /// Color of the vertex markers in RGB format (None disables them).
+    pub vertex_color: Option<[u8; 3]>,
+    /// Side in pixels of the square drawn for features that collapse below
+    /// one pixel (None drops their fill).
+    pub min_feature_px: Option<u32>,
+    /// Largest width or height accepted, in pixels (usually `MAX_DIMENSION`).
+    pub max_dimension: u32,
+    /// Clockwise rotation of the map around the bbox center, in degrees; the
+    /// bbox is then the rotated frame of the image (0 keeps north up).
+    pub rotation: f64,
+    /// Halo drawn just outside the polygon boundaries (None disables it).
+    pub glow: Option<Glow>,
+}
+
+/// A soft halo around polygons: a blurred stroke kept outside their interior.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glow {
+    /// Color at the boundary in RGBA format; the alpha fades out with the distance.
+    pub color: [u8; 4],
+    /// Distance in pixels at which the halo has faded out.
+    pub radius: u32,
+}
+
+impl RenderConfig {
+    /// The same render on a grid `factor` times finer, with the sizes given
+    /// in pixels (stroke width, sub-pixel marks) scaled to match.
+    pub fn supersampled(&self, factor: u32) -> Self {
+        Self {
+            resolution: self.resolution / factor as f64,
+            stroke_width: self.stroke_width * factor,
+            min_feature_px: self.min_feature_px.map(|px| px * factor),
+            glow: self.glow.map(|glow| Glow {
+                radius: glow.radius * factor,
+                ..glow
+            }),
+            ..self.clone()
+        }
+    }
+}
+
+/// Per-feature overrides of the layer style.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeatureStyle {
+    /// Fill color replacing the color of `RenderConfig::fill`, when set.
+    pub fill: Option<[u8; 3]>,
+    /// Fill alpha replacing the alpha of `RenderConfig::fill`, when set.
+    pub fill_alpha: Option<u8>,
+    /// Stroke color replacing `RenderConfig::stroke`, when set.
+    pub stroke: Option<[u8; 3]>,
+}
+
+/// Per-span shading callback: `(x_range, y, feature) -> color`.
+///
+/// Called for each horizontal run of pixels `x_range` of image row `y` filled
+/// for geometry `feature` (its index in the batch being rendered). The
+/// returned color is blended over the run instead of the fill color; a fully
+/// transparent color leaves it untouched. Bands are filled in parallel, so
+/// the shader must be thread-safe.
+pub type SpanShader = dyn Fn(Range<u32>, u32, usize) -> Rgba<u8> + Send + Sync;
+
+/// Extra metadata embedded in the saved PNG.
+#[derive(Debug, Clone, Default)]
+pub struct PngMetadata {
+    /// XMP packet, stored in an iTXt chunk.
+    pub xmp: Option<String>,
+    /// Keyword/text pairs, stored in tEXt chunks (iTXt when not Latin-1).
+    pub text: Vec<(String, String)>,
+}
+
+impl PngMetadata {
+    /// Adds the metadata chunks to a PNG about to be written.
+    pub(crate) fn add_chunks<W: Write>(&self, encoder: &mut png::Encoder<W>) -> Result<()> {
+        if let Some(xmp) = &self.xmp {
+            encoder.add_itxt_chunk(geotag::XMP_KEYWORD.to_string(), xmp.clone())?;
+        }
+        for (keyword, text) in &self.text {
+            if text.chars().all(|c| (c as u32) < 0x100) {
+                encoder.add_text_chunk(keyword.clone(), text.clone())?;
+            } else {
+                encoder.add_itxt_chunk(keyword.clone(), text.clone())?;
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Renderer that manages the output image buffer and rendering operations.
 ///
-/// Uses an internal `Arc<Mutex<RgbaImage>>` to allow parallel rendering of
-/// different geometries or image bands.
+/// The image is split into horizontal bands, each owning its own pixel
+/// buffer. Bands are rendered in parallel without any locking and stitched
+/// back together when the image is saved.
 pub struct Renderer {
     config: RenderConfig,
     width: u32,
     height: u32,
-    image: Arc<Mutex<RgbaImage>>,
+    bands: Vec<Band>,
+    shader: Option<Box<SpanShader>>,
+    png_options: PngOptions,
+    /// Number of geometries rendered so far that collapsed below one pixel.
+    degenerate: u64,
 }
 
-impl Renderer {
-    /// Create a new renderer with the given configuration.
-    ///
-    /// Validates that the resulting image dimensions don't exceed `MAX_DIMENSION`.
-    pub fn new(config: RenderConfig) -> Result<Self> {
-        let (width, height) = calculate_dimensions(&config.bbox, config.resolution);
-
-        if width > MAX_DIMENSION || height > MAX_DIMENSION {
-            return Err(GpkgError::ImageTooLarge {
-                width,
-                height,
-                max: MAX_DIMENSION,
-            });
-        }
-
-        let image = ImageBuffer::from_pixel(width, height, Rgba([0, 0, 0, 0]));
+/// A horizontal strip of the output image, written by a single worker at a time.
+struct Band {
+    /// First image row covered by the band.
+    y_start: u32,
+    pixels: RgbaImage,
+}
 
-        Ok(Self {
-            config,
-            width,
-            height,
-            image: Arc::new(Mutex::new(image)),
-        })
+impl Band {
+    /// One past the last image row covered by the band.
+    fn y_end(&self) -> u32 {
+        self.y_start + self.pixels.height()
     }
 
-    /// Get image dimensions
-    pub fn dimensions(&self) -> (u32, u32) {
-        (self.width, self.height)
+    /// Blend a pixel given in image coordinates (`y` must lie within the band).
+    fn blend(&mut self, x: u32, y: u32, color: Rgba<u8>) {
+        blend_pixel(&mut self.pixels, x, y - self.y_start, color);
     }
 
-    /// Render a MultiPolygon onto the image.
+    /// Blend a run of pixels `x_start..x_end` of image row `y` (within the band).
     ///
-    /// This uses a scanline fill algorithm:
-    /// 1. Build a Global Edge Table (GET) for all edges of the MultiPolygon.
-    /// 2. Divide the image into horizontal bands for parallel processing.
-    /// 3. For each band, iterate through scanlines using an Active Edge Table (AET).
-    /// 4. Apply the Even-Odd rule to determine which pixels to fill.
-    /// 5. Finally, draw the stroke if `stroke_width > 0`.
-    pub fn render_multipolygon(&self, mp: &MultiPolygon<f64>) {
-        // Build GET (Global Edge Table)
-        let mut scanline_table = ScanlineTable::new(0, self.height);
-        for polygon in mp {
-            scanline_table.extract_from_polygon(
-                polygon,
-                &self.config.bbox,
-                self.config.resolution,
-                self.height,
-            );
+    /// Works on the raw row bytes. Neighbouring pixels usually share the same
+    /// background, so the last blended pixel is reused while it repeats.
+    fn blend_span(&mut self, y: u32, x_start: u32, x_end: u32, color: Rgba<u8>) {
+        let width = self.pixels.width() as usize;
+        let start = ((y - self.y_start) as usize * width + x_start as usize) * 4;
+        let end = start + (x_end - x_start) as usize * 4;
+        let span = &mut self.pixels.as_mut()[start..end];
+
+        if color.0[3] == 255 {
+            for pixel in span.chunks_exact_mut(4) {
+                pixel.copy_from_slice(&color.0);
+            }
+            return;
         }
 
-        let num_bands = rayon::current_num_threads().max(1) * 4;
-        let band_height = (self.height as usize).div_ceil(num_bands);
+        let mut last: Option<([u8; 4], [u8; 4])> = None;
+        for pixel in span.chunks_exact_mut(4) {
+            match last {
+                Some((before, after)) if before == *pixel => pixel.copy_from_slice(&after),
+                _ => {
+                    let before = [pixel[0], pixel[1], pixel[2], pixel[3]];
+                    blend_over(pixel, color);
+                    last = Some((before, [pixel[0], pixel[1], pixel[2], pixel[3]]));
+                }
+            }
+        }
+    }
 
-        (0..num_bands).into_par_iter().for_each(|band_idx| {
-            let y_start = (band_idx * band_height) as i32;
-            let y_end = ((band_idx + 1) * band_height).min(self.height as usize) as i32;
+    /// Fill the band's rows of the geometries described by `scanline_table`.
+    ///
+    /// Edges starting above the band are advanced to its first row, then the
+    /// band's scanlines are swept once with an Active Edge Table. Edges are
+    /// grouped by feature so each geometry gets its own Even-Odd pairing and
+    /// fill color (`fill_colors[feature]`, or the `shader`'s color), and
+    /// overlapping geometries blend in order.
+    fn fill(
+        &mut self,
+        scanline_table: &ScanlineTable,
+        fill_colors: &[Rgba<u8>],
+        shader: Option<&SpanShader>,
+        width: u32,
+    ) {
+        let y_start = self.y_start as i32;
+        let y_end = self.y_end() as i32;
+
+        // Edges started above the band and still active at its first row
+        let mut active_edge_table: Vec<Edge> = Vec::new();
+        for (y, edges) in scanline_table.entries.iter().enumerate().take(y_start as usize) {
+            let skipped = (y_start - y as i32) as f64;
+            active_edge_table.extend(edges.iter().filter(|e| e.y_max > y_start).map(|e| Edge {
+                x_current: e.x_current + e.inv_slope * skipped,
+                ..e.clone()
+            }));
+        }
 
-            if y_start >= y_end {
-                return;
+        for y in y_start..y_end {
+            // Add new edges from GET
+            if let Some(new_edges) = scanline_table.entries.get(y as usize) {
+                active_edge_table.extend(new_edges.iter().cloned());
             }
 
-            let mut active_edge_table: Vec<Edge> = Vec::new();
-            let fill_color = Rgba(self.config.fill);
+            // Remove edges where y_max == y
+            active_edge_table.retain(|edge| edge.y_max > y);
 
-            for y in 0..y_end {
-                // Add new edges from GET
-                if y < self.height as i32 {
-                    if let Some(new_edges) = scanline_table.entries.get(y as usize) {
-                        for edge in new_edges {
-                            active_edge_table.push(edge.clone());
-                        }
-                    }
-                }
+            // Sort AET by feature, then x_current
+            active_edge_table.sort_by(|a, b| {
+                a.feature
+                    .cmp(&b.feature)
+                    .then(a.x_current.total_cmp(&b.x_current))
+            });
 
-                // Remove edges where y_max == y
-                active_edge_table.retain(|edge| edge.y_max > y);
-
-                // For rows in our band, fill pixels
-                if y >= y_start {
-                    // Sort AET by x_current
-                    active_edge_table
-                        .sort_by(|a, b| a.x_current.partial_cmp(&b.x_current).unwrap());
-
-                    // Fill intervals (Even-Odd rule)
-                    let mut intersections = active_edge_table.iter().peekable();
-                    if intersections.peek().is_some() {
-                        let mut img = self.image.lock().unwrap();
-                        while let (Some(e1), Some(e2)) =
-                            (intersections.next(), intersections.next())
-                        {
-                            let x_start = (e1.x_current.round() as i32)
-                                .max(0)
-                                .min(self.width as i32 - 1)
-                                as u32;
-                            let x_end =
-                                (e2.x_current.round() as i32).max(0).min(self.width as i32) as u32;
-
-                            for x in x_start..x_end {
-                                blend_pixel(&mut img, x, y as u32, fill_color);
-                            }
-                        }
-                    }
+            // Fill intervals (Even-Odd rule, per feature)
+            let mut i = 0;
+            while i + 1 < active_edge_table.len() {
+                let (e1, e2) = (&active_edge_table[i], &active_edge_table[i + 1]);
+                if e1.feature != e2.feature {
+                    // Unpaired edge of a degenerate ring
+                    i += 1;
+                    continue;
                 }
+                let x_start = (e1.x_current.round() as i32).max(0).min(width as i32 - 1) as u32;
+                let x_end = (e2.x_current.round() as i32).max(0).min(width as i32) as u32;
 
-                // Update x_current for next scanline
-                for edge in &mut active_edge_table {
-                    edge.x_current += edge.inv_slope;
+                if x_start < x_end {
+                    let feature = e1.feature as usize;
+                    let color = match shader {
+                        Some(shade) => shade(x_start..x_end, y as u32, feature),
+                        None => fill_colors[feature],
+                    };
+                    self.blend_span(y as u32, x_start, x_end, color);
                 }
+                i += 2;
             }
-        });
 
-        if self.config.stroke_width > 0 {
-            mp.iter().par_bridge().for_each(|polygon| {
-                self.render_polygon_stroke(polygon);
-            });
-        }
-    }
-
-    /// Draw the stroke (boundary) of a polygon.
-    fn render_polygon_stroke(&self, polygon: &geo::Polygon<f64>) {
-        let stroke = Rgba([
-            self.config.stroke[0],
-            self.config.stroke[1],
-            self.config.stroke[2],
-            255,
-        ]);
-        let width = self.config.stroke_width;
-
-        // Draw exterior ring
-        self.draw_linestring(polygon.exterior().coords().copied(), stroke, width);
-
-        // Draw interior rings (holes)
-        for interior in polygon.interiors() {
-            self.draw_linestring(interior.coords().copied(), stroke, width);
-        }
-    }
-
-    /// Draw a linestring with given color and width
-    fn draw_linestring(
-        &self,
-        coords: impl Iterator<Item = Coord<f64>>,
-        color: Rgba<u8>,
-        width: u32,
-    ) {
-        let screen_coords: Vec<(f64, f64)> = coords
-            .map(|c| {
-                world_to_screen(
-                    c.x,
-                    c.y,
-                    &self.config.bbox,
-                    self.config.resolution,
-                    self.height,
-                )
-            })
-            .collect();
-
-        for window in screen_coords.windows(2) {
-            self.draw_line(window[0], window[1], color, width);
+            // Update x_current for next scanline
+            for edge in &mut active_edge_table {
+                edge.x_current += edge.inv_slope;
+            }
         }
     }
 
-    /// Draw a line segment using Bresenham's algorithm.
+    /// Draw the part of a line segment that falls within the band, using
+    /// Bresenham's algorithm.
     ///
     /// This implementation supports thick lines by drawing a square of pixels
     /// around each point of the ideal line.
-    fn draw_line(&self, from: (f64, f64), to: (f64, f64), color: Rgba<u8>, width: u32) {
+    fn draw_line(&mut self, from: (f64, f64), to: (f64, f64), color: Rgba<u8>, width: u32, image_width: u32) {
         let (x0, y0) = (from.0 as i32, from.1 as i32);
         let (x1, y1) = (to.0 as i32, to.1 as i32);
+        let half_width = (width / 2) as i32;
+        let (band_start, band_end) = (self.y_start as i32, self.y_end() as i32);
+
+        // Skip segments that cannot touch the band
+        if y0.max(y1) + half_width < band_start || y0.min(y1) - half_width >= band_end {
+            return;
+        }
 
         let dx = (x1 - x0).abs();
         let dy = -(y1 - y0).abs();
@@ -223,17 +319,14 @@ impl Renderer {
         let mut x = x0;
         let mut y = y0;
 
-        let half_width = (width / 2) as i32;
-
         loop {
             // Draw thick line by drawing a square at each point
             for wx in -half_width..=half_width {
                 for wy in -half_width..=half_width {
                     let px = x + wx;
                     let py = y + wy;
-                    if px >= 0 && px < self.width as i32 && py >= 0 && py < self.height as i32 {
-                        let mut img = self.image.lock().unwrap();
-                        blend_pixel(&mut img, px as u32, py as u32, color);
+                    if px >= 0 && px < image_width as i32 && py >= band_start && py < band_end {
+                        self.blend(px as u32, py as u32, color);
                     }
                 }
             }
@@ -254,59 +347,595 @@ impl Renderer {
         }
     }
 
+    /// Blend a halo of `glow` around the boundary `segments` of the
+    /// geometries of `scanline_table`, leaving out the pixels they cover.
+    ///
+    /// The halo alpha falls off quadratically with the distance to the
+    /// nearest boundary, from the glow alpha to 0 at `radius`.
+    fn draw_glow(
+        &mut self,
+        segments: &[(Segment, Rgba<u8>)],
+        scanline_table: &ScanlineTable,
+        features: usize,
+        glow: Glow,
+        image_width: u32,
+    ) {
+        let radius = glow.radius as f64;
+        let (band_start, band_end) = (self.y_start as f64, self.y_end() as f64);
+        let band_width = self.pixels.width() as usize;
+        let mut strength = vec![0.0f64; band_width * self.pixels.height() as usize];
+        for &(((x0, y0), (x1, y1)), _) in segments {
+            // Pixels whose center lies within the radius of the segment
+            if y0.max(y1) + radius < band_start || y0.min(y1) - radius >= band_end {
+                continue;
+            }
+            let x_range = ((x0.min(x1) - radius).floor().max(0.0) as u32)
+                ..((x0.max(x1) + radius).ceil().min(image_width as f64) as u32);
+            let y_range = ((y0.min(y1) - radius).floor().max(band_start) as u32)
+                ..((y0.max(y1) + radius).ceil().min(band_end) as u32);
+            let (dx, dy) = (x1 - x0, y1 - y0);
+            let length_sq = dx * dx + dy * dy;
+            for y in y_range {
+                let row = (y - self.y_start) as usize * band_width;
+                let cy = y as f64 + 0.5;
+                for x in x_range.clone() {
+                    let cx = x as f64 + 0.5;
+                    let t = if length_sq > 0.0 {
+                        (((cx - x0) * dx + (cy - y0) * dy) / length_sq).clamp(0.0, 1.0)
+                    } else {
+                        0.0
+                    };
+                    let distance = (cx - x0 - t * dx).hypot(cy - y0 - t * dy);
+                    if distance < radius {
+                        let value = (1.0 - distance / radius).powi(2);
+                        let cell = &mut strength[row + x as usize];
+                        *cell = cell.max(value);
+                    }
+                }
+            }
+        }
+
+        // The interiors, filled opaque on a scratch band, mask the halo out
+        let mut interiors = Band {
+            y_start: self.y_start,
+            pixels: RgbaImage::new(band_width as u32, self.pixels.height()),
+        };
+        interiors.fill(scanline_table, &vec![Rgba([0, 0, 0, 255]); features], None, image_width);
+        let [r, g, b, a] = glow.color;
+        for (i, &value) in strength.iter().enumerate() {
+            let (x, y) = ((i % band_width) as u32, (i / band_width) as u32);
+            if value > 0.0 && interiors.pixels.get_pixel(x, y).0[3] == 0 {
+                let alpha = (a as f64 * value).round() as u8;
+                blend_pixel(&mut self.pixels, x, y, Rgba([r, g, b, alpha]));
+            }
+        }
+    }
+
+    /// Fill a `size`x`size` square centered on a point, clipped to the band.
+    fn draw_square(&mut self, center: (f64, f64), size: u32, color: Rgba<u8>, image_width: u32) {
+        let x0 = (center.0 - size as f64 / 2.0).round() as i64;
+        let y0 = (center.1 - size as f64 / 2.0).round() as i64;
+        let (x_start, x_end) = (x0.max(0), (x0 + size as i64).min(image_width as i64));
+        if x_start >= x_end {
+            return;
+        }
+        for y in y0.max(self.y_start as i64)..(y0 + size as i64).min(self.y_end() as i64) {
+            self.blend_span(y as u32, x_start as u32, x_end as u32, color);
+        }
+    }
+
+    /// Draw a cross-shaped marker centered on a point, clipped to the band.
+    fn draw_cross(&mut self, center: (f64, f64), color: Rgba<u8>, image_width: u32) {
+        let (cx, cy) = (center.0 as i32, center.1 as i32);
+        let (band_start, band_end) = (self.y_start as i32, self.y_end() as i32);
+        if cy + VERTEX_MARKER_RADIUS < band_start || cy - VERTEX_MARKER_RADIUS >= band_end {
+            return;
+        }
+
+        let horizontal = (-VERTEX_MARKER_RADIUS..=VERTEX_MARKER_RADIUS).map(|d| (cx + d, cy));
+        let vertical = (-VERTEX_MARKER_RADIUS..=VERTEX_MARKER_RADIUS)
+            .filter(|&d| d != 0)
+            .map(|d| (cx, cy + d));
+        for (px, py) in horizontal.chain(vertical) {
+            if px >= 0 && px < image_width as i32 && py >= band_start && py < band_end {
+                self.blend(px as u32, py as u32, color);
+            }
+        }
+    }
+}
+
+impl Renderer {
+    /// Create a new renderer with the given configuration.
+    ///
+    /// Validates that the resulting image dimensions don't exceed `max_dimension`.
+    pub fn new(config: RenderConfig) -> Result<Self> {
+        let (width, height) = calculate_dimensions(&config.bbox, config.resolution);
+
+        if width > config.max_dimension || height > config.max_dimension {
+            return Err(GpkgError::ImageTooLarge {
+                width,
+                height,
+                max: config.max_dimension,
+            });
+        }
+
+        // A few bands per thread keeps workers busy when polygons cover only part of the image
+        let num_bands = rayon::current_num_threads().max(1) as u32 * 4;
+        let band_height = height.div_ceil(num_bands).max(1);
+        let bands = (0..height)
+            .step_by(band_height as usize)
+            .map(|y_start| Band {
+                y_start,
+                pixels: ImageBuffer::from_pixel(
+                    width,
+                    band_height.min(height - y_start),
+                    Rgba([0, 0, 0, 0]),
+                ),
+            })
+            .collect();
+
+        Ok(Self {
+            config,
+            width,
+            height,
+            bands,
+            shader: None,
+            png_options: PngOptions::default(),
+            degenerate: 0,
+        })
+    }
+
+    /// Shade filled spans with `shader` instead of the fill color.
+    ///
+    /// Strokes and vertex markers are not affected.
+    pub fn set_shader<F>(&mut self, shader: F)
+    where
+        F: Fn(Range<u32>, u32, usize) -> Rgba<u8> + Send + Sync + 'static,
+    {
+        self.shader = Some(Box::new(shader));
+    }
+
+    /// Encode saved PNGs with `options` instead of the defaults.
+    pub fn set_png_options(&mut self, options: PngOptions) {
+        self.png_options = options;
+    }
+
+    /// Get image dimensions
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Number of geometries rendered so far whose extent collapsed below one
+    /// pixel on both axes.
+    ///
+    /// Their fill is skipped, or replaced by a mark with `min_feature_px`.
+    pub fn degenerate_count(&self) -> u64 {
+        self.degenerate
+    }
+
+    /// Render a MultiPolygon onto the image.
+    pub fn render_multipolygon(&mut self, mp: &MultiPolygon<f64>) {
+        self.render_layer(std::slice::from_ref(mp));
+    }
+
+    /// Render a batch of geometries onto the image in a single sweep.
+    ///
+    /// This uses a scanline fill algorithm:
+    /// 1. Build one Global Edge Table (GET) for the edges of all geometries.
+    /// 2. Process the image bands in parallel, each writing only its own pixels.
+    /// 3. For each band, iterate through scanlines using an Active Edge Table (AET).
+    /// 4. Apply the Even-Odd rule to each geometry to determine which pixels to fill,
+    ///    unless the fill is None, over the halo of the glow if one is set.
+    /// 5. Draw the strokes if there is a stroke color and `stroke_width > 0`.
+    /// 6. Finally, mark every ring vertex if a vertex color is set.
+    ///
+    /// Geometries smaller than one pixel in both directions cover no
+    /// scanline interval reliably; they bypass the edge table and are drawn
+    /// as a `min_feature_px` square when set. Thin but long geometries, such
+    /// as rivers or roads, are filled normally.
+    ///
+    /// Geometries whose screen-space extent lies entirely outside the image,
+    /// beyond the reach of their strokes, marks and halo, are culled before
+    /// any edge, segment or vertex is built.
+    ///
+    /// Fills overlap in the order of `geometries`; strokes are drawn on top of
+    /// all the fills of the batch.
+    pub fn render_layer<G>(&mut self, geometries: &[G])
+    where
+        G: Borrow<MultiPolygon<f64>>,
+    {
+        self.render_styled_layer(geometries, &[]);
+    }
+
+    /// Render a batch of geometries like [`Self::render_layer`], with
+    /// `styles[i]` applied to `geometries[i]`.
+    ///
+    /// Geometries without a style (`styles` may be shorter, or empty) use the
+    /// layer style.
+    pub fn render_styled_layer<G>(&mut self, geometries: &[G], styles: &[FeatureStyle])
+    where
+        G: Borrow<MultiPolygon<f64>>,
+    {
+        // Without a fill, the edge table stays empty and only outlines are drawn
+        let fill_colors: Vec<Rgba<u8>> = match self.config.fill {
+            Some([r, g, b, a]) => (0..geometries.len())
+                .map(|i| {
+                    let [r, g, b] = styles.get(i).and_then(|s| s.fill).unwrap_or([r, g, b]);
+                    let alpha = styles.get(i).and_then(|s| s.fill_alpha).unwrap_or(a);
+                    Rgba([r, g, b, alpha])
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        // Cull the geometries out of reach of the image
+        let transform = self.transform();
+        let glow = self.config.glow.filter(|glow| glow.radius > 0 && glow.color[3] > 0);
+        let reach = self.config.stroke_width / 2
+            + glow.map_or(0, |glow| glow.radius)
+            + self.config.min_feature_px.unwrap_or(0)
+            + VERTEX_MARKER_RADIUS as u32
+            + 2;
+        let rects: Vec<Option<Rect<f64>>> = geometries
+            .iter()
+            .map(|mp| self.visible_rect(mp.borrow(), &transform, reach as f64))
+            .collect();
+        // Visible geometries below one pixel, counted whatever is drawn of them
+        let centers: Vec<Option<(f64, f64)>> = rects
+            .iter()
+            .map(|rect| rect.and_then(|rect| self.degenerate_center(&rect, &transform)))
+            .collect();
+        self.degenerate += centers.iter().filter(|center| center.is_some()).count() as u64;
+
+        // Build GET (Global Edge Table), setting degenerate geometries aside;
+        // a glow needs the interiors even without a fill
+        let mut scanline_table = ScanlineTable::new(0, self.height);
+        let mut marks = Vec::new();
+        let filled = if self.config.fill.is_some() || glow.is_some() { geometries } else { &[] };
+        for (feature, mp) in filled.iter().enumerate() {
+            if rects[feature].is_none() {
+                continue;
+            }
+            if let Some(center) = centers[feature] {
+                if self.config.min_feature_px.is_some() && !fill_colors.is_empty() {
+                    marks.push((center, fill_colors[feature]));
+                }
+                continue;
+            }
+            scanline_table.extract_from_multipolygon(mp.borrow(), feature as u32, &transform);
+        }
+        let mut glow_segments = Vec::new();
+        if let Some(glow) = glow {
+            for (mp, _) in geometries.iter().zip(&rects).filter(|(_, rect)| rect.is_some()) {
+                stroke_segments(mp.borrow(), &transform, Rgba(glow.color), &mut glow_segments);
+            }
+        }
+
+        let stroke = self.config.stroke.filter(|_| self.config.stroke_width > 0);
+        let mut segments = Vec::new();
+        let mut vertices = Vec::new();
+        for (i, mp) in geometries.iter().enumerate() {
+            if rects[i].is_none() {
+                continue;
+            }
+            if let Some(stroke) = stroke {
+                let [r, g, b] = styles.get(i).and_then(|s| s.stroke).unwrap_or(stroke);
+                stroke_segments(mp.borrow(), &transform, Rgba([r, g, b, 255]), &mut segments);
+            }
+            if self.config.vertex_color.is_some() {
+                ring_vertices(mp.borrow(), &transform, &mut vertices);
+            }
+        }
+
+        let stroke_width = self.config.stroke_width;
+        let width = self.width;
+        let [r, g, b] = self.config.vertex_color.unwrap_or_default();
+        let vertex_color = Rgba([r, g, b, 255]);
+        let shader = self.shader.as_deref();
+        let mark_size = self.config.min_feature_px.unwrap_or(0);
+
+        let features = geometries.len();
+
+        self.bands.par_iter_mut().for_each(|band| {
+            if let Some(glow) = glow {
+                band.draw_glow(&glow_segments, &scanline_table, features, glow, width);
+            }
+            if !fill_colors.is_empty() {
+                band.fill(&scanline_table, &fill_colors, shader, width);
+            }
+            for &(center, color) in &marks {
+                band.draw_square(center, mark_size, color, width);
+            }
+            for &((from, to), stroke_color) in &segments {
+                band.draw_line(from, to, stroke_color, stroke_width, width);
+            }
+            for &vertex in &vertices {
+                band.draw_cross(vertex, vertex_color, width);
+            }
+        });
+    }
+
+    /// Returns the extent of a geometry, or None if it is empty or lies
+    /// entirely more than `reach` pixels outside the image.
+    fn visible_rect(&self, mp: &MultiPolygon<f64>, transform: &ScreenTransform, reach: f64) -> Option<Rect<f64>> {
+        let rect = mp.bounding_rect()?;
+        let (min, max) = (rect.min(), rect.max());
+        let (min_x, min_y, max_x, max_y) = transform.apply_extent(&Bbox::new(min.x, min.y, max.x, max.y));
+        let visible = max_x >= -reach
+            && max_y >= -reach
+            && min_x <= self.width as f64 + reach
+            && min_y <= self.height as f64 + reach;
+        visible.then_some(rect)
+    }
+
+    /// Returns the screen-space center of a geometry whose extent `rect` is
+    /// below one pixel on both axes, or None if it can be filled normally.
+    fn degenerate_center(&self, rect: &Rect<f64>, transform: &ScreenTransform) -> Option<(f64, f64)> {
+        let resolution = self.config.resolution;
+        if rect.width() >= resolution || rect.height() >= resolution {
+            return None;
+        }
+        let center = rect.center();
+        Some(transform.apply(center.x, center.y))
+    }
+
+    /// Maps world coordinates to the pixels of the image.
+    fn transform(&self) -> ScreenTransform {
+        ScreenTransform::new(&self.config.bbox, self.config.resolution, self.height, self.config.rotation)
+    }
+
+    /// Stitch the bands into a single image, leaving the renderer usable.
+    ///
+    /// The image is rendered in bands, so there is no contiguous buffer to
+    /// borrow; use [`Renderer::into_image`] to avoid the copy.
+    pub fn to_image(&self) -> RgbaImage {
+        let mut raw = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+        for band in &self.bands {
+            raw.extend_from_slice(band.pixels.as_raw());
+        }
+        RgbaImage::from_raw(self.width, self.height, raw).expect("bands cover the image")
+    }
+
+    /// Start from an existing image instead of a transparent canvas.
+    ///
+    /// Geometries rendered afterwards are blended over it.
+    pub fn set_base(&mut self, image: &RgbaImage) -> Result<()> {
+        if image.dimensions() != (self.width, self.height) {
+            return Err(GpkgError::ImageSizeMismatch {
+                left: image.dimensions(),
+                right: (self.width, self.height),
+            });
+        }
+        for band in &mut self.bands {
+            let rows = image::imageops::crop_imm(image, 0, band.y_start, self.width, band.pixels.height());
+            band.pixels = rows.to_image();
+        }
+        Ok(())
+    }
+
+    /// Consume the renderer and return the image.
+    ///
+    /// The first band's buffer is reused, so a single-band image is returned
+    /// without copying pixels.
+    pub fn into_image(self) -> RgbaImage {
+        let mut bands = self.bands.into_iter();
+        let mut raw = bands.next().map(|band| band.pixels.into_raw()).unwrap_or_default();
+        raw.reserve_exact(self.width as usize * self.height as usize * 4 - raw.len());
+        for band in bands {
+            raw.extend_from_slice(band.pixels.as_raw());
+        }
+        RgbaImage::from_raw(self.width, self.height, raw).expect("bands cover the image")
+    }
+
+    /// Trim the borders whose alpha is at most `threshold`.
+    ///
+    /// The bbox shrinks by whole pixels, so the cropped image keeps the pixel
+    /// grid of the render. Returns the new bbox, or None (image untouched)
+    /// when no pixel exceeds the threshold.
+    pub fn autocrop(&mut self, threshold: u8) -> Option<Bbox> {
+        let (mut x0, mut y0, mut x1, mut y1) = (u32::MAX, u32::MAX, 0, 0);
+        for band in &self.bands {
+            for (x, y, pixel) in band.pixels.enumerate_pixels() {
+                if pixel.0[3] > threshold {
+                    let y = band.y_start + y;
+                    x0 = x0.min(x);
+                    x1 = x1.max(x);
+                    y0 = y0.min(y);
+                    y1 = y1.max(y);
+                }
+            }
+        }
+        if x0 > x1 {
+            return None;
+        }
+
+        let (width, height) = (x1 - x0 + 1, y1 - y0 + 1);
+        self.bands = self
+            .bands
+            .iter()
+            .filter(|band| band.y_end() > y0 && band.y_start <= y1)
+            .map(|band| {
+                let top = band.y_start.max(y0);
+                let rows = band.y_end().min(y1 + 1) - top;
+                Band {
+                    y_start: top - y0,
+                    pixels: image::imageops::crop_imm(&band.pixels, x0, top - band.y_start, width, rows).to_image(),
+                }
+            })
+            .collect();
+
+        // Row 0 lies `height` pixels above min_lat (see world_to_screen)
+        let res = self.config.resolution;
+        let bbox = &self.config.bbox;
+        let min_lat = bbox.min_lat + (self.height - y1 - 1) as f64 * res;
+        self.config.bbox = Bbox::new(
+            bbox.min_lon + x0 as f64 * res,
+            min_lat,
+            bbox.min_lon + (x1 + 1) as f64 * res,
+            min_lat + height as f64 * res,
+        );
+        self.width = width;
+        self.height = height;
+        Some(self.config.bbox)
+    }
+
+    /// Read back a pixel of the stitched image.
+    #[cfg(test)]
+    fn pixel(&self, x: u32, y: u32) -> Rgba<u8> {
+        let band = self
+            .bands
+            .iter()
+            .find(|b| y >= b.y_start && y < b.y_end())
+            .expect("row inside the image");
+        *band.pixels.get_pixel(x, y - band.y_start)
+    }
+
     /// Save the image to a PNG file
-    pub fn save(&self, path: &Path) -> Result<()> {
-        let img = self.image.lock().unwrap();
-        img.save(path)?;
+    ///
+    /// Bands are streamed to the encoder in order, so the full image is never
+    /// assembled in memory. The PNG is written next to `path` with a `.part`
+    /// suffix and renamed once complete, so an interrupted save never leaves a
+    /// truncated file behind.
+    pub fn save(&self, path: &Path, metadata: &PngMetadata) -> Result<()> {
+        let mut part = path.as_os_str().to_owned();
+        part.push(".part");
+        let part = PathBuf::from(part);
+        if let Err(e) = self.write_png(&part, metadata) {
+            let _ = std::fs::remove_file(&part);
+            return Err(e);
+        }
+        std::fs::rename(&part, path)?;
+        Ok(())
+    }
+
+    fn write_png(&self, path: &Path, metadata: &PngMetadata) -> Result<()> {
+        self.encode_png(BufWriter::new(File::create(path)?), metadata)
+    }
+
+    /// Encode the image as PNG in memory.
+    pub fn to_png(&self, metadata: &PngMetadata) -> Result<Vec<u8>> {
+        let mut png = Vec::new();
+        self.encode_png(&mut png, metadata)?;
+        Ok(png)
+    }
+
+    fn encode_png<W: Write>(&self, writer: W, metadata: &PngMetadata) -> Result<()> {
+        let options = self.png_options;
+        let mut encoder = options.encoder(writer, self.width, self.height)?;
+        encoder.set_depth(png::BitDepth::Eight);
+        metadata.add_chunks(&mut encoder)?;
+        if let Some(colors) = options.colors {
+            // Quantizing needs every pixel, so bands are stitched first
+            let indexed = encode::quantize(self.to_image().as_raw(), self.width as usize, colors as usize, options.dither);
+            encoder.set_color(png::ColorType::Indexed);
+            encoder.set_palette(indexed.rgb());
+            if let Some(alpha) = indexed.alpha() {
+                encoder.set_trns(alpha);
+            }
+            let mut writer = encoder.write_header()?;
+            if options.interlace {
+                options.write_interlaced(&mut writer, &indexed.indices, self.width as usize, 1)?;
+            } else {
+                writer.write_image_data(&indexed.indices)?;
+            }
+            writer.finish()?;
+            return Ok(());
+        }
+        encoder.set_color(png::ColorType::Rgba);
+
+        let mut writer = encoder.write_header()?;
+        if options.interlace {
+            // Adam7 passes span every band, so they are read from a stitched copy
+            options.write_interlaced(&mut writer, self.to_image().as_raw(), self.width as usize, 4)?;
+            writer.finish()?;
+            return Ok(());
+        }
+        let mut stream = writer.stream_writer()?;
+        for band in &self.bands {
+            stream.write_all(band.pixels.as_raw())?;
+        }
+        stream.finish()?;
         Ok(())
     }
 }
 
+/// Append the screen-space vertices of every ring, without the closing duplicate.
+fn ring_vertices(mp: &MultiPolygon<f64>, transform: &ScreenTransform, vertices: &mut Vec<(f64, f64)>) {
+    for polygon in mp {
+        for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+            let coords = match ring.0.split_last() {
+                Some((last, rest)) if rest.first() == Some(last) => rest,
+                _ => &ring.0[..],
+            };
+            vertices.extend(coords.iter().map(|c| transform.apply(c.x, c.y)));
+        }
+    }
+}
+
+/// Append the screen-space segments of every ring (exterior and holes),
+/// each with the stroke color of the geometry.
+fn stroke_segments(
+    mp: &MultiPolygon<f64>,
+    transform: &ScreenTransform,
+    color: Rgba<u8>,
+    segments: &mut Vec<(Segment, Rgba<u8>)>,
+) {
+    for polygon in mp {
+        for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+            let screen_coords: Vec<(f64, f64)> = ring.coords().map(|c| transform.apply(c.x, c.y)).collect();
+            segments.extend(screen_coords.windows(2).map(|w| ((w[0], w[1]), color)));
+        }
+    }
+}
+
 /// Blend a pixel with alpha compositing (Porter-Duff 'Over' operator).
 ///
 /// This performs standard alpha blending of the `src` color over the `dst` color.
 fn blend_pixel(image: &mut RgbaImage, x: u32, y: u32, color: Rgba<u8>) {
-    let dst = image.get_pixel(x, y);
-    let src_a = color.0[3] as f32 / 255.0;
-    let dst_a = dst.0[3] as f32 / 255.0;
-
-    let out_a = src_a + dst_a * (1.0 - src_a);
+    blend_over(&mut image.get_pixel_mut(x, y).0, color);
+}
 
-    if out_a == 0.0 {
+/// Composite `color` over one RGBA pixel, in 8-bit fixed point with rounding.
+#[inline]
+fn blend_over(dst: &mut [u8], color: Rgba<u8>) {
+    let src_a = color.0[3];
+    let dst_a = dst[3];
+    if src_a == 0 {
+        return;
+    }
+    if src_a == 255 || dst_a == 0 {
+        dst.copy_from_slice(&color.0);
         return;
     }
 
-    let blend = |src: u8, dst: u8| -> u8 {
-        let src = src as f32;
-        let dst = dst as f32;
-        ((src * src_a + dst * dst_a * (1.0 - src_a)) / out_a) as u8
-    };
-
-    image.put_pixel(
-        x,
-        y,
-        Rgba([
-            blend(color.0[0], dst.0[0]),
-            blend(color.0[1], dst.0[1]),
-            blend(color.0[2], dst.0[2]),
-            (out_a * 255.0) as u8,
-        ]),
-    );
+    // Channel weights, scaled by 255 * 255
+    let src_w = src_a as u32 * 255;
+    let dst_w = dst_a as u32 * (255 - src_a as u32);
+    let out_w = src_w + dst_w;
+    for (d, &s) in dst[..3].iter_mut().zip(&color.0[..3]) {
+        *d = ((s as u32 * src_w + *d as u32 * dst_w + out_w / 2) / out_w) as u8;
+    }
+    dst[3] = ((out_w + 127) / 255) as u8;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use geo::{coord, LineString, Polygon};
+    use geo::{coord, polygon, LineString, Polygon};
 
     #[test]
     fn test_renderer_creation() {
         let config = RenderConfig {
             bbox: Bbox::new(0.0, 0.0, 1.0, 1.0),
             resolution: 0.01,
-            fill: [255, 0, 0, 128],
-            stroke: [255, 0, 0],
+            fill: Some([255, 0, 0, 128]),
+            stroke: Some([255, 0, 0]),
             stroke_width: 1,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
         };
         let renderer = Renderer::new(config).unwrap();
         assert_eq!(renderer.dimensions(), (100, 100));
@@ -317,12 +946,94 @@ mod tests {
         let config = RenderConfig {
             bbox: Bbox::new(0.0, 0.0, 100.0, 100.0),
             resolution: 0.0001, // Would create 1M x 1M image
-            fill: [255, 0, 0, 128],
-            stroke: [255, 0, 0],
+            fill: Some([255, 0, 0, 128]),
+            stroke: Some([255, 0, 0]),
             stroke_width: 1,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
         };
-        let result = Renderer::new(config);
+        let result = Renderer::new(config.clone());
         assert!(matches!(result, Err(GpkgError::ImageTooLarge { .. })));
+
+        // A lower limit rejects smaller images
+        let small = RenderConfig {
+            resolution: 1.0,
+            max_dimension: 50,
+            rotation: 0.0,
+            ..config
+        };
+        assert!(matches!(Renderer::new(small), Err(GpkgError::ImageTooLarge { max: 50, .. })));
+    }
+
+    #[test]
+    fn test_fit_supersample() {
+        assert_eq!(fit_supersample(1000, 500, 4, MAX_DIMENSION), 4);
+        assert_eq!(fit_supersample(6000, 500, 4, MAX_DIMENSION), 3);
+        assert_eq!(fit_supersample(500, 15000, 4, MAX_DIMENSION), 1);
+        assert_eq!(fit_supersample(0, 0, 4, MAX_DIMENSION), 4);
+    }
+
+    #[test]
+    fn test_fit_resolution() {
+        let bbox = Bbox::new(0.0, 0.0, 100.0, 50.0);
+        assert_eq!(fit_resolution(&bbox, 0.01, MAX_DIMENSION), 0.01);
+
+        let fitted = fit_resolution(&bbox, 0.0001, MAX_DIMENSION);
+        let (width, height) = calculate_dimensions(&bbox, fitted);
+        assert_eq!(width, MAX_DIMENSION);
+        assert!(height <= MAX_DIMENSION);
+
+        let odd = Bbox::new(1.234567, 0.0, 7.654321, 0.1);
+        let (width, _) = calculate_dimensions(&odd, fit_resolution(&odd, 1e-9, MAX_DIMENSION));
+        assert!(width <= MAX_DIMENSION);
+
+        // A lower limit coarsens further
+        let (width, height) = calculate_dimensions(&bbox, fit_resolution(&bbox, 0.0001, 1000));
+        assert_eq!((width, height), (1000, 500));
+    }
+
+    #[test]
+    fn test_save_with_metadata() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 4.0, 4.0),
+            resolution: 1.0,
+            fill: Some([255, 0, 0, 255]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let renderer = Renderer::new(config).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tagged.png");
+        let metadata = PngMetadata {
+            xmp: Some("<x:xmpmeta/>".to_string()),
+            text: vec![
+                ("Source".to_string(), "données.gpkg".to_string()),
+                ("Layer".to_string(), "区域".to_string()),
+            ],
+        };
+        renderer.save(&path, &metadata).unwrap();
+
+        let decoder = png::Decoder::new(std::io::BufReader::new(File::open(&path).unwrap()));
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (4, 4));
+        let chunk = &info.utf8_text[0];
+        assert_eq!(chunk.keyword, geotag::XMP_KEYWORD);
+        assert_eq!(chunk.get_text().unwrap(), "<x:xmpmeta/>");
+        // Latin-1 text goes to tEXt, anything else to iTXt
+        let chunk = &info.uncompressed_latin1_text[0];
+        assert_eq!((chunk.keyword.as_str(), chunk.text.as_str()), ("Source", "données.gpkg"));
+        let chunk = &info.utf8_text[1];
+        assert_eq!((chunk.keyword.as_str(), chunk.get_text().unwrap().as_str()), ("Layer", "区域"));
+        assert!(!dir.path().join("tagged.png.part").exists());
     }
 
     #[test]
@@ -330,11 +1041,16 @@ mod tests {
         let config = RenderConfig {
             bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
             resolution: 1.0,
-            fill: [255, 0, 0, 255],
-            stroke: [0, 0, 0],
+            fill: Some([255, 0, 0, 255]),
+            stroke: Some([0, 0, 0]),
             stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
         };
-        let renderer = Renderer::new(config).unwrap();
+        let mut renderer = Renderer::new(config).unwrap();
 
         let polygon = Polygon::new(
             LineString::from(vec![
@@ -351,12 +1067,652 @@ mod tests {
         renderer.render_multipolygon(&mp);
 
         // Check center pixel is filled
-        let img = renderer.image.lock().unwrap();
-        let center = img.get_pixel(5, 5);
-        assert_eq!(center.0, [255, 0, 0, 255]);
+        assert_eq!(renderer.pixel(5, 5).0, [255, 0, 0, 255]);
 
         // Check corner pixel is transparent
-        let corner = img.get_pixel(0, 0);
-        assert_eq!(corner.0, [0, 0, 0, 0]);
+        assert_eq!(renderer.pixel(0, 0).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_render_rotated() {
+        // A strip along the northern edge turns to the eastern edge
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 1.0,
+            fill: Some([255, 0, 0, 255]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 90.0,
+            glow: None,
+        };
+        let mut renderer = Renderer::new(config).unwrap();
+        let strip = Polygon::new(
+            LineString::from(vec![
+                coord! { x: 0.0, y: 8.0 },
+                coord! { x: 10.0, y: 8.0 },
+                coord! { x: 10.0, y: 10.0 },
+                coord! { x: 0.0, y: 10.0 },
+                coord! { x: 0.0, y: 8.0 },
+            ]),
+            vec![],
+        );
+        renderer.render_multipolygon(&MultiPolygon::new(vec![strip]));
+
+        for y in 0..10 {
+            assert_eq!(renderer.pixel(9, y).0, [255, 0, 0, 255], "row {}", y);
+            assert_eq!(renderer.pixel(5, y).0, [0, 0, 0, 0], "row {}", y);
+        }
+    }
+
+    #[test]
+    fn test_bands_stitch_seamlessly() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 20.0, 200.0),
+            resolution: 1.0,
+            fill: Some([255, 0, 0, 255]),
+            stroke: Some([0, 0, 255]),
+            stroke_width: 1,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let mut renderer = Renderer::new(config).unwrap();
+        assert!(renderer.bands.len() > 1);
+
+        let polygon = Polygon::new(
+            LineString::from(vec![
+                coord! { x: 5.0, y: 10.0 },
+                coord! { x: 15.0, y: 10.0 },
+                coord! { x: 15.0, y: 190.0 },
+                coord! { x: 5.0, y: 190.0 },
+                coord! { x: 5.0, y: 10.0 },
+            ]),
+            vec![],
+        );
+        renderer.render_multipolygon(&MultiPolygon::new(vec![polygon]));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bands.png");
+        renderer.save(&path, &PngMetadata::default()).unwrap();
+        let img = image::open(&path).unwrap().to_rgba8();
+        assert_eq!(img.dimensions(), (20, 200));
+
+        // Every row crossed by the polygon has its fill and both strokes,
+        // whichever band it belongs to
+        for y in 11..190 {
+            assert_eq!(img.get_pixel(10, y).0, [255, 0, 0, 255], "fill at row {}", y);
+            assert_eq!(img.get_pixel(5, y).0, [0, 0, 255, 255], "stroke at row {}", y);
+            assert_eq!(renderer.pixel(15, y).0, [0, 0, 255, 255], "stroke at row {}", y);
+        }
+        assert_eq!(img.get_pixel(10, 5).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_draw_vertices() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 20.0, 20.0),
+            resolution: 1.0,
+            fill: Some([255, 0, 0, 0]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 0,
+            vertex_color: Some([0, 255, 0]),
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let mut renderer = Renderer::new(config).unwrap();
+        let polygon = Polygon::new(
+            LineString::from(vec![
+                coord! { x: 5.0, y: 5.0 },
+                coord! { x: 15.0, y: 5.0 },
+                coord! { x: 10.0, y: 15.0 },
+                coord! { x: 5.0, y: 5.0 },
+            ]),
+            vec![],
+        );
+        renderer.render_multipolygon(&MultiPolygon::new(vec![polygon]));
+
+        // Vertex (10, 15) maps to pixel (10, 5): a cross of radius 2 around it
+        let marker = [0, 255, 0, 255];
+        for d in -2..=2 {
+            assert_eq!(renderer.pixel((10 + d) as u32, 5).0, marker);
+            assert_eq!(renderer.pixel(10, (5 + d) as u32).0, marker);
+        }
+        assert_eq!(renderer.pixel(11, 6).0, [0, 0, 0, 0]);
+        assert_eq!(renderer.pixel(5, 15).0, marker);
+        assert_eq!(renderer.pixel(15, 15).0, marker);
+    }
+
+    #[test]
+    fn test_render_layer_matches_per_geometry_rendering() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 40.0, 120.0),
+            resolution: 0.5,
+            fill: Some([255, 0, 0, 128]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let square = |x: f64, y: f64, size: f64| {
+            MultiPolygon::new(vec![Polygon::new(
+                LineString::from(vec![
+                    coord! { x: x, y: y },
+                    coord! { x: x + size, y: y },
+                    coord! { x: x + size, y: y + size },
+                    coord! { x: x, y: y + size },
+                    coord! { x: x, y: y },
+                ]),
+                vec![],
+            )])
+        };
+        // Overlapping geometries spanning several bands
+        let geometries = vec![square(2.0, 5.0, 30.0), square(10.0, 20.0, 25.0), square(5.0, 60.0, 20.0)];
+
+        let mut single = Renderer::new(config.clone()).unwrap();
+        single.render_layer(&geometries);
+        let mut sequential = Renderer::new(config).unwrap();
+        for mp in &geometries {
+            sequential.render_multipolygon(mp);
+        }
+        assert_eq!(single.to_image(), sequential.to_image());
+
+        // Overlaps blend twice instead of cancelling out (Even-Odd is per geometry)
+        let overlap = single.pixel(40, 240 - 2 * 30);
+        assert_eq!(overlap.0[3], sequential.pixel(40, 240 - 2 * 30).0[3]);
+        assert!(overlap.0[3] > 128);
+    }
+
+    #[test]
+    fn test_styled_layer_overrides_fill() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 1.0,
+            fill: Some([0, 0, 255, 200]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let square = |x: f64| {
+            MultiPolygon::new(vec![polygon![
+                (x: x, y: 0.0),
+                (x: x + 4.0, y: 0.0),
+                (x: x + 4.0, y: 4.0),
+                (x: x, y: 4.0),
+            ]])
+        };
+        let mut renderer = Renderer::new(config.clone()).unwrap();
+        let styles = [
+            FeatureStyle {
+                fill_alpha: Some(51),
+                ..Default::default()
+            },
+            FeatureStyle {
+                fill: Some([255, 0, 0]),
+                ..Default::default()
+            },
+        ];
+        renderer.render_styled_layer(&[square(0.0), square(5.0)], &styles);
+
+        // The color and alpha overrides are independent
+        assert_eq!(renderer.pixel(1, 8).0, [0, 0, 255, 51]);
+        assert_eq!(renderer.pixel(6, 8).0, [255, 0, 0, 200]);
+
+        // Unstyled geometries keep the layer fill
+        let mut renderer = Renderer::new(config).unwrap();
+        renderer.render_styled_layer(&[square(0.0), square(5.0)], &styles[..1]);
+        assert_eq!(renderer.pixel(6, 8).0, [0, 0, 255, 200]);
+    }
+
+    #[test]
+    fn test_degenerate_features() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 1.0,
+            fill: Some([255, 0, 0, 255]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        // A speck of 0.2 by 0.4 px and a square filled normally
+        let sliver = MultiPolygon::new(vec![polygon![
+            (x: 2.4, y: 4.8),
+            (x: 2.6, y: 4.8),
+            (x: 2.6, y: 5.2),
+            (x: 2.4, y: 5.2),
+        ]]);
+        let square = MultiPolygon::new(vec![polygon![
+            (x: 5.0, y: 5.0),
+            (x: 9.0, y: 5.0),
+            (x: 9.0, y: 9.0),
+            (x: 5.0, y: 9.0),
+        ]]);
+
+        let mut renderer = Renderer::new(config.clone()).unwrap();
+        renderer.render_layer(&[sliver.clone(), square.clone()]);
+        assert_eq!(renderer.degenerate_count(), 1);
+        assert!((0..10).all(|y| renderer.pixel(2, y).0[3] == 0));
+        assert_eq!(renderer.pixel(6, 2).0, [255, 0, 0, 255]);
+
+        // With a minimum size, the sliver is drawn as a mark on its center
+        let mut renderer = Renderer::new(RenderConfig {
+            min_feature_px: Some(2),
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            ..config.clone()
+        })
+        .unwrap();
+        renderer.render_layer(&[sliver.clone(), square.clone()]);
+        assert_eq!(renderer.degenerate_count(), 1);
+        assert_eq!(renderer.pixel(2, 4).0, [255, 0, 0, 255]);
+        assert_eq!(renderer.pixel(3, 5).0, [255, 0, 0, 255]);
+        assert_eq!(renderer.pixel(1, 4).0[3], 0);
+        assert_eq!(renderer.pixel(2, 1).0[3], 0);
+
+        // Counted without a fill too
+        let mut renderer = Renderer::new(RenderConfig {
+            fill: None,
+            stroke_width: 1,
+            ..config
+        })
+        .unwrap();
+        renderer.render_layer(&[sliver, square]);
+        assert_eq!(renderer.degenerate_count(), 1);
+    }
+
+    #[test]
+    fn test_thin_long_features_are_filled() {
+        let mut renderer = Renderer::new(RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 1.0,
+            fill: Some([255, 0, 0, 255]),
+            stroke: None,
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        })
+        .unwrap();
+        // A river 0.5 px wide and 8 px long is not degenerate
+        let river = MultiPolygon::new(vec![polygon![
+            (x: 2.2, y: 1.0),
+            (x: 2.7, y: 1.0),
+            (x: 2.7, y: 9.0),
+            (x: 2.2, y: 9.0),
+        ]]);
+        renderer.render_layer(&[river]);
+        assert_eq!(renderer.degenerate_count(), 0);
+        assert!((2..8).all(|y| renderer.pixel(2, y).0 == [255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_nan_vertex() {
+        let mut renderer = Renderer::new(RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 1.0,
+            fill: Some([255, 0, 0, 255]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 1,
+            vertex_color: Some([0, 0, 255]),
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        })
+        .unwrap();
+        // A failed reprojection left a NaN vertex: drawn as best as possible, without panicking
+        let ring = LineString::from(vec![
+            coord! { x: 1.0, y: 1.0 },
+            coord! { x: 5.0, y: 1.0 },
+            coord! { x: f64::NAN, y: 3.0 },
+            coord! { x: 5.0, y: 5.0 },
+            coord! { x: 1.0, y: 5.0 },
+            coord! { x: 1.0, y: 1.0 },
+        ]);
+        renderer.render_layer(&[MultiPolygon::new(vec![Polygon::new(ring, vec![])])]);
+    }
+
+    #[test]
+    fn test_offscreen_geometries_are_culled() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 1.0,
+            fill: Some([255, 0, 0, 255]),
+            stroke: Some([0, 0, 255]),
+            stroke_width: 5,
+            vertex_color: None,
+            min_feature_px: Some(2),
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let square = |x: f64, y: f64, size: f64| {
+            MultiPolygon::new(vec![polygon![
+                (x: x, y: y),
+                (x: x + size, y: y),
+                (x: x + size, y: y + size),
+                (x: x, y: y + size),
+            ]])
+        };
+        // Far away, including a sliver that would count as degenerate
+        let far = [square(100.0, 100.0, 5.0), square(-50.0, 3.0, 0.1)];
+        // Just right of the image: its thick stroke still reaches in
+        let near = square(10.5, 3.0, 4.0);
+        let inside = square(2.0, 2.0, 4.0);
+
+        let mut renderer = Renderer::new(config.clone()).unwrap();
+        renderer.render_layer(&[far[0].clone(), inside.clone(), far[1].clone(), near.clone()]);
+        assert_eq!(renderer.degenerate_count(), 0);
+        assert_eq!(renderer.pixel(9, 5).0, [0, 0, 255, 255]);
+
+        let mut expected = Renderer::new(config.clone()).unwrap();
+        expected.render_layer(&[inside.clone(), near.clone()]);
+        assert_eq!(renderer.to_image(), expected.to_image());
+
+        // Rotated frames cull on the rotated extent
+        let rotated = RenderConfig { rotation: 45.0, ..config };
+        let mut renderer = Renderer::new(rotated.clone()).unwrap();
+        renderer.render_layer(&[far[0].clone(), inside.clone(), far[1].clone()]);
+        let mut expected = Renderer::new(rotated).unwrap();
+        expected.render_layer(&[inside]);
+        assert_eq!(renderer.to_image(), expected.to_image());
+    }
+
+    #[test]
+    fn test_shader_colors_spans() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 1.0,
+            fill: Some([0, 0, 255, 255]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let square = |x: f64| {
+            MultiPolygon::new(vec![polygon![
+                (x: x, y: 0.0),
+                (x: x + 4.0, y: 0.0),
+                (x: x + 4.0, y: 4.0),
+                (x: x, y: 4.0),
+            ]])
+        };
+        let mut renderer = Renderer::new(config).unwrap();
+        // Horizontal hatching for the first geometry, solid green for the second
+        renderer.set_shader(|x_range, y, feature| {
+            assert!(x_range.start < x_range.end);
+            match feature {
+                0 if y % 2 == 0 => Rgba([255, 0, 0, 255]),
+                0 => Rgba([0, 0, 0, 0]),
+                _ => Rgba([0, 255, 0, 255]),
+            }
+        });
+        renderer.render_layer(&[square(0.0), square(5.0)]);
+
+        assert_eq!(renderer.pixel(1, 8).0, [255, 0, 0, 255]);
+        assert_eq!(renderer.pixel(1, 7).0, [0, 0, 0, 0]);
+        assert_eq!(renderer.pixel(6, 7).0, [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn test_blend_over() {
+        let mut pixel = [0, 0, 0, 0];
+        blend_over(&mut pixel, Rgba([255, 0, 0, 128]));
+        assert_eq!(pixel, [255, 0, 0, 128]);
+
+        blend_over(&mut pixel, Rgba([0, 0, 255, 128]));
+        assert_eq!(pixel, [85, 0, 170, 192]);
+
+        let mut opaque = [10, 20, 30, 255];
+        blend_over(&mut opaque, Rgba([255, 255, 255, 0]));
+        assert_eq!(opaque, [10, 20, 30, 255]);
+        blend_over(&mut opaque, Rgba([110, 120, 130, 255]));
+        assert_eq!(opaque, [110, 120, 130, 255]);
+    }
+
+    #[test]
+    fn test_blend_span_matches_pixel_blending() {
+        let mut span_band = Band {
+            y_start: 4,
+            pixels: ImageBuffer::from_pixel(6, 2, Rgba([0, 0, 0, 0])),
+        };
+        span_band.blend(2, 5, Rgba([0, 255, 0, 200]));
+        let mut pixel_band = Band {
+            y_start: 4,
+            pixels: span_band.pixels.clone(),
+        };
+
+        let color = Rgba([255, 0, 0, 100]);
+        span_band.blend_span(5, 1, 5, color);
+        for x in 1..5 {
+            pixel_band.blend(x, 5, color);
+        }
+        assert_eq!(span_band.pixels, pixel_band.pixels);
+        assert_eq!(span_band.pixels.get_pixel(0, 1).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_styled_layer_overrides_stroke_color() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 1.0,
+            fill: Some([0, 0, 0, 0]),
+            stroke: Some([255, 0, 0]),
+            stroke_width: 1,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let square = |x: f64| {
+            MultiPolygon::new(vec![polygon![
+                (x: x, y: 1.0),
+                (x: x + 3.0, y: 1.0),
+                (x: x + 3.0, y: 4.0),
+                (x: x, y: 4.0),
+            ]])
+        };
+        let mut renderer = Renderer::new(config).unwrap();
+        let styles = [
+            FeatureStyle {
+                stroke: Some([0, 128, 0]),
+                ..Default::default()
+            },
+            FeatureStyle::default(),
+        ];
+        renderer.render_styled_layer(&[square(1.0), square(6.0)], &styles);
+
+        assert_eq!(renderer.pixel(1, 9).0, [0, 128, 0, 255]);
+        assert_eq!(renderer.pixel(6, 9).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_fill_or_stroke_only() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 1.0,
+            fill: None,
+            stroke: Some([255, 0, 0]),
+            stroke_width: 1,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let square = MultiPolygon::new(vec![polygon![(x: 1.0, y: 1.0), (x: 4.0, y: 1.0), (x: 4.0, y: 4.0), (x: 1.0, y: 4.0)]]);
+
+        // Outlines only
+        let mut renderer = Renderer::new(config.clone()).unwrap();
+        renderer.render_layer(std::slice::from_ref(&square));
+        assert_eq!(renderer.pixel(1, 9).0, [255, 0, 0, 255]);
+        assert_eq!(renderer.pixel(2, 7).0[3], 0);
+
+        // Interiors only
+        let mut renderer = Renderer::new(RenderConfig {
+            fill: Some([0, 0, 255, 255]),
+            stroke: None,
+            ..config
+        })
+        .unwrap();
+        renderer.render_layer(&[square]);
+        assert_eq!(renderer.pixel(2, 7).0, [0, 0, 255, 255]);
+        assert_eq!(renderer.pixel(1, 9).0[3], 0);
+    }
+
+    #[test]
+    fn test_glow() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 1.0,
+            fill: Some([0, 0, 255, 255]),
+            stroke: None,
+            stroke_width: 1,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: Some(Glow {
+                color: [255, 255, 0, 255],
+                radius: 3,
+            }),
+        };
+        let square = MultiPolygon::new(vec![polygon![(x: 1.0, y: 1.0), (x: 4.0, y: 1.0), (x: 4.0, y: 4.0), (x: 1.0, y: 4.0)]]);
+        let mut renderer = Renderer::new(config.clone()).unwrap();
+        renderer.render_layer(std::slice::from_ref(&square));
+
+        // Half a pixel outside the left edge, then fading out with the distance
+        assert_eq!(renderer.pixel(0, 7).0, [255, 255, 0, 177]);
+        assert_eq!(renderer.pixel(5, 7).0, [255, 255, 0, 64]);
+        assert_eq!(renderer.pixel(7, 7).0[3], 0);
+        assert_eq!(renderer.pixel(2, 7).0, [0, 0, 255, 255]);
+
+        // Without a fill, the interior stays clear
+        let mut renderer = Renderer::new(RenderConfig { fill: None, ..config.clone() }).unwrap();
+        renderer.render_layer(std::slice::from_ref(&square));
+        assert_eq!(renderer.pixel(2, 7).0[3], 0);
+        assert_eq!(renderer.pixel(0, 7).0, [255, 255, 0, 177]);
+
+        // Supersampling widens the halo in pixels
+        assert_eq!(config.supersampled(2).glow.unwrap().radius, 6);
+    }
+
+    #[test]
+    fn test_autocrop() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 1.0,
+            fill: Some([255, 0, 0, 128]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let mut renderer = Renderer::new(config).unwrap();
+        renderer.render_multipolygon(&MultiPolygon::new(vec![polygon![
+            (x: 2.0, y: 3.0),
+            (x: 5.0, y: 3.0),
+            (x: 5.0, y: 6.0),
+            (x: 2.0, y: 6.0),
+        ]]));
+
+        // Nothing exceeds the threshold: left untouched
+        assert!(renderer.autocrop(128).is_none());
+        assert_eq!(renderer.dimensions(), (10, 10));
+
+        let bbox = renderer.autocrop(0).unwrap();
+        assert_eq!(renderer.dimensions(), (3, 3));
+        assert_eq!(
+            (bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat),
+            (2.0, 3.0, 5.0, 6.0)
+        );
+        let image = renderer.to_image();
+        assert!(image.pixels().all(|p| p.0 == [255, 0, 0, 128]));
+    }
+
+    #[test]
+    fn test_into_image_matches_to_image() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 0.01,
+            fill: Some([255, 0, 0, 128]),
+            stroke: Some([0, 0, 255]),
+            stroke_width: 1,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let mut renderer = Renderer::new(config).unwrap();
+        renderer.render_multipolygon(&MultiPolygon::new(vec![polygon![
+            (x: 1.0, y: 1.0),
+            (x: 9.0, y: 2.0),
+            (x: 5.0, y: 9.0),
+        ]]));
+        assert!(renderer.bands.len() > 1);
+
+        let copy = renderer.to_image();
+        let image = renderer.into_image();
+        assert_eq!(image.dimensions(), (1000, 1000));
+        assert_eq!(image, copy);
+    }
+
+    #[test]
+    fn test_set_base() {
+        let config = RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, 10.0, 10.0),
+            resolution: 0.01,
+            fill: Some([255, 0, 0, 255]),
+            stroke: Some([0, 0, 0]),
+            stroke_width: 0,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        };
+        let mut renderer = Renderer::new(config).unwrap();
+        assert!(renderer.set_base(&RgbaImage::new(10, 10)).is_err());
+
+        let base = RgbaImage::from_pixel(1000, 1000, Rgba([0, 0, 255, 255]));
+        renderer.set_base(&base).unwrap();
+        renderer.render_multipolygon(&MultiPolygon::new(vec![polygon![
+            (x: 0.0, y: 0.0),
+            (x: 5.0, y: 0.0),
+            (x: 5.0, y: 10.0),
+            (x: 0.0, y: 10.0),
+        ]]));
+
+        let image = renderer.into_image();
+        assert_eq!(image.get_pixel(250, 500).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(750, 500).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(750, 999).0, [0, 0, 255, 255]);
     }
 }
@@ -3,7 +3,7 @@
 //! This module provides the `Edge` and `ScanlineTable` structures used by the
 //! scanline fill algorithm to rasterize polygons efficiently.
 
-use crate::math::{world_to_screen, Bbox};
+use crate::math::ScreenTransform;
 use geo::CoordsIter;
 
 /// Represents an edge in the scanline algorithm.
@@ -15,6 +15,9 @@ pub struct Edge {
     pub x_current: f64,
     /// Reciprocal of the slope (dx/dy). Used to update x_current for each new scanline.
     pub inv_slope: f64,
+    /// Index of the geometry the edge belongs to, so the Even-Odd rule is
+    /// applied to each geometry separately when a layer is swept at once.
+    pub feature: u32,
 }
 
 impl Edge {
@@ -37,6 +40,7 @@ impl Edge {
             y_max: p_end.1.round() as i32,
             x_current: p_start.0,
             inv_slope,
+            feature: 0,
         })
     }
 }
@@ -66,19 +70,30 @@ impl ScanlineTable {
         }
     }
 
+    /// Extracts all edges of a MultiPolygon, tagged with its feature index.
+    pub fn extract_from_multipolygon(
+        &mut self,
+        mp: &geo::MultiPolygon<f64>,
+        feature: u32,
+        transform: &ScreenTransform,
+    ) {
+        for polygon in mp {
+            self.extract_feature_polygon(polygon, feature, transform);
+        }
+    }
+
     /// Extracts all edges from a polygon and adds them to the table.
     ///
     /// This handles both the exterior ring and any interior holes.
-    pub fn extract_from_polygon(
+    fn extract_feature_polygon(
         &mut self,
         polygon: &geo::Polygon<f64>,
-        bbox: &Bbox,
-        resolution: f64,
-        img_height: u32,
+        feature: u32,
+        transform: &ScreenTransform,
     ) {
-        self.extract_from_ring(polygon.exterior(), bbox, resolution, img_height);
+        self.extract_from_ring(polygon.exterior(), feature, transform);
         for interior in polygon.interiors() {
-            self.extract_from_ring(interior, bbox, resolution, img_height);
+            self.extract_from_ring(interior, feature, transform);
         }
     }
 
@@ -86,9 +101,8 @@ impl ScanlineTable {
     fn extract_from_ring(
         &mut self,
         ring: &geo::LineString<f64>,
-        bbox: &Bbox,
-        resolution: f64,
-        img_height: u32,
+        feature: u32,
+        transform: &ScreenTransform,
     ) {
         if ring.coords_count() < 3 {
             return;
@@ -96,7 +110,7 @@ impl ScanlineTable {
 
         let coords: Vec<(f64, f64)> = ring
             .coords()
-            .map(|c| world_to_screen(c.x, c.y, bbox, resolution, img_height))
+            .map(|c| transform.apply(c.x, c.y))
             .collect();
 
         for i in 0..coords.len() {
@@ -105,7 +119,7 @@ impl ScanlineTable {
 
             if let Some(edge) = Edge::new(p1, p2) {
                 let y_start = p1.1.min(p2.1).round() as i32;
-                self.add_edge(y_start, edge);
+                self.add_edge(y_start, Edge { feature, ..edge });
             }
         }
     }
@@ -148,7 +162,11 @@ mod tests {
         );
 
         let mut st = ScanlineTable::new(0, img_height);
-        st.extract_from_polygon(&polygon, &bbox, resolution, img_height);
+        st.extract_from_multipolygon(
+            &geo::MultiPolygon::new(vec![polygon]),
+            0,
+            &ScreenTransform::new(&bbox, resolution, img_height, 0.0),
+        );
 
         // Polygons coordinates in screen space with world_to_screen:
         // x = (lon - 0.0) / 1.0
@@ -166,4 +184,25 @@ mod tests {
         assert_eq!(st.entries[2][0].x_current, 8.0); // depends on order
         assert_eq!(st.entries[2][1].x_current, 2.0);
     }
+
+    #[test]
+    fn test_multipolygon_edges_tagged_with_feature() {
+        let bbox = Bbox::new(0.0, 0.0, 10.0, 10.0);
+        let polygon = Polygon::new(
+            LineString::from(vec![
+                coord! { x: 2.0, y: 2.0 },
+                coord! { x: 8.0, y: 2.0 },
+                coord! { x: 8.0, y: 8.0 },
+                coord! { x: 2.0, y: 2.0 },
+            ]),
+            vec![],
+        );
+
+        let mut st = ScanlineTable::new(0, 10);
+        let transform = ScreenTransform::new(&bbox, 1.0, 10, 0.0);
+        st.extract_from_multipolygon(&geo::MultiPolygon::new(vec![polygon]), 3, &transform);
+        let edges: Vec<&Edge> = st.entries.iter().flatten().collect();
+        assert_eq!(edges.len(), 2);
+        assert!(edges.iter().all(|e| e.feature == 3));
+    }
 }
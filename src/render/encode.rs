@@ -0,0 +1,428 @@
+//! PNG encoder settings (`--png-compression`, `--png-filter`, `--interlace`,
+//! `--colors`, `--dither`).
+//!
+//! Compression level and row filters are handed to the png crate. The crate
+//! only writes non-interlaced image data, so Adam7 passes are extracted,
+//! filtered and compressed here, then written as IDAT chunks. Paletted images
+//! are quantized here too, with optional Floyd–Steinberg dithering.
+
+use flate2::write::ZlibEncoder;
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::error::Result;
+
+/// Adam7 passes as (first column, first row, column step, row step).
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Largest IDAT chunk written for interlaced images.
+const IDAT_CHUNK_SIZE: usize = 1 << 20;
+
+
+/// Deflate effort, trading encoding time for file size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum PngCompression {
+    /// No compression: fastest, largest files.
+    None,
+    /// Light compression, much faster on very large images.
+    Fast,
+    /// Balance of speed and size.
+    #[default]
+    Default,
+    /// Slowest, smallest files.
+    Best,
+}
+
+impl PngCompression {
+    fn png(self) -> png::Compression {
+        match self {
+            Self::None => png::Compression::NoCompression,
+            Self::Fast => png::Compression::Fast,
+            Self::Default => png::Compression::Balanced,
+            Self::Best => png::Compression::High,
+        }
+    }
+
+    /// zlib level of the interlaced image data.
+    fn zlib(self) -> flate2::Compression {
+        match self {
+            Self::None => flate2::Compression::none(),
+            Self::Fast => flate2::Compression::fast(),
+            Self::Default => flate2::Compression::default(),
+            Self::Best => flate2::Compression::best(),
+        }
+    }
+}
+
+/// Filter applied to each row before compression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum PngFilter {
+    None,
+    Sub,
+    Up,
+    Avg,
+    Paeth,
+    /// Pick the filter of each row that is likely to compress best.
+    #[default]
+    Adaptive,
+}
+
+impl PngFilter {
+    fn png(self) -> png::Filter {
+        match self {
+            Self::None => png::Filter::NoFilter,
+            Self::Sub => png::Filter::Sub,
+            Self::Up => png::Filter::Up,
+            Self::Avg => png::Filter::Avg,
+            Self::Paeth => png::Filter::Paeth,
+            Self::Adaptive => png::Filter::Adaptive,
+        }
+    }
+}
+
+/// How rendered PNGs are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PngOptions {
+    pub compression: PngCompression,
+    pub filter: PngFilter,
+    /// Write Adam7-interlaced images, which display progressively while downloading.
+    pub interlace: bool,
+    /// Write paletted images of at most this many colors (None writes RGBA).
+    pub colors: Option<u16>,
+    /// Diffuse the quantization error of paletted images (Floyd–Steinberg).
+    pub dither: bool,
+    /// Print resolution written in the pHYs chunk, in dots per inch.
+    pub dpi: Option<u32>,
+}
+
+impl PngOptions {
+    /// Creates an encoder for a `width` x `height` image with these settings.
+    pub(crate) fn encoder<'a, W: Write>(&self, writer: W, width: u32, height: u32) -> Result<png::Encoder<'a, W>> {
+        let mut info = png::Info::with_size(width, height);
+        info.interlaced = self.interlace;
+        info.pixel_dims = self.dpi.map(|dpi| {
+            let per_meter = (dpi as f64 / 0.0254).round() as u32;
+            png::PixelDimensions {
+                xppu: per_meter,
+                yppu: per_meter,
+                unit: png::Unit::Meter,
+            }
+        });
+        let mut encoder = png::Encoder::with_info(writer, info)?;
+        encoder.set_compression(self.compression.png());
+        encoder.set_filter(self.filter.png());
+        Ok(encoder)
+    }
+
+    /// Writes `pixels`, rows of `width` pixels of `bpp` bytes, as the Adam7
+    /// passes of an image whose header declared interlacing.
+    pub(crate) fn write_interlaced<W: Write>(
+        &self,
+        writer: &mut png::Writer<W>,
+        pixels: &[u8],
+        width: usize,
+        bpp: usize,
+    ) -> Result<()> {
+        let height = pixels.len() / (width * bpp);
+        let mut zlib = ZlibEncoder::new(Vec::new(), self.compression.zlib());
+        for (x0, y0, dx, dy) in ADAM7 {
+            if x0 >= width || y0 >= height {
+                continue;
+            }
+            let row_len = (width - x0).div_ceil(dx) * bpp;
+            let mut previous = vec![0; row_len];
+            let mut row = Vec::with_capacity(row_len);
+            let mut filtered = vec![0; row_len + 1];
+            for y in (y0..height).step_by(dy) {
+                let line = &pixels[y * width * bpp..(y + 1) * width * bpp];
+                row.clear();
+                for x in (x0..width).step_by(dx) {
+                    row.extend_from_slice(&line[x * bpp..(x + 1) * bpp]);
+                }
+                filter_row(self.filter, bpp, &previous, &row, &mut filtered);
+                zlib.write_all(&filtered)?;
+                std::mem::swap(&mut previous, &mut row);
+            }
+        }
+        for chunk in zlib.finish()?.chunks(IDAT_CHUNK_SIZE) {
+            writer.write_chunk(png::chunk::IDAT, chunk)?;
+        }
+        Ok(())
+    }
+}
+
+/// Paletted version of an RGBA image.
+#[derive(Debug)]
+pub(crate) struct Indexed {
+    /// RGBA colors of the palette.
+    pub palette: Vec<[u8; 4]>,
+    /// One palette index per pixel.
+    pub indices: Vec<u8>,
+}
+
+impl Indexed {
+    /// The `PLTE` chunk: RGB of every palette entry.
+    pub fn rgb(&self) -> Vec<u8> {
+        self.palette.iter().flat_map(|c| [c[0], c[1], c[2]]).collect()
+    }
+
+    /// The `tRNS` chunk: alpha of the palette entries, without the trailing
+    /// opaque ones (None when all are opaque).
+    pub fn alpha(&self) -> Option<Vec<u8>> {
+        let len = self.palette.iter().rposition(|c| c[3] < 255)? + 1;
+        Some(self.palette[..len].iter().map(|c| c[3]).collect())
+    }
+}
+
+/// Reduces `pixels`, rows of `width` RGBA pixels, to at most `colors` colors.
+///
+/// Images already within the limit keep their exact colors. Otherwise the
+/// palette is built by median cut and, with `dither`, the rounding error of
+/// each pixel is spread over its right and lower neighbours (Floyd–Steinberg),
+/// so smooth gradients such as overlapping translucent fills do not band.
+pub(crate) fn quantize(pixels: &[u8], width: usize, colors: usize, dither: bool) -> Indexed {
+    let mut histogram: HashMap<[u8; 4], u32> = HashMap::new();
+    for pixel in pixels.chunks_exact(4) {
+        *histogram.entry([pixel[0], pixel[1], pixel[2], pixel[3]]).or_default() += 1;
+    }
+    // Sorted, so the palette does not depend on the hash order
+    let mut histogram: Vec<([u8; 4], u32)> = histogram.into_iter().collect();
+    histogram.sort_unstable();
+    let palette = if histogram.len() <= colors {
+        histogram.into_iter().map(|(color, _)| color).collect()
+    } else {
+        median_cut(histogram, colors)
+    };
+
+    let mut cache: HashMap<[u8; 4], u8> = HashMap::new();
+    let mut index_of = |color: [u8; 4]| {
+        *cache.entry(color).or_insert_with(|| nearest(&palette, color))
+    };
+    let mut indices = Vec::with_capacity(pixels.len() / 4);
+    // Errors carried to the current and next rows, with a column of margin on both sides
+    let mut current = vec![[0.0f32; 4]; width + 2];
+    let mut next = vec![[0.0f32; 4]; width + 2];
+    for row in pixels.chunks_exact(width * 4) {
+        for (x, pixel) in row.chunks_exact(4).enumerate() {
+            if !dither {
+                indices.push(index_of([pixel[0], pixel[1], pixel[2], pixel[3]]));
+                continue;
+            }
+            let wanted: [f32; 4] = std::array::from_fn(|c| (pixel[c] as f32 + current[x + 1][c]).clamp(0.0, 255.0));
+            let index = index_of(wanted.map(|v| v.round() as u8));
+            indices.push(index);
+            for (c, &value) in wanted.iter().enumerate() {
+                let error = value - palette[index as usize][c] as f32;
+                current[x + 2][c] += error * 7.0 / 16.0;
+                next[x][c] += error * 3.0 / 16.0;
+                next[x + 1][c] += error * 5.0 / 16.0;
+                next[x + 2][c] += error / 16.0;
+            }
+        }
+        std::mem::swap(&mut current, &mut next);
+        next.fill([0.0; 4]);
+    }
+    Indexed { palette, indices }
+}
+
+/// Splits the colors of `histogram` into `colors` boxes, halving the box with
+/// the widest channel at its median pixel each time, and returns the mean
+/// color of every box.
+fn median_cut(histogram: Vec<([u8; 4], u32)>, colors: usize) -> Vec<[u8; 4]> {
+    // Widest channel of a box and its range
+    let widest = |entries: &[([u8; 4], u32)]| {
+        (0..4)
+            .map(|c| {
+                let values = entries.iter().map(|(color, _)| color[c]);
+                (c, values.clone().max().unwrap_or(0) - values.min().unwrap_or(0))
+            })
+            .max_by_key(|&(c, range)| (range, std::cmp::Reverse(c)))
+            .unwrap_or((0, 0))
+    };
+    let mut boxes = vec![histogram];
+    while boxes.len() < colors {
+        let Some((i, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, entries)| entries.len() > 1)
+            .map(|(i, entries)| (i, widest(entries)))
+            .max_by_key(|&(i, (_, range))| (range, std::cmp::Reverse(i)))
+            .map(|(i, (channel, _))| (i, channel))
+        else {
+            break;
+        };
+        let mut entries = std::mem::take(&mut boxes[i]);
+        entries.sort_by_key(|(color, _)| color[channel]);
+        let half = entries.iter().map(|&(_, count)| count as u64).sum::<u64>() / 2;
+        let mut seen = 0;
+        let split = entries
+            .iter()
+            .position(|&(_, count)| {
+                seen += count as u64;
+                seen > half
+            })
+            .unwrap_or(0)
+            .clamp(1, entries.len() - 1);
+        boxes[i] = entries.split_off(split);
+        boxes.push(entries);
+    }
+    boxes
+        .iter()
+        .map(|entries| {
+            let total: u64 = entries.iter().map(|&(_, count)| count as u64).sum();
+            std::array::from_fn(|c| {
+                let sum: u64 = entries.iter().map(|&(color, count)| color[c] as u64 * count as u64).sum();
+                ((sum + total / 2) / total) as u8
+            })
+        })
+        .collect()
+}
+
+/// Index of the palette color closest to `color`.
+fn nearest(palette: &[[u8; 4]], color: [u8; 4]) -> u8 {
+    let distance = |entry: &[u8; 4]| -> i32 { (0..4).map(|c| (entry[c] as i32 - color[c] as i32).pow(2)).sum() };
+    (0..palette.len()).min_by_key(|&i| distance(&palette[i])).unwrap_or(0) as u8
+}
+
+/// Filters `row` into `out` (filter type byte, then the filtered bytes).
+fn filter_row(filter: PngFilter, bpp: usize, previous: &[u8], row: &[u8], out: &mut [u8]) {
+    let kind = match filter {
+        PngFilter::None => 0,
+        PngFilter::Sub => 1,
+        PngFilter::Up => 2,
+        PngFilter::Avg => 3,
+        PngFilter::Paeth => 4,
+        PngFilter::Adaptive => {
+            // Smallest sum of the filtered bytes read as signed values (libpng heuristic)
+            let cost = |kind: u8, out: &mut [u8]| {
+                apply_filter(kind, bpp, previous, row, out);
+                out[1..].iter().map(|&b| (b as i8).unsigned_abs() as u64).sum::<u64>()
+            };
+            (0..5).min_by_key(|&kind| cost(kind, out)).unwrap_or(0)
+        }
+    };
+    apply_filter(kind, bpp, previous, row, out);
+}
+
+fn apply_filter(kind: u8, bpp: usize, previous: &[u8], row: &[u8], out: &mut [u8]) {
+    out[0] = kind;
+    for i in 0..row.len() {
+        let left = if i >= bpp { row[i - bpp] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= bpp { previous[i - bpp] } else { 0 };
+        let prediction = match kind {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            _ => paeth(left, up, up_left),
+        };
+        out[i + 1] = row[i].wrapping_sub(prediction);
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes a `width` x `height` RGBA gradient and decodes it back.
+    fn round_trip(options: PngOptions, width: u32, height: u32) -> Vec<u8> {
+        let pixels: Vec<u8> = (0..width * height * 4).map(|i| (i * 7 % 251) as u8).collect();
+        let mut png = Vec::new();
+        let mut encoder = options.encoder(&mut png, width, height).unwrap();
+        encoder.set_color(png::ColorType::Rgba);
+        let mut writer = encoder.write_header().unwrap();
+        if options.interlace {
+            options.write_interlaced(&mut writer, &pixels, width as usize, 4).unwrap();
+        } else {
+            writer.write_image_data(&pixels).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut reader = png::Decoder::new(std::io::Cursor::new(png)).read_info().unwrap();
+        assert_eq!(reader.info().interlaced, options.interlace);
+        assert_eq!(reader.info().pixel_dims.map(|d| (d.xppu, d.unit)), options.dpi.map(|_| (11811, png::Unit::Meter)));
+        let mut decoded = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut decoded).unwrap();
+        assert_eq!(decoded, pixels, "{:?} {}x{}", options, width, height);
+        decoded
+    }
+
+    #[test]
+    fn test_interlaced_round_trip() {
+        for filter in [PngFilter::None, PngFilter::Sub, PngFilter::Up, PngFilter::Avg, PngFilter::Paeth, PngFilter::Adaptive] {
+            let options = PngOptions {
+                filter,
+                interlace: true,
+                ..PngOptions::default()
+            };
+            // Sizes leaving some Adam7 passes empty
+            for (width, height) in [(1, 1), (3, 2), (13, 9)] {
+                round_trip(options, width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compression_levels() {
+        for compression in [PngCompression::None, PngCompression::Fast, PngCompression::Best] {
+            let options = PngOptions {
+                compression,
+                ..PngOptions::default()
+            };
+            round_trip(options, 16, 16);
+            round_trip(PngOptions { interlace: true, ..options }, 16, 16);
+            round_trip(PngOptions { dpi: Some(300), ..options }, 16, 16);
+        }
+    }
+
+    #[test]
+    fn test_quantize() {
+        // Few colors are kept exactly
+        let flat = [[255, 0, 0, 128], [0, 0, 0, 0], [255, 0, 0, 128], [9, 9, 9, 255]].concat();
+        let indexed = quantize(&flat, 2, 4, true);
+        assert_eq!(indexed.palette, vec![[0, 0, 0, 0], [9, 9, 9, 255], [255, 0, 0, 128]]);
+        assert_eq!(indexed.indices, vec![2, 0, 2, 1]);
+        assert_eq!(indexed.alpha(), Some(vec![0, 255, 128]));
+
+        // A translucent ramp of 256 alphas reduced to 16 colors
+        let (width, height) = (256, 8);
+        let ramp: Vec<u8> = (0..height).flat_map(|_| (0..width).flat_map(|x| [0, 0, 255, x as u8])).collect();
+        let banded = quantize(&ramp, width, 16, false);
+        let dithered = quantize(&ramp, width, 16, true);
+        assert_eq!(banded.palette.len(), 16);
+        assert_eq!(banded.palette, dithered.palette);
+        // Mean distance between the alpha of 4-column blocks and the ramp
+        let error = |indexed: &Indexed| {
+            let block = |x0: usize| {
+                let alphas = (0..height).flat_map(|y| (x0..x0 + 4).map(move |x| y * width + x));
+                let sum: u32 = alphas.map(|i| indexed.palette[indexed.indices[i] as usize][3] as u32).sum();
+                (sum as f32 / (4 * height) as f32 - (x0 as f32 + 1.5)).abs()
+            };
+            (0..width).step_by(4).map(block).sum::<f32>() / (width / 4) as f32
+        };
+        assert!(error(&dithered) < error(&banded) / 2.0, "{} vs {}", error(&dithered), error(&banded));
+    }
+}
@@ -0,0 +1,589 @@
+//! Coordinate reprojection.
+//!
+//! Two backends are available:
+//! - `reproject`: native libproj through the `proj` crate (accurate, default);
+//! - `reproject-pure`: pure-Rust `proj4rs`, for targets where libproj can't be
+//!   built (musl containers, WASM).
+//!
+//! When both are compiled in, proj is tried first and proj4rs is the fallback.
+//! proj4rs implements the classic PROJ.4 transformations only: no datum grid
+//! shifts and no WKT parsing (EPSG codes are extracted from WKT definitions),
+//! so results can be off by a few metres for datums that need grids. Without
+//! any backend, only identity transforms are available, which is enough for
+//! WGS84 data.
+
+use geo::{Coord, LineString, MapCoords, MultiPolygon, Polygon};
+use std::cell::RefCell;
+use std::sync::OnceLock;
+
+use crate::antimeridian;
+use crate::error::{GpkgError, Result};
+use crate::logger;
+use crate::math::Bbox;
+use crate::warning::{self, WarningCode};
+
+/// A coordinate transformation between two CRS definitions.
+///
+/// Not `Sync` when backed by proj: create one per thread.
+pub struct Transformer {
+    inner: Inner,
+}
+
+enum Inner {
+    Identity,
+    #[cfg(feature = "reproject")]
+    Proj(proj::Proj),
+    #[cfg(feature = "reproject-pure")]
+    Pure(Box<pure::PureTransform>),
+}
+
+/// A coordinate operation selected by PROJ.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    /// Name of the operation (e.g. "RGF93 v1 to WGS 84 (1)").
+    pub description: String,
+    /// Accuracy in metres, None when unknown (ballpark transformations).
+    pub accuracy: Option<f64>,
+}
+
+/// Implementation backing a `Transformer`.
+#[allow(dead_code)] // Variants depend on the enabled features
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Source and target CRS are identical.
+    Identity,
+    /// Native libproj.
+    Proj,
+    /// Pure-Rust proj4rs (reduced accuracy).
+    Pure,
+}
+
+impl Transformer {
+    /// Creates a transformation from `from` to `to` (e.g. "EPSG:2154" to "EPSG:4326").
+    pub fn new(from: &str, to: &str) -> Result<Self> {
+        if from == to {
+            return Ok(Self {
+                inner: Inner::Identity,
+            });
+        }
+
+        #[allow(unused_mut)]
+        let mut errors: Vec<String> = Vec::new();
+
+        #[cfg(feature = "reproject")]
+        match proj::Proj::new_known_crs(from, to, None) {
+            Ok(p) => {
+                return Ok(Self {
+                    inner: Inner::Proj(p),
+                })
+            }
+            Err(e) => errors.push(format!("proj: {}", e)),
+        }
+
+        #[cfg(feature = "reproject-pure")]
+        match pure::PureTransform::new(from, to) {
+            Ok(t) => {
+                return Ok(Self {
+                    inner: Inner::Pure(Box::new(t)),
+                })
+            }
+            Err(e) => errors.push(format!("proj4rs: {}", e)),
+        }
+
+        if errors.is_empty() {
+            errors.push(
+                "built without the `reproject` or `reproject-pure` feature".to_string(),
+            );
+        }
+        Err(GpkgError::Reprojection(format!(
+            "{} -> {}: {}",
+            from,
+            to,
+            errors.join("; ")
+        )))
+    }
+
+    /// Returns the backend performing this transformation.
+    pub fn backend(&self) -> Backend {
+        match &self.inner {
+            Inner::Identity => Backend::Identity,
+            #[cfg(feature = "reproject")]
+            Inner::Proj(_) => Backend::Proj,
+            #[cfg(feature = "reproject-pure")]
+            Inner::Pure(_) => Backend::Pure,
+        }
+    }
+
+    /// Transforms a single point, returning `None` on failure.
+    pub fn convert(&self, point: (f64, f64)) -> Option<(f64, f64)> {
+        match &self.inner {
+            Inner::Identity => Some(point),
+            #[cfg(feature = "reproject")]
+            Inner::Proj(p) => p.convert(point).ok(),
+            #[cfg(feature = "reproject-pure")]
+            Inner::Pure(t) => t.convert(point),
+        }
+    }
+
+    /// Returns the operation proj uses to transform `point`; None for
+    /// identity and proj4rs transforms, or when the point fails.
+    ///
+    /// proj chooses among the candidate operations by area of use, so
+    /// points far apart may use different ones.
+    #[cfg_attr(not(feature = "reproject"), allow(unused_variables))]
+    pub fn operation(&self, point: (f64, f64)) -> Option<Operation> {
+        #[cfg(feature = "reproject")]
+        if let Inner::Proj(p) = &self.inner {
+            // The info describes the operation of the last transformed point
+            let _: (f64, f64) = p.convert(point).ok()?;
+            let info = p.proj_info();
+            return Some(Operation {
+                description: info.description.unwrap_or_else(|| "unknown operation".to_string()),
+                accuracy: (info.accuracy >= 0.0).then_some(info.accuracy),
+            });
+        }
+        None
+    }
+}
+
+/// Accuracy in metres required from proj operations (`--proj-accuracy`).
+static REQUIRED_ACCURACY: OnceLock<f64> = OnceLock::new();
+
+/// Makes [`check_operation`] reject operations not known to be accurate to
+/// `metres`.
+///
+/// Only the first call has an effect.
+pub fn require_accuracy(metres: f64) {
+    REQUIRED_ACCURACY.set(metres).ok();
+}
+
+/// Reports the operation `transformer` uses around `point` for the input
+/// `name`: logged with its accuracy, warned about when its accuracy is
+/// unknown, and rejected when less accurate than [`require_accuracy`].
+pub fn check_operation(transformer: &Transformer, point: (f64, f64), name: &str) -> Result<()> {
+    check_operation_accuracy(transformer, point, name, REQUIRED_ACCURACY.get().copied())
+}
+
+/// [`check_operation`] with the `required` accuracy in metres.
+fn check_operation_accuracy(transformer: &Transformer, point: (f64, f64), name: &str, required: Option<f64>) -> Result<()> {
+    let Some(operation) = transformer.operation(point) else {
+        return match (required, transformer.backend()) {
+            (Some(required), Backend::Pure) => Err(GpkgError::Reprojection(format!(
+                "{}: proj4rs transformations have no known accuracy, --proj-accuracy {} needs the proj backend",
+                name, required
+            ))),
+            _ => Ok(()),
+        };
+    };
+    match operation.accuracy {
+        Some(accuracy) => logger::info(&format!(
+            "{}: reprojected with {} (accuracy {} m)",
+            name, operation.description, accuracy
+        )),
+        None => warning::emit(
+            WarningCode::ReprojectionBallpark,
+            &format!(
+                "{}: reprojected with {} (unknown accuracy, possibly off by many metres; missing datum grid?)",
+                name, operation.description
+            ),
+        )?,
+    }
+    match required {
+        Some(required) if !operation.accuracy.is_some_and(|accuracy| accuracy <= required) => {
+            Err(GpkgError::Reprojection(format!(
+                "{}: {} is not known to be accurate to {} m (--proj-accuracy)",
+                name, operation.description, required
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+thread_local! {
+    /// Transformers of this thread by source and target CRS (None when they
+    /// cannot be created).
+    static THREAD_TRANSFORMERS: RefCell<Vec<(String, String, Option<Transformer>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Calls `f` with this thread's transformer from `from` to `to`, returning
+/// None when it cannot be created.
+///
+/// The transformer is created on the first call and kept for the life of the
+/// thread, so rayon workers reprojecting many geometries set up one proj
+/// context each instead of one per geometry. `f` must not call this function.
+pub fn with_thread_transformer<R>(from: &str, to: &str, f: impl FnOnce(&Transformer) -> R) -> Option<R> {
+    THREAD_TRANSFORMERS.with(|cell| {
+        let mut transformers = cell.borrow_mut();
+        let index = match transformers.iter().position(|(a, b, _)| a == from && b == to) {
+            Some(index) => index,
+            None => {
+                transformers.push((from.to_string(), to.to_string(), Transformer::new(from, to).ok()));
+                transformers.len() - 1
+            }
+        };
+        transformers[index].2.as_ref().map(f)
+    })
+}
+
+/// Pure-Rust transformations with proj4rs.
+#[cfg(feature = "reproject-pure")]
+mod pure {
+    use proj4rs::proj::Proj;
+
+    /// A proj4rs source/target pair.
+    pub struct PureTransform {
+        src: Proj,
+        dst: Proj,
+    }
+
+    impl PureTransform {
+        pub fn new(from: &str, to: &str) -> Result<Self, String> {
+            Ok(Self {
+                src: parse(from)?,
+                dst: parse(to)?,
+            })
+        }
+
+        /// Transforms a point; geographic CRS use degrees (proj4rs works in radians).
+        pub fn convert(&self, (x, y): (f64, f64)) -> Option<(f64, f64)> {
+            let mut point = if self.src.is_latlong() {
+                (x.to_radians(), y.to_radians(), 0.0)
+            } else {
+                (x, y, 0.0)
+            };
+            proj4rs::transform::transform(&self.src, &self.dst, &mut point).ok()?;
+            let (x, y) = if self.dst.is_latlong() {
+                (point.0.to_degrees(), point.1.to_degrees())
+            } else {
+                (point.0, point.1)
+            };
+            // proj4rs reports some failures as NaN instead of an error
+            (x.is_finite() && y.is_finite()).then_some((x, y))
+        }
+    }
+
+    /// Parses a PROJ string, an "EPSG:XXXX" code, or a WKT definition carrying an EPSG id.
+    fn parse(definition: &str) -> Result<Proj, String> {
+        let definition = definition.trim();
+        let result = if definition.starts_with('+') || definition.starts_with("EPSG:") {
+            Proj::from_user_string(definition)
+        } else {
+            let code = super::epsg_from_wkt(definition)
+                .ok_or_else(|| "unsupported CRS definition (no EPSG id found)".to_string())?;
+            Proj::from_epsg_code(code)
+        };
+        result.map_err(|e| e.to_string())
+    }
+}
+
+/// Extracts the EPSG code of the root CRS of a WKT definition.
+///
+/// The root `AUTHORITY["EPSG","…"]` (WKT1) or `ID["EPSG",…]` (WKT2) is the
+/// last one in the string.
+#[cfg_attr(not(feature = "reproject-pure"), allow(dead_code))]
+fn epsg_from_wkt(wkt: &str) -> Option<u16> {
+    let start = ["AUTHORITY[\"EPSG\",", "ID[\"EPSG\","]
+        .iter()
+        .filter_map(|key| wkt.rfind(key).map(|i| i + key.len()))
+        .max()?;
+    let digits: String = wkt[start..]
+        .chars()
+        .skip_while(|c| *c == '"' || c.is_whitespace())
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// Reproject a bbox from source CRS to WGS84.
+///
+/// Points sampled around the bbox outline are reprojected as a ring, so a
+/// bbox straddling the antimeridian yields `max_lon > 180` instead of a
+/// world-spanning range, and one containing a pole (polar stereographic CRS)
+/// extends to it over every longitude. Returns `None` if the projection fails.
+pub fn reproject_bbox_to_wgs84(
+    min_x: f64,
+    min_y: f64,
+    max_x: f64,
+    max_y: f64,
+    srs_def: &str,
+) -> Option<(f64, f64, f64, f64)> {
+    let transformer = Transformer::new(srs_def, "EPSG:4326").ok()?;
+
+    // Counter-clockwise from the lower-left corner
+    let steps = (0..BBOX_EDGE_SAMPLES).map(|i| i as f64 / BBOX_EDGE_SAMPLES as f64);
+    let lerp = |a: f64, b: f64, t: f64| a + t * (b - a);
+    let outline = steps
+        .clone()
+        .map(|t| (lerp(min_x, max_x, t), min_y))
+        .chain(steps.clone().map(|t| (max_x, lerp(min_y, max_y, t))))
+        .chain(steps.clone().map(|t| (lerp(max_x, min_x, t), max_y)))
+        .chain(steps.map(|t| (min_x, lerp(max_y, min_y, t))));
+    let mut ring: Vec<Coord<f64>> = outline
+        .filter_map(|point| transformer.convert(point))
+        .map(|(x, y)| Coord { x, y })
+        .collect();
+    let first = *ring.first()?;
+    ring.push(first);
+
+    let polygon = MultiPolygon::new(vec![Polygon::new(LineString::new(ring), vec![])]);
+    let bbox = antimeridian::geometries_bbox(&[polygon])?;
+    Some((bbox.min_lon, bbox.min_lat.max(-90.0), bbox.max_lon, bbox.max_lat.min(90.0)))
+}
+
+/// Number of points sampled along each bbox edge when reprojecting.
+const BBOX_EDGE_SAMPLES: usize = 16;
+
+/// Reproject a WGS84 bbox to the given CRS.
+///
+/// Samples points along every edge (not only the corners) so curved edges in
+/// the target CRS are fully covered. Returns `None` if the projection fails.
+pub fn reproject_bbox_from_wgs84(bbox: &Bbox, srs_def: &str) -> Option<(f64, f64, f64, f64)> {
+    let transformer = Transformer::new("EPSG:4326", srs_def).ok()?;
+
+    let mut min_x = f64::MAX;
+    let mut min_y = f64::MAX;
+    let mut max_x = f64::MIN;
+    let mut max_y = f64::MIN;
+
+    for i in 0..=BBOX_EDGE_SAMPLES {
+        let t = i as f64 / BBOX_EDGE_SAMPLES as f64;
+        let lon = bbox.min_lon + t * bbox.width();
+        let lat = bbox.min_lat + t * bbox.height();
+        let samples = [
+            (lon, bbox.min_lat),
+            (lon, bbox.max_lat),
+            (bbox.min_lon, lat),
+            (bbox.max_lon, lat),
+        ];
+        for point in samples {
+            if let Some((x, y)) = transformer.convert(point) {
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+            }
+        }
+    }
+
+    if min_x == f64::MAX {
+        None
+    } else {
+        Some((min_x, min_y, max_x, max_y))
+    }
+}
+
+/// Reproject a MultiPolygon.
+///
+/// Returns `None` if any coordinate transformation fails (results in NaN).
+pub fn reproject_multipolygon(
+    mp: &MultiPolygon<f64>,
+    transformer: &Transformer,
+) -> Option<MultiPolygon<f64>> {
+    let reprojected = mp.map_coords(|coord| match transformer.convert((coord.x, coord.y)) {
+        Some((x, y)) => geo::Coord { x, y },
+        None => geo::Coord {
+            x: f64::NAN,
+            y: f64::NAN,
+        },
+    });
+
+    // Check if any coordinates failed (became NaN)
+    let has_nan = reprojected.iter().any(|poly| {
+        poly.exterior()
+            .coords()
+            .any(|c| c.x.is_nan() || c.y.is_nan())
+            || poly
+                .interiors()
+                .iter()
+                .any(|ring| ring.coords().any(|c| c.x.is_nan() || c.y.is_nan()))
+    });
+
+    if has_nan {
+        None
+    } else {
+        Some(reprojected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reproject_identity() {
+        use geo::{coord, LineString, Polygon};
+
+        // Test that reprojection with identity proj works
+        let poly = Polygon::new(
+            LineString::from(vec![
+                coord! { x: 0.0, y: 0.0 },
+                coord! { x: 1.0, y: 0.0 },
+                coord! { x: 1.0, y: 1.0 },
+                coord! { x: 0.0, y: 1.0 },
+                coord! { x: 0.0, y: 0.0 },
+            ]),
+            vec![],
+        );
+        let mp = MultiPolygon::new(vec![poly]);
+
+        // Create an identity-like projection (WGS84 to WGS84)
+        let transformer = Transformer::new("EPSG:4326", "EPSG:4326").unwrap();
+        let result = reproject_multipolygon(&mp, &transformer);
+        assert!(result.is_some());
+    }
+
+    #[test]
+    fn test_check_operation_accuracy() {
+        let identity = Transformer::new("EPSG:4326", "EPSG:4326").unwrap();
+        assert_eq!(identity.operation((2.0, 48.0)), None);
+        assert!(check_operation_accuracy(&identity, (2.0, 48.0), "roads", Some(1.0)).is_ok());
+
+        // proj4rs gives no accuracy to meet
+        #[cfg(all(feature = "reproject-pure", not(feature = "reproject")))]
+        {
+            let pure = Transformer::new("EPSG:2154", "EPSG:4326").unwrap();
+            assert!(check_operation_accuracy(&pure, (650000.0, 6860000.0), "roads", None).is_ok());
+            let err = check_operation_accuracy(&pure, (650000.0, 6860000.0), "roads", Some(1.0)).unwrap_err();
+            assert!(err.to_string().contains("--proj-accuracy 1 needs the proj backend"));
+        }
+    }
+
+    #[test]
+    fn test_with_thread_transformer() {
+        let converted = with_thread_transformer("EPSG:4326", "EPSG:4326", |t| t.convert((1.0, 2.0)));
+        assert_eq!(converted, Some(Some((1.0, 2.0))));
+        // Created once per thread, failures included
+        let count = || THREAD_TRANSFORMERS.with(|cell| cell.borrow().len());
+        let before = count();
+        assert!(with_thread_transformer("EPSG:4326", "not a crs", |_| ()).is_none());
+        assert!(with_thread_transformer("EPSG:4326", "not a crs", |_| ()).is_none());
+        with_thread_transformer("EPSG:4326", "EPSG:4326", |_| ()).unwrap();
+        assert_eq!(count(), before + 1);
+    }
+
+    #[test]
+    #[cfg(feature = "reproject")]
+    fn test_reproject_bbox_from_wgs84_covers_corners() {
+        let bbox = Bbox::new(2.0, 48.0, 3.0, 49.0);
+        let (min_x, min_y, max_x, max_y) = reproject_bbox_from_wgs84(&bbox, "EPSG:2154").unwrap();
+        let transformer = Transformer::new("EPSG:4326", "EPSG:2154").unwrap();
+        for corner in [(2.0, 48.0), (3.0, 48.0), (2.0, 49.0), (3.0, 49.0)] {
+            let (x, y) = transformer.convert(corner).unwrap();
+            assert!(x >= min_x && x <= max_x && y >= min_y && y <= max_y);
+        }
+    }
+
+    #[test]
+    fn test_reproject_bbox_to_wgs84() {
+        // Test reprojection from WGS84 to WGS84 (should be identity-like)
+        let result = reproject_bbox_to_wgs84(-4.5, 48.0, -4.0, 48.5, "EPSG:4326");
+        assert!(result.is_some());
+        let (min_lon, min_lat, max_lon, max_lat) = result.unwrap();
+        assert!((min_lon - (-4.5)).abs() < 0.001);
+        assert!((min_lat - 48.0).abs() < 0.001);
+        assert!((max_lon - (-4.0)).abs() < 0.001);
+        assert!((max_lat - 48.5).abs() < 0.001);
+    }
+
+    #[test]
+    #[cfg(feature = "reproject")]
+    fn test_reproject_bbox_from_lambert93() {
+        // Test reprojection from EPSG:2154 (Lambert-93) to WGS84
+        // Using approximate Lambert-93 coordinates for Brittany, France
+        // These coords are approximate - main test is that reprojection works
+        // and returns valid ordered bbox values
+        let result = reproject_bbox_to_wgs84(860000.0, 6250000.0, 880000.0, 6280000.0, "EPSG:2154");
+        assert!(result.is_some());
+        let (min_lon, min_lat, max_lon, max_lat) = result.unwrap();
+        // Check that we got valid values (not NaN/Inf)
+        assert!(!min_lon.is_nan() && !min_lon.is_infinite());
+        assert!(!min_lat.is_nan() && !min_lat.is_infinite());
+        assert!(!max_lon.is_nan() && !max_lon.is_infinite());
+        assert!(!max_lat.is_nan() && !max_lat.is_infinite());
+        // Check ordering
+        assert!(
+            min_lon < max_lon,
+            "min_lon {} should be < max_lon {}",
+            min_lon,
+            max_lon
+        );
+        assert!(
+            min_lat < max_lat,
+            "min_lat {} should be < max_lat {}",
+            min_lat,
+            max_lat
+        );
+        // Check values are in plausible range for France (roughly -10 to 10 lon, 40 to 52 lat)
+        assert!(
+            min_lon > -10.0 && min_lon < 10.0,
+            "min_lon {} should be in France",
+            min_lon
+        );
+        assert!(
+            min_lat > 40.0 && min_lat < 52.0,
+            "min_lat {} should be in France",
+            min_lat
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "reproject", feature = "reproject-pure"))]
+    fn test_reproject_bbox_around_pole() {
+        // Antarctic Polar Stereographic: the bbox is centered on the South Pole
+        let (min_lon, min_lat, max_lon, max_lat) =
+            reproject_bbox_to_wgs84(-3_000_000.0, -3_000_000.0, 3_000_000.0, 3_000_000.0, "EPSG:3031").unwrap();
+        assert_eq!((min_lon, max_lon), (-180.0, 180.0));
+        assert_eq!(min_lat, -90.0);
+        // The corners are the points farthest from the pole
+        assert!(max_lat > -55.0 && max_lat < -50.0, "max_lat {}", max_lat);
+    }
+
+    #[test]
+    #[cfg(any(feature = "reproject", feature = "reproject-pure"))]
+    fn test_reproject_bbox_across_antimeridian() {
+        // Fiji in PDC Mercator (central meridian 150°E), from 177°E to 178°W
+        let transformer = Transformer::new("EPSG:4326", "EPSG:3832").unwrap();
+        let (min_x, min_y) = transformer.convert((177.0, -19.0)).unwrap();
+        let (max_x, max_y) = transformer.convert((-178.0, -16.0)).unwrap();
+        let (min_lon, _, max_lon, _) = reproject_bbox_to_wgs84(min_x, min_y, max_x, max_y, "EPSG:3832").unwrap();
+        assert!((min_lon - 177.0).abs() < 1e-6, "min_lon {}", min_lon);
+        assert!((max_lon - 182.0).abs() < 1e-6, "max_lon {}", max_lon);
+    }
+
+    #[test]
+    fn test_reproject_bbox_invalid_crs() {
+        // Test with invalid CRS - should return None
+        let result = reproject_bbox_to_wgs84(0.0, 0.0, 1.0, 1.0, "INVALID:CRS");
+        assert!(result.is_none());
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "reproject", feature = "reproject-pure")))]
+    fn test_transformer_requires_feature() {
+        let err = Transformer::new("EPSG:2154", "EPSG:4326").err().unwrap();
+        assert!(err.to_string().contains("reproject"));
+    }
+
+    #[test]
+    fn test_epsg_from_wkt() {
+        let wkt1 = concat!(
+            "PROJCS[\"RGF93 / Lambert-93\",GEOGCS[\"RGF93\",AUTHORITY[\"EPSG\",\"4171\"]],",
+            "UNIT[\"metre\",1,AUTHORITY[\"EPSG\",\"9001\"]],AUTHORITY[\"EPSG\",\"2154\"]]"
+        );
+        assert_eq!(epsg_from_wkt(wkt1), Some(2154));
+        let wkt2 = "PROJCRS[\"WGS 84 / Pseudo-Mercator\",ID[\"EPSG\",3857]]";
+        assert_eq!(epsg_from_wkt(wkt2), Some(3857));
+        assert_eq!(epsg_from_wkt("LOCAL_CS[\"unknown\"]"), None);
+    }
+
+    #[test]
+    #[cfg(all(feature = "reproject-pure", not(feature = "reproject")))]
+    fn test_pure_lambert93_to_wgs84() {
+        let transformer = Transformer::new("EPSG:2154", "EPSG:4326").unwrap();
+        assert_eq!(transformer.backend(), Backend::Pure);
+        // Lambert-93 false origin: 3°E, 46.5°N
+        let (lon, lat) = transformer.convert((700_000.0, 6_600_000.0)).unwrap();
+        assert!((lon - 3.0).abs() < 1e-6, "lon {}", lon);
+        assert!((lat - 46.5).abs() < 1e-6, "lat {}", lat);
+    }
+}
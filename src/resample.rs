@@ -0,0 +1,158 @@
+//! Resampling kernels for downsampled images (`--resample`).
+//!
+//! Masks need `nearest` so every pixel keeps a value the renderer produced,
+//! while maps look smoother with `lanczos`. Filtering kernels work on
+//! premultiplied colors so transparent pixels do not darken polygon edges.
+
+use image::imageops::{self, FilterType};
+use image::{Rgba, Rgba32FImage, RgbaImage};
+
+/// Kernel used to downsample a rendered image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Resample {
+    /// Closest source pixel: no new colors, suited to masks.
+    Nearest,
+    /// Linear interpolation.
+    Bilinear,
+    /// Lanczos (3 lobes): sharpest, may ring slightly around edges.
+    Lanczos,
+    /// Average of the covered source pixels, weighted by overlap.
+    #[default]
+    Box,
+}
+
+/// Resizes `image` to `width` x `height` with `kernel`.
+pub fn downsample(image: &RgbaImage, width: u32, height: u32, kernel: Resample) -> RgbaImage {
+    let filter = match kernel {
+        Resample::Nearest => return imageops::resize(image, width, height, FilterType::Nearest),
+        Resample::Box => return unpremultiply(&box_filter(&premultiply(image), width, height)),
+        Resample::Bilinear => FilterType::Triangle,
+        Resample::Lanczos => FilterType::Lanczos3,
+    };
+    unpremultiply(&imageops::resize(&premultiply(image), width, height, filter))
+}
+
+/// Reduces a render made on a grid `factor` times finer (`--supersample`)
+/// to `width` x `height`.
+///
+/// Both grids start at the bottom-left corner of the bbox. When the bbox is
+/// not a whole number of output pixels, the finer grid can be a few pixels
+/// short on the right and top; the missing pixels count as transparent.
+pub fn reduce_supersampled(image: &RgbaImage, width: u32, height: u32, factor: u32, kernel: Resample) -> RgbaImage {
+    let (full_width, full_height) = (width * factor, height * factor);
+    if image.dimensions() == (full_width, full_height) {
+        return downsample(image, width, height, kernel);
+    }
+    let mut padded = RgbaImage::new(full_width, full_height);
+    imageops::replace(&mut padded, image, 0, full_height as i64 - image.height() as i64);
+    downsample(&padded, width, height, kernel)
+}
+
+fn premultiply(image: &RgbaImage) -> Rgba32FImage {
+    Rgba32FImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0.map(|c| c as f32 / 255.0);
+        Rgba([r * a, g * a, b * a, a])
+    })
+}
+
+fn unpremultiply(image: &Rgba32FImage) -> RgbaImage {
+    let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        if a <= 0.0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        Rgba([to_u8(r / a), to_u8(g / a), to_u8(b / a), to_u8(a)])
+    })
+}
+
+/// Averages the source pixels covered by each output pixel, weighted by overlap.
+fn box_filter(image: &Rgba32FImage, width: u32, height: u32) -> Rgba32FImage {
+    let columns = box_weights(image.width(), width);
+    let rows = box_weights(image.height(), height);
+    Rgba32FImage::from_fn(width, height, |x, y| {
+        let mut sum = [0.0; 4];
+        for &(sy, wy) in &rows[y as usize] {
+            for &(sx, wx) in &columns[x as usize] {
+                let pixel = image.get_pixel(sx, sy).0;
+                for (total, c) in sum.iter_mut().zip(pixel) {
+                    *total += c * wx * wy;
+                }
+            }
+        }
+        Rgba(sum)
+    })
+}
+
+/// Source pixels overlapped by each of `dst` output pixels, with their weights.
+fn box_weights(src: u32, dst: u32) -> Vec<Vec<(u32, f32)>> {
+    let scale = src as f64 / dst as f64;
+    (0..dst)
+        .map(|i| {
+            let start = i as f64 * scale;
+            let end = start + scale;
+            (start.floor() as u32..(end.ceil() as u32).min(src))
+                .map(|s| {
+                    let overlap = end.min(s as f64 + 1.0) - start.max(s as f64);
+                    (s, (overlap / scale.max(1.0)) as f32)
+                })
+                .filter(|&(_, weight)| weight > 0.0)
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4x2 image: opaque red on the left half, transparent on the right.
+    fn half_red() -> RgbaImage {
+        RgbaImage::from_fn(4, 2, |x, _| if x < 2 { Rgba([255, 0, 0, 255]) } else { Rgba([0, 0, 0, 0]) })
+    }
+
+    #[test]
+    fn test_box_averages_coverage() {
+        let image = downsample(&half_red(), 1, 1, Resample::Box);
+        // Transparent pixels lower the alpha, not the color
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 128]);
+
+        let image = downsample(&half_red(), 2, 1, Resample::Box);
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(1, 0).0, [0, 0, 0, 0]);
+
+        // Non-integer factors split source pixels between outputs
+        let image = downsample(&half_red(), 3, 1, Resample::Box);
+        assert_eq!(image.get_pixel(1, 0).0, [255, 0, 0, 128]);
+    }
+
+    #[test]
+    fn test_reduce_supersampled() {
+        let image = reduce_supersampled(&half_red(), 2, 1, 2, Resample::Box);
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(1, 0).0, [0, 0, 0, 0]);
+
+        // A finer grid one row short is aligned on the bottom edge
+        let short = RgbaImage::from_pixel(4, 3, Rgba([0, 0, 255, 255]));
+        let image = reduce_supersampled(&short, 2, 2, 2, Resample::Box);
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255, 128]);
+        assert_eq!(image.get_pixel(1, 1).0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_nearest_keeps_source_values() {
+        let image = downsample(&half_red(), 2, 1, Resample::Nearest);
+        assert!(image.pixels().all(|p| p.0 == [255, 0, 0, 255] || p.0 == [0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_filters_do_not_darken_edges() {
+        for kernel in [Resample::Bilinear, Resample::Lanczos] {
+            let image = downsample(&half_red(), 2, 1, kernel);
+            for pixel in image.pixels().filter(|p| p.0[3] > 0) {
+                assert_eq!(&pixel.0[..3], &[255, 0, 0], "{:?}", kernel);
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Resumable batch runs (`--resume`).
+//!
+//! Batch runs (the layers and inputs of a render, the tiles of `tiles`)
+//! append a key to a journal in their output directory as soon as each
+//! output is complete. A run that finishes removes its journal, so one left
+//! behind marks a run that crashed, failed or was interrupted: run again with
+//! `--resume`, it skips the outputs the journal lists. Keys cover the input
+//! files (size and modification time), the settings and the output path, so
+//! changing any of them renders the output again.
+
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::UNIX_EPOCH;
+
+use crate::error::Result;
+use crate::logger;
+
+/// Name of the journal in the output directory.
+pub const JOURNAL_NAME: &str = ".gpkg-to-png.resume";
+
+static JOURNAL: Mutex<Option<Journal>> = Mutex::new(None);
+
+/// Keys of the outputs completed by this run and the unfinished one it resumes.
+struct Journal {
+    path: PathBuf,
+    /// Outputs completed by the resumed run.
+    done: HashSet<String>,
+    file: File,
+}
+
+impl Journal {
+    /// Opens the journal of `dir`, keeping its keys with `resume` and
+    /// starting it over otherwise.
+    fn open(dir: &Path, resume: bool) -> Result<Self> {
+        let path = dir.join(JOURNAL_NAME);
+        let previous = match std::fs::read_to_string(&path) {
+            Ok(content) if resume => content,
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => String::new(),
+        };
+        std::fs::create_dir_all(dir)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resume)
+            .truncate(!resume)
+            .open(&path)?;
+        // A crash may have cut the last key short
+        if !previous.is_empty() && !previous.ends_with('\n') {
+            writeln!(file)?;
+        }
+        Ok(Self {
+            path,
+            done: previous.lines().filter(|l| !l.is_empty()).map(str::to_string).collect(),
+            file,
+        })
+    }
+
+    fn record(&mut self, key: &str) -> Result<()> {
+        // Unbuffered: the key is in the file once the call returns
+        writeln!(self.file, "{}", key)?;
+        Ok(())
+    }
+}
+
+/// Starts the journal of a run writing into `dir`; with `resume`, the outputs
+/// recorded by the unfinished run there are skipped.
+pub fn start(dir: &Path, resume: bool) -> Result<()> {
+    let journal = Journal::open(dir, resume)?;
+    if resume {
+        match journal.done.len() {
+            0 => logger::info(&format!("No unfinished run to resume in {}", dir.display())),
+            n => logger::info(&format!("Resuming: {} output(s) completed by the previous run", n)),
+        }
+    }
+    *JOURNAL.lock().unwrap_or_else(PoisonError::into_inner) = Some(journal);
+    Ok(())
+}
+
+/// Returns true when the resumed run completed the output of `key`.
+pub fn is_done(key: &str) -> bool {
+    JOURNAL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .is_some_and(|journal| journal.done.contains(key))
+}
+
+/// Records the output of `key` as complete. Safe to call from concurrent
+/// layer renders.
+pub fn record(key: &str) -> Result<()> {
+    match JOURNAL.lock().unwrap_or_else(PoisonError::into_inner).as_mut() {
+        Some(journal) => journal.record(key),
+        None => Ok(()),
+    }
+}
+
+/// Removes the journal once every output of the run is complete.
+pub fn finish() -> Result<()> {
+    if let Some(journal) = JOURNAL.lock().unwrap_or_else(PoisonError::into_inner).take() {
+        std::fs::remove_file(&journal.path)?;
+    }
+    Ok(())
+}
+
+/// Describes the state of an input file for the keys: its path, size and
+/// modification time.
+pub fn input_stamp(path: &Path) -> Result<String> {
+    let metadata = std::fs::metadata(path)?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos());
+    Ok(format!("{}:{}:{}", path.display(), metadata.len(), modified))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut journal = Journal::open(dir.path(), true).unwrap();
+        assert!(journal.done.is_empty());
+        journal.record("a").unwrap();
+        journal.record("b").unwrap();
+        drop(journal);
+
+        // A crash cut the last key short
+        let path = dir.path().join(JOURNAL_NAME);
+        std::fs::write(&path, "a\nb\nc1").unwrap();
+        let mut journal = Journal::open(dir.path(), true).unwrap();
+        assert_eq!(journal.done, ["a", "b", "c1"].map(String::from).into());
+        journal.record("d").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb\nc1\nd\n");
+
+        // Without resuming, the run starts over
+        let journal = Journal::open(dir.path(), false).unwrap();
+        assert!(journal.done.is_empty());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+    }
+}
@@ -0,0 +1,809 @@
+//! On-demand rendering over HTTP (`serve` subcommand).
+//!
+//! The GeoPackage stays open and every request renders its window from the
+//! features intersecting it (read through the RTree index when present):
+//! - `GET /tiles/{z}/{x}/{y}.png`: 256 px Web Mercator (XYZ) tiles;
+//! - `GET /wms?REQUEST=GetMap&...`: WMS 1.1.1/1.3.0 GetMap in EPSG:4326,
+//!   CRS:84 or EPSG:3857.
+//!
+//! The server is a minimal HTTP/1.1 implementation on tokio sockets: one GET
+//! request per connection, no keep-alive. Rendering runs on the blocking
+//! thread pool so that a large window does not stall the other connections. [`Source`] also renders the tiles
+//! written by the `tiles` subcommand and the frames of `animate`.
+//!
+//! With `--style-file`, the file is checked every second and a changed style
+//! applies to the next requests.
+
+use geo::{Coord, MapCoords, MultiPolygon, Simplify};
+use image::RgbaImage;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::cli::{self, LayerStyle, ServeArgs, TileStyleArgs};
+use crate::error::{GpkgError, Result};
+use crate::gpkg::{FeatureFilter, GpkgReader, LayerInfo, SqlitePragmas};
+use crate::logger;
+use crate::math::Bbox;
+use crate::render::{PngMetadata, RenderConfig, Renderer, MAX_DIMENSION};
+use crate::style::AttributeValue;
+
+/// Side of an XYZ tile in pixels.
+const TILE_SIZE: u32 = 256;
+
+/// Largest request head accepted, in bytes.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Delay for a client to send its request head before it is answered 408.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval at which `--style-file` is checked for changes.
+const STYLE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Earth radius used by Web Mercator, in meters.
+const EARTH_RADIUS: f64 = 6_378_137.0;
+
+/// Latitude beyond which Web Mercator is undefined (square world).
+const MAX_MERCATOR_LAT: f64 = 85.051_128_779_806_59;
+
+/// CRS of a rendered window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ViewCrs {
+    Wgs84,
+    WebMercator,
+}
+
+/// A window to render: an extent in `crs` and the image size.
+#[derive(Debug, Clone)]
+struct View {
+    crs: ViewCrs,
+    bbox: Bbox,
+    width: u32,
+    height: u32,
+    /// Layers requested by a WMS client (None = the served layers).
+    layers: Option<Vec<String>>,
+}
+
+impl View {
+    /// Returns the WGS84 extent used to select features.
+    fn wgs84_bbox(&self) -> Bbox {
+        match self.crs {
+            ViewCrs::Wgs84 => self.bbox,
+            ViewCrs::WebMercator => {
+                let (min_lon, min_lat) = from_mercator(self.bbox.min_lon, self.bbox.min_lat);
+                let (max_lon, max_lat) = from_mercator(self.bbox.max_lon, self.bbox.max_lat);
+                Bbox::new(min_lon, min_lat, max_lon, max_lat)
+            }
+        }
+    }
+
+    /// Maps a WGS84 geometry to the pixel space of the image (y up).
+    ///
+    /// Rendering in pixel units at a resolution of 1 gives exactly the
+    /// requested size, even when the pixels of the window are not square.
+    fn to_pixels(&self, mp: &MultiPolygon<f64>) -> MultiPolygon<f64> {
+        let scale_x = self.width as f64 / self.bbox.width();
+        let scale_y = self.height as f64 / self.bbox.height();
+        mp.map_coords(|c| {
+            let (x, y) = match self.crs {
+                ViewCrs::Wgs84 => (c.x, c.y),
+                ViewCrs::WebMercator => to_mercator(c.x, c.y),
+            };
+            Coord {
+                x: (x - self.bbox.min_lon) * scale_x,
+                y: (y - self.bbox.min_lat) * scale_y,
+            }
+        })
+    }
+}
+
+/// Projects WGS84 degrees to Web Mercator meters, clamping polar latitudes.
+fn to_mercator(lon: f64, lat: f64) -> (f64, f64) {
+    let lat = lat.clamp(-MAX_MERCATOR_LAT, MAX_MERCATOR_LAT).to_radians();
+    (EARTH_RADIUS * lon.to_radians(), EARTH_RADIUS * (PI / 4.0 + lat / 2.0).tan().ln())
+}
+
+/// Converts Web Mercator meters to WGS84 degrees.
+fn from_mercator(x: f64, y: f64) -> (f64, f64) {
+    ((x / EARTH_RADIUS).to_degrees(), (y / EARTH_RADIUS).sinh().atan().to_degrees())
+}
+
+/// Returns the window of XYZ tile `z/x/y`.
+fn tile_view(z: u32, x: u32, y: u32) -> std::result::Result<View, String> {
+    if z > 30 || x >= 1 << z || y >= 1 << z {
+        return Err(format!("tile {}/{}/{} does not exist", z, x, y));
+    }
+    let half = PI * EARTH_RADIUS;
+    let size = 2.0 * half / (1u64 << z) as f64;
+    let min_x = -half + x as f64 * size;
+    let max_y = half - y as f64 * size;
+    Ok(View {
+        crs: ViewCrs::WebMercator,
+        bbox: Bbox::new(min_x, max_y - size, min_x + size, max_y),
+        width: TILE_SIZE,
+        height: TILE_SIZE,
+        layers: None,
+    })
+}
+
+/// Returns the fractional XYZ tile coordinates of a WGS84 point at zoom `z`.
+///
+/// Longitudes beyond 180° (bboxes crossing the antimeridian) give columns
+/// beyond the last one.
+pub fn tile_position(lon: f64, lat: f64, z: u32) -> (f64, f64) {
+    let (x, y) = to_mercator(lon, lat);
+    let half = PI * EARTH_RADIUS;
+    let tiles = (1u64 << z) as f64;
+    ((x + half) / (2.0 * half) * tiles, (half - y) / (2.0 * half) * tiles)
+}
+
+/// Returns the ground size in meters of a pixel of zoom `z` tiles at latitude `lat`.
+pub fn tile_ground_resolution(z: u32, lat: f64) -> f64 {
+    2.0 * PI * EARTH_RADIUS * lat.to_radians().cos() / (TILE_SIZE as f64 * (1u64 << z) as f64)
+}
+
+/// Returns the window of a WMS GetMap request (keys in upper case).
+fn getmap_view(params: &HashMap<String, String>) -> std::result::Result<View, String> {
+    let param = |key: &str| params.get(key).map(String::as_str);
+    if !param("REQUEST").is_some_and(|r| r.eq_ignore_ascii_case("GetMap")) {
+        return Err("only REQUEST=GetMap is supported".to_string());
+    }
+    if let Some(format) = param("FORMAT") {
+        if format != "image/png" {
+            return Err(format!("unsupported FORMAT {}, only image/png", format));
+        }
+    }
+
+    // WMS 1.3.0 names the CRS "CRS" and uses the lat/lon axis order of EPSG:4326
+    let version = param("VERSION").unwrap_or("1.3.0");
+    let crs_name = param("CRS").or(param("SRS")).ok_or("missing CRS")?;
+    let (crs, lat_first) = match crs_name.to_ascii_uppercase().as_str() {
+        "EPSG:4326" => (ViewCrs::Wgs84, version == "1.3.0"),
+        "CRS:84" => (ViewCrs::Wgs84, false),
+        "EPSG:3857" | "EPSG:900913" => (ViewCrs::WebMercator, false),
+        _ => return Err(format!("unsupported CRS {}", crs_name)),
+    };
+
+    let values = param("BBOX")
+        .ok_or("missing BBOX")?
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| "invalid BBOX".to_string())?;
+    let [a, b, c, d] = values[..] else {
+        return Err("BBOX needs 4 values".to_string());
+    };
+    let bbox = if lat_first {
+        Bbox::new(b, a, d, c)
+    } else {
+        Bbox::new(a, b, c, d)
+    };
+    if bbox.min_lon >= bbox.max_lon || bbox.min_lat >= bbox.max_lat {
+        return Err("BBOX min must be less than max".to_string());
+    }
+
+    let size = |key: &str| {
+        param(key)
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|&v| v > 0)
+            .ok_or_else(|| format!("invalid {}", key))
+    };
+    let layers = param("LAYERS")
+        .filter(|l| !l.is_empty())
+        .map(|l| l.split(',').map(str::to_string).collect());
+    Ok(View {
+        crs,
+        bbox,
+        width: size("WIDTH")?,
+        height: size("HEIGHT")?,
+        layers,
+    })
+}
+
+/// Splits a query string into upper-cased keys and decoded values.
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key).to_ascii_uppercase(), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decodes `%XX` escapes and `+` in a query component.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// A request routed to a window, or an HTTP error.
+enum Route {
+    Render(View),
+    Error(u16, String),
+}
+
+/// Routes a request target (path and query).
+fn route(target: &str) -> Route {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path == "/wms" {
+        return match getmap_view(&parse_query(query)) {
+            Ok(view) => Route::Render(view),
+            Err(msg) => Route::Error(400, msg),
+        };
+    }
+    let tile = path
+        .strip_prefix("/tiles/")
+        .and_then(|rest| rest.strip_suffix(".png"))
+        .map(|rest| rest.split('/').map(str::parse::<u32>).collect::<Vec<_>>());
+    match tile.as_deref() {
+        Some([Ok(z), Ok(x), Ok(y)]) => match tile_view(*z, *x, *y) {
+            Ok(view) => Route::Render(view),
+            Err(msg) => Route::Error(404, msg),
+        },
+        _ => Route::Error(404, format!("no route for {}", path)),
+    }
+}
+
+/// Features of one value of an attribute, rendered by [`Source::render_frames`].
+pub struct Frame {
+    pub value: AttributeValue,
+    pub image: RgbaImage,
+    /// Number of features drawn.
+    pub features: usize,
+}
+
+/// Hashable form of a frame value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum FrameKey {
+    Number(u64),
+    Text(String),
+}
+
+/// Colors and stroke width the features are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Style {
+    fill: Option<[u8; 4]>,
+    stroke: Option<[u8; 3]>,
+    stroke_width: u32,
+}
+
+impl Style {
+    /// Returns this style with the values set in `overrides` replaced.
+    fn with(self, overrides: &LayerStyle) -> Style {
+        Style {
+            fill: overrides.fill.unwrap_or(self.fill),
+            stroke: overrides.stroke.unwrap_or(self.stroke),
+            stroke_width: overrides.stroke_width.unwrap_or(self.stroke_width),
+        }
+    }
+}
+
+/// Style file of `serve --style-file`, read again when it changes.
+struct StyleFile {
+    path: PathBuf,
+    /// Style of the command-line options, which the file overrides.
+    base: Style,
+    stamp: Option<FileStamp>,
+}
+
+/// Modification time and size of a file.
+type FileStamp = (SystemTime, u64);
+
+impl StyleFile {
+    /// Reads `path` and returns its style applied over `base`.
+    fn open(path: &Path, base: Style) -> Result<(StyleFile, Style)> {
+        let stamp = file_stamp(path);
+        let style = base.with(&cli::read_style_file(path)?);
+        let file = StyleFile {
+            path: path.to_path_buf(),
+            base,
+            stamp,
+        };
+        Ok((file, style))
+    }
+
+    /// Reads the file again when its modification time or size changed since
+    /// the last read and returns its style, None when it is unchanged.
+    fn poll(&mut self) -> Option<Result<Style>> {
+        let stamp = file_stamp(&self.path);
+        if stamp == self.stamp {
+            return None;
+        }
+        self.stamp = stamp;
+        Some(cli::read_style_file(&self.path).map(|style| self.base.with(&style)))
+    }
+}
+
+/// Returns the stamp of `path`, None when it cannot be read.
+fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Polygon layers of an open GeoPackage and the style they are drawn with.
+pub struct Source {
+    reader: GpkgReader,
+    layers: Vec<LayerInfo>,
+    style: RwLock<Style>,
+}
+
+impl Source {
+    /// Opens `input` and selects the `names` polygon layers (all when empty).
+    pub async fn open(input: &Path, names: &[String], style: &TileStyleArgs) -> Result<Source> {
+        if !input.exists() {
+            return Err(GpkgError::FileNotFound(input.display().to_string()));
+        }
+        let (fill, stroke) = style.colors()?;
+        let reader = GpkgReader::open(input, &SqlitePragmas::default()).await?;
+        let all_layers = reader.list_polygon_layers().await?;
+        let layers = if names.is_empty() {
+            all_layers
+        } else {
+            names
+                .iter()
+                .map(|name| {
+                    all_layers.iter().find(|l| l.name == *name).cloned().ok_or_else(|| {
+                        let available = all_layers.iter().map(|l| l.name.as_str()).collect::<Vec<_>>();
+                        GpkgError::LayerNotFound(name.clone(), available.join(", "))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+        Ok(Source {
+            reader,
+            layers,
+            style: RwLock::new(Style {
+                fill,
+                stroke,
+                stroke_width: style.stroke_width,
+            }),
+        })
+    }
+
+    /// Returns the style the features are drawn with.
+    fn style(&self) -> Style {
+        *self.style.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Draws the next renders with `style`.
+    fn set_style(&self, style: Style) {
+        *self.style.write().unwrap_or_else(PoisonError::into_inner) = style;
+    }
+
+    /// Returns the WGS84 extent of the selected layers.
+    pub async fn bbox(&self) -> Result<Bbox> {
+        self.reader
+            .get_layers_bbox_wgs84(&self.layers)
+            .await?
+            .ok_or_else(|| GpkgError::InvalidBbox("Could not determine bounding box from layers".to_string()))
+    }
+
+    /// Renders XYZ tile `z/x/y` to PNG bytes, or None when no feature intersects it.
+    ///
+    /// `simplify` is the tolerance in pixels of the simplification of the
+    /// geometries (None draws them exactly).
+    pub async fn render_tile(&self, z: u32, x: u32, y: u32, simplify: Option<f64>) -> Result<Option<Vec<u8>>> {
+        let view = tile_view(z, x, y).map_err(GpkgError::InvalidBbox)?;
+        let (png, features) = self.render(&view, simplify).await?;
+        Ok((features > 0).then_some(png))
+    }
+
+    /// Renders a window to PNG bytes, simplified with a tolerance of
+    /// `simplify` pixels; also returns the number of features drawn.
+    async fn render(&self, view: &View, simplify: Option<f64>) -> Result<(Vec<u8>, usize)> {
+        let layers = match &view.layers {
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    self.layers.iter().find(|l| l.name == *name).ok_or_else(|| {
+                        let available = self.layers.iter().map(|l| l.name.as_str()).collect::<Vec<_>>();
+                        GpkgError::LayerNotFound(name.clone(), available.join(", "))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => self.layers.iter().collect(),
+        };
+
+        let mut renderer = self.renderer(view)?;
+        let filter = FeatureFilter {
+            bbox: Some(view.wgs84_bbox()),
+            ..Default::default()
+        };
+        let mut features = 0;
+        let mut layer_geometries = Vec::with_capacity(layers.len());
+        for layer in layers {
+            let mut geometries = Vec::new();
+            self.reader
+                .for_each_chunk_wgs84(layer, &filter, &[], |chunk| {
+                    geometries.extend(chunk.iter().map(|f| {
+                        let mp = view.to_pixels(&f.geometry);
+                        match simplify {
+                            Some(tolerance) => mp.simplify(&tolerance),
+                            None => mp,
+                        }
+                    }));
+                    Ok(())
+                })
+                .await?;
+            features += geometries.len();
+            layer_geometries.push(geometries);
+        }
+
+        let png = tokio::task::spawn_blocking(move || {
+            for geometries in &layer_geometries {
+                renderer.render_layer(geometries);
+            }
+            renderer.to_png(&PngMetadata::default())
+        })
+        .await;
+        match png {
+            Ok(png) => Ok((png?, features)),
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+
+    /// Renders one `width` x `height` image of `bbox` per distinct value of
+    /// `column`, numbers first then texts, in ascending order.
+    ///
+    /// Every frame draws the selected layers in order, with only the
+    /// features holding its value; features without a value are left out.
+    pub async fn render_frames(&self, column: &str, bbox: Bbox, width: u32, height: u32) -> Result<Vec<Frame>> {
+        let view = View {
+            crs: ViewCrs::Wgs84,
+            bbox,
+            width,
+            height,
+            layers: None,
+        };
+        let filter = FeatureFilter {
+            bbox: Some(bbox),
+            ..Default::default()
+        };
+        let columns = [column.to_string()];
+        // Geometries of every value, one list per layer
+        let mut values: Vec<(AttributeValue, Vec<Vec<MultiPolygon<f64>>>)> = Vec::new();
+        let mut index: HashMap<FrameKey, usize> = HashMap::new();
+        let mut missing = 0;
+        for (i, layer) in self.layers.iter().enumerate() {
+            self.reader
+                .for_each_chunk_wgs84(layer, &filter, &columns, |chunk| {
+                    for feature in chunk {
+                        let key = match &feature.attributes[0] {
+                            AttributeValue::Number(n) => FrameKey::Number(n.to_bits()),
+                            AttributeValue::Text(s) => FrameKey::Text(s.clone()),
+                            AttributeValue::Null => {
+                                missing += 1;
+                                continue;
+                            }
+                        };
+                        let slot = *index.entry(key).or_insert_with(|| {
+                            values.push((feature.attributes[0].clone(), vec![Vec::new(); self.layers.len()]));
+                            values.len() - 1
+                        });
+                        values[slot].1[i].push(view.to_pixels(&feature.geometry));
+                    }
+                    Ok(())
+                })
+                .await?;
+        }
+        if missing > 0 {
+            logger::info(&format!("{} feature(s) without a value of {} left out", missing, column));
+        }
+
+        values.sort_by(|(a, _), (b, _)| match (a, b) {
+            (AttributeValue::Number(a), AttributeValue::Number(b)) => a.total_cmp(b),
+            (AttributeValue::Text(a), AttributeValue::Text(b)) => a.cmp(b),
+            (AttributeValue::Number(_), _) => std::cmp::Ordering::Less,
+            _ => std::cmp::Ordering::Greater,
+        });
+        values
+            .into_iter()
+            .map(|(value, layers)| {
+                let mut renderer = self.renderer(&view)?;
+                let mut features = 0;
+                for geometries in &layers {
+                    features += geometries.len();
+                    renderer.render_layer(geometries);
+                }
+                Ok(Frame {
+                    value,
+                    image: renderer.to_image(),
+                    features,
+                })
+            })
+            .collect()
+    }
+
+    /// Creates the renderer of a window, drawing in its pixel space.
+    fn renderer(&self, view: &View) -> Result<Renderer> {
+        let style = self.style();
+        Renderer::new(RenderConfig {
+            bbox: Bbox::new(0.0, 0.0, view.width as f64, view.height as f64),
+            resolution: 1.0,
+            fill: style.fill,
+            stroke: style.stroke,
+            stroke_width: style.stroke_width,
+            vertex_color: None,
+            min_feature_px: None,
+            max_dimension: MAX_DIMENSION,
+            rotation: 0.0,
+            glow: None,
+        })
+    }
+
+    /// Answers one request on `stream`.
+    async fn handle(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        let start = Instant::now();
+        let target = match read_request(&mut stream, REQUEST_TIMEOUT).await? {
+            Ok(target) => target,
+            Err(408) => {
+                logger::debug(&format!("No request after {:?}", REQUEST_TIMEOUT));
+                return respond(&mut stream, 408, "text/plain", b"request timeout").await;
+            }
+            Err(status) => return respond(&mut stream, status, "text/plain", b"bad request").await,
+        };
+
+        let status = match route(&target) {
+            Route::Render(view) => match self.render(&view, None).await {
+                Ok((png, _)) => {
+                    respond(&mut stream, 200, "image/png", &png).await?;
+                    200
+                }
+                Err(e @ (GpkgError::LayerNotFound(..) | GpkgError::ImageTooLarge { .. })) => {
+                    error_response(&mut stream, &target, 400, &e.to_string()).await?
+                }
+                Err(e) => error_response(&mut stream, &target, 500, &e.to_string()).await?,
+            },
+            Route::Error(status, msg) => error_response(&mut stream, &target, status, &msg).await?,
+        };
+        logger::debug(&format!("GET {} -> {} ({:.2?})", target, status, start.elapsed()));
+        Ok(())
+    }
+}
+
+/// Reads the request head within `timeout` and returns the target of a GET
+/// request, or the status to answer: 400 for a malformed request, 408 when
+/// the head is not complete in time.
+async fn read_request(stream: &mut TcpStream, timeout: Duration) -> std::io::Result<std::result::Result<String, u16>> {
+    match tokio::time::timeout(timeout, read_request_target(stream)).await {
+        Ok(target) => Ok(target?.ok_or(400)),
+        Err(_) => Ok(Err(408)),
+    }
+}
+
+/// Reads the request head and returns the target of a GET request.
+async fn read_request_target(stream: &mut TcpStream) -> std::io::Result<Option<String>> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buffer).await?;
+        if n == 0 || head.len() + n > MAX_REQUEST_BYTES {
+            return Ok(None);
+        }
+        head.extend_from_slice(&buffer[..n]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut parts = head.lines().next().unwrap_or_default().split(' ');
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => Ok(Some(target.to_string())),
+        _ => Ok(None),
+    }
+}
+
+/// Sends an error as a WMS exception report or plain text; returns `status`.
+async fn error_response(stream: &mut TcpStream, target: &str, status: u16, msg: &str) -> std::io::Result<u16> {
+    if target.starts_with("/wms") {
+        let body = format!(
+            "<?xml version=\"1.0\"?>\n<ServiceExceptionReport version=\"1.3.0\"><ServiceException>{}</ServiceException></ServiceExceptionReport>\n",
+            msg.replace('&', "&amp;").replace('<', "&lt;")
+        );
+        respond(stream, status, "text/xml", body.as_bytes()).await?;
+    } else {
+        respond(stream, status, "text/plain", msg.as_bytes()).await?;
+    }
+    Ok(status)
+}
+
+/// Writes a complete response and closes the connection.
+async fn respond(stream: &mut TcpStream, status: u16, content_type: &str, body: &[u8]) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        408 => "Request Timeout",
+        _ => "Internal Server Error",
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        content_type,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.shutdown().await
+}
+
+/// Applies the style file to `source` whenever it changes, keeping the
+/// previous style while the file cannot be read.
+async fn watch_style(mut file: StyleFile, source: Arc<Source>) {
+    let mut interval = tokio::time::interval(STYLE_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        match file.poll() {
+            Some(Ok(style)) => {
+                source.set_style(style);
+                logger::info(&format!("Style reloaded from {}", file.path.display()));
+            }
+            Some(Err(e)) => logger::warn(&format!("{}; keeping the previous style", e)),
+            None => {}
+        }
+    }
+}
+
+/// Opens the GeoPackage and serves requests until the process is stopped.
+pub async fn run(args: ServeArgs) -> Result<()> {
+    let source = Source::open(&args.input, &args.layer, &args.style).await?;
+    let style_file = match &args.style_file {
+        Some(path) => {
+            let (file, style) = StyleFile::open(path, source.style())?;
+            source.set_style(style);
+            Some(file)
+        }
+        None => None,
+    };
+    let listener = TcpListener::bind(&args.bind).await?;
+    logger::info(&format!(
+        "Serving {} layer(s) of {} on http://{}/tiles/{{z}}/{{x}}/{{y}}.png and http://{}/wms",
+        source.layers.len(),
+        args.input.display(),
+        args.bind,
+        args.bind
+    ));
+
+    let server = Arc::new(source);
+    if let Some(file) = style_file {
+        tokio::spawn(watch_style(file, Arc::clone(&server)));
+    }
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let server = Arc::clone(&server);
+        tokio::spawn(async move {
+            if let Err(e) = server.handle(stream).await {
+                logger::debug(&format!("Connection error: {}", e));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_view() {
+        let world = tile_view(0, 0, 0).unwrap();
+        let half = PI * EARTH_RADIUS;
+        assert!((world.bbox.min_lon + half).abs() < 1e-6);
+        assert!((world.bbox.max_lat - half).abs() < 1e-6);
+        let wgs84 = world.wgs84_bbox();
+        assert!((wgs84.max_lon - 180.0).abs() < 1e-9);
+        assert!((wgs84.max_lat - MAX_MERCATOR_LAT).abs() < 1e-9);
+
+        // North-east quarter at zoom 1
+        let tile = tile_view(1, 1, 0).unwrap().wgs84_bbox();
+        assert!(tile.min_lon.abs() < 1e-9 && tile.min_lat.abs() < 1e-9);
+        assert!(tile_view(1, 2, 0).is_err());
+    }
+
+    #[test]
+    fn test_getmap_axis_order() {
+        let params = |query: &str| getmap_view(&parse_query(query));
+        let v130 = params("SERVICE=WMS&VERSION=1.3.0&REQUEST=GetMap&LAYERS=a%2Cb&CRS=EPSG:4326&BBOX=48,-4.5,48.5,-4&WIDTH=200&HEIGHT=100").unwrap();
+        assert_eq!((v130.bbox.min_lon, v130.bbox.min_lat), (-4.5, 48.0));
+        assert_eq!(v130.layers, Some(vec!["a".to_string(), "b".to_string()]));
+        assert_eq!((v130.width, v130.height), (200, 100));
+
+        let v111 = params("version=1.1.1&request=GetMap&srs=EPSG:4326&bbox=-4.5,48,-4,48.5&width=10&height=10").unwrap();
+        assert_eq!((v111.bbox.min_lon, v111.bbox.min_lat), (-4.5, 48.0));
+        assert_eq!(v111.layers, None);
+
+        assert!(params("REQUEST=GetCapabilities").is_err());
+        assert!(params("REQUEST=GetMap&CRS=EPSG:2154&BBOX=0,0,1,1&WIDTH=1&HEIGHT=1").is_err());
+    }
+
+    #[test]
+    fn test_to_pixels_fills_requested_size() {
+        let view = View {
+            crs: ViewCrs::Wgs84,
+            bbox: Bbox::new(0.0, 0.0, 10.0, 5.0),
+            width: 100,
+            height: 100,
+            layers: None,
+        };
+        let triangle = geo::Polygon::new(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 5.0)].into(), vec![]);
+        let mp = MultiPolygon::new(vec![triangle]);
+        let projected = view.to_pixels(&mp);
+        let corner = projected.0[0].exterior().0[2];
+        assert_eq!((corner.x, corner.y), (100.0, 100.0));
+    }
+
+    #[test]
+    fn test_route() {
+        assert!(matches!(route("/tiles/3/4/2.png"), Route::Render(_)));
+        assert!(matches!(route("/tiles/3/4.png"), Route::Error(404, _)));
+        assert!(matches!(route("/tiles/1/5/0.png"), Route::Error(404, _)));
+        assert!(matches!(route("/wms?REQUEST=GetMap"), Route::Error(400, _)));
+        assert_eq!(percent_decode("a%2Cb+c%zz"), "a,b c%zz");
+    }
+
+    #[tokio::test]
+    async fn test_read_request_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // A client sending its head gets routed, a silent one times out
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET /tiles/0/0/0.png HTTP/1.1\r\n\r\n").await.unwrap();
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let target = read_request(&mut stream, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(target, Ok("/tiles/0/0/0.png".to_string()));
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET /tiles/0/0").await.unwrap();
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let target = read_request(&mut stream, Duration::from_millis(50)).await.unwrap();
+        assert_eq!(target, Err(408));
+    }
+
+    #[test]
+    fn test_style_file_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("style.toml");
+        std::fs::write(&path, "fill = \"none\"").unwrap();
+        let base = Style {
+            fill: Some([255, 0, 0, 128]),
+            stroke: Some([255, 0, 0]),
+            stroke_width: 1,
+        };
+        let (mut file, style) = StyleFile::open(&path, base).unwrap();
+        assert_eq!(style, Style { fill: None, ..base });
+        assert!(file.poll().is_none());
+
+        // Every change applies over the command-line style
+        std::fs::write(&path, "stroke-width = 4").unwrap();
+        assert_eq!(file.poll().unwrap().unwrap(), Style { stroke_width: 4, ..base });
+        assert!(file.poll().is_none());
+
+        // An invalid file is reported once, until it changes again
+        std::fs::write(&path, "stroke-width = \"4\"").unwrap();
+        assert!(file.poll().unwrap().is_err());
+        assert!(file.poll().is_none());
+    }
+}
@@ -0,0 +1,139 @@
+//! STAC Items (`--stac`).
+//!
+//! Every produced raster gets a `<name>.stac.json` Item next to it, with the
+//! grid described by the projection extension, so outputs can be ingested
+//! into a STAC catalog without a separate script.
+
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::manifest::Entry;
+use crate::math::Bbox;
+
+/// STAC specification version of the Items.
+pub const STAC_VERSION: &str = "1.0.0";
+
+/// Schema of the projection extension (`proj:` fields).
+const PROJECTION_EXTENSION: &str = "https://stac-extensions.github.io/projection/v1.1.0/schema.json";
+
+/// Returns where the STAC Item of an output is written: `<name>.stac.json`.
+pub fn item_path(path: &Path) -> PathBuf {
+    path.with_extension("stac.json")
+}
+
+/// Builds the STAC Item of `entry`, dated `datetime` (Unix seconds).
+///
+/// `role` is the STAC asset role, `visual` for maps and `data` for rasters
+/// of values.
+pub fn item(entry: &Entry, datetime: u64, role: &str) -> Value {
+    let bbox = entry.bbox;
+    let id = entry.path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let href = entry.path.file_name().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let media_type = match entry.path.extension().and_then(|e| e.to_str()) {
+        Some("tif") => "image/tiff; application=geotiff",
+        _ => "image/png",
+    };
+    // The grid starts at the bottom-left corner and may extend past the top of the bbox
+    let top = bbox.min_lat + entry.height as f64 * entry.resolution;
+    // GeoJSON writes a bbox crossing the antimeridian with min_lon > max_lon
+    let east = if bbox.crosses_antimeridian() { bbox.max_lon - 360.0 } else { bbox.max_lon };
+    json!({
+        "type": "Feature",
+        "stac_version": STAC_VERSION,
+        "stac_extensions": [PROJECTION_EXTENSION],
+        "id": id,
+        "bbox": [bbox.min_lon, bbox.min_lat, east, bbox.max_lat],
+        "geometry": geometry(&bbox, east),
+        "properties": {
+            "title": entry.layer,
+            "datetime": rfc3339(datetime),
+            "proj:epsg": 4326,
+            "proj:shape": [entry.height, entry.width],
+            "proj:transform": [entry.resolution, 0.0, bbox.min_lon, 0.0, -entry.resolution, top],
+        },
+        "links": [],
+        "assets": {
+            "image": {
+                "href": href,
+                "type": media_type,
+                "title": entry.layer,
+                "roles": [role],
+            }
+        },
+    })
+}
+
+/// Writes the STAC Item of `entry` next to its file and returns its path.
+pub fn write(entry: &Entry, datetime: u64, role: &str) -> Result<PathBuf> {
+    let path = item_path(&entry.path);
+    std::fs::write(&path, format!("{:#}\n", item(entry, datetime, role)))?;
+    Ok(path)
+}
+
+/// Footprint of `bbox`, ending at longitude `east`, split in two polygons
+/// when it crosses the antimeridian.
+fn geometry(bbox: &Bbox, east: f64) -> Value {
+    let rectangle = |min_lon: f64, max_lon: f64| {
+        json!([[
+            [min_lon, bbox.min_lat],
+            [max_lon, bbox.min_lat],
+            [max_lon, bbox.max_lat],
+            [min_lon, bbox.max_lat],
+            [min_lon, bbox.min_lat]
+        ]])
+    };
+    if bbox.crosses_antimeridian() {
+        json!({
+            "type": "MultiPolygon",
+            "coordinates": [rectangle(bbox.min_lon, 180.0), rectangle(-180.0, east)],
+        })
+    } else {
+        json!({"type": "Polygon", "coordinates": rectangle(bbox.min_lon, east)})
+    }
+}
+
+/// Formats a Unix time as an RFC 3339 UTC date-time.
+pub(crate) fn rfc3339(timestamp: u64) -> String {
+    let (date, time) = crate::output::utc_date_time(timestamp);
+    format!("{}T{}:{}:{}Z", date, &time[..2], &time[2..4], &time[4..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item() {
+        let entry = Entry {
+            path: PathBuf::from("out/roads.png"),
+            input: PathBuf::from("city.gpkg"),
+            layer: "roads".to_string(),
+            bbox: Bbox::new(-4.5, 48.0, -4.0, 48.5),
+            width: 500,
+            height: 500,
+            resolution: 0.001,
+            features: Some(42),
+            timings: None,
+        };
+        let item = item(&entry, 1_700_000_000, "visual");
+        assert_eq!(item["id"], "roads");
+        assert_eq!(item["geometry"]["type"], "Polygon");
+        assert_eq!(item["properties"]["datetime"], "2023-11-14T22:13:20Z");
+        assert_eq!(item["properties"]["proj:shape"], json!([500, 500]));
+        assert_eq!(item["properties"]["proj:transform"], json!([0.001, 0.0, -4.5, 0.0, -0.001, 48.5]));
+        assert_eq!(item["assets"]["image"]["href"], "roads.png");
+        assert_eq!(item["assets"]["image"]["type"], "image/png");
+        assert_eq!(item_path(&entry.path), PathBuf::from("out/roads.stac.json"));
+
+        let fiji = Entry {
+            path: PathBuf::from("fiji.tif"),
+            bbox: Bbox::new(177.0, -19.0, 182.0, -16.0),
+            ..entry
+        };
+        let item = super::item(&fiji, 0, "data");
+        assert_eq!(item["geometry"]["type"], "MultiPolygon");
+        assert_eq!(item["bbox"], json!([177.0, -19.0, -178.0, -16.0]));
+        assert_eq!(item["assets"]["image"]["type"], "image/tiff; application=geotiff");
+    }
+}
@@ -0,0 +1,153 @@
+//! Polygon hole statistics (`stats` subcommand).
+//!
+//! Reports how many holes each layer has, how deeply rings are nested
+//! (island in a hole in a polygon...), and which features have holes lying
+//! outside their exterior ring. The even-odd fill renders such holes as
+//! stray filled regions, so they are worth fixing in the source data.
+
+use geo::{Intersects, LineString, MultiPolygon, Point, Polygon};
+use std::fmt::Display;
+
+/// Maximum number of offending feature ids kept for the report.
+const MAX_REPORTED: usize = 100;
+
+/// Hole statistics accumulated over the features of a layer.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HoleStats {
+    /// Number of features.
+    pub features: u64,
+    /// Number of polygons (parts of multipolygons).
+    pub polygons: u64,
+    /// Number of interior rings.
+    pub holes: u64,
+    /// Number of features with at least one hole.
+    pub features_with_holes: u64,
+    /// Deepest ring nesting (1 = plain polygon, 2 = with holes, 3 = island in a hole...).
+    pub max_depth: u32,
+    /// Number of features with a hole outside its exterior ring.
+    pub holes_outside_count: u64,
+    /// Ids of those features (first `MAX_REPORTED` only).
+    pub holes_outside: Vec<String>,
+}
+
+impl HoleStats {
+    /// Accounts for one feature, identified by `id` in the report.
+    pub fn add(&mut self, id: impl Display, mp: &MultiPolygon<f64>) {
+        self.features += 1;
+        self.polygons += mp.0.len() as u64;
+
+        let holes: usize = mp.iter().map(|p| p.interiors().len()).sum();
+        self.holes += holes as u64;
+        if holes > 0 {
+            self.features_with_holes += 1;
+        }
+
+        self.max_depth = self.max_depth.max(ring_depth(mp));
+
+        if mp.iter().any(has_hole_outside) {
+            self.holes_outside_count += 1;
+            if self.holes_outside.len() < MAX_REPORTED {
+                self.holes_outside.push(id.to_string());
+            }
+        }
+    }
+}
+
+/// Returns true if some vertex of a hole lies strictly outside the exterior ring.
+fn has_hole_outside(polygon: &Polygon<f64>) -> bool {
+    if polygon.interiors().is_empty() {
+        return false;
+    }
+    let exterior = Polygon::new(polygon.exterior().clone(), vec![]);
+    polygon
+        .interiors()
+        .iter()
+        .flat_map(|hole| hole.points())
+        .any(|p| !exterior.intersects(&p))
+}
+
+/// Returns the deepest ring nesting of a multipolygon.
+///
+/// The depth of a ring is one plus the number of other rings enclosing it,
+/// whatever polygon they belong to.
+fn ring_depth(mp: &MultiPolygon<f64>) -> u32 {
+    let rings: Vec<&LineString<f64>> = mp
+        .iter()
+        .flat_map(|p| std::iter::once(p.exterior()).chain(p.interiors()))
+        .filter(|r| r.0.len() >= 4)
+        .collect();
+    let areas: Vec<Polygon<f64>> = rings
+        .iter()
+        .map(|r| Polygon::new((*r).clone(), vec![]))
+        .collect();
+
+    rings
+        .iter()
+        .enumerate()
+        .map(|(i, ring)| {
+            let probe = Point::from(ring.0[0]);
+            let enclosing = areas
+                .iter()
+                .enumerate()
+                .filter(|&(j, area)| j != i && area.intersects(&probe) && !area.exterior().intersects(&probe))
+                .count();
+            enclosing as u32 + 1
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::polygon;
+
+    fn square(x: f64, y: f64, size: f64) -> LineString<f64> {
+        LineString::from(vec![
+            (x, y),
+            (x + size, y),
+            (x + size, y + size),
+            (x, y + size),
+            (x, y),
+        ])
+    }
+
+    #[test]
+    fn test_plain_polygon() {
+        let mut stats = HoleStats::default();
+        stats.add(1, &MultiPolygon::new(vec![polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0)]]));
+        assert_eq!(stats.features, 1);
+        assert_eq!(stats.holes, 0);
+        assert_eq!(stats.max_depth, 1);
+        assert!(stats.holes_outside.is_empty());
+    }
+
+    #[test]
+    fn test_island_in_hole_depth() {
+        let donut = Polygon::new(square(0.0, 0.0, 10.0), vec![square(2.0, 2.0, 6.0)]);
+        let island = Polygon::new(square(4.0, 4.0, 2.0), vec![]);
+        let mut stats = HoleStats::default();
+        stats.add(7, &MultiPolygon::new(vec![donut, island]));
+        assert_eq!(stats.polygons, 2);
+        assert_eq!(stats.holes, 1);
+        assert_eq!(stats.features_with_holes, 1);
+        assert_eq!(stats.max_depth, 3);
+        assert!(stats.holes_outside.is_empty());
+    }
+
+    #[test]
+    fn test_hole_outside_exterior() {
+        let broken = Polygon::new(square(0.0, 0.0, 10.0), vec![square(8.0, 8.0, 5.0)]);
+        let mut stats = HoleStats::default();
+        stats.add(3, &MultiPolygon::new(vec![broken]));
+        stats.add(4, &MultiPolygon::new(vec![Polygon::new(square(0.0, 0.0, 1.0), vec![])]));
+        assert_eq!(stats.holes_outside_count, 1);
+        assert_eq!(stats.holes_outside, vec!["3".to_string()]);
+    }
+
+    #[test]
+    fn test_hole_touching_exterior_is_valid() {
+        let touching = Polygon::new(square(0.0, 0.0, 10.0), vec![square(0.0, 2.0, 3.0)]);
+        assert!(!has_hole_outside(&touching));
+    }
+}
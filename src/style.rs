@@ -0,0 +1,572 @@
+//! Per-feature styling driven by attribute values.
+//!
+//! Style columns (`--opacity-column`, `--stroke-color-column`,
+//! `--fill-column`) are read alongside the geometries and turned into a
+//! [`FeatureStyle`] for each feature. Features whose value is missing or
+//! cannot be interpreted keep the layer style given on the command line.
+//! `--color-per-feature` fills each feature with a color hashed from its id
+//! instead.
+
+use crate::classify::Classification;
+use crate::ramp::Ramp;
+use crate::render::FeatureStyle;
+
+/// An attribute value read alongside a geometry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    Null,
+    Number(f64),
+    Text(String),
+}
+
+impl AttributeValue {
+    /// Converts a GeoJSON property value; booleans, arrays and objects are not styled.
+    pub fn from_json(value: &geojson::JsonValue) -> Self {
+        match value {
+            geojson::JsonValue::Number(n) => n.as_f64().map_or(Self::Null, Self::Number),
+            geojson::JsonValue::String(s) => Self::Text(s.clone()),
+            _ => Self::Null,
+        }
+    }
+
+    /// Returns the value as a color, if it is a hex string or a color name.
+    pub fn as_color(&self) -> Option<[u8; 3]> {
+        match self {
+            Self::Text(s) => parse_color(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is numeric.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(v) if v.is_finite() => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+/// Range of the values of an opacity column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpacityScale {
+    /// Values from 0 to 1.
+    Fraction,
+    /// Values from 0 to 100.
+    Percent,
+}
+
+impl OpacityScale {
+    /// Guesses the range from the largest value of the column: percentages
+    /// as soon as one value exceeds 1.
+    pub fn detect(max: Option<f64>) -> Self {
+        match max {
+            Some(max) if max > 1.0 => Self::Percent,
+            _ => Self::Fraction,
+        }
+    }
+
+    /// Converts an opacity value to an alpha channel value, clamping out-of-range values.
+    pub fn alpha(self, value: f64) -> u8 {
+        let fraction = match self {
+            Self::Fraction => value,
+            Self::Percent => value / 100.0,
+        };
+        (fraction.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+}
+
+/// Maps the values of a fill column onto a color ramp.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillScale {
+    pub ramp: Ramp,
+    /// Smallest value of the column, at the start of the ramp.
+    pub min: f64,
+    /// Largest value of the column, at the end of the ramp.
+    pub max: f64,
+    /// Upper bounds of the classes but the last (`--classify`); empty for
+    /// colors proportional to the values.
+    pub breaks: Vec<f64>,
+}
+
+impl FillScale {
+    /// Spreads the values of `range` (smallest, largest) along the ramp.
+    pub fn continuous(ramp: Ramp, range: Option<(f64, f64)>) -> Self {
+        let (min, max) = range.unwrap_or((0.0, 0.0));
+        Self {
+            ramp,
+            min,
+            max,
+            breaks: Vec::new(),
+        }
+    }
+
+    /// Spreads `values` along the ramp, or with a classification and class
+    /// count, splits them into classes colored evenly along the ramp.
+    pub fn from_values(ramp: Ramp, values: &[f64], classification: Option<(Classification, usize)>) -> Self {
+        let finite = values.iter().copied().filter(|v| v.is_finite());
+        let range = finite.fold(None, |range, v| Some(range.map_or((v, v), |(a, b): (f64, f64)| (a.min(v), b.max(v)))));
+        Self {
+            breaks: classification.map_or_else(Vec::new, |(method, classes)| method.breaks(values, classes)),
+            ..Self::continuous(ramp, range)
+        }
+    }
+
+    /// Returns the color of `value`: the color of its class, or its position
+    /// between the smallest and largest value. A column holding a single
+    /// value gets the middle of the ramp.
+    pub fn color(&self, value: f64) -> [u8; 3] {
+        let position = if !self.breaks.is_empty() {
+            self.breaks.partition_point(|&bound| bound < value) as f64 / self.breaks.len() as f64
+        } else if self.max > self.min {
+            (value - self.min) / (self.max - self.min)
+        } else {
+            0.5
+        };
+        self.ramp.color(position)
+    }
+}
+
+/// Attribute columns driving the style of each feature.
+#[derive(Debug, Clone, Default)]
+pub struct StyleColumns {
+    /// Column controlling the fill alpha, with the range of its values.
+    pub opacity: Option<(String, OpacityScale)>,
+    /// Column holding the stroke color.
+    pub stroke: Option<String>,
+    /// Column whose numeric values pick the fill color on a ramp.
+    pub fill: Option<(String, FillScale)>,
+    /// Seed of the per-feature fill colors (`--color-per-feature`).
+    pub feature_colors: Option<u64>,
+}
+
+impl StyleColumns {
+    /// Returns the columns to read with each feature, in the order expected by [`Self::style`].
+    pub fn names(&self) -> Vec<String> {
+        self.opacity
+            .iter()
+            .map(|(name, _)| name)
+            .chain(&self.stroke)
+            .chain(self.fill.iter().map(|(name, _)| name))
+            .cloned()
+            .collect()
+    }
+
+    /// Builds the style of feature `fid` from the values of [`Self::names`].
+    pub fn style(&self, fid: i64, values: &[AttributeValue]) -> FeatureStyle {
+        let mut values = values.iter();
+        let fill_alpha = self.opacity.as_ref().and_then(|(_, scale)| {
+            let value = values.next()?.as_f64()?;
+            Some(scale.alpha(value))
+        });
+        let stroke = self.stroke.as_ref().and_then(|_| values.next()?.as_color());
+        let fill = match &self.fill {
+            Some((_, scale)) => values.next().and_then(AttributeValue::as_f64).map(|v| scale.color(v)),
+            None => self.feature_colors.map(|seed| feature_color(fid, seed)),
+        };
+        FeatureStyle {
+            fill,
+            fill_alpha,
+            stroke,
+        }
+    }
+
+    /// Whether features need a style of their own.
+    pub fn is_empty(&self) -> bool {
+        self.names().is_empty() && self.feature_colors.is_none()
+    }
+}
+
+/// Parses a color given as `#RRGGBB`, `RRGGBB`, `#RGB` or a CSS color name
+/// (case-insensitive).
+pub fn parse_color(s: &str) -> Option<[u8; 3]> {
+    let s = s.trim();
+    if let Some(&(_, rgb)) = NAMED_COLORS.iter().find(|(name, _)| name.eq_ignore_ascii_case(s)) {
+        return Some(rgb);
+    }
+
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    match hex.len() {
+        6 => {
+            let bytes = hex::decode(hex).ok()?;
+            Some([bytes[0], bytes[1], bytes[2]])
+        }
+        3 if s.starts_with('#') => {
+            // Each digit is doubled: #F80 is #FF8800
+            let mut rgb = [0u8; 3];
+            for (channel, digit) in rgb.iter_mut().zip(hex.chars()) {
+                *channel = digit.to_digit(16)? as u8 * 17;
+            }
+            Some(rgb)
+        }
+        _ => None,
+    }
+}
+
+/// Colors assigned to layers by `--auto-colors` (Tableau 10).
+pub const PALETTE: [[u8; 3]; 10] = [
+    [0x4E, 0x79, 0xA7],
+    [0xF2, 0x8E, 0x2B],
+    [0xE1, 0x57, 0x59],
+    [0x76, 0xB7, 0xB2],
+    [0x59, 0xA1, 0x4F],
+    [0xED, 0xC9, 0x48],
+    [0xB0, 0x7A, 0xA1],
+    [0xFF, 0x9D, 0xA7],
+    [0x9C, 0x75, 0x5F],
+    [0xBA, 0xB0, 0xAC],
+];
+
+/// Color of the layer at `index` with `--auto-colors`.
+///
+/// A non-zero `seed` shuffles the palette deterministically. Past the
+/// palette size, colors come back lightened, then darkened, so the first
+/// 30 layers all differ.
+pub fn palette_color(index: usize, seed: u64) -> [u8; 3] {
+    let mut order: Vec<usize> = (0..PALETTE.len()).collect();
+    if seed != 0 {
+        // Fisher-Yates driven by splitmix64
+        let mut state = seed;
+        for i in (1..order.len()).rev() {
+            order.swap(i, (splitmix64(&mut state) % (i as u64 + 1)) as usize);
+        }
+    }
+    let color = PALETTE[order[index % PALETTE.len()]];
+    let mix = |target: u8, amount: f64| {
+        color.map(|c| (c as f64 + (target as f64 - c as f64) * amount).round() as u8)
+    };
+    match index / PALETTE.len() % 3 {
+        0 => color,
+        1 => mix(255, 0.4),
+        _ => mix(0, 0.35),
+    }
+}
+
+/// Returns the fill color of feature `fid` with `--color-per-feature=seed`.
+///
+/// The id is hashed to a hue, so neighboring ids get unrelated colors;
+/// saturation and lightness stay in a band that reads well on white and black.
+pub fn feature_color(fid: i64, seed: u64) -> [u8; 3] {
+    let mut state = fid as u64 ^ seed.wrapping_mul(0xD605_BBB5_8C8A_BBD5);
+    let hash = splitmix64(&mut state);
+    let hue = (hash % 360) as f64;
+    let saturation = 0.6 + ((hash >> 16) % 30) as f64 / 100.0;
+    let lightness = 0.4 + ((hash >> 32) % 20) as f64 / 100.0;
+    hsl_to_rgb(hue, saturation, lightness)
+}
+
+/// Advances a splitmix64 generator and returns its next value.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Converts a hue in degrees and saturation and lightness in [0, 1] to RGB.
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [u8; 3] {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let h = hue / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    [r, g, b].map(|c| ((c + m) * 255.0).round() as u8)
+}
+
+/// CSS named colors.
+const NAMED_COLORS: &[(&str, [u8; 3])] = &[
+    ("aliceblue", [240, 248, 255]),
+    ("antiquewhite", [250, 235, 215]),
+    ("aqua", [0, 255, 255]),
+    ("aquamarine", [127, 255, 212]),
+    ("azure", [240, 255, 255]),
+    ("beige", [245, 245, 220]),
+    ("bisque", [255, 228, 196]),
+    ("black", [0, 0, 0]),
+    ("blanchedalmond", [255, 235, 205]),
+    ("blue", [0, 0, 255]),
+    ("blueviolet", [138, 43, 226]),
+    ("brown", [165, 42, 42]),
+    ("burlywood", [222, 184, 135]),
+    ("cadetblue", [95, 158, 160]),
+    ("chartreuse", [127, 255, 0]),
+    ("chocolate", [210, 105, 30]),
+    ("coral", [255, 127, 80]),
+    ("cornflowerblue", [100, 149, 237]),
+    ("cornsilk", [255, 248, 220]),
+    ("crimson", [220, 20, 60]),
+    ("cyan", [0, 255, 255]),
+    ("darkblue", [0, 0, 139]),
+    ("darkcyan", [0, 139, 139]),
+    ("darkgoldenrod", [184, 134, 11]),
+    ("darkgray", [169, 169, 169]),
+    ("darkgreen", [0, 100, 0]),
+    ("darkgrey", [169, 169, 169]),
+    ("darkkhaki", [189, 183, 107]),
+    ("darkmagenta", [139, 0, 139]),
+    ("darkolivegreen", [85, 107, 47]),
+    ("darkorange", [255, 140, 0]),
+    ("darkorchid", [153, 50, 204]),
+    ("darkred", [139, 0, 0]),
+    ("darksalmon", [233, 150, 122]),
+    ("darkseagreen", [143, 188, 143]),
+    ("darkslateblue", [72, 61, 139]),
+    ("darkslategray", [47, 79, 79]),
+    ("darkslategrey", [47, 79, 79]),
+    ("darkturquoise", [0, 206, 209]),
+    ("darkviolet", [148, 0, 211]),
+    ("deeppink", [255, 20, 147]),
+    ("deepskyblue", [0, 191, 255]),
+    ("dimgray", [105, 105, 105]),
+    ("dimgrey", [105, 105, 105]),
+    ("dodgerblue", [30, 144, 255]),
+    ("firebrick", [178, 34, 34]),
+    ("floralwhite", [255, 250, 240]),
+    ("forestgreen", [34, 139, 34]),
+    ("fuchsia", [255, 0, 255]),
+    ("gainsboro", [220, 220, 220]),
+    ("ghostwhite", [248, 248, 255]),
+    ("gold", [255, 215, 0]),
+    ("goldenrod", [218, 165, 32]),
+    ("gray", [128, 128, 128]),
+    ("green", [0, 128, 0]),
+    ("greenyellow", [173, 255, 47]),
+    ("grey", [128, 128, 128]),
+    ("honeydew", [240, 255, 240]),
+    ("hotpink", [255, 105, 180]),
+    ("indianred", [205, 92, 92]),
+    ("indigo", [75, 0, 130]),
+    ("ivory", [255, 255, 240]),
+    ("khaki", [240, 230, 140]),
+    ("lavender", [230, 230, 250]),
+    ("lavenderblush", [255, 240, 245]),
+    ("lawngreen", [124, 252, 0]),
+    ("lemonchiffon", [255, 250, 205]),
+    ("lightblue", [173, 216, 230]),
+    ("lightcoral", [240, 128, 128]),
+    ("lightcyan", [224, 255, 255]),
+    ("lightgoldenrodyellow", [250, 250, 210]),
+    ("lightgray", [211, 211, 211]),
+    ("lightgreen", [144, 238, 144]),
+    ("lightgrey", [211, 211, 211]),
+    ("lightpink", [255, 182, 193]),
+    ("lightsalmon", [255, 160, 122]),
+    ("lightseagreen", [32, 178, 170]),
+    ("lightskyblue", [135, 206, 250]),
+    ("lightslategray", [119, 136, 153]),
+    ("lightslategrey", [119, 136, 153]),
+    ("lightsteelblue", [176, 196, 222]),
+    ("lightyellow", [255, 255, 224]),
+    ("lime", [0, 255, 0]),
+    ("limegreen", [50, 205, 50]),
+    ("linen", [250, 240, 230]),
+    ("magenta", [255, 0, 255]),
+    ("maroon", [128, 0, 0]),
+    ("mediumaquamarine", [102, 205, 170]),
+    ("mediumblue", [0, 0, 205]),
+    ("mediumorchid", [186, 85, 211]),
+    ("mediumpurple", [147, 112, 219]),
+    ("mediumseagreen", [60, 179, 113]),
+    ("mediumslateblue", [123, 104, 238]),
+    ("mediumspringgreen", [0, 250, 154]),
+    ("mediumturquoise", [72, 209, 204]),
+    ("mediumvioletred", [199, 21, 133]),
+    ("midnightblue", [25, 25, 112]),
+    ("mintcream", [245, 255, 250]),
+    ("mistyrose", [255, 228, 225]),
+    ("moccasin", [255, 228, 181]),
+    ("navajowhite", [255, 222, 173]),
+    ("navy", [0, 0, 128]),
+    ("oldlace", [253, 245, 230]),
+    ("olive", [128, 128, 0]),
+    ("olivedrab", [107, 142, 35]),
+    ("orange", [255, 165, 0]),
+    ("orangered", [255, 69, 0]),
+    ("orchid", [218, 112, 214]),
+    ("palegoldenrod", [238, 232, 170]),
+    ("palegreen", [152, 251, 152]),
+    ("paleturquoise", [175, 238, 238]),
+    ("palevioletred", [219, 112, 147]),
+    ("papayawhip", [255, 239, 213]),
+    ("peachpuff", [255, 218, 185]),
+    ("peru", [205, 133, 63]),
+    ("pink", [255, 192, 203]),
+    ("plum", [221, 160, 221]),
+    ("powderblue", [176, 224, 230]),
+    ("purple", [128, 0, 128]),
+    ("rebeccapurple", [102, 51, 153]),
+    ("red", [255, 0, 0]),
+    ("rosybrown", [188, 143, 143]),
+    ("royalblue", [65, 105, 225]),
+    ("saddlebrown", [139, 69, 19]),
+    ("salmon", [250, 128, 114]),
+    ("sandybrown", [244, 164, 96]),
+    ("seagreen", [46, 139, 87]),
+    ("seashell", [255, 245, 238]),
+    ("sienna", [160, 82, 45]),
+    ("silver", [192, 192, 192]),
+    ("skyblue", [135, 206, 235]),
+    ("slateblue", [106, 90, 205]),
+    ("slategray", [112, 128, 144]),
+    ("slategrey", [112, 128, 144]),
+    ("snow", [255, 250, 250]),
+    ("springgreen", [0, 255, 127]),
+    ("steelblue", [70, 130, 180]),
+    ("tan", [210, 180, 140]),
+    ("teal", [0, 128, 128]),
+    ("thistle", [216, 191, 216]),
+    ("tomato", [255, 99, 71]),
+    ("turquoise", [64, 224, 208]),
+    ("violet", [238, 130, 238]),
+    ("wheat", [245, 222, 179]),
+    ("white", [255, 255, 255]),
+    ("whitesmoke", [245, 245, 245]),
+    ("yellow", [255, 255, 0]),
+    ("yellowgreen", [154, 205, 50]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_opacity_scale() {
+        assert_eq!(OpacityScale::detect(None), OpacityScale::Fraction);
+        assert_eq!(OpacityScale::detect(Some(1.0)), OpacityScale::Fraction);
+        assert_eq!(OpacityScale::detect(Some(42.0)), OpacityScale::Percent);
+    }
+
+    #[test]
+    fn test_opacity_to_alpha() {
+        assert_eq!(OpacityScale::Fraction.alpha(0.5), 128);
+        assert_eq!(OpacityScale::Percent.alpha(100.0), 255);
+        assert_eq!(OpacityScale::Percent.alpha(-5.0), 0);
+        assert_eq!(OpacityScale::Fraction.alpha(3.0), 255);
+    }
+
+    #[test]
+    fn test_style_from_values() {
+        let columns = StyleColumns {
+            opacity: Some(("p".to_string(), OpacityScale::Fraction)),
+            ..Default::default()
+        };
+        assert_eq!(columns.names(), vec!["p".to_string()]);
+        assert_eq!(columns.style(0, &[AttributeValue::Number(0.0)]).fill_alpha, Some(0));
+        assert_eq!(columns.style(0, &[AttributeValue::Null]).fill_alpha, None);
+        assert_eq!(columns.style(0, &[AttributeValue::Text("high".to_string())]).fill_alpha, None);
+        assert_eq!(StyleColumns::default().style(0, &[]), FeatureStyle::default());
+    }
+
+    #[test]
+    fn test_stroke_follows_opacity_column() {
+        let columns = StyleColumns {
+            opacity: Some(("p".to_string(), OpacityScale::Percent)),
+            stroke: Some("color".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(columns.names(), vec!["p".to_string(), "color".to_string()]);
+        let style = columns.style(0, &[AttributeValue::Null, AttributeValue::Text("#00ff00".to_string())]);
+        assert_eq!(style.fill_alpha, None);
+        assert_eq!(style.stroke, Some([0, 255, 0]));
+    }
+
+    #[test]
+    fn test_fill_column() {
+        let scale = FillScale::continuous(Ramp::parse("black,white").unwrap(), Some((10.0, 20.0)));
+        let columns = StyleColumns {
+            stroke: Some("color".to_string()),
+            fill: Some(("density".to_string(), scale.clone())),
+            ..Default::default()
+        };
+        assert!(!columns.is_empty());
+        assert_eq!(columns.names(), vec!["color".to_string(), "density".to_string()]);
+        let style = columns.style(0, &[AttributeValue::Null, AttributeValue::Number(15.0)]);
+        assert_eq!(style.fill, Some([128, 128, 128]));
+        assert_eq!(columns.style(0, &[AttributeValue::Null, AttributeValue::Number(99.0)]).fill, Some([255; 3]));
+        assert_eq!(columns.style(0, &[AttributeValue::Null, AttributeValue::Null]).fill, None);
+
+        // A single value takes the middle of the ramp
+        let flat = FillScale { min: 3.0, max: 3.0, ..scale };
+        assert_eq!(flat.color(3.0), [128, 128, 128]);
+
+        // Classes take evenly spaced colors, bounds in the lower class
+        let values = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 100.0];
+        let ramp = Ramp::parse("black,white").unwrap();
+        assert_eq!(FillScale::from_values(ramp.clone(), &values, None).max, 100.0);
+        let classes = FillScale::from_values(ramp, &values, Some((Classification::Quantile, 3)));
+        assert_eq!((classes.min, classes.max), (0.0, 100.0));
+        assert_eq!(classes.breaks, vec![2.0, 5.0]);
+        assert_eq!(classes.color(2.0), [0, 0, 0]);
+        assert_eq!(classes.color(2.5), [128, 128, 128]);
+        assert_eq!(classes.color(7.0), [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#FF8000"), Some([255, 128, 0]));
+        assert_eq!(parse_color("ff8000"), Some([255, 128, 0]));
+        assert_eq!(parse_color("#f80"), Some([255, 136, 0]));
+        assert_eq!(parse_color(" DarkGreen "), Some([0, 100, 0]));
+        assert_eq!(parse_color("f80"), None);
+        assert_eq!(parse_color("#12345"), None);
+        assert_eq!(parse_color("not a color"), None);
+    }
+
+    #[test]
+    fn test_attribute_from_json() {
+        let value = geojson::JsonValue::from(0.25);
+        assert_eq!(AttributeValue::from_json(&value), AttributeValue::Number(0.25));
+        assert_eq!(AttributeValue::from_json(&geojson::JsonValue::Bool(true)), AttributeValue::Null);
+    }
+
+    #[test]
+    fn test_palette_color() {
+        assert_eq!(palette_color(0, 0), PALETTE[0]);
+        assert_eq!(palette_color(3, 0), PALETTE[3]);
+        // The first 30 layers get distinct colors
+        let colors: std::collections::HashSet<_> = (0..30).map(|i| palette_color(i, 0)).collect();
+        assert_eq!(colors.len(), 30);
+
+        // A seed reorders the palette, the same way on every run
+        let shuffled: Vec<_> = (0..10).map(|i| palette_color(i, 42)).collect();
+        assert_eq!(shuffled, (0..10).map(|i| palette_color(i, 42)).collect::<Vec<_>>());
+        assert_ne!(shuffled, PALETTE.to_vec());
+        let mut sorted = shuffled.clone();
+        sorted.sort();
+        let mut palette = PALETTE.to_vec();
+        palette.sort();
+        assert_eq!(sorted, palette);
+    }
+
+    #[test]
+    fn test_feature_color() {
+        // Stable for a given id and seed, unrelated between neighboring ids
+        assert_eq!(feature_color(7, 0), feature_color(7, 0));
+        let colors: std::collections::HashSet<_> = (0..100).map(|fid| feature_color(fid, 0)).collect();
+        assert!(colors.len() > 95, "{} distinct colors", colors.len());
+        assert_ne!(feature_color(7, 0), feature_color(7, 1));
+
+        let columns = StyleColumns {
+            feature_colors: Some(3),
+            ..Default::default()
+        };
+        assert!(!columns.is_empty());
+        assert_eq!(columns.style(7, &[]).fill, Some(feature_color(7, 3)));
+        assert_eq!(StyleColumns::default().style(7, &[]).fill, None);
+    }
+
+    #[test]
+    fn test_hsl_to_rgb() {
+        assert_eq!(hsl_to_rgb(0.0, 1.0, 0.5), [255, 0, 0]);
+        assert_eq!(hsl_to_rgb(120.0, 1.0, 0.5), [0, 255, 0]);
+        assert_eq!(hsl_to_rgb(240.0, 1.0, 0.25), [0, 0, 128]);
+        assert_eq!(hsl_to_rgb(90.0, 0.0, 1.0), [255, 255, 255]);
+    }
+}
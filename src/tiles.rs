@@ -0,0 +1,171 @@
+//! Pre-rendered XYZ tiles (`tiles` subcommand).
+//!
+//! Writes the 256 px Web Mercator tiles that `serve` renders on demand as
+//! `{z}/{x}/{y}.png` files, for static hosting, at one zoom level or over a
+//! range of them. Only tiles over the extent of the layers are rendered, and
+//! tiles without features are not written. With `--resume`, the tiles
+//! completed by an unfinished previous run, empty or not, are skipped.
+
+use std::ops::RangeInclusive;
+
+use crate::cli::TilesArgs;
+use crate::error::{GpkgError, Result};
+use crate::logger;
+use crate::math::Bbox;
+use crate::output;
+use crate::serve::{tile_ground_resolution, tile_position, Source};
+use crate::{interrupt, resume};
+
+/// Highest zoom level of the tiles.
+const MAX_ZOOM: u32 = 24;
+
+/// Renders the tiles of the selected layers over the requested zoom levels.
+pub async fn run(args: TilesArgs) -> Result<()> {
+    let source = Source::open(&args.input, &args.layer, &args.style).await?;
+    let bbox = source.bbox().await?;
+    let zooms = zoom_levels(&args, &bbox)?;
+    if let Some(tolerance) = args.simplify {
+        if tolerance.is_nan() || tolerance <= 0.0 {
+            return Err(GpkgError::InvalidFormatOption(format!(
+                "--simplify must be a positive number of pixels, got {}",
+                tolerance
+            )));
+        }
+        logger::info(&format!("Simplifying geometries to {} pixel(s)", tolerance));
+    }
+    interrupt::install();
+    resume::start(&args.output_dir, args.resume)?;
+    let stamp = resume::input_stamp(&args.input)?;
+    let settings = [
+        ("version", env!("CARGO_PKG_VERSION").to_string()),
+        ("layers", args.layer.join(",")),
+        ("style", format!("{:?}", args.style)),
+        ("simplify", format!("{:?}", args.simplify)),
+    ];
+
+    let (mut total, mut written, mut skipped) = (0, 0, 0);
+    for z in zooms {
+        let (columns, rows) = tile_range(&bbox, z);
+        let count = columns.len() * rows.clone().count();
+        logger::info(&format!("Rendering {} tile(s) at zoom {}", count, z));
+        total += count;
+        for &x in &columns {
+            let dir = args.output_dir.join(z.to_string()).join(x.to_string());
+            for y in rows.clone() {
+                if interrupt::requested() {
+                    return Err(GpkgError::Interrupted);
+                }
+                let key = output::content_id(&stamp, &format!("{}/{}/{}", z, x, y), &settings);
+                if resume::is_done(&key) {
+                    skipped += 1;
+                    continue;
+                }
+                if let Some(png) = source.render_tile(z, x, y, args.simplify).await? {
+                    std::fs::create_dir_all(&dir)?;
+                    let path = dir.join(format!("{}.png", y));
+                    std::fs::write(&path, png)?;
+                    logger::debug(&format!("Saved: {}", path.display()));
+                    written += 1;
+                }
+                resume::record(&key)?;
+            }
+        }
+    }
+    if skipped > 0 {
+        logger::info(&format!("{} tile(s) completed by the previous run", skipped));
+    }
+    logger::info(&format!(
+        "{} tile(s) written to {} ({} empty)",
+        written,
+        args.output_dir.display(),
+        total - written - skipped
+    ));
+    resume::finish()
+
+}
+
+/// Returns the zoom levels to render: `--zoom`, or `--min-zoom` up to
+/// `--max-zoom` or the zoom of `--scale` at the center of `bbox`.
+fn zoom_levels(args: &TilesArgs, bbox: &Bbox) -> Result<RangeInclusive<u32>> {
+    if let Some(zoom) = args.zoom {
+        return Ok(zoom as u32..=zoom as u32);
+    }
+    let max = match (args.max_zoom, args.scale) {
+        (Some(zoom), _) => zoom as u32,
+        (None, Some(scale)) if scale > 0.0 => {
+            let zoom = zoom_for_scale(scale, bbox.center().1);
+            logger::info(&format!("--scale {} m/pixel: maximum zoom {}", scale, zoom));
+            zoom
+        }
+        (None, scale) => {
+            return Err(GpkgError::InvalidFormatOption(format!(
+                "--scale must be a positive number of meters, got {}",
+                scale.unwrap_or_default()
+            )))
+        }
+    };
+    let min = args.min_zoom as u32;
+    if min > max {
+        return Err(GpkgError::InvalidFormatOption(format!(
+            "--min-zoom {} is above the maximum zoom {}",
+            min, max
+        )));
+    }
+    Ok(min..=max)
+}
+
+/// Returns the first zoom level whose pixels are at most `scale` meters at
+/// latitude `lat` (the highest zoom when none is).
+fn zoom_for_scale(scale: f64, lat: f64) -> u32 {
+    (0..=MAX_ZOOM)
+        .find(|&z| tile_ground_resolution(z, lat) <= scale)
+        .unwrap_or(MAX_ZOOM)
+}
+
+/// Returns the columns and rows of the zoom `z` tiles covering `bbox`.
+///
+/// Columns of a bbox crossing the antimeridian wrap around to 0.
+fn tile_range(bbox: &Bbox, z: u32) -> (Vec<u32>, RangeInclusive<u32>) {
+    let tiles = 1i64 << z;
+    let (min_x, min_y) = tile_position(bbox.min_lon, bbox.max_lat, z);
+    let (max_x, max_y) = tile_position(bbox.max_lon, bbox.min_lat, z);
+    // A bbox edge on a tile boundary does not reach the next tile
+    let first = |v: f64| (v.floor() as i64).clamp(0, tiles - 1);
+    let last = |v: f64, first: i64| (v.ceil() as i64 - 1).max(first);
+
+    let first_x = first(min_x);
+    let last_x = last(max_x, first_x).min(first_x + tiles - 1);
+    let columns = (first_x..=last_x).map(|x| (x % tiles) as u32).collect();
+    let first_y = first(min_y);
+    let last_y = last(max_y, first_y).min(tiles - 1);
+    (columns, first_y as u32..=last_y as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_range() {
+        let world = Bbox::new(-180.0, -90.0, 180.0, 90.0);
+        assert_eq!(tile_range(&world, 0), (vec![0], 0..=0));
+        assert_eq!(tile_range(&world, 2), (vec![0, 1, 2, 3], 0..=3));
+
+        // North-east quarter, edges on tile boundaries
+        assert_eq!(tile_range(&Bbox::new(0.0, 0.0, 180.0, 85.0), 1), (vec![1], 0..=0));
+
+        // Fiji crosses the antimeridian: the last column, then the first
+        assert_eq!(tile_range(&Bbox::new(177.0, -19.0, 181.0, -16.0), 2), (vec![3, 0], 2..=2));
+    }
+
+    #[test]
+    fn test_zoom_for_scale() {
+        // Zoom 0 pixels are about 156 km at the equator, halving at each zoom
+        assert_eq!(zoom_for_scale(200_000.0, 0.0), 0);
+        assert_eq!(zoom_for_scale(100_000.0, 0.0), 1);
+        assert_eq!(zoom_for_scale(1.0, 0.0), 18);
+        // Pixels shrink towards the poles
+        assert_eq!(zoom_for_scale(1.0, 60.0), 17);
+        assert_eq!(zoom_for_scale(0.000_001, 0.0), MAX_ZOOM);
+    }
+}
@@ -0,0 +1,214 @@
+//! Stable warning codes.
+//!
+//! Every warning emitted by the tool carries a code (e.g. `W014`) and a slug
+//! (e.g. `feature-skipped-invalid-wkb`) that stay stable across releases, so
+//! pipelines can turn specific warnings into hard failures with `--deny`.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::error::{GpkgError, Result};
+use crate::logger;
+
+/// A stable warning identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningCode {
+    /// The bounding box was not provided and had to be derived from the data.
+    BboxAutoDetected,
+    /// The GeoPackage contains no polygon layers.
+    NoPolygonLayers,
+    /// A layer produced no renderable geometries and was skipped.
+    LayerEmpty,
+    /// The resolution was coarsened so the image fits the maximum dimensions.
+    ResolutionDownscaled,
+    /// Two renders mapped to the same output file and the later one was renamed.
+    OutputNameCollision,
+    /// Features with a non-polygon geometry were ignored.
+    FeatureSkippedNonPolygon,
+    /// Features whose WKB blob could not be decoded were ignored.
+    FeatureSkippedInvalidWkb,
+    /// Features that failed to reproject to WGS84 were ignored.
+    FeatureSkippedReprojection,
+    /// Features collapsed below one pixel at the output resolution.
+    FeatureDegenerate,
+    /// Reprojection used the pure-Rust fallback (reduced accuracy).
+    ReprojectionApproximate,
+    /// Malformed GeoJSON was repaired before parsing.
+    GeojsonRepaired,
+    /// Burned values outside the range of the `--burn-type` were clamped.
+    BurnValueClamped,
+    /// The `--supersample` factor was lowered so the canvas fits the maximum dimensions.
+    SupersampleReduced,
+    /// Tiles of a `--raster` layer whose image could not be decoded were ignored.
+    TileSkippedInvalidImage,
+    /// A layer or the GeoPackage declares a read-write extension the reader does not support.
+    UnsupportedExtension,
+    /// The `--reprojection-cache` file of a layer could not be written.
+    ReprojectionCacheUnavailable,
+    /// Reprojection used an operation of unknown accuracy, such as a ballpark
+    /// transformation without datum shift.
+    ReprojectionBallpark,
+}
+
+impl WarningCode {
+    /// All known warning codes, in code order.
+    pub const ALL: [WarningCode; 17] = [
+        WarningCode::BboxAutoDetected,
+        WarningCode::NoPolygonLayers,
+        WarningCode::LayerEmpty,
+        WarningCode::ResolutionDownscaled,
+        WarningCode::OutputNameCollision,
+        WarningCode::FeatureSkippedNonPolygon,
+        WarningCode::FeatureSkippedInvalidWkb,
+        WarningCode::FeatureSkippedReprojection,
+        WarningCode::FeatureDegenerate,
+        WarningCode::ReprojectionApproximate,
+        WarningCode::GeojsonRepaired,
+        WarningCode::BurnValueClamped,
+        WarningCode::SupersampleReduced,
+        WarningCode::TileSkippedInvalidImage,
+        WarningCode::UnsupportedExtension,
+        WarningCode::ReprojectionCacheUnavailable,
+        WarningCode::ReprojectionBallpark,
+    ];
+
+    /// Returns the short code (e.g. `W014`).
+    pub fn code(&self) -> &'static str {
+        match self {
+            WarningCode::BboxAutoDetected => "W001",
+            WarningCode::NoPolygonLayers => "W002",
+            WarningCode::LayerEmpty => "W003",
+            WarningCode::ResolutionDownscaled => "W004",
+            WarningCode::OutputNameCollision => "W005",
+            WarningCode::FeatureSkippedNonPolygon => "W013",
+            WarningCode::FeatureSkippedInvalidWkb => "W014",
+            WarningCode::FeatureSkippedReprojection => "W015",
+            WarningCode::FeatureDegenerate => "W017",
+            WarningCode::ReprojectionApproximate => "W016",
+            WarningCode::GeojsonRepaired => "W020",
+            WarningCode::BurnValueClamped => "W021",
+            WarningCode::SupersampleReduced => "W022",
+            WarningCode::TileSkippedInvalidImage => "W023",
+            WarningCode::UnsupportedExtension => "W024",
+            WarningCode::ReprojectionCacheUnavailable => "W025",
+            WarningCode::ReprojectionBallpark => "W026",
+        }
+    }
+
+    /// Returns the human-readable slug (e.g. `feature-skipped-invalid-wkb`).
+    pub fn slug(&self) -> &'static str {
+        match self {
+            WarningCode::BboxAutoDetected => "bbox-auto-detected",
+            WarningCode::NoPolygonLayers => "no-polygon-layers",
+            WarningCode::LayerEmpty => "layer-empty",
+            WarningCode::ResolutionDownscaled => "resolution-downscaled",
+            WarningCode::OutputNameCollision => "output-name-collision",
+            WarningCode::FeatureSkippedNonPolygon => "feature-skipped-non-polygon",
+            WarningCode::FeatureSkippedInvalidWkb => "feature-skipped-invalid-wkb",
+            WarningCode::FeatureSkippedReprojection => "feature-skipped-reprojection",
+            WarningCode::FeatureDegenerate => "feature-degenerate",
+            WarningCode::ReprojectionApproximate => "reprojection-approximate",
+            WarningCode::GeojsonRepaired => "geojson-repaired",
+            WarningCode::BurnValueClamped => "burn-value-clamped",
+            WarningCode::SupersampleReduced => "supersample-reduced",
+            WarningCode::TileSkippedInvalidImage => "tile-skipped-invalid-image",
+            WarningCode::UnsupportedExtension => "unsupported-extension",
+            WarningCode::ReprojectionCacheUnavailable => "reprojection-cache-unavailable",
+            WarningCode::ReprojectionBallpark => "reprojection-ballpark",
+        }
+    }
+}
+
+impl fmt::Display for WarningCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl FromStr for WarningCode {
+    type Err = GpkgError;
+
+    /// Parses either the code (`W014`, case-insensitive) or the slug.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        WarningCode::ALL
+            .into_iter()
+            .find(|w| w.code().eq_ignore_ascii_case(s) || w.slug() == s)
+            .ok_or_else(|| GpkgError::InvalidWarningCode(s.to_string()))
+    }
+}
+
+/// Warning codes that must abort the run instead of being logged.
+static DENIED: OnceLock<HashSet<WarningCode>> = OnceLock::new();
+
+/// Registers the set of denied warning codes.
+///
+/// Only the first call has an effect.
+pub fn init(deny: &[WarningCode]) {
+    DENIED.set(deny.iter().copied().collect()).ok();
+}
+
+/// Returns true if the given code was denied on the command line.
+fn is_denied(code: WarningCode) -> bool {
+    DENIED.get().is_some_and(|set| set.contains(&code))
+}
+
+/// Formats a warning message with its code prefix.
+fn format_warning(code: WarningCode, msg: &str) -> String {
+    format!("[{}] {} ({})", code.code(), msg, code.slug())
+}
+
+/// Emits a coded warning.
+///
+/// Returns `GpkgError::DeniedWarning` if the code was passed to `--deny`,
+/// otherwise logs the warning and returns `Ok(())`.
+pub fn emit(code: WarningCode, msg: &str) -> Result<()> {
+    if is_denied(code) {
+        return Err(GpkgError::DeniedWarning(code.code().to_string(), msg.to_string()));
+    }
+    logger::warn(&format_warning(code, msg));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_unique() {
+        let codes: HashSet<&str> = WarningCode::ALL.iter().map(|w| w.code()).collect();
+        let slugs: HashSet<&str> = WarningCode::ALL.iter().map(|w| w.slug()).collect();
+        assert_eq!(codes.len(), WarningCode::ALL.len());
+        assert_eq!(slugs.len(), WarningCode::ALL.len());
+    }
+
+    #[test]
+    fn test_parse_code_and_slug() {
+        assert_eq!(
+            "W014".parse::<WarningCode>().unwrap(),
+            WarningCode::FeatureSkippedInvalidWkb
+        );
+        assert_eq!(
+            "w001".parse::<WarningCode>().unwrap(),
+            WarningCode::BboxAutoDetected
+        );
+        assert_eq!(
+            "layer-empty".parse::<WarningCode>().unwrap(),
+            WarningCode::LayerEmpty
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_code() {
+        let err = "W999".parse::<WarningCode>().unwrap_err();
+        assert!(err.to_string().contains("W999"));
+    }
+
+    #[test]
+    fn test_format_warning() {
+        let msg = format_warning(WarningCode::FeatureSkippedInvalidWkb, "3 features skipped");
+        assert_eq!(msg, "[W014] 3 features skipped (feature-skipped-invalid-wkb)");
+    }
+}
@@ -0,0 +1,125 @@
+//! WebAssembly bindings for GeoJSON rendering (feature `wasm`).
+//!
+//! Only the GeoJSON path is exposed: build a cdylib with `--no-default-features
+//! --features wasm --target wasm32-unknown-unknown` so neither SQLite nor
+//! libproj is compiled. From JavaScript:
+//!
+//! ```js
+//! const options = new RenderOptions(0.0001);
+//! options.set_bbox(-4.5, 48.0, -4.0, 48.5);
+//! options.fill = 0x00ff0080;
+//! const png = render_geojson_png(text, options); // Uint8Array
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::{GpkgError, Result};
+use crate::geojson::GeojsonReader;
+use crate::math::Bbox;
+use crate::render::{self, FeatureStyle, PngMetadata, RenderConfig, Renderer};
+
+/// Render settings; colors are packed as `0xRRGGBBAA` (fill) and `0xRRGGBB` (stroke).
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    bbox: Option<Bbox>,
+    /// Resolution in degrees per pixel.
+    pub resolution: f64,
+    pub fill: u32,
+    pub stroke: u32,
+    /// Stroke width in pixels (0 disables strokes).
+    pub stroke_width: u32,
+}
+
+#[wasm_bindgen]
+impl RenderOptions {
+    /// Creates options with the CLI default colors and an auto-detected bbox.
+    #[wasm_bindgen(constructor)]
+    pub fn new(resolution: f64) -> RenderOptions {
+        RenderOptions {
+            bbox: None,
+            resolution,
+            fill: 0xFF000080,
+            stroke: 0xFF0000,
+            stroke_width: 1,
+        }
+    }
+
+    /// Renders this extent instead of the extent of the features.
+    pub fn set_bbox(&mut self, min_lon: f64, min_lat: f64, max_lon: f64, max_lat: f64) {
+        self.bbox = Some(Bbox::new(min_lon, min_lat, max_lon, max_lat));
+    }
+}
+
+/// Renders the polygons of a GeoJSON document to PNG bytes.
+///
+/// A document declaring a CRS other than WGS84 (legacy `crs` member) is
+/// reprojected, which needs the `reproject-pure` feature on WebAssembly.
+#[wasm_bindgen]
+pub fn render_geojson_png(geojson: &str, options: &RenderOptions) -> std::result::Result<Vec<u8>, JsError> {
+    render_png(geojson, options).map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn render_png(geojson: &str, options: &RenderOptions) -> Result<Vec<u8>> {
+    let mut reader = GeojsonReader::parse(geojson)?;
+    reader.to_wgs84(None, "GeoJSON")?;
+    let bbox = match options.bbox {
+        Some(bbox) => bbox,
+        None => reader.declared_bbox().or_else(|| reader.compute_bbox()).ok_or_else(|| {
+            GpkgError::InvalidBbox("Could not determine bounding box from geometries".to_string())
+        })?,
+    };
+    if options.resolution.is_nan() || options.resolution <= 0.0 {
+        return Err(GpkgError::InvalidResolution(options.resolution));
+    }
+    if bbox.min_lon >= bbox.max_lon || bbox.min_lat >= bbox.max_lat {
+        return Err(GpkgError::InvalidBbox("min must be less than max".to_string()));
+    }
+
+    let [_, r, g, b] = options.stroke.to_be_bytes();
+    let mut renderer = Renderer::new(RenderConfig {
+        bbox,
+        resolution: options.resolution,
+        fill: Some(options.fill.to_be_bytes()),
+        stroke: Some([r, g, b]),
+        stroke_width: options.stroke_width,
+        vertex_color: None,
+        min_feature_px: None,
+        max_dimension: render::MAX_DIMENSION,
+        rotation: 0.0,
+        glow: None,
+    })?;
+    for batch in reader.get_geometries().chunks(render::BATCH_SIZE) {
+        let features = batch.iter().map(|geom| (geom, FeatureStyle::default()));
+        crate::render_features(&mut renderer, features, &bbox);
+    }
+    renderer.to_png(&PngMetadata::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_png_auto_bbox() {
+        let mut options = RenderOptions::new(1.0);
+        options.fill = 0x00FF00FF;
+        options.stroke_width = 0;
+        let png = render_png(
+            r#"{"type": "Polygon", "coordinates": [[[0, 0], [10, 0], [10, 10], [0, 10], [0, 0]]]}"#,
+            &options,
+        )
+        .unwrap();
+
+        let image = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (10, 10));
+        assert_eq!(image.get_pixel(5, 5).0, [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn test_render_png_invalid_resolution() {
+        let json = r#"{"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 0]]]}"#;
+        let err = render_png(json, &RenderOptions::new(0.0)).unwrap_err();
+        assert!(matches!(err, GpkgError::InvalidResolution(_)));
+    }
+}
@@ -11,6 +11,17 @@ fn test_help_flag() {
     assert!(output.status.success());
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("gpkg-to-png"));
+    for command in ["render", "info", "list-layers", "tiles", "serve", "compare-images"] {
+        assert!(stdout.contains(command), "missing command {}: {}", command, stdout);
+    }
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "render", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("--bbox"));
     assert!(stdout.contains("--resolution"));
     assert!(stdout.contains("--scale"));
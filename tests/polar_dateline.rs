@@ -0,0 +1,141 @@
+//! Regression tests for data near the poles and across the antimeridian.
+//!
+//! Fixtures are generated GeoJSON shaped like real datasets: Svalbard
+//! (high-latitude islands), Fiji (islands on both sides of ±180°), an Arctic
+//! cap (a ring circling the North Pole) and Antarctica (a cap closed through
+//! explicit -90° vertices, as in Natural Earth).
+
+use gpkg_to_png::math::{calculate_dimensions, scale_to_resolution};
+use gpkg_to_png::render::MAX_DIMENSION;
+use gpkg_to_png::{render_features, Bbox, FeatureStyle, GeojsonReader, RenderConfig, Renderer};
+
+/// A closed rectangle ring from `(lon0, lat0)` to `(lon1, lat1)`.
+fn rectangle(lon0: f64, lat0: f64, lon1: f64, lat1: f64) -> Vec<[f64; 2]> {
+    vec![[lon0, lat0], [lon1, lat0], [lon1, lat1], [lon0, lat1], [lon0, lat0]]
+}
+
+/// A FeatureCollection with one polygon feature per ring.
+fn feature_collection(rings: &[Vec<[f64; 2]>]) -> String {
+    let features: Vec<String> = rings
+        .iter()
+        .map(|ring| {
+            format!(
+                r#"{{"type": "Feature", "properties": {{}}, "geometry": {{"type": "Polygon", "coordinates": [{:?}]}}}}"#,
+                ring
+            )
+        })
+        .collect();
+    format!(r#"{{"type": "FeatureCollection", "features": [{}]}}"#, features.join(", "))
+}
+
+/// Spitsbergen, Nordaustlandet and Kvitøya.
+fn svalbard() -> String {
+    feature_collection(&[
+        rectangle(11.0, 76.5, 22.0, 80.0),
+        rectangle(18.0, 79.2, 27.0, 80.5),
+        rectangle(31.0, 80.0, 33.5, 80.2),
+    ])
+}
+
+/// Viti Levu and Vanua Levu (east of 177°E) and the Lau islands (west of 180°W).
+fn fiji() -> String {
+    feature_collection(&[
+        rectangle(177.2, -18.3, 178.7, -17.3),
+        rectangle(178.4, -17.0, 179.9, -16.1),
+        // Taveuni straddles the antimeridian
+        vec![[179.8, -17.0], [-179.8, -17.0], [-179.8, -16.7], [179.8, -16.7], [179.8, -17.0]],
+        rectangle(-179.0, -19.0, -178.4, -17.2),
+    ])
+}
+
+/// The Arctic north of 80°N: a ring circling the pole, without pole vertices.
+fn arctic_cap() -> String {
+    let ring: Vec<[f64; 2]> = (0..=8).map(|i| [-180.0 + 45.0 * i as f64, 80.0]).collect();
+    feature_collection(&[ring])
+}
+
+/// Antarctica south of 70°S, closed through the pole like Natural Earth.
+fn antarctica() -> String {
+    let mut ring: Vec<[f64; 2]> = (0..=12).map(|i| [-180.0 + 30.0 * i as f64, -70.0]).collect();
+    ring.extend([[180.0, -90.0], [-180.0, -90.0], [-180.0, -70.0]]);
+    feature_collection(&[ring])
+}
+
+/// Renders every polygon of `geojson` over its auto-detected bbox at `scale` m/px.
+fn render(geojson: &str, scale: f64) -> (Bbox, image::RgbaImage) {
+    let reader = GeojsonReader::parse(geojson).unwrap();
+    let bbox = reader.compute_bbox().unwrap();
+    let resolution = scale_to_resolution(scale, &bbox);
+    let mut renderer = Renderer::new(RenderConfig {
+        bbox,
+        resolution,
+        fill: Some([255, 0, 0, 255]),
+        stroke: Some([0, 0, 0]),
+        stroke_width: 0,
+        vertex_color: None,
+        min_feature_px: None,
+        max_dimension: MAX_DIMENSION,
+        rotation: 0.0,
+        glow: None,
+    })
+    .unwrap();
+    let features = reader.get_geometries().iter().map(|geom| (geom, FeatureStyle::default()));
+    render_features(&mut renderer, features, &bbox);
+    (bbox, renderer.into_image())
+}
+
+/// Share of pixels with a non-zero alpha.
+fn coverage(image: &image::RgbaImage) -> f64 {
+    image.pixels().filter(|p| p.0[3] > 0).count() as f64 / (image.width() * image.height()) as f64
+}
+
+/// Whether every pixel of row `y` is drawn.
+fn row_filled(image: &image::RgbaImage, y: u32) -> bool {
+    (0..image.width()).all(|x| image.get_pixel(x, y).0[3] > 0)
+}
+
+#[test]
+fn test_svalbard() {
+    let (bbox, image) = render(&svalbard(), 2000.0);
+    assert_eq!((bbox.min_lat, bbox.max_lat), (76.5, 80.5));
+    assert!(coverage(&image) > 0.3, "coverage {}", coverage(&image));
+}
+
+#[test]
+fn test_fiji() {
+    let (bbox, image) = render(&fiji(), 1000.0);
+    assert!(bbox.crosses_antimeridian(), "{:?}", bbox);
+    assert!(bbox.width() < 5.0, "{:?}", bbox);
+    assert!(coverage(&image) > 0.2, "coverage {}", coverage(&image));
+    // Both sides of the dateline are drawn
+    let (width, _) = image.dimensions();
+    let column_filled = |x: u32| (0..image.height()).any(|y| image.get_pixel(x, y).0[3] > 0);
+    assert!(column_filled(width / 10) && column_filled(width - width / 10));
+}
+
+#[test]
+fn test_arctic_cap() {
+    let (bbox, image) = render(&arctic_cap(), 20000.0);
+    assert_eq!((bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat), (-180.0, 80.0, 180.0, 90.0));
+    assert!(coverage(&image) > 0.9, "coverage {}", coverage(&image));
+    // The cap reaches the pole on every longitude
+    assert!(row_filled(&image, 0));
+}
+
+#[test]
+fn test_antarctica() {
+    let (bbox, image) = render(&antarctica(), 20000.0);
+    assert_eq!((bbox.min_lon, bbox.min_lat, bbox.max_lon, bbox.max_lat), (-180.0, -90.0, 180.0, -70.0));
+    assert!(coverage(&image) > 0.9, "coverage {}", coverage(&image));
+    assert!(row_filled(&image, image.height() - 1));
+}
+
+#[test]
+fn test_dimensions_near_pole() {
+    // A thin strip at the pole must still produce a finite, small image
+    let bbox = Bbox::new(0.0, 89.9, 10.0, 90.0);
+    let (width, height) = calculate_dimensions(&bbox, scale_to_resolution(100.0, &bbox));
+    assert!(width > 0 && height > 0);
+    assert!(width < 100_000 && height < 100_000, "{}x{}", width, height);
+}
+